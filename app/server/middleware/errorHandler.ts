@@ -59,7 +59,10 @@ function parseSolanaError(msg: string): { message: string; status: number } {
     return { message: "Poll cannot have more than 10 candidates.", status: 400 };
   }
   if (msg.includes("TitleTooLong")) {
-    return { message: "Title must be 100 characters or less.", status: 400 };
+    return { message: "Title must be 100 bytes or less (UTF-8).", status: 400 };
+  }
+  if (msg.includes("CandidateNameTooLong")) {
+    return { message: "Candidate names must be 50 bytes or less (UTF-8).", status: 400 };
   }
   if (msg.includes("ADMIN_PRIVATE_KEY not configured")) {
     return { message: "Admin key not configured on server.", status: 501 };
@@ -78,13 +81,12 @@ export function validatePublicKey(address: string): boolean {
 }
 
 export const validateCreatePoll = (req: Request, res: Response, next: NextFunction) => {
-  const { pollId, title, candidates } = req.body;
-  if (!pollId || isNaN(Number(pollId)) || Number(pollId) <= 0)
-    return res.status(400).json({ success: false, error: "pollId must be a positive number." });
+  const { title, candidates } = req.body;
   if (!title || typeof title !== "string" || title.trim().length === 0)
     return res.status(400).json({ success: false, error: "title is required." });
-  if (title.length > 100)
-    return res.status(400).json({ success: false, error: "title must be 100 characters or less." });
+  // The program counts UTF-8 bytes, not characters
+  if (Buffer.byteLength(title, "utf8") > 100)
+    return res.status(400).json({ success: false, error: "title must be 100 bytes or less (UTF-8)." });
   if (!Array.isArray(candidates) || candidates.length < 2)
     return res.status(400).json({ success: false, error: "At least 2 candidates are required." });
   if (candidates.length > 10)
@@ -92,8 +94,8 @@ export const validateCreatePoll = (req: Request, res: Response, next: NextFuncti
   for (const c of candidates) {
     if (typeof c !== "string" || c.trim().length === 0)
       return res.status(400).json({ success: false, error: "Each candidate must be a non-empty string." });
-    if (c.length > 50)
-      return res.status(400).json({ success: false, error: "Candidate names must be 50 characters or less." });
+    if (Buffer.byteLength(c, "utf8") > 50)
+      return res.status(400).json({ success: false, error: "Candidate names must be 50 bytes or less (UTF-8)." });
   }
   next();
 };
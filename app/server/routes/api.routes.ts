@@ -3,15 +3,20 @@ import {
   fetchPoll,
   fetchAllPolls,
   checkVoteStatus,
+  pageVoteRecords,
+  getCurrentSlot,
   getBalance,
   createPoll,
   closePoll,
   buildVoteTransaction,
+  buildOfflineVoteTransaction,
+  attachOfflineSignatures,
   submitSignedTransaction,
   getExplorerUrl,
   getConnectionStatus,
   getProgramId,
 } from "../services/solana.service";
+import { rankResults } from "../services/results.service";
 import {
   asyncHandler,
   AppError,
@@ -61,8 +66,8 @@ router.post(
   "/polls",
   validateCreatePoll,
   asyncHandler(async (req, res) => {
-    const { pollId, title, candidates } = req.body;
-    const result = await createPoll(Number(pollId), title.trim(), candidates.map((c: string) => c.trim()));
+    const { title, candidates } = req.body;
+    const result = await createPoll(title.trim(), candidates.map((c: string) => c.trim()));
     res.status(201).json({
       success: true,
       message: `Poll "${title}" created successfully.`,
@@ -91,23 +96,24 @@ router.get(
     const pollId = parseInt(req.params.pollId, 10);
     if (isNaN(pollId) || pollId <= 0) throw new AppError("Invalid poll ID.", 400);
     const poll = await fetchPoll(pollId);
-    const ranked = [...poll.candidates]
-      .sort((a, b) => b.votes - a.votes)
-      .map((c, i) => ({
-        rank: i + 1,
-        name: c.name,
-        votes: c.votes,
-        percentage: poll.totalVotes > 0 ? ((c.votes / poll.totalVotes) * 100).toFixed(1) : "0.0",
-      }));
+    // Embargoed results stay out of the feed until the poll's embargo slot.
+    // This is a courtesy only: the tallies are readable on-chain meanwhile.
+    if (poll.embargoSlot > 0 && (await getCurrentSlot()) < poll.embargoSlot) {
+      throw new AppError(`Results are embargoed until slot ${poll.embargoSlot}.`, 403);
+    }
+    const { results, rawResults } = rankResults(poll);
     res.json({
       success: true,
       data: {
         pollId: poll.pollId,
         title: poll.title,
+        status: poll.status,
         isActive: poll.isActive,
         totalVotes: poll.totalVotes,
-        results: ranked,
-        winner: poll.totalVotes > 0 ? ranked[0] : null,
+        reportingThresholdBps: poll.reportingThresholdBps,
+        results,
+        rawResults,
+        winner: poll.totalVotes > 0 ? rawResults[0] : null,
       },
     });
   })
@@ -142,6 +148,45 @@ router.post(
   })
 );
 
+router.post(
+  "/vote/build-offline",
+  validateVote,
+  asyncHandler(async (req, res) => {
+    const { pollId, candidateIndex, voterAddress, nonceAccount, nonceAuthority } = req.body;
+    if (!nonceAccount || !validatePublicKey(nonceAccount))
+      throw new AppError("nonceAccount must be a valid Solana address.", 400);
+    if (nonceAuthority !== undefined && !validatePublicKey(nonceAuthority))
+      throw new AppError("nonceAuthority must be a valid Solana address.", 400);
+    const { hasVoted } = await checkVoteStatus(Number(pollId), voterAddress);
+    if (hasVoted) throw new AppError("This wallet has already voted in this poll.", 409);
+    const result = await buildOfflineVoteTransaction(
+      Number(pollId),
+      Number(candidateIndex),
+      voterAddress,
+      nonceAccount,
+      nonceAuthority
+    );
+    res.json({ success: true, message: "Durable-nonce transaction built. Sign the message offline.", data: result });
+  })
+);
+
+router.post(
+  "/vote/attach-signatures",
+  asyncHandler(async (req, res) => {
+    const { transaction, signatures } = req.body;
+    if (!transaction || typeof transaction !== "string")
+      throw new AppError("transaction (base64) is required.", 400);
+    if (!Array.isArray(signatures) || signatures.length === 0)
+      throw new AppError("signatures must be a non-empty array.", 400);
+    for (const s of signatures) {
+      if (!s || !validatePublicKey(s.publicKey) || typeof s.signature !== "string")
+        throw new AppError("Each signature needs a valid publicKey and a base58 signature.", 400);
+    }
+    const result = attachOfflineSignatures(transaction, signatures);
+    res.json({ success: true, message: "Signatures attached. Submit via /vote/submit.", data: result });
+  })
+);
+
 router.get(
   "/vote/status/:pollId/:voterAddress",
   asyncHandler(async (req, res) => {
@@ -154,6 +199,20 @@ router.get(
   })
 );
 
+router.get(
+  "/polls/:pollId/votes",
+  asyncHandler(async (req, res) => {
+    const pollId = parseInt(req.params.pollId, 10);
+    if (isNaN(pollId) || pollId <= 0) throw new AppError("Invalid poll ID.", 400);
+    const limit = req.query.limit ? parseInt(String(req.query.limit), 10) : undefined;
+    if (limit !== undefined && (isNaN(limit) || limit <= 0)) throw new AppError("Invalid page limit.", 400);
+    const cursor = req.query.cursor ? String(req.query.cursor) : null;
+    if (cursor && !validatePublicKey(cursor)) throw new AppError("Invalid page cursor.", 400);
+    const page = await pageVoteRecords(pollId, { limit, cursor });
+    res.json({ success: true, data: page });
+  })
+);
+
 // ─── Wallet ──────────────────────────────────────────────────────────────────
 
 router.get(
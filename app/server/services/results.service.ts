@@ -0,0 +1,51 @@
+import type { PollInfo } from "./solana.service";
+
+// ─── Types ───────────────────────────────────────────────────────────────────
+
+export interface RankedResult {
+  rank: number;
+  name: string;
+  votes: number;
+  percentage: string;
+}
+
+export interface ResultsFeed {
+  // Published feed: candidates below the reporting threshold grouped as "Other"
+  results: RankedResult[];
+  // Every candidate, ranked by votes
+  rawResults: RankedResult[];
+}
+
+// ─── Ranking ─────────────────────────────────────────────────────────────────
+
+export function rankResults(
+  poll: Pick<PollInfo, "candidates" | "totalVotes" | "reportingThresholdBps">
+): ResultsFeed {
+  const percentage = (votes: number) =>
+    poll.totalVotes > 0 ? ((votes / poll.totalVotes) * 100).toFixed(1) : "0.0";
+  const ranked = [...poll.candidates]
+    .sort((a, b) => b.votes - a.votes)
+    .map((c, i) => ({
+      rank: i + 1,
+      name: c.name,
+      votes: c.votes,
+      percentage: percentage(c.votes),
+    }));
+
+  // Candidates below the poll's reporting threshold are published as "Other"
+  const reportable = (votes: number) =>
+    votes * 10_000 >= poll.reportingThresholdBps * poll.totalVotes;
+  const reported = ranked.filter((c) => reportable(c.votes));
+  const otherVotes = ranked
+    .filter((c) => !reportable(c.votes))
+    .reduce((sum, c) => sum + c.votes, 0);
+  if (reported.length < ranked.length) {
+    reported.push({
+      rank: reported.length + 1,
+      name: "Other",
+      votes: otherVotes,
+      percentage: percentage(otherVotes),
+    });
+  }
+  return { results: reported, rawResults: ranked };
+}
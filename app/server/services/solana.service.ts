@@ -1,11 +1,12 @@
 import {
   Connection,
+  GetProgramAccountsFilter,
   PublicKey,
   Keypair,
   SystemProgram,
   Transaction,
 } from "@solana/web3.js";
-import { Program, AnchorProvider, BN, Idl, Wallet } from "@coral-xyz/anchor";
+import { Program, AnchorProvider, BN, BorshAccountsCoder, Idl, Wallet } from "@coral-xyz/anchor";
 import bs58 from "bs58";
 import { config } from "../config";
 import idl from "../idl/solana_voting.json";
@@ -23,17 +24,61 @@ export interface PollInfo {
   title: string;
   candidates: CandidateInfo[];
   totalVotes: number;
+  // PollStatus variant name, e.g. "active", "closed", "finalized"
+  status: string;
   isActive: boolean;
+  reportingThresholdBps: number;
+  embargoSlot: number;
   pollAddress: string;
 }
 
+export interface OfflineVoteTransaction {
+  transaction: string;
+  message: string;
+  nonce: string;
+  nonceAccount: string;
+}
+
+export interface OfflineSignature {
+  publicKey: string;
+  signature: string;
+}
+
 export interface VoteRecordInfo {
   voter: string;
   pollId: number;
   candidateIndex: number;
+  votedAt: number;
+  slot: number;
   recordAddress: string;
 }
 
+export interface RollEntryInfo {
+  poll: string;
+  identityHash: string;
+  entryAddress: string;
+}
+
+export interface AdminPollEntryInfo {
+  admin: string;
+  index: number;
+  poll: string;
+  pollId: number;
+  entryAddress: string;
+}
+
+export interface PageOptions {
+  limit?: number;
+  // Address of the last item of the previous page
+  cursor?: string | null;
+}
+
+export interface AccountPage<T> {
+  items: T[];
+  // Pass back as `cursor` to fetch the next page; null on the last page
+  nextCursor: string | null;
+}
+
 // ─── Setup ───────────────────────────────────────────────────────────────────
 
 const PROGRAM_ID = new PublicKey(config.programId);
@@ -69,6 +114,16 @@ function getAdminProgram(): { program: Program; admin: Keypair } {
 
 // ─── PDAs ────────────────────────────────────────────────────────────────────
 
+function findPDA(...seeds: Buffer[]): PublicKey {
+  return PublicKey.findProgramAddressSync(seeds, PROGRAM_ID)[0];
+}
+
+const CONFIG_PDA = findPDA(Buffer.from("config"));
+const POLL_COUNTER_PDA = findPDA(Buffer.from("poll_counter"));
+const TREASURY_PDA = findPDA(Buffer.from("treasury"));
+// Signs the self-CPI through which instructions emit their events
+const EVENT_AUTHORITY_PDA = findPDA(Buffer.from("__event_authority"));
+
 export function getPollPDA(pollId: number): [PublicKey, number] {
   const bn = new BN(pollId);
   return PublicKey.findProgramAddressSync(
@@ -85,6 +140,16 @@ export function getVoteRecordPDA(pollId: number, voter: PublicKey): [PublicKey,
   );
 }
 
+// Accounts every instruction that emits events via CPI takes
+function eventAccounts() {
+  return { eventAuthority: EVENT_AUTHORITY_PDA, program: PROGRAM_ID };
+}
+
+// An enum account field decodes as `{ variantName: {} }`
+function variantName(value: unknown): string {
+  return value && typeof value === "object" ? Object.keys(value)[0] ?? "unknown" : "unknown";
+}
+
 // ─── Read ────────────────────────────────────────────────────────────────────
 
 export async function fetchPoll(pollId: number): Promise<PollInfo> {
@@ -100,7 +165,10 @@ export async function fetchPoll(pollId: number): Promise<PollInfo> {
       votes: (c.votes as BN).toNumber(),
     })),
     totalVotes: (acct.totalVotes as BN).toNumber(),
-    isActive: acct.isActive as boolean,
+    status: variantName(acct.status),
+    isActive: variantName(acct.status) === "active",
+    reportingThresholdBps: (acct.reportingThresholdBps as number) ?? 0,
+    embargoSlot: (acct.embargoSlot as BN | undefined)?.toNumber() ?? 0,
     pollAddress: pollPda.toBase58(),
   };
 }
@@ -117,7 +185,10 @@ export async function fetchAllPolls(): Promise<PollInfo[]> {
       votes: (c.votes as BN).toNumber(),
     })),
     totalVotes: (item.account.totalVotes as BN).toNumber(),
-    isActive: item.account.isActive as boolean,
+    status: variantName(item.account.status),
+    isActive: variantName(item.account.status) === "active",
+    reportingThresholdBps: (item.account.reportingThresholdBps as number) ?? 0,
+    embargoSlot: (item.account.embargoSlot as BN | undefined)?.toNumber() ?? 0,
     pollAddress: item.publicKey.toBase58(),
   }));
 }
@@ -129,20 +200,31 @@ export async function checkVoteStatus(
   const program = getReadOnlyProgram();
   const voter = new PublicKey(voterAddress);
   const [voteRecordPda] = getVoteRecordPDA(pollId, voter);
+  // Only a missing record means "not voted"; a record that fails to decode
+  // still blocks a second ballot instead of reading as absent
+  const info = await connection.getAccountInfo(voteRecordPda);
+  if (!info) return { hasVoted: false, voteRecord: null };
+  let record: Record<string, any>;
   try {
-    const record = await program.account.voteRecord.fetch(voteRecordPda);
-    return {
-      hasVoted: true,
-      voteRecord: {
-        voter: (record.voter as PublicKey).toBase58(),
-        pollId: (record.pollId as BN).toNumber(),
-        candidateIndex: record.candidateIndex as number,
-        recordAddress: voteRecordPda.toBase58(),
-      },
-    };
+    record = program.coder.accounts.decode("VoteRecord", info.data);
   } catch {
-    return { hasVoted: false, voteRecord: null };
+    return { hasVoted: true, voteRecord: null };
   }
+  return {
+    hasVoted: true,
+    voteRecord: {
+      voter: (record.voter as PublicKey | undefined)?.toBase58() ?? voter.toBase58(),
+      pollId: (record.pollId as BN | undefined)?.toNumber() ?? pollId,
+      candidateIndex: (record.candidateIndex as number | undefined) ?? 0,
+      votedAt: (record.votedAt as BN | undefined)?.toNumber() ?? 0,
+      slot: (record.slot as BN | undefined)?.toNumber() ?? 0,
+      recordAddress: voteRecordPda.toBase58(),
+    },
+  };
+}
+
+export async function getCurrentSlot(): Promise<number> {
+  return connection.getSlot("confirmed");
 }
 
 export async function getBalance(address: string): Promise<number> {
@@ -153,21 +235,107 @@ export async function getBalance(address: string): Promise<number> {
 
 // ─── Write (Admin) ──────────────────────────────────────────────────────────
 
+// Settings for a plain poll: open now, no deadline, one direct vote per wallet
+export function defaultPollSettings(): Record<string, unknown> {
+  return {
+    parallelTallies: false,
+    clockSource: { unixTimestamp: {} },
+    startTs: new BN(0),
+    endTs: new BN(0),
+    approvers: [],
+    approvalThreshold: 0,
+    allowVoteChanges: false,
+    voterRoll: false,
+    contentMode: { inline: {} },
+    startAsDraft: false,
+    eventVerbosity: { standard: {} },
+    reopenWindow: new BN(0),
+    maxExtension: new BN(0),
+    publishAttendance: false,
+    finalizeTip: new BN(0),
+    reportingThresholdBps: 0,
+    quorum: new BN(0),
+    quorumBps: 0,
+    embargoSlot: new BN(0),
+    allowWriteIns: false,
+    disqualificationPolicy: { freeze: {} },
+    allowMemos: false,
+    gatekeeperNetwork: PublicKey.default,
+    badgeMint: PublicKey.default,
+    tokenGateMint: PublicKey.default,
+    minTokenBalance: new BN(0),
+    voteDeposit: new BN(0),
+    stakeWeighted: false,
+    ballotKey: PublicKey.default,
+    revealWindow: new BN(0),
+    revealBond: new BN(0),
+    directVotesOnly: false,
+    rateLimitVotes: 0,
+    rateLimitSlots: new BN(0),
+    maxVotes: null,
+    category: 0,
+    tags: [],
+    certifier: PublicKey.default,
+    challengeWindow: new BN(0),
+    challengeDeposit: new BN(0),
+    auditLog: false,
+    convictionMint: PublicKey.default,
+    convictionPeriod: new BN(0),
+    quadraticFunding: false,
+    predictionMarket: false,
+    predictionMint: PublicKey.default,
+    oracle: PublicKey.default,
+    petitionThreshold: new BN(0),
+    nftCollection: PublicKey.default,
+    minReputation: new BN(0),
+    recordSalt: Array(32).fill(0),
+    maxTitleBytes: 0,
+    maxCandidateNameBytes: 0,
+    hideResultsUntilClose: false,
+    snapshotOracle: PublicKey.default,
+    decayBps: 0,
+    tierWeights: [],
+    tallyShards: 0,
+  };
+}
+
+// The program assigns poll IDs from its counter, so the new poll lands at the
+// counter's current value
 export async function createPoll(
-  pollId: number,
   title: string,
-  candidates: string[]
-): Promise<{ signature: string; pollAddress: string }> {
+  candidates: string[],
+  settings: Record<string, unknown> = {}
+): Promise<{ signature: string; pollId: number; pollAddress: string }> {
   const { program, admin } = getAdminProgram();
-  const pollIdBN = new BN(pollId);
+  const counter = await program.account.pollCounter.fetch(POLL_COUNTER_PDA);
+  const pollId = (counter.nextPollId as BN).toNumber();
   const [pollPda] = getPollPDA(pollId);
+  const adminProfile = findPDA(Buffer.from("admin_profile"), admin.publicKey.toBuffer());
+  const profile = await program.account.adminProfile.fetchNullable(adminProfile);
+  const entryIndex = profile ? (profile.pollsCreated as BN) : new BN(0);
+  const programConfig = await program.account.programConfig.fetch(CONFIG_PDA);
   const tx = await program.methods
-    .createPoll(pollIdBN, title, candidates)
-    .accounts({ poll: pollPda, admin: admin.publicKey, systemProgram: SystemProgram.programId })
+    .createPoll(title, candidates, { ...defaultPollSettings(), ...settings })
+    .accounts({
+      ...eventAccounts(),
+      config: CONFIG_PDA,
+      pollCounter: POLL_COUNTER_PDA,
+      poll: pollPda,
+      adminProfile,
+      adminPollEntry: findPDA(
+        Buffer.from("admin_poll"),
+        admin.publicKey.toBuffer(),
+        entryIndex.toArrayLike(Buffer, "le", 8)
+      ),
+      // Only needed to collect a poll creation fee
+      treasury: (programConfig.pollCreationFee as BN).isZero() ? null : TREASURY_PDA,
+      admin: admin.publicKey,
+      systemProgram: SystemProgram.programId,
+    })
     .signers([admin])
     .rpc();
   await connection.confirmTransaction(tx, "confirmed");
-  return { signature: tx, pollAddress: pollPda.toBase58() };
+  return { signature: tx, pollId, pollAddress: pollPda.toBase58() };
 }
 
 export async function closePoll(pollId: number): Promise<{ signature: string }> {
@@ -176,7 +344,7 @@ export async function closePoll(pollId: number): Promise<{ signature: string }>
   const [pollPda] = getPollPDA(pollId);
   const tx = await program.methods
     .closePoll(pollIdBN)
-    .accounts({ poll: pollPda, admin: admin.publicKey })
+    .accounts({ ...eventAccounts(), poll: pollPda, config: CONFIG_PDA, admin: admin.publicKey })
     .signers([admin])
     .rpc();
   await connection.confirmTransaction(tx, "confirmed");
@@ -185,6 +353,30 @@ export async function closePoll(pollId: number): Promise<{ signature: string }>
 
 // ─── Vote Transaction Builder ───────────────────────────────────────────────
 
+// A plain direct ballot. The optional gating accounts (session token, voter
+// roll membership, token gates, conviction lock, reputation) are left out, so
+// polls that require them reject the vote on-chain.
+function voteInstruction(
+  program: Program,
+  pollIdBN: BN,
+  candidateIndex: number,
+  voter: PublicKey,
+  pollPda: PublicKey,
+  voteRecordPda: PublicKey
+) {
+  return program.methods
+    .vote(pollIdBN, candidateIndex)
+    .accounts({
+      ...eventAccounts(),
+      poll: pollPda,
+      config: CONFIG_PDA,
+      voteRecord: voteRecordPda,
+      voter,
+      systemProgram: SystemProgram.programId,
+    })
+    .instruction();
+}
+
 export async function buildVoteTransaction(
   pollId: number,
   candidateIndex: number,
@@ -196,10 +388,7 @@ export async function buildVoteTransaction(
   const [pollPda] = getPollPDA(pollId);
   const [voteRecordPda] = getVoteRecordPDA(pollId, voter);
 
-  const ix = await program.methods
-    .vote(pollIdBN, candidateIndex)
-    .accounts({ poll: pollPda, voteRecord: voteRecordPda, voter, systemProgram: SystemProgram.programId })
-    .instruction();
+  const ix = await voteInstruction(program, pollIdBN, candidateIndex, voter, pollPda, voteRecordPda);
 
   const { blockhash, lastValidBlockHeight } = await connection.getLatestBlockhash("confirmed");
   const tx = new Transaction({ blockhash, lastValidBlockHeight, feePayer: voter }).add(ix);
@@ -217,6 +406,186 @@ export async function submitSignedTransaction(signedTxBase64: string): Promise<{
   return { signature };
 }
 
+// ─── Offline Signing ─────────────────────────────────────────────────────────
+
+// Builds an unsigned vote transaction anchored to a durable nonce instead of a
+// recent blockhash, so it stays valid while it is carried to an air-gapped
+// signer. `message` is the exact byte string the offline signer must sign.
+export async function buildOfflineVoteTransaction(
+  pollId: number,
+  candidateIndex: number,
+  voterAddress: string,
+  nonceAccountAddress: string,
+  nonceAuthorityAddress?: string
+): Promise<OfflineVoteTransaction> {
+  const program = getReadOnlyProgram();
+  const voter = new PublicKey(voterAddress);
+  const nonceAccount = new PublicKey(nonceAccountAddress);
+  const nonceAuthority = nonceAuthorityAddress ? new PublicKey(nonceAuthorityAddress) : voter;
+  const pollIdBN = new BN(pollId);
+  const [pollPda] = getPollPDA(pollId);
+  const [voteRecordPda] = getVoteRecordPDA(pollId, voter);
+
+  const nonceInfo = await connection.getNonce(nonceAccount, "confirmed");
+  if (!nonceInfo) throw new Error(`Nonce account ${nonceAccountAddress} not found or not initialized.`);
+  if (!nonceInfo.authorizedPubkey.equals(nonceAuthority))
+    throw new Error(`Nonce authority mismatch: account is controlled by ${nonceInfo.authorizedPubkey.toBase58()}.`);
+
+  const ix = await voteInstruction(program, pollIdBN, candidateIndex, voter, pollPda, voteRecordPda);
+
+  // compileMessage() places nonceInstruction first, as the runtime requires.
+  const tx = new Transaction({ feePayer: voter }).add(ix);
+  tx.recentBlockhash = nonceInfo.nonce;
+  tx.nonceInfo = {
+    nonce: nonceInfo.nonce,
+    nonceInstruction: SystemProgram.nonceAdvance({ noncePubkey: nonceAccount, authorizedPubkey: nonceAuthority }),
+  };
+
+  return {
+    transaction: tx.serialize({ requireAllSignatures: false }).toString("base64"),
+    message: tx.serializeMessage().toString("base64"),
+    nonce: nonceInfo.nonce,
+    nonceAccount: nonceAccount.toBase58(),
+  };
+}
+
+// Attaches detached signatures (base58, as produced by `solana sign-offline`
+// style tooling) to a transaction from buildOfflineVoteTransaction. The result
+// can be passed straight to submitSignedTransaction.
+export function attachOfflineSignatures(
+  unsignedTxBase64: string,
+  signatures: OfflineSignature[]
+): { transaction: string } {
+  const tx = Transaction.from(Buffer.from(unsignedTxBase64, "base64"));
+  for (const { publicKey, signature } of signatures) {
+    tx.addSignature(new PublicKey(publicKey), Buffer.from(bs58.decode(signature)));
+  }
+  if (!tx.verifySignatures()) throw new Error("Offline signatures are missing or invalid for this transaction.");
+  return { transaction: tx.serialize().toString("base64") };
+}
+
+// ─── Pagination ──────────────────────────────────────────────────────────────
+//
+// Full getProgramAccounts scans time out on polls with many voters. These
+// helpers first list only the matching addresses (filtered by discriminator,
+// size and owning poll, with an empty data slice), then load one page of
+// account data with getMultipleAccountsInfo. Pages are ordered by address and
+// the cursor is the last address returned, so a scan can resume from where it
+// stopped; accounts created since then are only picked up if they sort after
+// the cursor.
+
+const DEFAULT_PAGE_SIZE = 100;
+const MAX_PAGE_SIZE = 1000;
+// getMultipleAccountsInfo accepts at most 100 addresses per call
+const MULTIPLE_ACCOUNTS_LIMIT = 100;
+
+const VOTE_RECORD_POLL_ID_OFFSET = 8 + 32;
+// voter, poll_id, candidate_index, bump, weight, delegated_bps
+const VOTE_RECORD_CHANGES_OFFSET = 8 + 32 + 8 + 1 + 1 + 8 + 2;
+const ROLL_ENTRY_SIZE = 8 + 32 + 32 + 1;
+const ADMIN_POLL_ENTRY_SIZE = 8 + 32 + 8 + 32 + 8 + 1;
+
+async function pageProgramAccounts<T>(
+  accountName: string,
+  filters: GetProgramAccountsFilter[],
+  options: PageOptions,
+  decode: (address: PublicKey, data: Buffer) => T
+): Promise<AccountPage<T>> {
+  const limit = Math.min(Math.max(options.limit ?? DEFAULT_PAGE_SIZE, 1), MAX_PAGE_SIZE);
+  const matches = await connection.getProgramAccounts(PROGRAM_ID, {
+    dataSlice: { offset: 0, length: 0 },
+    filters: [
+      {
+        memcmp: {
+          offset: 0,
+          bytes: bs58.encode(BorshAccountsCoder.accountDiscriminator(accountName)),
+        },
+      },
+      ...filters,
+    ],
+  });
+
+  const cursor = options.cursor ? new PublicKey(options.cursor).toBuffer() : null;
+  const addresses = matches
+    .map(({ pubkey }) => pubkey)
+    .filter((pubkey) => !cursor || Buffer.compare(pubkey.toBuffer(), cursor) > 0)
+    .sort((a, b) => Buffer.compare(a.toBuffer(), b.toBuffer()));
+  const pageAddresses = addresses.slice(0, limit);
+
+  const items: T[] = [];
+  for (let i = 0; i < pageAddresses.length; i += MULTIPLE_ACCOUNTS_LIMIT) {
+    const chunk = pageAddresses.slice(i, i + MULTIPLE_ACCOUNTS_LIMIT);
+    const infos = await connection.getMultipleAccountsInfo(chunk);
+    infos.forEach((info, j) => {
+      // Skip accounts closed between the listing and the fetch
+      if (info) items.push(decode(chunk[j], info.data));
+    });
+  }
+  return {
+    items,
+    nextCursor:
+      addresses.length > limit ? pageAddresses[pageAddresses.length - 1].toBase58() : null,
+  };
+}
+
+export async function pageVoteRecords(
+  pollId: number,
+  options: PageOptions = {}
+): Promise<AccountPage<VoteRecordInfo>> {
+  const filters: GetProgramAccountsFilter[] = [
+    {
+      memcmp: {
+        offset: VOTE_RECORD_POLL_ID_OFFSET,
+        bytes: bs58.encode(new BN(pollId).toArrayLike(Buffer, "le", 8)),
+      },
+    },
+  ];
+  return pageProgramAccounts("VoteRecord", filters, options, (address, data) => {
+    // voted_at and slot follow the variable-length change history
+    const stampOffset = VOTE_RECORD_CHANGES_OFFSET + 4 + data.readUInt32LE(VOTE_RECORD_CHANGES_OFFSET) * 10;
+    return {
+      voter: new PublicKey(data.subarray(8, 40)).toBase58(),
+      pollId: Number(data.readBigUInt64LE(40)),
+      candidateIndex: data[48],
+      votedAt: Number(data.readBigInt64LE(stampOffset)),
+      slot: Number(data.readBigUInt64LE(stampOffset + 8)),
+      recordAddress: address.toBase58(),
+    };
+  });
+}
+
+export async function pageRollEntries(
+  pollAddress: string,
+  options: PageOptions = {}
+): Promise<AccountPage<RollEntryInfo>> {
+  const filters: GetProgramAccountsFilter[] = [
+    { dataSize: ROLL_ENTRY_SIZE },
+    { memcmp: { offset: 8, bytes: new PublicKey(pollAddress).toBase58() } },
+  ];
+  return pageProgramAccounts("RollEntry", filters, options, (address, data) => ({
+    poll: new PublicKey(data.subarray(8, 40)).toBase58(),
+    identityHash: data.subarray(40, 72).toString("hex"),
+    entryAddress: address.toBase58(),
+  }));
+}
+
+export async function pageAdminPollEntries(
+  adminAddress: string,
+  options: PageOptions = {}
+): Promise<AccountPage<AdminPollEntryInfo>> {
+  const filters: GetProgramAccountsFilter[] = [
+    { dataSize: ADMIN_POLL_ENTRY_SIZE },
+    { memcmp: { offset: 8, bytes: new PublicKey(adminAddress).toBase58() } },
+  ];
+  return pageProgramAccounts("AdminPollEntry", filters, options, (address, data) => ({
+    admin: new PublicKey(data.subarray(8, 40)).toBase58(),
+    index: Number(data.readBigUInt64LE(40)),
+    poll: new PublicKey(data.subarray(48, 80)).toBase58(),
+    pollId: Number(data.readBigUInt64LE(80)),
+    entryAddress: address.toBase58(),
+  }));
+}
+
 // ─── Utility ─────────────────────────────────────────────────────────────────
 
 export function getExplorerUrl(signature: string): string {
@@ -11,6 +11,14 @@ import {
   lamportsToSol,
   getExplorerUrl,
   parseError,
+  pollStatusName,
+  defaultPollSettings,
+  getAdminProfilePDA,
+  getAdminPollEntryPDA,
+  CONFIG_PDA,
+  POLL_COUNTER_PDA,
+  TREASURY_PDA,
+  EVENT_ACCOUNTS,
   PollData,
 } from "../utils/anchor";
 
@@ -24,7 +32,6 @@ const VotingDApp: React.FC = () => {
 
   // Create poll state
   const [pollTitle, setPollTitle] = useState("");
-  const [pollId, setPollId] = useState("");
   const [candidateName, setCandidateName] = useState("");
   const [candidatesList, setCandidatesList] = useState<string[]>([]);
 
@@ -99,14 +106,6 @@ const VotingDApp: React.FC = () => {
 
   const handleCreatePoll = async () => {
     if (!publicKey || !wallet) return;
-    const idNum = parseInt(pollId);
-    if (isNaN(idNum) || idNum <= 0) {
-      setStatusMessage({
-        type: "error",
-        text: "Please enter a valid poll ID (positive number).",
-      });
-      return;
-    }
     if (!pollTitle.trim()) {
       setStatusMessage({ type: "error", text: "Please enter a poll title." });
       return;
@@ -124,13 +123,29 @@ const VotingDApp: React.FC = () => {
 
     try {
       const program = getProgram(wallet);
-      const pollIdBN = new BN(idNum);
+      // The program assigns the next ID from its poll counter
+      const counter = await program.account.pollCounter.fetch(POLL_COUNTER_PDA);
+      const pollIdBN = counter.nextPollId as BN;
+      const idNum = pollIdBN.toNumber();
       const [pollPda] = getPollPDA(idNum);
+      const [adminProfile] = getAdminProfilePDA(publicKey);
+      const profile = await program.account.adminProfile.fetchNullable(adminProfile);
+      const [adminPollEntry] = getAdminPollEntryPDA(
+        publicKey,
+        profile ? (profile.pollsCreated as BN) : new BN(0)
+      );
+      const programConfig = await program.account.programConfig.fetch(CONFIG_PDA);
 
       const tx = await program.methods
-        .createPoll(pollIdBN, pollTitle.trim(), candidatesList)
+        .createPoll(pollTitle.trim(), candidatesList, defaultPollSettings())
         .accounts({
+          ...EVENT_ACCOUNTS,
+          config: CONFIG_PDA,
+          pollCounter: POLL_COUNTER_PDA,
           poll: pollPda,
+          adminProfile,
+          adminPollEntry,
+          treasury: (programConfig.pollCreationFee as BN).isZero() ? null : TREASURY_PDA,
           admin: publicKey,
           systemProgram: SystemProgram.programId,
         })
@@ -141,13 +156,12 @@ const VotingDApp: React.FC = () => {
 
       setStatusMessage({
         type: "success",
-        text: `Poll "${pollTitle}" created successfully!`,
+        text: `Poll "${pollTitle}" created with ID ${idNum}!`,
         txSig: tx,
       });
 
       // Reset form
       setPollTitle("");
-      setPollId("");
       setCandidatesList([]);
 
       // Auto-load the created poll
@@ -215,7 +229,9 @@ const VotingDApp: React.FC = () => {
       const tx = await program.methods
         .vote(pollIdBN, candidateIndex)
         .accounts({
+          ...EVENT_ACCOUNTS,
           poll: pollPda,
+          config: CONFIG_PDA,
           voteRecord: voteRecordPda,
           voter: publicKey,
           systemProgram: SystemProgram.programId,
@@ -286,6 +302,8 @@ const VotingDApp: React.FC = () => {
       1
     )
     : 1;
+  const statusName = currentPoll ? pollStatusName(currentPoll.status) : "";
+  const pollActive = statusName === "active";
 
   return (
     <div>
@@ -321,17 +339,6 @@ const VotingDApp: React.FC = () => {
           <span className="admin-badge">Admin</span>
         </div>
 
-        <div className="form-group">
-          <label>Poll ID (unique number)</label>
-          <input
-            type="number"
-            placeholder="e.g. 1"
-            value={pollId}
-            onChange={(e) => setPollId(e.target.value)}
-            min="1"
-          />
-        </div>
-
         <div className="form-group">
           <label>Poll Title</label>
           <input
@@ -416,10 +423,10 @@ const VotingDApp: React.FC = () => {
                 </span>
               </div>
               <span
-                className={`poll-status ${currentPoll.isActive ? "active" : "closed"
+                className={`poll-status ${pollActive ? "active" : "closed"
                   }`}
               >
-                {currentPoll.isActive ? "Active" : "Closed"}
+                {statusName.charAt(0).toUpperCase() + statusName.slice(1)}
               </span>
             </div>
 
@@ -458,7 +465,7 @@ const VotingDApp: React.FC = () => {
                     <div className="candidate-action">
                       {hasVoted ? (
                         <span className="voted-badge">✓ Voted</span>
-                      ) : currentPoll.isActive ? (
+                      ) : pollActive ? (
                         <button
                           className="btn btn-vote"
                           onClick={() => handleVote(index)}
@@ -32,6 +32,31 @@ export const getPollPDA = (pollId: number) => {
   );
 };
 
+/** Program-wide PDAs with fixed seeds */
+export const CONFIG_PDA = PublicKey.findProgramAddressSync([Buffer.from("config")], PROGRAM_ID)[0];
+export const POLL_COUNTER_PDA = PublicKey.findProgramAddressSync(
+  [Buffer.from("poll_counter")],
+  PROGRAM_ID
+)[0];
+export const TREASURY_PDA = PublicKey.findProgramAddressSync([Buffer.from("treasury")], PROGRAM_ID)[0];
+
+/** Accounts every instruction that emits events via CPI takes */
+export const EVENT_ACCOUNTS = {
+  eventAuthority: PublicKey.findProgramAddressSync([Buffer.from("__event_authority")], PROGRAM_ID)[0],
+  program: PROGRAM_ID,
+};
+
+/** Derive the PDA for an admin's poll index profile */
+export const getAdminProfilePDA = (admin: PublicKey) =>
+  PublicKey.findProgramAddressSync([Buffer.from("admin_profile"), admin.toBuffer()], PROGRAM_ID);
+
+/** Derive the PDA for one entry of an admin's poll index */
+export const getAdminPollEntryPDA = (admin: PublicKey, index: BN) =>
+  PublicKey.findProgramAddressSync(
+    [Buffer.from("admin_poll"), admin.toBuffer(), index.toArrayLike(Buffer, "le", 8)],
+    PROGRAM_ID
+  );
+
 /** Derive the PDA for a vote record account */
 export const getVoteRecordPDA = (pollId: number, voter: PublicKey) => {
   const bn = new BN(pollId);
@@ -56,6 +81,71 @@ export const getExplorerUrl = (signature: string): string => {
   return `https://explorer.solana.com/tx/${signature}?cluster=devnet`;
 };
 
+/** Settings for a plain poll: open now, no deadline, one direct vote per wallet */
+export const defaultPollSettings = () => ({
+  parallelTallies: false,
+  clockSource: { unixTimestamp: {} },
+  startTs: new BN(0),
+  endTs: new BN(0),
+  approvers: [],
+  approvalThreshold: 0,
+  allowVoteChanges: false,
+  voterRoll: false,
+  contentMode: { inline: {} },
+  startAsDraft: false,
+  eventVerbosity: { standard: {} },
+  reopenWindow: new BN(0),
+  maxExtension: new BN(0),
+  publishAttendance: false,
+  finalizeTip: new BN(0),
+  reportingThresholdBps: 0,
+  quorum: new BN(0),
+  quorumBps: 0,
+  embargoSlot: new BN(0),
+  allowWriteIns: false,
+  disqualificationPolicy: { freeze: {} },
+  allowMemos: false,
+  gatekeeperNetwork: PublicKey.default,
+  badgeMint: PublicKey.default,
+  tokenGateMint: PublicKey.default,
+  minTokenBalance: new BN(0),
+  voteDeposit: new BN(0),
+  stakeWeighted: false,
+  ballotKey: PublicKey.default,
+  revealWindow: new BN(0),
+  revealBond: new BN(0),
+  directVotesOnly: false,
+  rateLimitVotes: 0,
+  rateLimitSlots: new BN(0),
+  maxVotes: null,
+  category: 0,
+  tags: [],
+  certifier: PublicKey.default,
+  challengeWindow: new BN(0),
+  challengeDeposit: new BN(0),
+  auditLog: false,
+  convictionMint: PublicKey.default,
+  convictionPeriod: new BN(0),
+  quadraticFunding: false,
+  predictionMarket: false,
+  predictionMint: PublicKey.default,
+  oracle: PublicKey.default,
+  petitionThreshold: new BN(0),
+  nftCollection: PublicKey.default,
+  minReputation: new BN(0),
+  recordSalt: Array(32).fill(0),
+  maxTitleBytes: 0,
+  maxCandidateNameBytes: 0,
+  hideResultsUntilClose: false,
+  snapshotOracle: PublicKey.default,
+  decayBps: 0,
+  tierWeights: [],
+  tallyShards: 0,
+});
+
+/** Name of a decoded PollStatus, e.g. "active" or "quorumNotMet" */
+export const pollStatusName = (status: object): string => Object.keys(status)[0] ?? "unknown";
+
 /** Parse Anchor error messages */
 export const parseError = (error: any): string => {
   const msg = error?.message || error?.toString() || "Unknown error";
@@ -96,6 +186,6 @@ export interface PollData {
   title: string;
   candidates: CandidateData[];
   totalVotes: BN;
-  isActive: boolean;
+  status: object;
   bump: number;
 }
@@ -0,0 +1,957 @@
+//! Rust client for the `solana_voting` program.
+//!
+//! - PDA helpers, re-exported from `solana_voting::pda`
+//!   (`find_poll_address`, `find_vote_record_address`, ...)
+//! - Instruction builders that fill in every PDA and the event-CPI accounts,
+//!   returning plain `Instruction`s to sign and send with any RPC client
+//! - Account fetch and decode helpers over `RpcClient`
+//!
+//! Polls from `create_poll` have no namespace; pass the admin as `namespace`
+//! for polls from `create_namespaced_poll` and `create_governed_poll`.
+
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::solana_program::sysvar::instructions as ix_sysvar;
+use anchor_lang::{AccountDeserialize, Discriminator, InstructionData, ToAccountMetas};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_voting::{accounts, instruction};
+
+pub use solana_voting::pda::*;
+pub use solana_voting::{
+    compute_result_hash, AdminProfile, Candidate, CandidateTally, Poll, PollCounter, PollResult,
+    PollResults, PollSettings, PollStatus, ProgramConfig, VoteRecord,
+    ACCOUNT_COMPRESSION_PROGRAM_ID, ID as PROGRAM_ID, NOOP_PROGRAM_ID,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error(transparent)]
+    Rpc(Box<solana_client::client_error::ClientError>),
+    #[error("failed to decode account {0}: {1}")]
+    Decode(Pubkey, anchor_lang::error::Error),
+}
+
+impl From<solana_client::client_error::ClientError> for ClientError {
+    fn from(e: solana_client::client_error::ClientError) -> Self {
+        Self::Rpc(Box::new(e))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+// ─── Accounts ────────────────────────────────────────────────────────────────
+
+/// Fetches and decodes the program account at `address`.
+pub fn fetch<T: AccountDeserialize>(rpc: &RpcClient, address: &Pubkey) -> Result<T> {
+    let data = rpc.get_account_data(address)?;
+    T::try_deserialize(&mut data.as_slice()).map_err(|e| ClientError::Decode(*address, e))
+}
+
+/// Like `fetch`, but `None` if no account exists at `address`.
+pub fn fetch_optional<T: AccountDeserialize>(
+    rpc: &RpcClient,
+    address: &Pubkey,
+) -> Result<Option<T>> {
+    let account = rpc
+        .get_account_with_commitment(address, rpc.commitment())?
+        .value;
+    account
+        .map(|account| {
+            T::try_deserialize(&mut account.data.as_slice())
+                .map_err(|e| ClientError::Decode(*address, e))
+        })
+        .transpose()
+}
+
+pub fn fetch_config(rpc: &RpcClient) -> Result<ProgramConfig> {
+    fetch(rpc, &find_config_address().0)
+}
+
+pub fn fetch_poll(rpc: &RpcClient, namespace: Option<&Pubkey>, poll_id: u64) -> Result<Poll> {
+    fetch(rpc, &find_poll_address(namespace, poll_id).0)
+}
+
+/// The voter's record in a poll, or `None` if they haven't voted.
+pub fn fetch_vote_record(
+    rpc: &RpcClient,
+    namespace: Option<&Pubkey>,
+    poll_id: u64,
+    voter: &Pubkey,
+) -> Result<Option<VoteRecord>> {
+    fetch_optional(rpc, &find_vote_record_address(namespace, poll_id, voter).0)
+}
+
+/// Live `(votes, weight)` of each candidate of a parallel-tally poll at
+/// `address`, summed over its tally shards. Until `aggregate_tallies` runs,
+/// the poll account itself holds no counts.
+pub fn fetch_parallel_tallies(
+    rpc: &RpcClient,
+    address: &Pubkey,
+    poll: &Poll,
+) -> Result<Vec<(u64, u64)>> {
+    let shards = poll.tally_shard_count() as u8;
+    (0..poll.candidates.len() as u8)
+        .map(|index| {
+            (0..shards).try_fold((0u64, 0u64), |(votes, weight), shard| {
+                let tally: CandidateTally =
+                    fetch(rpc, &find_candidate_tally_address(address, index, shard).0)?;
+                Ok((
+                    votes.saturating_add(tally.votes),
+                    weight.saturating_add(tally.weight),
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Every poll the program holds, with its address.
+pub fn fetch_all_polls(rpc: &RpcClient) -> Result<Vec<(Pubkey, Poll)>> {
+    let accounts = rpc.get_program_accounts_with_config(
+        &PROGRAM_ID,
+        RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                0,
+                &Poll::DISCRIMINATOR,
+            ))]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )?;
+    accounts
+        .into_iter()
+        .map(|(address, account)| {
+            Poll::try_deserialize(&mut account.data.as_slice())
+                .map(|poll| (address, poll))
+                .map_err(|e| ClientError::Decode(address, e))
+        })
+        .collect()
+}
+
+/// Up to `limit` listed polls, newest first, walking the on-chain poll list
+/// from `after` (exclusive), or from the latest poll if `None`. Pass the last
+/// address of one page as `after` to fetch the next. Unlike
+/// `fetch_all_polls`, this only reads accounts by address. Archived polls
+/// are skipped.
+pub fn fetch_poll_page(
+    rpc: &RpcClient,
+    after: Option<&Pubkey>,
+    limit: usize,
+) -> Result<Vec<(Pubkey, Poll)>> {
+    let mut next = match after {
+        Some(address) => match fetch_optional::<Poll>(rpc, address)? {
+            Some(poll) => poll.prev_poll,
+            None => fetch_archived_link(rpc, address)?,
+        },
+        None => fetch_config(rpc)?.latest_poll,
+    };
+    let mut polls = Vec::new();
+    while next != Pubkey::default() && polls.len() < limit {
+        let address = next;
+        match fetch_optional::<Poll>(rpc, &address)? {
+            Some(poll) => {
+                next = poll.prev_poll;
+                polls.push((address, poll));
+            }
+            None => next = fetch_archived_link(rpc, &address)?,
+        }
+    }
+    Ok(polls)
+}
+
+/// The `prev_poll` an archived poll left in its `PollResult`.
+fn fetch_archived_link(rpc: &RpcClient, poll: &Pubkey) -> Result<Pubkey> {
+    let result: PollResult = fetch(rpc, &find_poll_result_address(poll).0)?;
+    Ok(result.prev_poll)
+}
+
+/// What creating a poll as `admin` depends on besides the poll itself.
+pub struct CreationContext {
+    /// `poll_counter.next_poll_id`, the ID `create_poll` assigns next.
+    pub next_poll_id: u64,
+    /// Index of the admin's next `AdminPollEntry`.
+    pub admin_poll_index: u64,
+    /// Whether a creation fee is set, so the treasury must be passed.
+    pub fee_charged: bool,
+}
+
+impl CreationContext {
+    pub fn fetch(rpc: &RpcClient, admin: &Pubkey) -> Result<Self> {
+        let counter: PollCounter = fetch(rpc, &find_poll_counter_address().0)?;
+        let profile: Option<AdminProfile> =
+            fetch_optional(rpc, &find_admin_profile_address(admin).0)?;
+        Ok(Self {
+            next_poll_id: counter.next_poll_id,
+            admin_poll_index: profile.map_or(0, |profile| profile.polls_created),
+            fee_charged: fetch_config(rpc)?.poll_creation_fee > 0,
+        })
+    }
+}
+
+// ─── Instructions ────────────────────────────────────────────────────────────
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn event_authority() -> Pubkey {
+    find_event_authority_address().0
+}
+
+/// `create_poll` under the counter's next ID; the poll lands at
+/// `find_poll_address(None, ctx.next_poll_id)`.
+pub fn create_poll(
+    admin: &Pubkey,
+    ctx: &CreationContext,
+    title: String,
+    candidates: Vec<String>,
+    settings: PollSettings,
+) -> Instruction {
+    build(
+        accounts::CreatePoll {
+            config: find_config_address().0,
+            poll_counter: find_poll_counter_address().0,
+            poll: find_poll_address(None, ctx.next_poll_id).0,
+            admin_profile: find_admin_profile_address(admin).0,
+            admin_poll_entry: find_admin_poll_entry_address(admin, ctx.admin_poll_index).0,
+            treasury: ctx.fee_charged.then(|| find_treasury_address().0),
+            admin: *admin,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::CreatePoll {
+            title,
+            candidates,
+            settings,
+        },
+    )
+}
+
+/// `create_namespaced_poll` with `poll_id` in the admin's namespace.
+pub fn create_namespaced_poll(
+    admin: &Pubkey,
+    ctx: &CreationContext,
+    poll_id: u64,
+    title: String,
+    candidates: Vec<String>,
+    settings: PollSettings,
+) -> Instruction {
+    build(
+        accounts::CreateNamespacedPoll {
+            config: find_config_address().0,
+            poll: find_poll_address(Some(admin), poll_id).0,
+            admin_profile: find_admin_profile_address(admin).0,
+            admin_poll_entry: find_admin_poll_entry_address(admin, ctx.admin_poll_index).0,
+            treasury: ctx.fee_charged.then(|| find_treasury_address().0),
+            admin: *admin,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::CreateNamespacedPoll {
+            poll_id,
+            title,
+            candidates,
+            settings,
+        },
+    )
+}
+
+/// `clone_poll` of the poll at `source`, into `new_poll_id` in `admin`'s
+/// namespace. `new_record_salt` must be fresh and non-zero when the source
+/// poll's vote records are pseudonymous, and zero otherwise.
+pub fn clone_poll(
+    admin: &Pubkey,
+    ctx: &CreationContext,
+    source: &Pubkey,
+    new_poll_id: u64,
+    new_start_ts: i64,
+    new_end_ts: i64,
+    new_record_salt: [u8; 32],
+) -> Instruction {
+    build(
+        accounts::ClonePoll {
+            config: find_config_address().0,
+            source_poll: *source,
+            poll: find_poll_address(Some(admin), new_poll_id).0,
+            admin_profile: find_admin_profile_address(admin).0,
+            admin_poll_entry: find_admin_poll_entry_address(admin, ctx.admin_poll_index).0,
+            treasury: ctx.fee_charged.then(|| find_treasury_address().0),
+            admin: *admin,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::ClonePoll {
+            new_poll_id,
+            new_start_ts,
+            new_end_ts,
+            new_record_salt,
+        },
+    )
+}
+
+/// `create_org_poll` with `poll_id` in `organization`'s namespace. `ctx`
+/// is fetched for the organization rather than the creator, and
+/// `as_member` passes the creator's organization role, which members other
+/// than the owner need.
+#[allow(clippy::too_many_arguments)]
+pub fn create_org_poll(
+    creator: &Pubkey,
+    organization: &Pubkey,
+    as_member: bool,
+    ctx: &CreationContext,
+    poll_id: u64,
+    title: String,
+    candidates: Vec<String>,
+    settings: PollSettings,
+) -> Instruction {
+    build(
+        accounts::CreateOrgPoll {
+            config: find_config_address().0,
+            organization: *organization,
+            role: as_member.then(|| find_poll_role_address(organization, creator).0),
+            poll: find_poll_address(Some(organization), poll_id).0,
+            admin_profile: find_admin_profile_address(organization).0,
+            admin_poll_entry: find_admin_poll_entry_address(organization, ctx.admin_poll_index).0,
+            treasury: ctx.fee_charged.then(|| find_treasury_address().0),
+            creator: *creator,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::CreateOrgPoll {
+            poll_id,
+            title,
+            candidates,
+            settings,
+        },
+    )
+}
+
+/// A direct `vote` with no session key, voter roll or gate accounts.
+pub fn vote(
+    voter: &Pubkey,
+    namespace: Option<&Pubkey>,
+    poll_id: u64,
+    candidate_index: u8,
+) -> Instruction {
+    build(
+        accounts::Vote {
+            poll: find_poll_address(namespace, poll_id).0,
+            config: find_config_address().0,
+            session_token: None,
+            vote_record: find_vote_record_address(namespace, poll_id, voter).0,
+            membership: None,
+            gateway_token: None,
+            badge_mint: None,
+            badge_token: None,
+            gate_token: None,
+            gate_mint: None,
+            snapshot_balance: None,
+            instructions: None,
+            conviction_lock: None,
+            reputation: None,
+            voter: *voter,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::Vote {
+            _poll_id: poll_id,
+            candidate_index,
+        },
+    )
+}
+
+/// `attest_snapshot_balance` recording `voter`'s `balance` at the poll's
+/// snapshot, signed by its snapshot oracle.
+pub fn attest_snapshot_balance(
+    oracle: &Pubkey,
+    namespace: Option<&Pubkey>,
+    poll_id: u64,
+    voter: &Pubkey,
+    balance: u64,
+) -> Instruction {
+    let poll = find_poll_address(namespace, poll_id).0;
+    build(
+        accounts::AttestSnapshotBalance {
+            poll,
+            snapshot_balance: find_snapshot_balance_address(&poll, voter).0,
+            oracle: *oracle,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::AttestSnapshotBalance {
+            _poll_id: poll_id,
+            voter: *voter,
+            balance,
+        },
+    )
+}
+
+/// `fund_rent_sponsor` adding `amount` lamports from `funder`.
+pub fn fund_rent_sponsor(
+    funder: &Pubkey,
+    namespace: Option<&Pubkey>,
+    poll_id: u64,
+    amount: u64,
+) -> Instruction {
+    let poll = find_poll_address(namespace, poll_id).0;
+    build(
+        accounts::FundRentSponsor {
+            poll,
+            rent_sponsor: find_rent_sponsor_address(&poll).0,
+            funder: *funder,
+            system_program: system_program::ID,
+        },
+        instruction::FundRentSponsor {
+            _poll_id: poll_id,
+            amount,
+        },
+    )
+}
+
+/// `sponsor_vote_record` for `voter`; place it right before the ballot in
+/// the same transaction.
+pub fn sponsor_vote_record(
+    voter: &Pubkey,
+    namespace: Option<&Pubkey>,
+    poll_id: u64,
+) -> Instruction {
+    let poll = find_poll_address(namespace, poll_id).0;
+    build(
+        accounts::SponsorVoteRecord {
+            poll,
+            rent_sponsor: find_rent_sponsor_address(&poll).0,
+            vote_record: find_vote_record_address(namespace, poll_id, voter).0,
+            voter: *voter,
+            instructions: ix_sysvar::ID,
+        },
+        instruction::SponsorVoteRecord { _poll_id: poll_id },
+    )
+}
+
+/// `enable_compressed_votes` for a tree and filter the admin has already
+/// allocated.
+#[allow(clippy::too_many_arguments)]
+pub fn enable_compressed_votes(
+    admin: &Pubkey,
+    namespace: Option<&Pubkey>,
+    poll_id: u64,
+    vote_tree: &Pubkey,
+    vote_filter: &Pubkey,
+    max_depth: u32,
+    max_buffer_size: u32,
+    filter_hashes: u8,
+) -> Instruction {
+    build(
+        accounts::EnableCompressedVotes {
+            poll: find_poll_address(namespace, poll_id).0,
+            vote_tree: *vote_tree,
+            vote_filter: *vote_filter,
+            admin: *admin,
+            compression_program: ACCOUNT_COMPRESSION_PROGRAM_ID,
+            noop_program: NOOP_PROGRAM_ID,
+        },
+        instruction::EnableCompressedVotes {
+            _poll_id: poll_id,
+            max_depth,
+            max_buffer_size,
+            filter_hashes,
+        },
+    )
+}
+
+/// `vote_compressed` on `poll`, as fetched, with no voter roll membership.
+pub fn vote_compressed(voter: &Pubkey, poll: &Poll, candidate_index: u8) -> Instruction {
+    let namespace = (poll.namespace != Pubkey::default()).then_some(&poll.namespace);
+    build(
+        accounts::VoteCompressed {
+            poll: find_poll_address(namespace, poll.poll_id).0,
+            config: find_config_address().0,
+            membership: None,
+            vote_tree: poll.vote_tree,
+            vote_filter: poll.vote_filter,
+            voter: *voter,
+            compression_program: ACCOUNT_COMPRESSION_PROGRAM_ID,
+            noop_program: NOOP_PROGRAM_ID,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::VoteCompressed {
+            _poll_id: poll.poll_id,
+            candidate_index,
+        },
+    )
+}
+
+/// `enable_private_ballots` signed by the poll's admin, who pays for the
+/// encrypted tally.
+pub fn enable_private_ballots(
+    admin: &Pubkey,
+    namespace: Option<&Pubkey>,
+    poll_id: u64,
+    elgamal_pubkey: [u8; 32],
+    tally_authority: &Pubkey,
+) -> Instruction {
+    let poll = find_poll_address(namespace, poll_id).0;
+    build(
+        accounts::EnablePrivateBallots {
+            poll,
+            encrypted_tally: find_encrypted_tally_address(&poll).0,
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        instruction::EnablePrivateBallots {
+            _poll_id: poll_id,
+            elgamal_pubkey,
+            tally_authority: *tally_authority,
+        },
+    )
+}
+
+/// `vote_private` on `poll`, as fetched, with no voter roll membership.
+/// `range_proof` and `validity_proofs` are the voter's verified proof
+/// context accounts for `ciphertexts`.
+pub fn vote_private(
+    voter: &Pubkey,
+    poll: &Poll,
+    ciphertexts: Vec<[u8; 64]>,
+    range_proof: &Pubkey,
+    validity_proofs: &[Pubkey],
+) -> Instruction {
+    let namespace = (poll.namespace != Pubkey::default()).then_some(&poll.namespace);
+    let poll_address = find_poll_address(namespace, poll.poll_id).0;
+    let mut ix = build(
+        accounts::VotePrivate {
+            poll: poll_address,
+            config: find_config_address().0,
+            encrypted_tally: poll.encrypted_tally,
+            nullifier: find_nullifier_address(&poll_address, voter).0,
+            membership: None,
+            range_proof: *range_proof,
+            voter: *voter,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::VotePrivate {
+            _poll_id: poll.poll_id,
+            ciphertexts,
+        },
+    );
+    ix.accounts.extend(
+        validity_proofs
+            .iter()
+            .map(|proof| AccountMeta::new_readonly(*proof, false)),
+    );
+    ix
+}
+
+/// `post_private_tally` on `poll`, as fetched, signed by its tally
+/// authority, with one verified zero-balance proof context per count.
+pub fn post_private_tally(
+    tally_authority: &Pubkey,
+    poll: &Poll,
+    counts: Vec<u64>,
+    zero_balance_proofs: &[Pubkey],
+) -> Instruction {
+    let namespace = (poll.namespace != Pubkey::default()).then_some(&poll.namespace);
+    let mut ix = build(
+        accounts::PostPrivateTally {
+            poll: find_poll_address(namespace, poll.poll_id).0,
+            encrypted_tally: poll.encrypted_tally,
+            tally_authority: *tally_authority,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::PostPrivateTally {
+            _poll_id: poll.poll_id,
+            counts,
+        },
+    );
+    ix.accounts.extend(
+        zero_balance_proofs
+            .iter()
+            .map(|proof| AccountMeta::new_readonly(*proof, false)),
+    );
+    ix
+}
+
+/// `vote_encrypted` with no voter roll membership. `ephemeral_key` and
+/// `masked_choice` encrypt the choice to the poll's `ballot_key`, as
+/// described on `vote_encrypted`.
+pub fn vote_encrypted(
+    voter: &Pubkey,
+    namespace: Option<&Pubkey>,
+    poll_id: u64,
+    ephemeral_key: [u8; 32],
+    masked_choice: u8,
+) -> Instruction {
+    let poll = find_poll_address(namespace, poll_id).0;
+    build(
+        accounts::VoteEncrypted {
+            poll,
+            config: find_config_address().0,
+            ballot: find_encrypted_ballot_address(&poll, voter).0,
+            membership: None,
+            voter: *voter,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::VoteEncrypted {
+            _poll_id: poll_id,
+            ephemeral_key,
+            masked_choice,
+        },
+    )
+}
+
+/// `reveal_ballot_key` signed by the poll's admin, who gets the reveal bond
+/// back.
+pub fn reveal_ballot_key(
+    admin: &Pubkey,
+    namespace: Option<&Pubkey>,
+    poll_id: u64,
+    secret: [u8; 32],
+) -> Instruction {
+    build(
+        accounts::RevealBallotKey {
+            poll: find_poll_address(namespace, poll_id).0,
+            admin: *admin,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::RevealBallotKey {
+            _poll_id: poll_id,
+            secret,
+        },
+    )
+}
+
+/// `tally_encrypted_votes` over the encrypted ballots of `voters`, which
+/// anyone may send.
+pub fn tally_encrypted_votes(
+    namespace: Option<&Pubkey>,
+    poll_id: u64,
+    voters: &[Pubkey],
+) -> Instruction {
+    let poll = find_poll_address(namespace, poll_id).0;
+    let mut ix = build(
+        accounts::TallyEncryptedVotes {
+            poll,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::TallyEncryptedVotes { _poll_id: poll_id },
+    );
+    ix.accounts.extend(voters.iter().flat_map(|voter| {
+        [
+            AccountMeta::new(find_encrypted_ballot_address(&poll, voter).0, false),
+            AccountMeta::new(*voter, false),
+        ]
+    }));
+    ix
+}
+
+/// `slash_reveal_bond`, which anyone may send once the reveal deadline
+/// passes without a reveal.
+pub fn slash_reveal_bond(caller: &Pubkey, namespace: Option<&Pubkey>, poll_id: u64) -> Instruction {
+    build(
+        accounts::SlashRevealBond {
+            poll: find_poll_address(namespace, poll_id).0,
+            treasury: find_treasury_address().0,
+            caller: *caller,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::SlashRevealBond { _poll_id: poll_id },
+    )
+}
+
+/// `open_audit_page` for the poll's current audit log `page`
+/// (`Poll::audit_page`), paid for by `payer`.
+pub fn open_audit_page(
+    payer: &Pubkey,
+    namespace: Option<&Pubkey>,
+    poll_id: u64,
+    page: u32,
+) -> Instruction {
+    let poll = find_poll_address(namespace, poll_id).0;
+    build(
+        accounts::OpenAuditPage {
+            poll,
+            audit_log: find_audit_log_address(&poll, page).0,
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        instruction::OpenAuditPage { _poll_id: poll_id },
+    )
+}
+
+/// `close_poll` signed by the poll's admin. Polls with an audit log need
+/// its current page as `audit_log`.
+pub fn close_poll(
+    admin: &Pubkey,
+    namespace: Option<&Pubkey>,
+    poll_id: u64,
+    audit_log: Option<&Pubkey>,
+) -> Instruction {
+    build(
+        accounts::ClosePoll {
+            poll: find_poll_address(namespace, poll_id).0,
+            config: find_config_address().0,
+            role: None,
+            audit_log: audit_log.copied(),
+            admin: *admin,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::ClosePoll { _poll_id: poll_id },
+    )
+}
+
+/// `freeze_poll` signed by the poll's admin, or `unfreeze_poll` when
+/// `frozen` is false. Polls with an audit log need its current page as
+/// `audit_log`.
+pub fn freeze_poll(
+    admin: &Pubkey,
+    namespace: Option<&Pubkey>,
+    poll_id: u64,
+    frozen: bool,
+    audit_log: Option<&Pubkey>,
+) -> Instruction {
+    let accounts = accounts::FreezePoll {
+        poll: find_poll_address(namespace, poll_id).0,
+        config: find_config_address().0,
+        role: None,
+        audit_log: audit_log.copied(),
+        admin: *admin,
+        event_authority: event_authority(),
+        program: PROGRAM_ID,
+    };
+    if frozen {
+        build(accounts, instruction::FreezePoll { _poll_id: poll_id })
+    } else {
+        build(accounts, instruction::UnfreezePoll { _poll_id: poll_id })
+    }
+}
+
+/// `crank_close_expired`, which anyone may send once the deadline passes.
+pub fn crank_close_expired(namespace: Option<&Pubkey>, poll_id: u64) -> Instruction {
+    build(
+        accounts::CrankCloseExpired {
+            poll: find_poll_address(namespace, poll_id).0,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::CrankCloseExpired { _poll_id: poll_id },
+    )
+}
+
+/// `finalize_poll` signed by the poll's admin.
+pub fn finalize_poll(admin: &Pubkey, namespace: Option<&Pubkey>, poll_id: u64) -> Instruction {
+    build(
+        accounts::FinalizePoll {
+            poll: find_poll_address(namespace, poll_id).0,
+            config: find_config_address().0,
+            role: None,
+            admin: *admin,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::FinalizePoll { _poll_id: poll_id },
+    )
+}
+
+/// `get_results`; simulate it and decode the return data as `PollResults`.
+pub fn get_results(namespace: Option<&Pubkey>, poll_id: u64) -> Instruction {
+    build(
+        accounts::GetResults {
+            poll: find_poll_address(namespace, poll_id).0,
+        },
+        instruction::GetResults { _poll_id: poll_id },
+    )
+}
+
+/// `challenge_result` by a voter in the poll, who puts up its challenge
+/// deposit.
+pub fn challenge_result(
+    challenger: &Pubkey,
+    namespace: Option<&Pubkey>,
+    poll_id: u64,
+    reason: String,
+) -> Instruction {
+    let poll = find_poll_address(namespace, poll_id).0;
+    build(
+        accounts::ChallengeResult {
+            poll,
+            vote_record: find_vote_record_address(namespace, poll_id, challenger).0,
+            challenge: find_challenge_address(&poll, challenger).0,
+            challenger: *challenger,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::ChallengeResult {
+            _poll_id: poll_id,
+            reason,
+        },
+    )
+}
+
+/// `resolve_challenge` of `challenger`'s challenge, signed by the poll's
+/// admin.
+pub fn resolve_challenge(
+    admin: &Pubkey,
+    namespace: Option<&Pubkey>,
+    poll_id: u64,
+    challenger: &Pubkey,
+    upheld: bool,
+) -> Instruction {
+    let poll = find_poll_address(namespace, poll_id).0;
+    build(
+        accounts::ResolveChallenge {
+            poll,
+            challenge: find_challenge_address(&poll, challenger).0,
+            challenger: *challenger,
+            treasury: find_treasury_address().0,
+            admin: *admin,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::ResolveChallenge {
+            _poll_id: poll_id,
+            upheld,
+        },
+    )
+}
+
+/// `certify_result` signed by the poll's admin or designated certifier.
+pub fn certify_result(certifier: &Pubkey, namespace: Option<&Pubkey>, poll_id: u64) -> Instruction {
+    build(
+        accounts::CertifyResult {
+            poll: find_poll_address(namespace, poll_id).0,
+            certifier: *certifier,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::CertifyResult { _poll_id: poll_id },
+    )
+}
+
+/// `archive_poll` signed by the poll's admin, who pays for the
+/// `PollResult` and gets the poll's rent back.
+pub fn archive_poll(admin: &Pubkey, namespace: Option<&Pubkey>, poll_id: u64) -> Instruction {
+    let poll = find_poll_address(namespace, poll_id).0;
+    build(
+        accounts::ArchivePoll {
+            poll,
+            result: find_poll_result_address(&poll).0,
+            prize_pool: find_prize_pool_address(&poll).0,
+            reward_vault: find_reward_vault_address(&poll).0,
+            rent_sponsor: find_rent_sponsor_address(&poll).0,
+            funding_round: find_funding_round_address(&poll).0,
+            admin: *admin,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::ArchivePoll { _poll_id: poll_id },
+    )
+}
+
+/// `close_archived_vote_record` for `voter`'s ballot in the archived `poll`.
+pub fn close_archived_vote_record(
+    voter: &Pubkey,
+    namespace: Option<&Pubkey>,
+    poll_id: u64,
+) -> Instruction {
+    let poll = find_poll_address(namespace, poll_id).0;
+    build(
+        accounts::CloseArchivedVoteRecord {
+            result: find_poll_result_address(&poll).0,
+            vote_record: find_vote_record_address(namespace, poll_id, voter).0,
+            voter: *voter,
+        },
+        instruction::CloseArchivedVoteRecord {},
+    )
+}
+
+/// `gc_expired_poll` closing the vote records of `voters`, which anyone may
+/// send once the poll's retention period has passed. With `archive`, the
+/// poll is archived too, with `cranker` paying for its `PollResult`.
+pub fn gc_expired_poll(
+    cranker: &Pubkey,
+    poll: &Poll,
+    voters: &[Pubkey],
+    archive: bool,
+) -> Instruction {
+    let namespace = (poll.namespace != Pubkey::default()).then_some(&poll.namespace);
+    let address = find_poll_address(namespace, poll.poll_id).0;
+    let mut ix = build(
+        accounts::GcExpiredPoll {
+            config: find_config_address().0,
+            poll: address,
+            admin: poll.admin,
+            result: archive.then(|| find_poll_result_address(&address).0),
+            prize_pool: find_prize_pool_address(&address).0,
+            reward_vault: find_reward_vault_address(&address).0,
+            rent_sponsor: find_rent_sponsor_address(&address).0,
+            funding_round: find_funding_round_address(&address).0,
+            cranker: *cranker,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: PROGRAM_ID,
+        },
+        instruction::GcExpiredPoll {
+            _poll_id: poll.poll_id,
+        },
+    );
+    ix.accounts.extend(voters.iter().flat_map(|voter| {
+        [
+            AccountMeta::new(
+                find_vote_record_address(namespace, poll.poll_id, voter).0,
+                false,
+            ),
+            AccountMeta::new(*voter, false),
+        ]
+    }));
+    ix
+}
+
+/// `migrate_poll`, paid for by `payer` if the poll must grow. Pass the
+/// poll's stored `namespace` (`Pubkey::default()` for global polls).
+pub fn migrate_poll(payer: &Pubkey, namespace: &Pubkey, poll_id: u64) -> Instruction {
+    build(
+        accounts::MigratePoll {
+            poll: find_poll_address(
+                (*namespace != Pubkey::default()).then_some(namespace),
+                poll_id,
+            )
+            .0,
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        instruction::MigratePoll {
+            _namespace: *namespace,
+            _poll_id: poll_id,
+        },
+    )
+}
@@ -0,0 +1,81 @@
+//! Runs a complete election against a deployed `solana_voting` program:
+//! create a poll, vote, close it and finalize it, printing the winner.
+//!
+//! Expects the poll counter and program config to be initialized (the
+//! integration tests do both). Usage:
+//!
+//!     cargo run -p election-bot -- [cluster-url] [keypair-path]
+//!
+//! Defaults to a local validator and `~/.config/solana/id.json`.
+
+use std::rc::Rc;
+
+use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+use anchor_client::solana_sdk::signature::{read_keypair_file, Signer};
+use anchor_client::{Client, Cluster};
+use anyhow::{anyhow, Result};
+use solana_voting_client::{self as voting, CreationContext, PollSettings};
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let cluster: Cluster = args
+        .next()
+        .unwrap_or_else(|| "localnet".to_string())
+        .parse()
+        .map_err(|e| anyhow!("invalid cluster: {e}"))?;
+    let keypair_path = args.next().unwrap_or_else(|| {
+        format!("{}/.config/solana/id.json", std::env::var("HOME").unwrap_or_default())
+    });
+    let payer = Rc::new(
+        read_keypair_file(&keypair_path).map_err(|e| anyhow!("reading {keypair_path}: {e}"))?,
+    );
+    let admin = payer.pubkey();
+
+    let client = Client::new_with_options(cluster, payer.clone(), CommitmentConfig::confirmed());
+    let program = client.program(voting::PROGRAM_ID)?;
+    let rpc = program.rpc();
+
+    // 1. Create a poll under the counter's next ID
+    let creation = CreationContext::fetch(&rpc, &admin)?;
+    let poll_id = creation.next_poll_id;
+    let signature = program
+        .request()
+        .instruction(voting::create_poll(
+            &admin,
+            &creation,
+            "Lunch".to_string(),
+            vec!["Pizza".to_string(), "Sushi".to_string()],
+            PollSettings::default(),
+        ))
+        .send()?;
+    let poll = voting::find_poll_address(None, poll_id).0;
+    println!("Created poll {poll_id} at {poll}: {signature}");
+
+    // 2. Vote
+    let signature = program
+        .request()
+        .instruction(voting::vote(&admin, None, poll_id, 1))
+        .send()?;
+    println!("Voted: {signature}");
+
+    // 3. Close
+    let signature = program
+        .request()
+        .instruction(voting::close_poll(&admin, None, poll_id, None))
+        .send()?;
+    println!("Closed: {signature}");
+
+    // 4. Finalize
+    let signature = program
+        .request()
+        .instruction(voting::finalize_poll(&admin, None, poll_id))
+        .send()?;
+    println!("Finalized: {signature}");
+
+    let poll = voting::fetch_poll(&rpc, None, poll_id)?;
+    match poll.winner() {
+        Some(index) => println!("Winner: {}", poll.candidates[index as usize].name),
+        None => println!("No single winner"),
+    }
+    Ok(())
+}
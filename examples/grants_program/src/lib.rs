@@ -0,0 +1,173 @@
+//! Example of embedding `solana_voting` through its `cpi` feature.
+//!
+//! Every grant application gets its own poll, created by CPI into
+//! `create_governed_poll` under this program's committee PDA, with the
+//! applicant paying the rent. Voting runs for `VOTING_PERIOD` seconds; once
+//! the poll is closed (e.g. by `crank_close_expired`), anyone can call
+//! `finalize_application`, which finalizes it with the committee's signature.
+
+use anchor_lang::prelude::*;
+use solana_voting::cpi::accounts::{CreateGovernedPoll, FinalizePoll};
+use solana_voting::program::SolanaVoting;
+use solana_voting::{pda, Poll, PollSettings};
+
+declare_id!("GFokV3RBdCW4T7qwPTj7pvQzudwQiCQtYXsqLJKvKy6n");
+
+/// How long each application's poll accepts votes, in seconds.
+pub const VOTING_PERIOD: i64 = 7 * 24 * 60 * 60;
+
+#[program]
+pub mod grants_program {
+    use super::*;
+
+    /// Records an application and opens a Fund / Reject poll on it, with
+    /// `application_id` as the poll's ID in the committee's namespace.
+    pub fn submit_application(
+        ctx: Context<SubmitApplication>,
+        application_id: u64,
+        title: String,
+    ) -> Result<()> {
+        let application = &mut ctx.accounts.application;
+        application.applicant = ctx.accounts.applicant.key();
+        application.poll = ctx.accounts.poll.key();
+        application.bump = ctx.bumps.application;
+
+        let settings = PollSettings {
+            end_ts: Clock::get()?.unix_timestamp + VOTING_PERIOD,
+            ..Default::default()
+        };
+        let accounts = &ctx.accounts;
+        solana_voting::cpi::create_governed_poll(
+            CpiContext::new_with_signer(
+                accounts.voting_program.to_account_info(),
+                CreateGovernedPoll {
+                    config: accounts.config.to_account_info(),
+                    poll: accounts.poll.to_account_info(),
+                    admin_profile: accounts.admin_profile.to_account_info(),
+                    admin_poll_entry: accounts.admin_poll_entry.to_account_info(),
+                    treasury: accounts.treasury.as_ref().map(|t| t.to_account_info()),
+                    admin: accounts.committee.to_account_info(),
+                    payer: accounts.applicant.to_account_info(),
+                    system_program: accounts.system_program.to_account_info(),
+                    event_authority: accounts.event_authority.to_account_info(),
+                    program: accounts.voting_program.to_account_info(),
+                },
+                &[&[b"committee", &[ctx.bumps.committee]]],
+            ),
+            application_id,
+            title,
+            vec!["Fund".to_string(), "Reject".to_string()],
+            settings,
+        )?;
+
+        msg!("Application {} submitted", application_id);
+        Ok(())
+    }
+
+    /// Finalizes a closed application poll on the committee's behalf.
+    pub fn finalize_application(ctx: Context<FinalizeApplication>, application_id: u64) -> Result<()> {
+        let accounts = &ctx.accounts;
+        solana_voting::cpi::finalize_poll(
+            CpiContext::new_with_signer(
+                accounts.voting_program.to_account_info(),
+                FinalizePoll {
+                    poll: accounts.poll.to_account_info(),
+                    config: accounts.config.to_account_info(),
+                    role: None,
+                    admin: accounts.committee.to_account_info(),
+                    event_authority: accounts.event_authority.to_account_info(),
+                    program: accounts.voting_program.to_account_info(),
+                },
+                &[&[b"committee", &[ctx.bumps.committee]]],
+            ),
+            application_id,
+        )
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(application_id: u64)]
+pub struct SubmitApplication<'info> {
+    #[account(
+        init,
+        payer = applicant,
+        space = Application::SPACE,
+        seeds = [b"application", application_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub application: Account<'info, Application>,
+
+    /// CHECK: Signs as the poll admin; holds no data or lamports.
+    #[account(seeds = [b"committee"], bump)]
+    pub committee: UncheckedAccount<'info>,
+
+    /// CHECK: Created by the voting program.
+    #[account(
+        mut,
+        address = pda::find_poll_address(Some(&committee.key()), application_id).0,
+    )]
+    pub poll: UncheckedAccount<'info>,
+
+    /// CHECK: Validated and updated by the voting program.
+    #[account(mut)]
+    pub config: UncheckedAccount<'info>,
+
+    /// CHECK: Created or updated by the voting program.
+    #[account(mut)]
+    pub admin_profile: UncheckedAccount<'info>,
+
+    /// CHECK: Created by the voting program.
+    #[account(mut)]
+    pub admin_poll_entry: UncheckedAccount<'info>,
+
+    /// CHECK: The voting program's treasury, while it charges a creation fee.
+    #[account(mut)]
+    pub treasury: Option<UncheckedAccount<'info>>,
+
+    #[account(mut)]
+    pub applicant: Signer<'info>,
+
+    /// CHECK: Validated by the voting program.
+    pub event_authority: UncheckedAccount<'info>,
+
+    pub voting_program: Program<'info, SolanaVoting>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(application_id: u64)]
+pub struct FinalizeApplication<'info> {
+    #[account(
+        seeds = [b"application", application_id.to_le_bytes().as_ref()],
+        bump = application.bump,
+        has_one = poll,
+    )]
+    pub application: Account<'info, Application>,
+
+    /// CHECK: Signs as the poll admin; holds no data or lamports.
+    #[account(seeds = [b"committee"], bump)]
+    pub committee: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub poll: Account<'info, Poll>,
+
+    /// CHECK: Validated by the voting program.
+    pub config: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the voting program.
+    pub event_authority: UncheckedAccount<'info>,
+
+    pub voting_program: Program<'info, SolanaVoting>,
+}
+
+#[account]
+pub struct Application {
+    pub applicant: Pubkey, // 32
+    pub poll: Pubkey,      // 32
+    pub bump: u8,          // 1
+}
+
+impl Application {
+    pub const SPACE: usize = 8 + 32 + 32 + 1;
+}
@@ -0,0 +1,147 @@
+//! Example consumer of `solana_voting::assert_winner`.
+//!
+//! A treasury PDA is attached to a poll with one payout recipient per
+//! candidate. Anyone can fund it; once the poll is finalized, `release` pays
+//! the whole balance to the winning candidate's recipient. The voting program
+//! verifies the result and records a `ConsumptionReceipt` for this program.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions as ix_sysvar;
+use solana_voting::cpi::accounts::AssertWinner;
+use solana_voting::program::SolanaVoting;
+use solana_voting::Poll;
+
+declare_id!("5TFcM9psDpxXcJiFvmkRcqFdT21DpSN31SDaNavhthwu");
+
+#[program]
+pub mod treasury_gate {
+    use super::*;
+
+    /// Attaches a treasury to `poll`. `recipients[i]` is paid if candidate
+    /// `i` wins.
+    pub fn create_treasury(ctx: Context<CreateTreasury>, recipients: Vec<Pubkey>) -> Result<()> {
+        require!(
+            recipients.len() == ctx.accounts.poll.candidates.len(),
+            TreasuryError::RecipientCountMismatch
+        );
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.poll = ctx.accounts.poll.key();
+        treasury.recipients = recipients;
+        treasury.released = false;
+        treasury.bump = ctx.bumps.treasury;
+        Ok(())
+    }
+
+    /// Pays the treasury's balance above rent to the winner's recipient,
+    /// after the voting program confirms `winner` won the finalized poll.
+    pub fn release(ctx: Context<Release>, winner: u8) -> Result<()> {
+        require!(!ctx.accounts.treasury.released, TreasuryError::AlreadyReleased);
+        require!(
+            ctx.accounts.treasury.recipients.get(winner as usize)
+                == Some(&ctx.accounts.recipient.key()),
+            TreasuryError::WrongRecipient
+        );
+
+        solana_voting::cpi::assert_winner(
+            CpiContext::new(
+                ctx.accounts.voting_program.to_account_info(),
+                AssertWinner {
+                    poll: ctx.accounts.poll.to_account_info(),
+                    receipt: ctx.accounts.receipt.to_account_info(),
+                    consumer_program: ctx.accounts.this_program.to_account_info(),
+                    payer: ctx.accounts.payer.to_account_info(),
+                    instructions: ctx.accounts.instructions.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                },
+            ),
+            winner,
+        )?;
+
+        let treasury = ctx.accounts.treasury.to_account_info();
+        let rent = Rent::get()?.minimum_balance(treasury.data_len());
+        let amount = treasury.lamports().saturating_sub(rent);
+        **treasury.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.recipient.try_borrow_mut_lamports()? += amount;
+        ctx.accounts.treasury.released = true;
+
+        msg!("Released {} lamports to {}", amount, ctx.accounts.recipient.key());
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(recipients: Vec<Pubkey>)]
+pub struct CreateTreasury<'info> {
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = Treasury::space(recipients.len()),
+        seeds = [b"treasury", poll.key().as_ref()],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Release<'info> {
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", poll.key().as_ref()],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    /// CHECK: Must match the winner's recipient, checked in the handler.
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    /// CHECK: Created and validated by the voting program.
+    #[account(mut)]
+    pub receipt: UncheckedAccount<'info>,
+
+    pub voting_program: Program<'info, SolanaVoting>,
+
+    pub this_program: Program<'info, crate::program::TreasuryGate>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Address is pinned to the instructions sysvar.
+    #[account(address = ix_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+pub struct Treasury {
+    pub poll: Pubkey,            // 32
+    pub recipients: Vec<Pubkey>, // 4 + (len * 32)
+    pub released: bool,          // 1
+    pub bump: u8,                // 1
+}
+
+impl Treasury {
+    pub fn space(recipient_count: usize) -> usize {
+        8 + 32 + 4 + recipient_count * 32 + 1 + 1
+    }
+}
+
+#[error_code]
+pub enum TreasuryError {
+    #[msg("Provide exactly one recipient per candidate")]
+    RecipientCountMismatch,
+    #[msg("The treasury has already been released")]
+    AlreadyReleased,
+    #[msg("Recipient does not belong to the winning candidate")]
+    WrongRecipient,
+}
@@ -0,0 +1,286 @@
+//! Forwards `solana_voting` lifecycle events to HTTP webhooks, so web2
+//! systems (Slack, email, a CMS) can react to polls without an indexer.
+//!
+//! The bridge polls the program's confirmed transactions, decodes the events
+//! emitted through the self-CPI event authority and POSTs one JSON payload
+//! per lifecycle event to every configured URL:
+//!
+//! - `poll.created` from `PollCreated`
+//! - `poll.quorum_reached` when a `VoteCast` brings `total_votes` up to the
+//!   poll's absolute `quorum` (needs `Standard` event verbosity)
+//! - `poll.finalized` from `PollFinalized`
+//!
+//! Each request carries `X-Voting-Signature: sha256=<hex>`, the HMAC-SHA256
+//! of the raw body under `WEBHOOK_SECRET`, plus `X-Voting-Event` and a
+//! `X-Voting-Delivery` ID that stays the same across retries so receivers can
+//! deduplicate. Failed deliveries are retried with exponential backoff.
+//! Usage:
+//!
+//!     WEBHOOK_URLS=https://example.com/hook WEBHOOK_SECRET=... \
+//!         cargo run -p webhook-bridge -- [cluster-url]
+//!
+//! Only transactions after startup are forwarded. `POLL_INTERVAL_SECS`
+//! (default 5) and `MAX_ATTEMPTS` (default 5) tune the loop.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::Duration;
+
+use anchor_client::anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_client::anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator};
+use anchor_client::solana_client::rpc_client::{
+    GetConfirmedSignaturesForAddress2Config, RpcClient,
+};
+use anchor_client::solana_client::rpc_config::RpcTransactionConfig;
+use anchor_client::solana_sdk::bs58;
+use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::Signature;
+use anchor_client::Cluster;
+use anyhow::{anyhow, Context, Result};
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use solana_transaction_status::{UiInstruction, UiLoadedAddresses, UiTransactionEncoding};
+use solana_voting::{Poll, PollCreated, PollFinalized, VoteCast};
+
+struct Webhooks {
+    http: reqwest::blocking::Client,
+    urls: Vec<String>,
+    secret: Vec<u8>,
+    max_attempts: u32,
+}
+
+fn main() -> Result<()> {
+    let cluster: Cluster = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "localnet".to_string())
+        .parse()
+        .map_err(|e| anyhow!("invalid cluster: {e}"))?;
+    let urls: Vec<String> = env("WEBHOOK_URLS")?
+        .split(',')
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+        .collect();
+    let webhooks = Webhooks {
+        http: reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?,
+        urls,
+        secret: env("WEBHOOK_SECRET")?.into_bytes(),
+        max_attempts: env_or("MAX_ATTEMPTS", 5)?,
+    };
+    let interval = Duration::from_secs(env_or("POLL_INTERVAL_SECS", 5)?);
+
+    let rpc =
+        RpcClient::new_with_commitment(cluster.url().to_string(), CommitmentConfig::confirmed());
+    let mut quorums: HashMap<Pubkey, u64> = HashMap::new();
+    let mut cursor = rpc
+        .get_signatures_for_address_with_config(
+            &solana_voting::ID,
+            GetConfirmedSignaturesForAddress2Config {
+                limit: Some(1),
+                ..Default::default()
+            },
+        )?
+        .first()
+        .map(|status| Signature::from_str(&status.signature))
+        .transpose()?;
+    println!("Forwarding events to {} webhook(s)", webhooks.urls.len());
+
+    loop {
+        // Newest first; a gap longer than one page (1000 transactions) between
+        // iterations is not backfilled.
+        let statuses = rpc.get_signatures_for_address_with_config(
+            &solana_voting::ID,
+            GetConfirmedSignaturesForAddress2Config {
+                until: cursor,
+                commitment: Some(CommitmentConfig::confirmed()),
+                ..Default::default()
+            },
+        )?;
+        for status in statuses.iter().rev().filter(|status| status.err.is_none()) {
+            let signature = Signature::from_str(&status.signature)?;
+            for (index, (event, payload)) in events(&rpc, &signature, &mut quorums)?
+                .into_iter()
+                .enumerate()
+            {
+                let delivery = format!("{signature}:{index}");
+                let body = json!({
+                    "event": event,
+                    "signature": status.signature,
+                    "slot": status.slot,
+                    "data": payload,
+                })
+                .to_string();
+                webhooks.deliver(event, &delivery, &body);
+            }
+        }
+        if let Some(newest) = statuses.first() {
+            cursor = Some(Signature::from_str(&newest.signature)?);
+        }
+        sleep(interval);
+    }
+}
+
+/// Decodes this program's events from `signature`'s inner instructions and
+/// maps them to webhook `(event, payload)` pairs.
+fn events(
+    rpc: &RpcClient,
+    signature: &Signature,
+    quorums: &mut HashMap<Pubkey, u64>,
+) -> Result<Vec<(&'static str, Value)>> {
+    let tx = rpc.get_transaction_with_config(
+        signature,
+        RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        },
+    )?;
+    let meta = tx
+        .transaction
+        .meta
+        .ok_or_else(|| anyhow!("{signature} has no status meta"))?;
+    let message = tx
+        .transaction
+        .transaction
+        .decode()
+        .ok_or_else(|| anyhow!("{signature} could not be decoded"))?
+        .message;
+
+    // Inner instructions index into the static keys followed by any keys
+    // loaded from lookup tables.
+    let mut keys = message.static_account_keys().to_vec();
+    let loaded: Option<UiLoadedAddresses> = meta.loaded_addresses.into();
+    if let Some(loaded) = loaded {
+        for key in loaded.writable.iter().chain(&loaded.readonly) {
+            keys.push(Pubkey::from_str(key)?);
+        }
+    }
+
+    let mut out = Vec::new();
+    let inner: Option<Vec<_>> = meta.inner_instructions.into();
+    for ix in inner.into_iter().flatten().flat_map(|set| set.instructions) {
+        let UiInstruction::Compiled(ix) = ix else {
+            continue;
+        };
+        if keys.get(ix.program_id_index as usize) != Some(&solana_voting::ID) {
+            continue;
+        }
+        let data = bs58::decode(&ix.data).into_vec()?;
+        let Some(event) = data.strip_prefix(&EVENT_IX_TAG_LE[..]) else {
+            continue;
+        };
+        let (discriminator, mut body) = event.split_at(8.min(event.len()));
+
+        if discriminator == PollCreated::DISCRIMINATOR {
+            let e = PollCreated::deserialize(&mut body)?;
+            out.push((
+                "poll.created",
+                json!({
+                    "poll": e.poll.to_string(),
+                    "pollId": e.poll_id,
+                    "admin": e.admin.to_string(),
+                    "title": e.title,
+                    "candidateCount": e.candidate_count,
+                    "startTs": e.start_ts,
+                    "endTs": e.end_ts,
+                    "timestamp": e.timestamp,
+                }),
+            ));
+        } else if discriminator == VoteCast::DISCRIMINATOR {
+            let e = VoteCast::deserialize(&mut body)?;
+            let quorum = match quorums.get(&e.poll) {
+                Some(quorum) => *quorum,
+                None => {
+                    let data = rpc.get_account_data(&e.poll).context("fetching poll")?;
+                    let quorum = Poll::try_deserialize(&mut data.as_slice())?.quorum;
+                    quorums.insert(e.poll, quorum);
+                    quorum
+                }
+            };
+            // Exactly-equal fires once: later votes only push the total past it.
+            if quorum > 0 && e.total_votes == quorum {
+                out.push((
+                    "poll.quorum_reached",
+                    json!({
+                        "poll": e.poll.to_string(),
+                        "pollId": e.poll_id,
+                        "quorum": quorum,
+                        "totalVotes": e.total_votes,
+                        "timestamp": e.timestamp,
+                    }),
+                ));
+            }
+        } else if discriminator == PollFinalized::DISCRIMINATOR {
+            let e = PollFinalized::deserialize(&mut body)?;
+            quorums.remove(&e.poll);
+            out.push((
+                "poll.finalized",
+                json!({
+                    "poll": e.poll.to_string(),
+                    "pollId": e.poll_id,
+                    "winner": e.winner,
+                    "quorumMet": e.quorum_met,
+                    "tallies": e.tallies,
+                    "totalVotes": e.total_votes,
+                    "totalWeight": e.total_weight,
+                    "timestamp": e.timestamp,
+                }),
+            ));
+        }
+    }
+    Ok(out)
+}
+
+impl Webhooks {
+    /// POSTs `body` to every URL, retrying each with exponential backoff
+    /// (1s, 2s, 4s, ...) until it answers 2xx or `max_attempts` run out.
+    fn deliver(&self, event: &str, delivery: &str, body: &str) {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts any key length");
+        mac.update(body.as_bytes());
+        let signature = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+
+        for url in &self.urls {
+            for attempt in 1..=self.max_attempts {
+                let result = self
+                    .http
+                    .post(url)
+                    .header("Content-Type", "application/json")
+                    .header("X-Voting-Event", event)
+                    .header("X-Voting-Delivery", delivery)
+                    .header("X-Voting-Signature", &signature)
+                    .body(body.to_string())
+                    .send()
+                    .and_then(|response| response.error_for_status());
+                match result {
+                    Ok(_) => {
+                        println!("Delivered {event} ({delivery}) to {url}");
+                        break;
+                    }
+                    Err(e) if attempt < self.max_attempts => {
+                        eprintln!("{url}: attempt {attempt} failed: {e}");
+                        sleep(Duration::from_secs(1 << (attempt - 1).min(6)));
+                    }
+                    Err(e) => eprintln!("{url}: giving up on {event} ({delivery}): {e}"),
+                }
+            }
+        }
+    }
+}
+
+fn env(name: &str) -> Result<String> {
+    std::env::var(name).map_err(|_| anyhow!("{name} must be set"))
+}
+
+fn env_or<T: FromStr>(name: &str, default: T) -> Result<T> {
+    match std::env::var(name) {
+        Ok(value) => value
+            .parse()
+            .map_err(|_| anyhow!("invalid {name}: {value}")),
+        Err(_) => Ok(default),
+    }
+}
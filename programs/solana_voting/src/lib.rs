@@ -1,192 +1,13756 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::clock::DEFAULT_MS_PER_SLOT;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::stake::{self, state::StakeStateV2};
+use anchor_lang::solana_program::sysvar::instructions as ix_sysvar;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::interest_bearing_mint::InterestBearingConfig;
+use anchor_spl::token_2022::spl_token_2022::extension::non_transferable::NonTransferable;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    BaseStateWithExtensions, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::solana_zk_token_sdk::curve25519::ristretto::{
+    multiply_ristretto, validate_ristretto, PodRistrettoPoint,
+};
+use anchor_spl::token_2022::spl_token_2022::solana_zk_token_sdk::curve25519::scalar::PodScalar;
+use anchor_spl::token_2022::spl_token_2022::solana_zk_token_sdk::instruction::{
+    BatchedGroupedCiphertext2HandlesValidityProofContext, BatchedRangeProofContext, Pod,
+    ProofType, ZeroBalanceProofContext,
+};
+use anchor_spl::token_2022::spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::{
+    ops, pod::ElGamalCiphertext,
+};
+use anchor_spl::token_2022::spl_token_2022::solana_zk_token_sdk::zk_token_proof_program;
+use anchor_spl::token_2022::spl_token_2022::solana_zk_token_sdk::zk_token_proof_state::ProofContextState;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
 
 declare_id!("65sD6MWQPZieeMfBrcbe2mgHpRkxosobzKgTCmnbqQqi");
 
+/// Weight of one full ballot. Weights are kept in basis points of a ballot so
+/// delegated fractions of a vote can be tallied exactly.
+pub const BALLOT_WEIGHT: u64 = 10_000;
+/// Denominator for percentages expressed in basis points.
+pub const BPS_DENOMINATOR: u16 = 10_000;
+/// Maximum number of delegates a single delegator can split weight across.
+pub const MAX_DELEGATE_SHARES: usize = 5;
+/// `VoteRecord::candidate_index` of a ballot cast piecewise by delegates.
+pub const SPLIT_BALLOT: u8 = u8::MAX;
+/// Number of vote changes a `VoteRecord` keeps in its audit history, and
+/// therefore the most times a voter can change their vote.
+pub const MAX_VOTE_CHANGES: usize = 3;
+
+/// `PollRole::permissions` bits granted to co-admins.
+pub const PERMISSION_CLOSE: u8 = 1 << 0;
+pub const PERMISSION_FINALIZE: u8 = 1 << 1;
+pub const PERMISSION_MANAGE_CANDIDATES: u8 = 1 << 2;
+pub const PERMISSION_MANAGE_WHITELIST: u8 = 1 << 3;
+pub const PERMISSION_ALL: u8 = PERMISSION_CLOSE
+    | PERMISSION_FINALIZE
+    | PERMISSION_MANAGE_CANDIDATES
+    | PERMISSION_MANAGE_WHITELIST;
+/// Organization-wide roles may also hold this, to create polls under the
+/// organization with `create_org_poll`.
+pub const PERMISSION_CREATE_POLLS: u8 = 1 << 4;
+
+/// Maximum number of approvers in a poll's M-of-N multisig.
+pub const MAX_APPROVERS: usize = 5;
+
+/// Actions governed by `ProgramConfig::permissions`.
+pub const ACTION_CREATE: u8 = 1 << 0;
+pub const ACTION_CLOSE: u8 = 1 << 1;
+pub const ACTION_FINALIZE: u8 = 1 << 2;
+pub const ACTION_MODERATE: u8 = 1 << 3;
+pub const ACTION_WITHDRAW: u8 = 1 << 4;
+pub const ACTION_MANAGE_WHITELIST: u8 = 1 << 5;
+pub const ACTION_MANAGE_CANDIDATES: u8 = 1 << 6;
+pub const ACTION_ALL: u8 = ACTION_CREATE
+    | ACTION_CLOSE
+    | ACTION_FINALIZE
+    | ACTION_MODERATE
+    | ACTION_WITHDRAW
+    | ACTION_MANAGE_WHITELIST
+    | ACTION_MANAGE_CANDIDATES;
+/// Longest user-generated text stored inline on-chain.
+pub const MAX_INLINE_CONTENT_LEN: usize = 280;
+/// Longest URI a `Content::Hashed` reference can point to.
+pub const MAX_CONTENT_URI_LEN: usize = 200;
+
+/// Longest poll description.
+pub const MAX_DESCRIPTION_LEN: usize = 500;
+/// Longest reason recorded when a poll is cancelled.
+pub const MAX_CANCEL_REASON_LEN: usize = 200;
+/// Longest reason given when a candidate is disqualified.
+pub const MAX_DISQUALIFY_REASON_LEN: usize = 200;
+/// Max length in bytes of the reason given for challenging a result.
+pub const MAX_CHALLENGE_REASON_LEN: usize = 200;
+/// Longest memo a voter can attach to their ballot.
+pub const MAX_VOTE_MEMO_LEN: usize = 140;
+/// Most tags a poll can carry.
+pub const MAX_POLL_TAGS: usize = 5;
+/// Longest poll tag.
+pub const MAX_TAG_LEN: usize = 16;
+/// Conviction lock tiers: tier `t` locks tokens for `conviction_period << t`
+/// seconds and weighs them `1 << t` times (1x, 2x, 4x).
+pub const CONVICTION_TIERS: u8 = 3;
+
+/// Entries per `AuditLog` page.
+pub const AUDIT_LOG_PAGE_ENTRIES: usize = 32;
+/// Most ballots `vote_batch` casts in one instruction.
+pub const MAX_BATCH_VOTES: usize = 8;
+/// Longest a session key stays valid, in seconds (one day).
+pub const MAX_SESSION_DURATION: i64 = 24 * 60 * 60;
+/// How long, in seconds, winners and voters have to claim a finalized
+/// poll's prize and rewards once its challenge window closes (90 days);
+/// after that the admin may reclaim what's left.
+pub const PAYOUT_CLAIM_WINDOW: i64 = 90 * 24 * 60 * 60;
+/// Most stake accounts one `vote` on a stake-weighted poll can count.
+pub const MAX_STAKE_ACCOUNTS: usize = 8;
+/// Most NFTs one `vote` on an NFT-weighted poll can count.
+pub const MAX_NFTS_PER_VOTE: usize = 8;
+/// Most bits a compressed poll's vote filter sets per voter.
+pub const MAX_FILTER_HASHES: u8 = 16;
+/// Most candidates a private-ballot poll can have: a ballot's range proof
+/// covers one commitment per candidate plus two for its sum, out of the
+/// eight a batched range proof takes.
+pub const MAX_PRIVATE_CANDIDATES: usize = 6;
+/// Compressed Ristretto basepoint `G`, which a poll's `ballot_key` and
+/// ballots' ephemeral keys are multiples of.
+pub const RISTRETTO_BASEPOINT: [u8; 32] = [
+    226, 242, 174, 10, 106, 188, 78, 113, 168, 132, 169, 97, 197, 0, 81, 95, 88, 227, 11, 106,
+    165, 130, 221, 141, 182, 166, 89, 69, 224, 141, 45, 118,
+];
+
+/// Most write-in candidates a poll with `allow_write_ins` can gain. Room for
+/// them is reserved when the poll is created.
+pub const MAX_WRITE_INS: usize = 8;
+
+/// Most voter tiers a poll can weigh and tally separately.
+pub const MAX_VOTER_TIERS: usize = 8;
+
+/// Most counters a parallel-tally poll can split each candidate's tally
+/// across. All of them must fit in one `aggregate_tallies`, so a poll also
+/// can't hold more counters than `HARD_MAX_CANDIDATES`.
+pub const MAX_TALLY_SHARDS: u8 = 8;
+
+/// Tally slots in a `PollResult`: every listed candidate and write-in a poll
+/// can hold. A literal, since the IDL parser can't size arrays by an
+/// expression.
+pub const MAX_ARCHIVED_CANDIDATES: usize = 40;
+const _: () = assert!(MAX_ARCHIVED_CANDIDATES == HARD_MAX_CANDIDATES as usize + MAX_WRITE_INS);
+
+/// Upper bounds on `ConfigLimits`, keeping polls within account and
+/// transaction size limits whatever the config says.
+pub const HARD_MAX_CANDIDATES: u8 = 32;
+pub const HARD_MAX_TITLE_LEN: u16 = 200;
+pub const HARD_MAX_CANDIDATE_NAME_LEN: u16 = 100;
+
+/// Largest account a program can create, or grow, in one instruction.
+pub const MAX_ACCOUNT_ALLOCATION: usize = MAX_PERMITTED_DATA_INCREASE;
+
+// Raising the hard caps must not allow polls the runtime can't allocate
+const _: () = assert!(
+    Poll::space(HARD_MAX_CANDIDATES as usize + MAX_WRITE_INS, &ConfigLimits::HARD_MAX)
+        + PollSettings::max_tier_space(HARD_MAX_CANDIDATES as usize)
+        <= MAX_ACCOUNT_ALLOCATION
+);
+/// Number of `ConfigRole` variants, i.e. rows in the permission matrix.
+pub const CONFIG_ROLE_COUNT: usize = 4;
+
+/// Version reported by `get_program_capabilities`.
+pub const PROGRAM_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Layout versions stamped on new accounts. Accounts from before versioning
+/// read as version 0; `migrate_poll` brings polls up to date. Bump these
+/// whenever fields are appended.
+///
+/// - Poll v2 appends everything from `vote_tree` to `canonical_result_hash`.
+/// - VoteRecord v2 appends `reputation_counted` to `tier`, all carved out of
+///   v1's padding, so v1 records decode unchanged with them zeroed.
+pub const POLL_VERSION: u8 = 2;
+pub const VOTE_RECORD_VERSION: u8 = 2;
+
+/// `ProgramCapabilities::features` bits, one per optional feature a
+/// deployment supports.
+pub const FEATURE_NAMESPACED_POLLS: u64 = 1 << 0;
+pub const FEATURE_SETTINGS_HASH_POLLS: u64 = 1 << 1;
+pub const FEATURE_PARALLEL_TALLIES: u64 = 1 << 2;
+pub const FEATURE_DELEGATION: u64 = 1 << 3;
+pub const FEATURE_VOTE_CHANGES: u64 = 1 << 4;
+pub const FEATURE_VOTER_ROLL: u64 = 1 << 5;
+pub const FEATURE_MULTISIG: u64 = 1 << 6;
+pub const FEATURE_CO_ADMIN_ROLES: u64 = 1 << 7;
+pub const FEATURE_DRAFTS: u64 = 1 << 8;
+pub const FEATURE_REOPEN: u64 = 1 << 9;
+pub const FEATURE_DEADLINE_EXTENSION: u64 = 1 << 10;
+pub const FEATURE_RECOUNT: u64 = 1 << 11;
+pub const FEATURE_ATTENDANCE: u64 = 1 << 12;
+pub const FEATURE_AUTO_FINALIZE: u64 = 1 << 13;
+pub const FEATURE_QUORUM: u64 = 1 << 14;
+pub const FEATURE_RANDOM_TIE_BREAK: u64 = 1 << 15;
+pub const FEATURE_RUNOFFS: u64 = 1 << 16;
+pub const FEATURE_RESULT_EMBARGO: u64 = 1 << 17;
+pub const FEATURE_ASSERT_WINNER: u64 = 1 << 18;
+pub const FEATURE_WRITE_INS: u64 = 1 << 19;
+pub const FEATURE_DISQUALIFICATION: u64 = 1 << 20;
+pub const FEATURE_VOTE_MEMOS: u64 = 1 << 21;
+pub const FEATURE_BATCH_VOTING: u64 = 1 << 22;
+pub const FEATURE_SIGNED_VOTES: u64 = 1 << 23;
+pub const FEATURE_SESSION_KEYS: u64 = 1 << 24;
+pub const FEATURE_POLL_PROXIES: u64 = 1 << 25;
+pub const FEATURE_GATEWAY_GATING: u64 = 1 << 26;
+pub const FEATURE_BADGE_GATING: u64 = 1 << 27;
+pub const FEATURE_TOKEN_BALANCE_GATING: u64 = 1 << 28;
+pub const FEATURE_VOTE_DEPOSITS: u64 = 1 << 29;
+pub const FEATURE_CREATION_FEES: u64 = 1 << 30;
+pub const FEATURE_PRIZE_POOLS: u64 = 1 << 31;
+pub const FEATURE_VOTER_REWARDS: u64 = 1 << 32;
+pub const FEATURE_STAKE_WEIGHTING: u64 = 1 << 33;
+pub const FEATURE_GOVERNED_POLLS: u64 = 1 << 34;
+pub const FEATURE_RESULTS_VIEW: u64 = 1 << 35;
+pub const FEATURE_ACCOUNT_MIGRATION: u64 = 1 << 36;
+pub const FEATURE_COMPRESSED_VOTES: u64 = 1 << 37;
+pub const FEATURE_PRIVATE_BALLOTS: u64 = 1 << 38;
+pub const FEATURE_ENCRYPTED_BALLOTS: u64 = 1 << 39;
+pub const FEATURE_DIRECT_VOTES_ONLY: u64 = 1 << 40;
+pub const FEATURE_RATE_LIMIT: u64 = 1 << 41;
+pub const FEATURE_MAX_VOTES: u64 = 1 << 42;
+pub const FEATURE_POLL_TAGS: u64 = 1 << 43;
+pub const FEATURE_POLL_LIST: u64 = 1 << 44;
+pub const FEATURE_RESULT_ARCHIVAL: u64 = 1 << 45;
+pub const FEATURE_RESULT_CERTIFICATION: u64 = 1 << 46;
+pub const FEATURE_RESULT_CHALLENGES: u64 = 1 << 47;
+pub const FEATURE_AUDIT_LOG: u64 = 1 << 48;
+pub const FEATURE_RENT_SPONSORSHIP: u64 = 1 << 49;
+pub const FEATURE_CONVICTION_VOTING: u64 = 1 << 50;
+pub const FEATURE_QUADRATIC_FUNDING: u64 = 1 << 51;
+pub const FEATURE_PREDICTION_MARKETS: u64 = 1 << 52;
+pub const FEATURE_PETITIONS: u64 = 1 << 53;
+pub const FEATURE_PSEUDONYMOUS_RECORDS: u64 = 1 << 54;
+pub const FEATURE_NFT_WEIGHTING: u64 = 1 << 55;
+pub const FEATURE_REPUTATION: u64 = 1 << 56;
+pub const FEATURE_VOTE_RECEIPTS: u64 = 1 << 57;
+pub const FEATURE_ORGANIZATIONS: u64 = 1 << 58;
+pub const FEATURE_HIDDEN_TALLIES: u64 = 1 << 59;
+pub const FEATURE_BALANCE_SNAPSHOTS: u64 = 1 << 60;
+pub const FEATURE_VOTER_TIERS: u64 = 1 << 61;
+pub const FEATURE_TALLY_SHARDS: u64 = 1 << 62;
+pub const FEATURES_ENABLED: u64 = FEATURE_NAMESPACED_POLLS
+    | FEATURE_SETTINGS_HASH_POLLS
+    | FEATURE_PARALLEL_TALLIES
+    | FEATURE_DELEGATION
+    | FEATURE_VOTE_CHANGES
+    | FEATURE_VOTER_ROLL
+    | FEATURE_MULTISIG
+    | FEATURE_CO_ADMIN_ROLES
+    | FEATURE_DRAFTS
+    | FEATURE_REOPEN
+    | FEATURE_DEADLINE_EXTENSION
+    | FEATURE_RECOUNT
+    | FEATURE_ATTENDANCE
+    | FEATURE_AUTO_FINALIZE
+    | FEATURE_QUORUM
+    | FEATURE_RANDOM_TIE_BREAK
+    | FEATURE_RUNOFFS
+    | FEATURE_RESULT_EMBARGO
+    | FEATURE_ASSERT_WINNER
+    | FEATURE_WRITE_INS
+    | FEATURE_DISQUALIFICATION
+    | FEATURE_VOTE_MEMOS
+    | FEATURE_BATCH_VOTING
+    | FEATURE_SIGNED_VOTES
+    | FEATURE_SESSION_KEYS
+    | FEATURE_POLL_PROXIES
+    | FEATURE_GATEWAY_GATING
+    | FEATURE_BADGE_GATING
+    | FEATURE_TOKEN_BALANCE_GATING
+    | FEATURE_VOTE_DEPOSITS
+    | FEATURE_CREATION_FEES
+    | FEATURE_PRIZE_POOLS
+    | FEATURE_VOTER_REWARDS
+    | FEATURE_STAKE_WEIGHTING
+    | FEATURE_GOVERNED_POLLS
+    | FEATURE_RESULTS_VIEW
+    | FEATURE_ACCOUNT_MIGRATION
+    | FEATURE_COMPRESSED_VOTES
+    | FEATURE_PRIVATE_BALLOTS
+    | FEATURE_ENCRYPTED_BALLOTS
+    | FEATURE_DIRECT_VOTES_ONLY
+    | FEATURE_RATE_LIMIT
+    | FEATURE_MAX_VOTES
+    | FEATURE_POLL_TAGS
+    | FEATURE_POLL_LIST
+    | FEATURE_RESULT_ARCHIVAL
+    | FEATURE_RESULT_CERTIFICATION
+    | FEATURE_RESULT_CHALLENGES
+    | FEATURE_AUDIT_LOG
+    | FEATURE_RENT_SPONSORSHIP
+    | FEATURE_CONVICTION_VOTING
+    | FEATURE_QUADRATIC_FUNDING
+    | FEATURE_PREDICTION_MARKETS
+    | FEATURE_PETITIONS
+    | FEATURE_PSEUDONYMOUS_RECORDS
+    | FEATURE_NFT_WEIGHTING
+    | FEATURE_REPUTATION
+    | FEATURE_VOTE_RECEIPTS
+    | FEATURE_ORGANIZATIONS
+    | FEATURE_HIDDEN_TALLIES
+    | FEATURE_BALANCE_SNAPSHOTS
+    | FEATURE_VOTER_TIERS
+    | FEATURE_TALLY_SHARDS;
+
+/// Addresses of this program's PDAs, for clients and for programs that
+/// embed voting through the `cpi` feature. Each returns `(address, bump)`.
+/// Polls created by `create_poll` have no namespace; namespaced and governed
+/// polls live under their admin.
+pub mod pda {
+    use super::*;
+
+    fn find(seeds: &[&[u8]]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(seeds, &crate::ID)
+    }
+
+    fn namespace_seed(namespace: Option<&Pubkey>) -> &[u8] {
+        namespace.map_or(&[], |namespace| namespace.as_ref())
+    }
+
+    pub fn find_config_address() -> (Pubkey, u8) {
+        find(&[b"config"])
+    }
+
+    pub fn find_poll_counter_address() -> (Pubkey, u8) {
+        find(&[b"poll_counter"])
+    }
+
+    pub fn find_treasury_address() -> (Pubkey, u8) {
+        find(&[b"treasury"])
+    }
+
+    /// The self-CPI event authority `#[event_cpi]` instructions take.
+    pub fn find_event_authority_address() -> (Pubkey, u8) {
+        find(&[b"__event_authority"])
+    }
+
+    pub fn find_poll_address(namespace: Option<&Pubkey>, poll_id: u64) -> (Pubkey, u8) {
+        find(&[b"poll", namespace_seed(namespace), &poll_id.to_le_bytes()])
+    }
+
+    pub fn find_vote_record_address(
+        namespace: Option<&Pubkey>,
+        poll_id: u64,
+        voter: &Pubkey,
+    ) -> (Pubkey, u8) {
+        find(&[b"vote", namespace_seed(namespace), &poll_id.to_le_bytes(), voter.as_ref()])
+    }
+
+    pub fn find_admin_profile_address(admin: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"admin_profile", admin.as_ref()])
+    }
+
+    /// The `index`-th poll created by `admin`; the next poll creation uses
+    /// the admin profile's `polls_created` (0 before the profile exists).
+    pub fn find_admin_poll_entry_address(admin: &Pubkey, index: u64) -> (Pubkey, u8) {
+        find(&[b"admin_poll", admin.as_ref(), &index.to_le_bytes()])
+    }
+
+    /// Also an organization member's role, with the organization as `poll`.
+    pub fn find_poll_role_address(poll: &Pubkey, grantee: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"role", poll.as_ref(), grantee.as_ref()])
+    }
+
+    pub fn find_organization_address(owner: &Pubkey, name: &str) -> (Pubkey, u8) {
+        find(&[b"org", owner.as_ref(), name.as_bytes()])
+    }
+
+    pub fn find_roll_membership_address(poll: &Pubkey, voter: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"member", poll.as_ref(), voter.as_ref()])
+    }
+
+    pub fn find_delegation_address(delegator: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"delegation", delegator.as_ref()])
+    }
+
+    pub fn find_session_token_address(authority: &Pubkey, session_key: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"session", authority.as_ref(), session_key.as_ref()])
+    }
+
+    /// Shard 0 is also the counter of a poll without `tally_shards`.
+    pub fn find_candidate_tally_address(
+        poll: &Pubkey,
+        candidate_index: u8,
+        shard: u8,
+    ) -> (Pubkey, u8) {
+        find(&[
+            b"tally",
+            poll.as_ref(),
+            &[candidate_index],
+            &CandidateTally::shard_seed(shard),
+        ])
+    }
+
+    pub fn find_candidate_payout_address(poll: &Pubkey, candidate_index: u8) -> (Pubkey, u8) {
+        find(&[b"payout", poll.as_ref(), &[candidate_index]])
+    }
+
+    pub fn find_prize_pool_address(poll: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"prize", poll.as_ref()])
+    }
+
+    pub fn find_reward_vault_address(poll: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"reward", poll.as_ref()])
+    }
+
+    pub fn find_stake_ballot_address(poll: &Pubkey, stake_account: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"stake_ballot", poll.as_ref(), stake_account.as_ref()])
+    }
+
+    pub fn find_receipt_address(poll: &Pubkey, consumer_program: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"receipt", poll.as_ref(), consumer_program.as_ref()])
+    }
+
+    pub fn find_encrypted_tally_address(poll: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"encrypted_tally", poll.as_ref()])
+    }
+
+    pub fn find_nullifier_address(poll: &Pubkey, voter: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"nullifier", poll.as_ref(), voter.as_ref()])
+    }
+
+    pub fn find_encrypted_ballot_address(poll: &Pubkey, voter: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"encrypted_ballot", poll.as_ref(), voter.as_ref()])
+    }
+
+    pub fn find_poll_result_address(poll: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"result", poll.as_ref()])
+    }
+
+    pub fn find_challenge_address(poll: &Pubkey, challenger: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"challenge", poll.as_ref(), challenger.as_ref()])
+    }
+
+    pub fn find_audit_log_address(poll: &Pubkey, page: u32) -> (Pubkey, u8) {
+        find(&[b"audit_log", poll.as_ref(), &page.to_le_bytes()])
+    }
+
+    pub fn find_rent_sponsor_address(poll: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"rent_sponsor", poll.as_ref()])
+    }
+
+    pub fn find_conviction_lock_address(poll: &Pubkey, voter: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"conviction", poll.as_ref(), voter.as_ref()])
+    }
+
+    pub fn find_prediction_pool_address(poll: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"prediction", poll.as_ref()])
+    }
+
+    pub fn find_funding_round_address(poll: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"funding", poll.as_ref()])
+    }
+
+    pub fn find_contribution_address(
+        poll: &Pubkey,
+        contributor: &Pubkey,
+        candidate_index: u8,
+    ) -> (Pubkey, u8) {
+        find(&[b"contribution", poll.as_ref(), contributor.as_ref(), &[candidate_index]])
+    }
+
+    pub fn find_reputation_address(voter: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"reputation", voter.as_ref()])
+    }
+
+    pub fn find_snapshot_balance_address(poll: &Pubkey, voter: &Pubkey) -> (Pubkey, u8) {
+        find(&[b"snapshot", poll.as_ref(), voter.as_ref()])
+    }
+}
+
 #[program]
 pub mod solana_voting {
     use super::*;
 
-    /// Creates a new voting poll. Only the admin (signer) can create polls.
+    /// Initializes the global poll counter. Can only succeed once, and only
+    /// the program's upgrade authority can do it, since `first_poll_id` near
+    /// `u64::MAX` would leave no IDs for `create_poll`. Deployments that
+    /// already hold polls should start past their highest existing ID.
+    pub fn initialize_counter(ctx: Context<InitializeCounter>, first_poll_id: u64) -> Result<()> {
+        let counter = &mut ctx.accounts.poll_counter;
+        counter.next_poll_id = first_poll_id;
+        counter.bump = ctx.bumps.poll_counter;
+        Ok(())
+    }
+
+    /// Initializes the program config with the default permission matrix.
+    /// Only the program's upgrade authority can do this, and it becomes the
+    /// config authority.
+    pub fn initialize_config(ctx: Context<InitializeConfig>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.authority = ctx.accounts.authority.key();
+        config.permissions = ProgramConfig::DEFAULT_PERMISSIONS;
+        config.limits = ConfigLimits::default();
+        config.paused = false;
+        config.poll_creation_fee = 0;
+        config.min_poll_duration = 0;
+        config.bump = ctx.bumps.config;
+        Ok(())
+    }
+
+    /// Pauses or resumes poll creation and voting program-wide. An incident
+    /// response lever; only the config authority can flip it.
+    pub fn set_pause(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(
+            config.authority == ctx.accounts.authority.key(),
+            VotingError::Unauthorized
+        );
+        config.paused = paused;
+        msg!("Program {}", if paused { "paused" } else { "resumed" });
+        emit_cpi!(PauseChanged {
+            paused,
+            authority: config.authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Replaces the limits new polls are validated and sized against.
+    /// Existing polls keep the space they were created with. Only the
+    /// config authority can change them.
+    pub fn update_config(ctx: Context<UpdateConfig>, limits: ConfigLimits) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(
+            config.authority == ctx.accounts.authority.key(),
+            VotingError::Unauthorized
+        );
+        limits.validate()?;
+        config.limits = limits;
+        msg!(
+            "Config limits updated: {} candidates, {}-char titles, {}-char names",
+            limits.max_candidates,
+            limits.max_title_len,
+            limits.max_candidate_name_len
+        );
+        Ok(())
+    }
+
+    /// Sets the lamport fee charged into the program treasury on every poll
+    /// creation (0 = free), creating the treasury on first use. Only the
+    /// config authority can change it.
+    pub fn set_poll_creation_fee(ctx: Context<SetPollCreationFee>, fee: u64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(
+            config.authority == ctx.accounts.authority.key(),
+            VotingError::Unauthorized
+        );
+        config.poll_creation_fee = fee;
+        ctx.accounts.treasury.bump = ctx.bumps.treasury;
+        msg!("Poll creation fee set to {} lamports", fee);
+        Ok(())
+    }
+
+    /// Sets the minimum voting window, in seconds, that new polls must have
+    /// (0 = none), so polls can't be run as snap elections. Only the config
+    /// authority can change it; existing polls keep the minimum they were
+    /// created under.
+    pub fn set_min_poll_duration(ctx: Context<UpdateConfig>, duration: i64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(
+            config.authority == ctx.accounts.authority.key(),
+            VotingError::Unauthorized
+        );
+        require!(duration >= 0, VotingError::InvalidConfig);
+        config.min_poll_duration = duration;
+        msg!("Minimum poll duration set to {} seconds", duration);
+        Ok(())
+    }
+
+    /// Sets how long, in seconds, sealed polls are kept before anyone may
+    /// clean them up with `gc_expired_poll` (0 = never). Only the config
+    /// authority can change it.
+    pub fn set_retention_period(ctx: Context<UpdateConfig>, period: i64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(
+            config.authority == ctx.accounts.authority.key(),
+            VotingError::Unauthorized
+        );
+        require!(period >= 0, VotingError::InvalidConfig);
+        config.retention_period = period;
+        msg!("Poll retention period set to {} seconds", period);
+        Ok(())
+    }
+
+    /// Sends `amount` lamports of collected fees from the treasury to
+    /// `destination`, keeping the treasury rent-exempt. Only the config
+    /// authority can withdraw; set it to a multisig's address to require
+    /// several signers.
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        let config = &ctx.accounts.config;
+        require!(
+            config.authority == ctx.accounts.authority.key(),
+            VotingError::Unauthorized
+        );
+        let treasury = ctx.accounts.treasury.to_account_info();
+        let available = treasury
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(treasury.data_len()));
+        require!(amount > 0 && amount <= available, VotingError::InsufficientTreasury);
+
+        move_lamports(&treasury, &ctx.accounts.destination.to_account_info(), amount)?;
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.total_withdrawn = treasury.total_withdrawn.safe_add(amount)?;
+
+        msg!(
+            "Withdrew {} lamports from the treasury to {}",
+            amount,
+            ctx.accounts.destination.key()
+        );
+        emit_cpi!(TreasuryWithdrawn {
+            authority: config.authority,
+            destination: ctx.accounts.destination.key(),
+            amount,
+            remaining: available - amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Reports the deployed version, supported features and current limits
+    /// through return data, so clients can adapt at runtime. Read-only; meant
+    /// to be simulated.
+    pub fn get_program_capabilities(
+        ctx: Context<GetProgramCapabilities>,
+    ) -> Result<ProgramCapabilities> {
+        let config = &ctx.accounts.config;
+        Ok(ProgramCapabilities {
+            version: PROGRAM_VERSION.to_string(),
+            features: FEATURES_ENABLED,
+            limits: config.limits,
+            hard_limits: ConfigLimits::HARD_MAX,
+            permissions: config.permissions,
+            paused: config.paused,
+            poll_creation_fee: config.poll_creation_fee,
+            min_poll_duration: config.min_poll_duration,
+            retention_period: config.retention_period,
+        })
+    }
+
+    /// Reports a poll's results through return data, computed the way
+    /// finalization does, so clients don't reimplement disqualification,
+    /// quorum or weighting rules. Read-only; meant to be simulated. The
+    /// winner is only set once results are sealed, and parallel-tally polls
+    /// report tallies once aggregated.
+    pub fn get_results(ctx: Context<GetResults>, _poll_id: u64) -> Result<PollResults> {
+        Ok(poll_results(&ctx.accounts.poll))
+    }
+
+    /// Replaces the permission matrix: `permissions[role as usize]` holds the
+    /// `ACTION_*` bits that `ConfigRole` may perform. Only the config
+    /// authority can change it.
+    pub fn set_permission_matrix(
+        ctx: Context<UpdateConfig>,
+        permissions: [u8; CONFIG_ROLE_COUNT],
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(
+            config.authority == ctx.accounts.authority.key(),
+            VotingError::Unauthorized
+        );
+        require!(
+            permissions.iter().all(|actions| actions & !ACTION_ALL == 0),
+            VotingError::InvalidPermissions
+        );
+        config.permissions = permissions;
+        msg!("Permission matrix updated: {:?}", permissions);
+        Ok(())
+    }
+
+    /// Creates a new voting poll under the next ID from the poll counter.
+    /// The signer becomes the poll's admin.
     pub fn create_poll(
         ctx: Context<CreatePoll>,
+        title: String,
+        candidates: Vec<String>,
+        settings: PollSettings,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            None,
+            None,
+            ACTION_CREATE,
+        )?;
+        ctx.accounts.config.charge_creation_fee(
+            ctx.accounts.treasury.as_mut(),
+            ctx.accounts.admin.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        let counter = &mut ctx.accounts.poll_counter;
+        let poll_id = counter.next_poll_id;
+        counter.next_poll_id = poll_id.safe_add(1)?;
+
+        let poll = &mut ctx.accounts.poll;
+        poll.admin = ctx.accounts.admin.key();
+        poll.namespace = Pubkey::default();
+        poll.poll_id = poll_id;
+        poll.bump = ctx.bumps.poll;
+        poll.configure(title, candidates, &settings, &ctx.accounts.config.limits)?;
+        poll.apply_min_duration(ctx.accounts.config.min_poll_duration)?;
+        poll.hold_reveal_bond(
+            ctx.accounts.admin.to_account_info(),
+            poll.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+        ctx.accounts.config.list_poll(poll)?;
+
+        let index = ctx.accounts.admin_profile.register(poll.admin, ctx.bumps.admin_profile)?;
+        ctx.accounts
+            .admin_poll_entry
+            .set_inner(AdminPollEntry::new(poll, index, ctx.bumps.admin_poll_entry));
+
+        msg!("Poll '{}' created with {} candidates", poll.title, poll.candidates.len());
+        emit_cpi!(poll_created_event(poll)?);
+        Ok(())
+    }
+
+    /// Creates a poll namespaced under the signer, with a caller-chosen ID.
+    /// Its address is derived from `[b"poll", admin, poll_id]`, so independent
+    /// admins can reuse the same IDs without colliding with each other or
+    /// with global polls from `create_poll`.
+    pub fn create_namespaced_poll(
+        ctx: Context<CreateNamespacedPoll>,
+        poll_id: u64,
+        title: String,
+        candidates: Vec<String>,
+        settings: PollSettings,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            None,
+            None,
+            ACTION_CREATE,
+        )?;
+        ctx.accounts.config.charge_creation_fee(
+            ctx.accounts.treasury.as_mut(),
+            ctx.accounts.admin.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        let poll = &mut ctx.accounts.poll;
+        poll.admin = ctx.accounts.admin.key();
+        poll.namespace = ctx.accounts.admin.key();
+        poll.poll_id = poll_id;
+        poll.bump = ctx.bumps.poll;
+        poll.configure(title, candidates, &settings, &ctx.accounts.config.limits)?;
+        poll.apply_min_duration(ctx.accounts.config.min_poll_duration)?;
+        poll.hold_reveal_bond(
+            ctx.accounts.admin.to_account_info(),
+            poll.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+        ctx.accounts.config.list_poll(poll)?;
+
+        let index = ctx.accounts.admin_profile.register(poll.admin, ctx.bumps.admin_profile)?;
+        ctx.accounts
+            .admin_poll_entry
+            .set_inner(AdminPollEntry::new(poll, index, ctx.bumps.admin_poll_entry));
+
+        msg!(
+            "Poll '{}' created in namespace {} with {} candidates",
+            poll.title,
+            poll.namespace,
+            poll.candidates.len()
+        );
+        emit_cpi!(poll_created_event(poll)?);
+        Ok(())
+    }
+
+    /// Creates a namespaced poll like `create_namespaced_poll`, with rent and
+    /// any creation fee paid by a separate `payer`. Lets an account that
+    /// can't fund accounts itself, such as an SPL Governance PDA executing a
+    /// Realms proposal, be the admin; see `CreateGovernedPoll` for the
+    /// account metas.
+    pub fn create_governed_poll(
+        ctx: Context<CreateGovernedPoll>,
+        poll_id: u64,
+        title: String,
+        candidates: Vec<String>,
+        settings: PollSettings,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            None,
+            None,
+            ACTION_CREATE,
+        )?;
+        ctx.accounts.config.charge_creation_fee(
+            ctx.accounts.treasury.as_mut(),
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        let poll = &mut ctx.accounts.poll;
+        poll.admin = ctx.accounts.admin.key();
+        poll.namespace = ctx.accounts.admin.key();
+        poll.poll_id = poll_id;
+        poll.bump = ctx.bumps.poll;
+        poll.configure(title, candidates, &settings, &ctx.accounts.config.limits)?;
+        poll.apply_min_duration(ctx.accounts.config.min_poll_duration)?;
+        poll.hold_reveal_bond(
+            ctx.accounts.payer.to_account_info(),
+            poll.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+        ctx.accounts.config.list_poll(poll)?;
+
+        let index = ctx.accounts.admin_profile.register(poll.admin, ctx.bumps.admin_profile)?;
+        ctx.accounts
+            .admin_poll_entry
+            .set_inner(AdminPollEntry::new(poll, index, ctx.bumps.admin_poll_entry));
+
+        msg!(
+            "Poll '{}' created in namespace {} with {} candidates, paid by {}",
+            poll.title,
+            poll.namespace,
+            poll.candidates.len(),
+            ctx.accounts.payer.key()
+        );
+        emit_cpi!(poll_created_event(poll)?);
+        Ok(())
+    }
+
+    /// Creates an organization owned by the signer at `[b"org", owner, name]`,
+    /// under which teams create and jointly manage polls with
+    /// `create_org_poll`.
+    pub fn create_organization(ctx: Context<CreateOrganization>, name: String) -> Result<()> {
+        require!(
+            !name.is_empty() && name.len() <= Organization::MAX_NAME_LEN,
+            VotingError::InvalidOrganizationName
+        );
+        let organization = &mut ctx.accounts.organization;
+        organization.owner = ctx.accounts.owner.key();
+        organization.name = name;
+        organization.bump = ctx.bumps.organization;
+
+        msg!("Organization '{}' created by {}", organization.name, organization.owner);
+        emit_cpi!(OrganizationCreated {
+            organization: organization.key(),
+            owner: organization.owner,
+            name: organization.name.clone(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Gives `member` a role across all of the organization's polls with the
+    /// given `PERMISSION_*` bits, replacing any it already held. The role is
+    /// a `PollRole` at `[b"role", organization, member]`, which the
+    /// organization's polls accept wherever a co-admin role is taken. Only
+    /// the owner manages members.
+    pub fn set_org_member(ctx: Context<SetOrgMember>, member: Pubkey, permissions: u8) -> Result<()> {
+        require!(
+            permissions != 0 && permissions & !(PERMISSION_ALL | PERMISSION_CREATE_POLLS) == 0,
+            VotingError::InvalidPermissions
+        );
+        let organization = &ctx.accounts.organization;
+        let role = &mut ctx.accounts.role;
+        role.poll = organization.key();
+        role.grantee = member;
+        role.permissions = permissions;
+        role.bump = ctx.bumps.role;
+
+        msg!(
+            "Granted permissions {:#07b} in organization '{}' to {}",
+            permissions,
+            organization.name,
+            member
+        );
+        emit_cpi!(OrgMemberUpdated {
+            organization: organization.key(),
+            member,
+            permissions,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Removes a member's role from the organization, closing its account
+    /// and refunding the rent to the owner.
+    pub fn remove_org_member(ctx: Context<RemoveOrgMember>) -> Result<()> {
+        let organization = &ctx.accounts.organization;
+        let member = ctx.accounts.role.grantee;
+        msg!("Removed {} from organization '{}'", member, organization.name);
+        emit_cpi!(OrgMemberUpdated {
+            organization: organization.key(),
+            member,
+            permissions: 0,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Creates a poll owned by an organization, with a caller-chosen ID in
+    /// its namespace: `[b"poll", organization, poll_id]`. The signer must be
+    /// the owner or a member holding `PERMISSION_CREATE_POLLS`, and pays for
+    /// it. The owner becomes the poll's admin, members' roles act as
+    /// co-admin roles on it, and it is listed under the organization's
+    /// admin profile, so the organization's polls can be walked together.
+    pub fn create_org_poll(
+        ctx: Context<CreateOrgPoll>,
         poll_id: u64,
         title: String,
         candidates: Vec<String>,
+        settings: PollSettings,
+    ) -> Result<()> {
+        let creator = ctx.accounts.creator.key();
+        ctx.accounts.config.assert_not_paused()?;
+        ctx.accounts.config.assert_permission(&creator, None, None, ACTION_CREATE)?;
+        let organization = &ctx.accounts.organization;
+        require!(
+            organization.owner == creator
+                || ctx
+                    .accounts
+                    .role
+                    .as_ref()
+                    .is_some_and(|role| role.permissions & PERMISSION_CREATE_POLLS != 0),
+            VotingError::Unauthorized
+        );
+        ctx.accounts.config.charge_creation_fee(
+            ctx.accounts.treasury.as_mut(),
+            ctx.accounts.creator.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        let poll = &mut ctx.accounts.poll;
+        poll.admin = organization.owner;
+        poll.namespace = organization.key();
+        poll.poll_id = poll_id;
+        poll.bump = ctx.bumps.poll;
+        poll.configure(title, candidates, &settings, &ctx.accounts.config.limits)?;
+        poll.organization = organization.key();
+        poll.apply_min_duration(ctx.accounts.config.min_poll_duration)?;
+        poll.hold_reveal_bond(
+            ctx.accounts.creator.to_account_info(),
+            poll.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+        ctx.accounts.config.list_poll(poll)?;
+
+        let index = ctx.accounts.admin_profile.register(poll.organization, ctx.bumps.admin_profile)?;
+        let mut entry = AdminPollEntry::new(poll, index, ctx.bumps.admin_poll_entry);
+        entry.admin = poll.organization;
+        ctx.accounts.admin_poll_entry.set_inner(entry);
+
+        msg!(
+            "Poll '{}' created in organization '{}' by {} with {} candidates",
+            poll.title,
+            organization.name,
+            creator,
+            poll.candidates.len()
+        );
+        emit_cpi!(poll_created_event(poll)?);
+        Ok(())
+    }
+
+    /// Creates a poll under the v2 seed scheme, where the address is derived
+    /// from a hash of the admin and the full creation parameters (see
+    /// `Poll::settings_hash`). Retrying the same creation lands on the same
+    /// address and fails harmlessly, so one intended election can't turn into
+    /// two subtly different polls. v2 polls are addressed by their hash, which
+    /// is stored as the namespace, and always have poll ID 0.
+    pub fn create_poll_v2(
+        ctx: Context<CreatePollV2>,
+        title: String,
+        candidates: Vec<String>,
+        settings: PollSettings,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            None,
+            None,
+            ACTION_CREATE,
+        )?;
+        ctx.accounts.config.charge_creation_fee(
+            ctx.accounts.treasury.as_mut(),
+            ctx.accounts.admin.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        let admin = ctx.accounts.admin.key();
+        let poll = &mut ctx.accounts.poll;
+        poll.admin = admin;
+        poll.namespace = Pubkey::new_from_array(Poll::settings_hash(&admin, &title, &candidates, &settings));
+        poll.poll_id = 0;
+        poll.bump = ctx.bumps.poll;
+        poll.configure(title, candidates, &settings, &ctx.accounts.config.limits)?;
+        poll.apply_min_duration(ctx.accounts.config.min_poll_duration)?;
+        poll.hold_reveal_bond(
+            ctx.accounts.admin.to_account_info(),
+            poll.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+        ctx.accounts.config.list_poll(poll)?;
+
+        let index = ctx.accounts.admin_profile.register(poll.admin, ctx.bumps.admin_profile)?;
+        ctx.accounts
+            .admin_poll_entry
+            .set_inner(AdminPollEntry::new(poll, index, ctx.bumps.admin_poll_entry));
+
+        msg!(
+            "Poll '{}' created with settings hash {} and {} candidates",
+            poll.title,
+            poll.namespace,
+            poll.candidates.len()
+        );
+        emit_cpi!(poll_created_event(poll)?);
+        Ok(())
+    }
+
+    /// Starts the next round of a finalized poll. The runoff is a new poll at
+    /// `[b"poll", parent, parent_poll_id]` holding only the parent's `top_k`
+    /// candidates by weight, plus any tied with the last of them. `settings`
+    /// supplies the new schedule and options, except that the voter roll is
+    /// inherited: voters prove membership of the runoff against the first
+    /// round's roll entries with the same salt.
+    pub fn start_runoff(
+        ctx: Context<StartRunoff>,
+        _poll_id: u64,
+        top_k: u8,
+        settings: PollSettings,
     ) -> Result<()> {
-        require!(candidates.len() >= 2, VotingError::TooFewCandidates);
-        require!(candidates.len() <= 10, VotingError::TooManyCandidates);
-        require!(title.len() <= 100, VotingError::TitleTooLong);
+        ctx.accounts.config.assert_not_paused()?;
+        let admin = ctx.accounts.admin.key();
+        let config = &ctx.accounts.config;
+        let parent = &ctx.accounts.parent_poll;
+        config.assert_permission(&admin, None, None, ACTION_CREATE)?;
+        config.assert_permission(&admin, Some(parent), None, ACTION_MODERATE)?;
+        config.charge_creation_fee(
+            ctx.accounts.treasury.as_mut(),
+            ctx.accounts.admin.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+        require!(parent.status == PollStatus::Finalized, VotingError::PollNotFinalized);
+        require!(!parent.embargoed()?, VotingError::ResultsEmbargoed);
+        let mut weights: Vec<u64> = parent.eligible_candidates().map(|(_, c)| c.weight).collect();
+        require!(
+            top_k >= 2
+                && (top_k as usize) < parent.candidates.len()
+                && (top_k as usize) <= weights.len(),
+            VotingError::InvalidRunoff
+        );
+
+        weights.sort_unstable();
+        let cutoff = weights[weights.len() - top_k as usize];
+        let candidates = parent
+            .eligible_candidates()
+            .filter(|(_, c)| c.weight >= cutoff)
+            .map(|(_, c)| c.name.clone())
+            .collect();
+
+        let mut settings = settings;
+        settings.voter_roll = parent.voter_roll;
+
+        let poll = &mut ctx.accounts.poll;
+        poll.admin = admin;
+        poll.namespace = parent.key();
+        poll.poll_id = parent.poll_id;
+        poll.bump = ctx.bumps.poll;
+        poll.configure(parent.title.clone(), candidates, &settings, &config.limits)?;
+        poll.apply_min_duration(config.min_poll_duration)?;
+        poll.hold_reveal_bond(
+            ctx.accounts.admin.to_account_info(),
+            poll.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+        ctx.accounts.config.list_poll(poll)?;
+        poll.parent_poll = parent.key();
+        poll.round = parent.round + 1;
+        if parent.voter_roll {
+            poll.roll_source = parent.roll_poll(parent.key());
+            poll.eligible_voter_count = parent.eligible_voter_count;
+        }
+
+        let index = ctx.accounts.admin_profile.register(poll.admin, ctx.bumps.admin_profile)?;
+        ctx.accounts
+            .admin_poll_entry
+            .set_inner(AdminPollEntry::new(poll, index, ctx.bumps.admin_poll_entry));
+
+        msg!(
+            "Round {} of poll '{}' started with {} candidates",
+            poll.round,
+            poll.title,
+            poll.candidates.len()
+        );
+        emit_cpi!(poll_created_event(poll)?);
+        emit_cpi!(RunoffStarted {
+            poll: poll.key(),
+            parent_poll: poll.parent_poll,
+            round: poll.round,
+            candidates: poll.candidates.iter().map(|c| c.name.clone()).collect(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Creates a namespaced poll under the signer, like
+    /// `create_namespaced_poll`, with the title, listed candidates and
+    /// settings of `source_poll` and a new schedule. Write-ins, tallies and
+    /// per-poll state start fresh; the encryption key and embargo slot,
+    /// which belong to a single election, are not carried over, and a voter
+    /// roll is inherited as for runoffs. A poll with pseudonymous vote
+    /// records needs a `new_record_salt` of its own, so a voter's records
+    /// can't be linked across the series; other polls pass zeroes. Only the
+    /// source poll's admin or a moderator may clone it.
+    pub fn clone_poll(
+        ctx: Context<ClonePoll>,
+        new_poll_id: u64,
+        new_start_ts: i64,
+        new_end_ts: i64,
+        new_record_salt: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        let admin = ctx.accounts.admin.key();
+        let config = &ctx.accounts.config;
+        let source = &ctx.accounts.source_poll;
+        config.assert_permission(&admin, None, None, ACTION_CREATE)?;
+        config.assert_permission(&admin, Some(source), None, ACTION_MODERATE)?;
+        config.charge_creation_fee(
+            ctx.accounts.treasury.as_mut(),
+            ctx.accounts.admin.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        let fresh_salt = if source.record_salt == [0; 32] {
+            new_record_salt == [0; 32]
+        } else {
+            new_record_salt != [0; 32] && new_record_salt != source.record_salt
+        };
+        require!(fresh_salt, VotingError::RecordSaltReused);
+        let mut settings = source.template_settings(new_start_ts, new_end_ts);
+        settings.record_salt = new_record_salt;
+        let candidates = source.candidates[..source.listed_candidates()]
+            .iter()
+            .map(|c| c.name.clone())
+            .collect();
+
+        let poll = &mut ctx.accounts.poll;
+        poll.admin = admin;
+        poll.namespace = admin;
+        poll.poll_id = new_poll_id;
+        poll.bump = ctx.bumps.poll;
+        poll.configure(source.title.clone(), candidates, &settings, &config.limits)?;
+        poll.apply_min_duration(config.min_poll_duration)?;
+        ctx.accounts.config.list_poll(poll)?;
+        if source.voter_roll {
+            poll.roll_source = source.roll_poll(source.key());
+            poll.eligible_voter_count = source.eligible_voter_count;
+        }
+
+        let index = ctx.accounts.admin_profile.register(poll.admin, ctx.bumps.admin_profile)?;
+        ctx.accounts
+            .admin_poll_entry
+            .set_inner(AdminPollEntry::new(poll, index, ctx.bumps.admin_poll_entry));
+
+        msg!(
+            "Poll '{}' cloned from {} in namespace {}",
+            poll.title,
+            source.key(),
+            poll.namespace
+        );
+        emit_cpi!(poll_created_event(poll)?);
+        emit_cpi!(PollCloned {
+            poll: poll.key(),
+            source_poll: source.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Adds lamports to a poll's rent sponsor, which pays voters' vote record
+    /// rent through `sponsor_vote_record`. Anyone may fund or top it up.
+    pub fn fund_rent_sponsor(ctx: Context<FundRentSponsor>, _poll_id: u64, amount: u64) -> Result<()> {
+        require!(amount > 0, VotingError::InvalidFundingAmount);
+        let sponsor = &mut ctx.accounts.rent_sponsor;
+        if sponsor.poll == Pubkey::default() {
+            sponsor.poll = ctx.accounts.poll.key();
+            sponsor.bump = ctx.bumps.rent_sponsor;
+        }
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.funder.to_account_info(),
+                    to: sponsor.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        msg!("Rent sponsor of poll '{}' funded with {}", ctx.accounts.poll.title, amount);
+        Ok(())
+    }
+
+    /// Pre-pays the signer's vote record rent from the poll's rent sponsor,
+    /// by moving it to the record's still-empty address. The `vote` (or other
+    /// ballot) that creates the record later in the same transaction then
+    /// costs the voter no rent, so wallets without SOL can vote through a fee
+    /// payer. Fails with `RentSponsorDrained` once the sponsor can't cover a
+    /// record; clients can then leave it out and have the voter pay. The
+    /// next instruction must be this program's, writing the record: only a
+    /// ballot can, so rent is only sponsored for ballots that pass the
+    /// poll's roll and gate checks.
+    pub fn sponsor_vote_record(ctx: Context<SponsorVoteRecord>, _poll_id: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        poll.assert_accepting_votes()?;
+        let record = ctx.accounts.vote_record.to_account_info();
+        require!(record.lamports() == 0, VotingError::VoteRecordSponsored);
+        let ballot_ix = ix_sysvar::get_instruction_relative(1, &ctx.accounts.instructions)
+            .map_err(|_| VotingError::SponsoredBallotMissing)?;
+        require!(
+            ballot_ix.program_id == crate::ID
+                && ballot_ix
+                    .accounts
+                    .iter()
+                    .any(|meta| meta.pubkey == record.key() && meta.is_writable),
+            VotingError::SponsoredBallotMissing
+        );
+
+        let rent = Rent::get()?;
+        let amount = rent.minimum_balance(VoteRecord::space(poll.allow_memos));
+        let sponsor = &mut ctx.accounts.rent_sponsor;
+        let available = sponsor
+            .get_lamports()
+            .saturating_sub(rent.minimum_balance(RentSponsor::SPACE));
+        require!(available >= amount, VotingError::RentSponsorDrained);
+        move_lamports(&sponsor.to_account_info(), &record, amount)?;
+        sponsor.sponsored = sponsor.sponsored.safe_add(1)?;
+
+        msg!(
+            "Vote record rent of {} in poll '{}' sponsored",
+            ctx.accounts.voter.key(),
+            poll.title
+        );
+        Ok(())
+    }
+
+    /// Returns what's left in a poll's rent sponsor to the admin once the
+    /// poll is sealed or cancelled.
+    pub fn reclaim_rent_sponsor(ctx: Context<ReclaimRentSponsor>, _poll_id: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(
+            poll.is_sealed() || poll.status == PollStatus::Cancelled,
+            VotingError::PollNotOver
+        );
+        msg!(
+            "Rent sponsor of poll '{}' reclaimed after {} records",
+            poll.title,
+            ctx.accounts.rent_sponsor.sponsored
+        );
+        Ok(())
+    }
+
+    /// Turns on "I voted" receipts for a poll: `receipt_tree` is a Bubblegum
+    /// tree the admin created and delegated to the poll (`set_tree_delegate`),
+    /// which `mint_vote_receipt` mints compressed NFTs into.
+    pub fn enable_vote_receipts(ctx: Context<EnableVoteReceipts>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        poll.receipt_tree = ctx.accounts.receipt_tree.key();
+        msg!("Vote receipts for poll '{}' go to tree {}", poll.title, poll.receipt_tree);
+        Ok(())
+    }
+
+    /// Mints the signer a compressed "I voted" NFT for their ballot, named
+    /// after the poll and pointing at its `metadata_uri`, once per vote
+    /// record. Usually placed after `vote` in the same transaction. The
+    /// poll's rent sponsor signs as Bubblegum's payer, so receipts are only
+    /// minted while the poll sponsors its voters.
+    pub fn mint_vote_receipt(ctx: Context<MintVoteReceipt>, _poll_id: u64) -> Result<()> {
+        let record = &mut ctx.accounts.vote_record;
+        require!(!record.invalidated, VotingError::VoteInvalidated);
+        require!(!record.receipt_minted, VotingError::VoteReceiptMinted);
+        record.receipt_minted = true;
+
+        let poll = &ctx.accounts.poll;
+        VoteReceipt::mint(
+            poll,
+            &ctx.accounts.rent_sponsor,
+            &ctx.accounts.voter,
+            &ctx.accounts.tree_authority,
+            &ctx.accounts.receipt_tree,
+            [
+                &ctx.accounts.bubblegum_program,
+                &ctx.accounts.noop_program,
+                &ctx.accounts.compression_program,
+            ],
+            &ctx.accounts.system_program,
+        )?;
+        emit_cpi!(VoteReceiptMinted {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            voter: ctx.accounts.voter.key(),
+            tree: poll.receipt_tree,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Locks `amount` of a conviction poll's `conviction_mint` in a
+    /// `ConvictionLock` escrow (its associated token account, which the
+    /// caller creates) at lock tier `tier`. The lock's weight, what it
+    /// received times `1 << tier`, is fixed now and becomes the weight of
+    /// the voter's ballot; the tokens stay locked until both the poll has
+    /// ended and `conviction_period << tier` seconds have passed.
+    pub fn lock_conviction(
+        ctx: Context<LockConviction>,
+        _poll_id: u64,
+        amount: u64,
+        tier: u8,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(
+            poll.conviction_mint != Pubkey::default(),
+            VotingError::ConvictionVotingDisabled
+        );
+        poll.assert_accepting_votes()?;
+        require!(amount > 0, VotingError::InvalidFundingAmount);
+        require!(tier < CONVICTION_TIERS, VotingError::InvalidConvictionTier);
+
+        let tokens = EscrowTokens {
+            mint: Some(&ctx.accounts.mint),
+            vault: Some(&ctx.accounts.lock_vault),
+            token: Some(&ctx.accounts.voter_token),
+            token_program: Some(&ctx.accounts.token_program),
+        };
+        let lock = &mut ctx.accounts.conviction_lock;
+        let (_, vault, received) = tokens.deposit(
+            &lock.to_account_info(),
+            ctx.accounts.voter.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            amount,
+        )?;
+        let timestamp = Clock::get()?.unix_timestamp;
+        lock.poll = poll.key();
+        lock.voter = ctx.accounts.voter.key();
+        lock.vault = vault;
+        lock.amount = received;
+        lock.tier = tier;
+        lock.weight = received
+            .checked_mul(1 << tier)
+            .ok_or(VotingError::ArithmeticOverflow)?;
+        lock.unlock_at = timestamp
+            .checked_add(poll.conviction_period << tier)
+            .ok_or(VotingError::ArithmeticOverflow)?;
+        lock.bump = ctx.bumps.conviction_lock;
+        poll.conviction_locks = poll.conviction_locks.safe_add(1)?;
+
+        emit_cpi!(ConvictionLocked {
+            poll: poll.key(),
+            voter: lock.voter,
+            amount: lock.amount,
+            tier,
+            weight: lock.weight,
+            unlock_at: lock.unlock_at,
+            timestamp,
+        });
+        Ok(())
+    }
+
+    /// Returns a conviction lock's tokens to the voter and closes it, once
+    /// the poll no longer takes votes and the lock period has passed.
+    pub fn unlock_conviction(ctx: Context<UnlockConviction>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(
+            !matches!(poll.status, PollStatus::Active | PollStatus::Draft),
+            VotingError::ConvictionLocked
+        );
+        let lock = &ctx.accounts.conviction_lock;
+        require!(
+            Clock::get()?.unix_timestamp >= lock.unlock_at,
+            VotingError::ConvictionLocked
+        );
+
+        let tokens = EscrowTokens {
+            mint: Some(&ctx.accounts.mint),
+            vault: Some(&ctx.accounts.lock_vault),
+            token: Some(&ctx.accounts.voter_token),
+            token_program: Some(&ctx.accounts.token_program),
+        };
+        let amount = tokens.withdraw(
+            lock.to_account_info(),
+            (poll.conviction_mint, lock.vault),
+            &[b"conviction", poll.key().as_ref(), lock.voter.as_ref(), &[lock.bump]],
+            ctx.accounts.voter.to_account_info(),
+            None,
+        )?;
+        poll.conviction_locks = poll.conviction_locks.saturating_sub(1);
+        msg!("Unlocked {} conviction tokens from poll '{}'", amount, poll.title);
+        Ok(())
+    }
+
+    /// Records `voter`'s balance of a token-gated poll's `token_gate_mint`
+    /// as of its `snapshot_slot`, signed (and paid for) by the poll's
+    /// `snapshot_oracle`. Ballots on such polls are judged by this balance
+    /// rather than the voter's live token account. Each voter is attested
+    /// once, before the poll closes.
+    pub fn attest_snapshot_balance(
+        ctx: Context<AttestSnapshotBalance>,
+        _poll_id: u64,
+        voter: Pubkey,
+        balance: u64,
+    ) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(
+            matches!(poll.status, PollStatus::Draft | PollStatus::Active),
+            VotingError::PollClosed
+        );
+
+        let snapshot = &mut ctx.accounts.snapshot_balance;
+        snapshot.poll = poll.key();
+        snapshot.voter = voter;
+        snapshot.balance = balance;
+        snapshot.slot = poll.snapshot_slot;
+        snapshot.bump = ctx.bumps.snapshot_balance;
+
+        msg!(
+            "{} held {} of the gate token at slot {} for poll '{}'",
+            voter,
+            balance,
+            poll.snapshot_slot,
+            poll.title
+        );
+        emit_cpi!(SnapshotBalanceAttested {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            voter,
+            balance,
+            snapshot_slot: poll.snapshot_slot,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Casts a vote for a candidate in a poll. Each wallet can only vote once per poll.
+    ///
+    /// The first vote after the deadline closes the poll instead. An error
+    /// would roll that back, so the call succeeds but the ballot is not
+    /// counted and the new vote record is refunded to the voter.
+    ///
+    /// On `stake_weighted` polls, remaining accounts are `(stake_account,
+    /// stake_ballot)` pairs, the ballot writable at `[b"stake_ballot", poll,
+    /// stake_account]`: the ballot weighs the voter's active stake across
+    /// them, and each stake account counts once per poll. On conviction polls
+    /// the ballot owner's `conviction_lock` sets the weight. On NFT-weighted
+    /// polls, remaining accounts are `(token_account, metadata, nft_ballot)`
+    /// triples, the ballot writable at `[b"nft_ballot", poll, mint]`: the
+    /// weight is the number of the collection's NFTs counted, each once per
+    /// poll.
+    pub fn vote<'info>(
+        ctx: Context<'_, '_, '_, 'info, Vote<'info>>,
+        _poll_id: u64,
+        candidate_index: u8,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        // With a session token the signer is a session key, voting for the
+        // wallet that authorized it.
+        let voter = match &ctx.accounts.session_token {
+            Some(token) => {
+                token.assert_active()?;
+                token.authority
+            }
+            None => ctx.accounts.voter.key(),
+        };
+        let poll_info = ctx.accounts.poll.to_account_info();
+        let poll = &mut ctx.accounts.poll;
+
+        if poll.expire()? {
+            ctx.accounts
+                .vote_record
+                .close(ctx.accounts.voter.to_account_info())?;
+            msg!(
+                "Poll '{}' closed at its deadline; vote by {} not counted",
+                poll.title,
+                voter
+            );
+            emit_cpi!(PollClosed {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                total_votes: poll.total_votes,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+            return Ok(());
+        }
+
+        require!(!poll.parallel_tallies, VotingError::ParallelTalliesEnabled);
+        let passes = VoterPasses {
+            gatekeeper_network: match &ctx.accounts.gateway_token {
+                Some(info) => Some(GatewayToken::load(info, &voter)?.gatekeeper_network),
+                None => None,
+            },
+            badge_mint: match (&ctx.accounts.badge_mint, &ctx.accounts.badge_token) {
+                (Some(mint), Some(token)) => Some(VoterPasses::verify_badge(mint, token, &voter)?),
+                _ => None,
+            },
+            token_balance: if poll.snapshot_oracle != Pubkey::default() {
+                // The balance at the snapshot counts, not what is held now
+                ctx.accounts
+                    .snapshot_balance
+                    .as_ref()
+                    .map(|snapshot| (poll.token_gate_mint, snapshot.balance))
+            } else {
+                match &ctx.accounts.gate_token {
+                    Some(token) => Some(VoterPasses::verify_token_balance(
+                        token,
+                        ctx.accounts.gate_mint.as_ref(),
+                        &voter,
+                    )?),
+                    None => None,
+                }
+            },
+            direct_call: match &ctx.accounts.instructions {
+                Some(info) => VoterPasses::verify_direct_call(info)?,
+                None => false,
+            },
+            stake_weight: if poll.stake_weighted {
+                Some(StakeBallot::count(
+                    ctx.remaining_accounts,
+                    &poll.key(),
+                    &voter,
+                    ctx.accounts.voter.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                )?)
+            } else {
+                None
+            },
+            nft_count: if poll.nft_collection != Pubkey::default() {
+                Some(NftBallot::count(
+                    ctx.remaining_accounts,
+                    &poll.key(),
+                    &poll.nft_collection,
+                    &voter,
+                    ctx.accounts.voter.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                )?)
+            } else {
+                None
+            },
+            conviction_weight: ctx.accounts.conviction_lock.as_ref().map(|lock| lock.weight),
+            prediction_stake: None,
+            reputation: ctx.accounts.reputation.as_ref().map(|reputation| reputation.score()),
+        };
+        let weight = poll.check_ballot(
+            candidate_index,
+            ctx.accounts.membership.as_deref(),
+            Some(&passes),
+        )?;
+        poll.throttle()?;
+
+        // Increment vote count for the selected candidate
+        poll.count_ballot(candidate_index, weight)?;
+        let tier = poll.ballot_tier(ctx.accounts.membership.as_deref());
+        poll.tally_tier(tier, candidate_index, true)?;
+        if let Some(reputation) = ctx.accounts.reputation.as_mut() {
+            reputation.votes_cast = reputation.votes_cast.safe_add(1)?;
+        }
+
+        // Record the voter's choice
+        let vote_record = &mut ctx.accounts.vote_record;
+        vote_record.tier = tier;
+        vote_record.voter = poll.record_voter(&voter);
+        vote_record.poll_id = poll.poll_id;
+        vote_record.candidate_index = candidate_index;
+        vote_record.bump = ctx.bumps.vote_record;
+        vote_record.weight = weight;
+        vote_record.delegated_bps = 0;
+        vote_record.changes = Vec::new();
+        vote_record.stamp()?;
+        vote_record.memo = String::new();
+        vote_record.hold_deposit(
+            poll.vote_deposit,
+            ctx.accounts.voter.to_account_info(),
+            poll_info,
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        if poll.emits(EventVerbosity::Standard) {
+            msg!(
+                "Vote cast by {} for candidate '{}' in poll '{}'",
+                voter,
+                poll.candidates[candidate_index as usize].name,
+                poll.title
+            );
+            emit_cpi!(VoteCast {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                voter,
+                candidate_index,
+                weight,
+                candidate_votes: poll.candidates[candidate_index as usize].votes,
+                total_votes: poll.total_votes,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        if poll.emits(EventVerbosity::Verbose) {
+            emit_cpi!(BallotDetail {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                voter,
+                candidate_index,
+                weight,
+                cohort: poll.direct_cohort(),
+                candidate_weight: poll.candidates[candidate_index as usize].weight,
+                total_weight: poll.total_weight,
+            });
+        }
+        if poll.status == PollStatus::ThresholdReached {
+            emit_cpi!(PetitionThresholdReached {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                signatures: poll.total_votes,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        if poll.is_full() {
+            emit_cpi!(PollClosed {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                total_votes: poll.total_votes,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        Ok(())
+    }
+
+    /// Switches a poll to compressed vote records before it takes any votes.
+    /// `vote_tree` is an spl-account-compression concurrent merkle tree the
+    /// admin allocated (owned by that program) for `max_depth` and
+    /// `max_buffer_size`; this initializes it with the poll as its authority.
+    /// `vote_filter` is a zeroed account owned by this program that becomes
+    /// the poll's nullifier filter, setting `filter_hashes` bits per voter.
+    /// Not available with parallel tallies, gates or stake weighting.
+    pub fn enable_compressed_votes(
+        ctx: Context<EnableCompressedVotes>,
+        _poll_id: u64,
+        max_depth: u32,
+        max_buffer_size: u32,
+        filter_hashes: u8,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(
+            poll.vote_tree == Pubkey::default(),
+            VotingError::CompressedVotesEnabled
+        );
+        require!(
+            !poll.parallel_tallies
+                && !poll.stake_weighted
+                && poll.nft_collection == Pubkey::default()
+                && poll.encrypted_tally == Pubkey::default()
+                && poll.ballot_key == Pubkey::default()
+                && !poll.hide_results_until_close
+                && poll.tier_weights.is_empty()
+                && !poll.direct_votes_only
+                && poll.gatekeeper_network == Pubkey::default()
+                && poll.badge_mint == Pubkey::default()
+                && poll.token_gate_mint == Pubkey::default(),
+            VotingError::CompressionUnsupported
+        );
+        require!(
+            (1..=MAX_FILTER_HASHES).contains(&filter_hashes),
+            VotingError::InvalidVoteFilter
+        );
+        poll.assert_no_votes_yet()?;
+
+        VoteFilter::init(&ctx.accounts.vote_filter, poll.key(), filter_hashes)?;
+        let mut data = VoteTree::INIT_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&max_depth.to_le_bytes());
+        data.extend_from_slice(&max_buffer_size.to_le_bytes());
+        VoteTree::invoke(data, &ctx.accounts.vote_tree, poll, &ctx.accounts.noop_program)?;
+
+        poll.vote_tree = ctx.accounts.vote_tree.key();
+        poll.vote_filter = ctx.accounts.vote_filter.key();
+        msg!("Poll '{}' now records compressed votes", poll.title);
+        Ok(())
+    }
+
+    /// Casts a ballot on a compressed poll. Instead of a vote record, the
+    /// ballot is appended to the poll's vote tree as the leaf
+    /// `VoteTree::leaf(..)` and the voter's nullifier is added to its vote
+    /// filter, which rejects a second ballot. Tallies are kept on the poll as
+    /// usual; the `CompressedVoteCast` event carries the leaf's preimage.
+    pub fn vote_compressed(
+        ctx: Context<VoteCompressed>,
+        _poll_id: u64,
+        candidate_index: u8,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        let voter = ctx.accounts.voter.key();
+        let poll = &mut ctx.accounts.poll;
+
+        if poll.expire()? {
+            msg!(
+                "Poll '{}' closed at its deadline; vote by {} not counted",
+                poll.title,
+                voter
+            );
+            emit_cpi!(PollClosed {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                total_votes: poll.total_votes,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+            return Ok(());
+        }
+
+        let weight =
+            poll.check_compressed_ballot(candidate_index, ctx.accounts.membership.as_deref())?;
+        poll.throttle()?;
+        let leaf_index = VoteFilter::insert(
+            &ctx.accounts.vote_filter,
+            &poll.key(),
+            &VoteFilter::nullifier(&poll.key(), &voter),
+        )?;
+        poll.count_ballot(candidate_index, weight)?;
+
+        let slot = Clock::get()?.slot;
+        let leaf = VoteTree::leaf(&poll.key(), &voter, candidate_index, weight, slot);
+        let mut data = VoteTree::APPEND_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&leaf);
+        VoteTree::invoke(data, &ctx.accounts.vote_tree, poll, &ctx.accounts.noop_program)?;
+
+        if poll.emits(EventVerbosity::Standard) {
+            emit_cpi!(CompressedVoteCast {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                voter,
+                candidate_index,
+                weight,
+                leaf_index,
+                leaf,
+                slot,
+                candidate_votes: poll.candidates[candidate_index as usize].votes,
+                total_votes: poll.total_votes,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        Ok(())
+    }
+
+    /// Switches a poll to private ballots before it takes any votes. Ballots
+    /// are then encrypted to `elgamal_pubkey` (a twisted ElGamal key held by
+    /// `tally_authority`, e.g. an MPC or threshold committee) and summed
+    /// homomorphically in the poll's `EncryptedTally`; only the aggregate is
+    /// ever decrypted, by `post_private_tally`. Needs a fixed list of at most
+    /// `MAX_PRIVATE_CANDIDATES` candidates and no gates, stake weighting,
+    /// parallel tallies, compressed votes or `ballot_key`.
+    pub fn enable_private_ballots(
+        ctx: Context<EnablePrivateBallots>,
+        _poll_id: u64,
+        elgamal_pubkey: [u8; 32],
+        tally_authority: Pubkey,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(
+            poll.encrypted_tally == Pubkey::default(),
+            VotingError::PrivateBallotsEnabled
+        );
+        require!(
+            poll.candidates.len() <= MAX_PRIVATE_CANDIDATES
+                && !poll.allow_write_ins
+                && !poll.parallel_tallies
+                && !poll.stake_weighted
+                && poll.nft_collection == Pubkey::default()
+                && poll.vote_tree == Pubkey::default()
+                && poll.ballot_key == Pubkey::default()
+                && !poll.hide_results_until_close
+                && poll.decay_bps == 0
+                && poll.tier_weights.is_empty()
+                && !poll.direct_votes_only
+                && poll.gatekeeper_network == Pubkey::default()
+                && poll.badge_mint == Pubkey::default()
+                && poll.token_gate_mint == Pubkey::default(),
+            VotingError::PrivateBallotsUnsupported
+        );
+        poll.assert_no_votes_yet()?;
+
+        let tally = &mut ctx.accounts.encrypted_tally;
+        tally.poll = poll.key();
+        tally.elgamal_pubkey = elgamal_pubkey;
+        tally.tally_authority = tally_authority;
+        // All-zero bytes encode the identity points, i.e. an encrypted zero
+        tally.tallies = vec![[0; 64]; poll.candidates.len()];
+        tally.bump = ctx.bumps.encrypted_tally;
+        poll.encrypted_tally = tally.key();
+
+        msg!("Poll '{}' now takes private ballots", poll.title);
+        Ok(())
+    }
+
+    /// Casts a private ballot: one ciphertext per candidate under the poll's
+    /// ElGamal key, encrypting 1 for the chosen candidate and 0 for the
+    /// rest. The voter first verifies, with the zk-token-proof program, a
+    /// `BatchedRangeProofU64` over every ciphertext's commitment (1 bit
+    /// each), then `sum - 1` (1 bit) and `1 - sum` (the remaining bits),
+    /// passed as `range_proof`, and a
+    /// `BatchedGroupedCiphertext2HandlesValidity` proof per pair of
+    /// ciphertexts (both handles under the poll's key), passed in order as
+    /// remaining accounts; all owned by the voter. The `BallotNullifier` PDA
+    /// stops a second ballot. Turnout is public; choices are not.
+    pub fn vote_private<'info>(
+        ctx: Context<'_, '_, '_, 'info, VotePrivate<'info>>,
+        _poll_id: u64,
+        ciphertexts: Vec<[u8; 64]>,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        let voter = ctx.accounts.voter.key();
+        let poll = &mut ctx.accounts.poll;
+
+        if poll.expire()? {
+            ctx.accounts
+                .nullifier
+                .close(ctx.accounts.voter.to_account_info())?;
+            msg!(
+                "Poll '{}' closed at its deadline; vote by {} not counted",
+                poll.title,
+                voter
+            );
+            emit_cpi!(PollClosed {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                total_votes: poll.total_votes,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+            return Ok(());
+        }
+
+        let weight = poll.check_private_ballot(ctx.accounts.membership.as_deref())?;
+        poll.throttle()?;
+        let tally = &mut ctx.accounts.encrypted_tally;
+        require!(
+            ciphertexts.len() == tally.tallies.len(),
+            VotingError::InvalidBallotProof
+        );
+        let ciphertexts: Vec<ElGamalCiphertext> =
+            ciphertexts.into_iter().map(ElGamalCiphertext).collect();
+        BallotProofs::verify_ballot(
+            &ciphertexts,
+            &tally.elgamal_pubkey,
+            &voter,
+            &ctx.accounts.range_proof,
+            ctx.remaining_accounts,
+        )?;
+        for (sum, ciphertext) in tally.tallies.iter_mut().zip(&ciphertexts) {
+            *sum = ops::add(&ElGamalCiphertext(*sum), ciphertext)
+                .ok_or(VotingError::InvalidBallotProof)?
+                .0;
+        }
+        poll.total_votes = poll.total_votes.safe_add(1)?;
+        poll.total_weight = poll.total_weight.safe_add(weight)?;
+        poll.close_if_full();
+        ctx.accounts.nullifier.bump = ctx.bumps.nullifier;
+
+        if poll.emits(EventVerbosity::Standard) {
+            emit_cpi!(PrivateVoteCast {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                voter,
+                ciphertexts: ciphertexts.iter().map(|ciphertext| ciphertext.0).collect(),
+                total_votes: poll.total_votes,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        Ok(())
+    }
+
+    /// Posts the decrypted per-candidate counts of a closed private-ballot
+    /// poll, signed by its tally authority, so it can be finalized. Each
+    /// count is checked against the encrypted aggregate: the authority
+    /// verifies a `ZeroBalance` proof that `aggregate - count` encrypts zero
+    /// for each candidate, passed in candidate order as remaining accounts.
+    pub fn post_private_tally(
+        ctx: Context<PostPrivateTally>,
+        _poll_id: u64,
+        counts: Vec<u64>,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.status == PollStatus::Closed, VotingError::PollNotClosed);
+        require!(!poll.tallies_aggregated, VotingError::TallyAlreadyPosted);
+        let tally = &ctx.accounts.encrypted_tally;
+        require!(
+            counts.len() == tally.tallies.len()
+                && ctx.remaining_accounts.len() == counts.len(),
+            VotingError::InvalidBallotProof
+        );
+
+        let mut total = 0u64;
+        for ((count, sum), proof) in counts.iter().zip(&tally.tallies).zip(ctx.remaining_accounts) {
+            BallotProofs::verify_count(&ElGamalCiphertext(*sum), *count, &tally.elgamal_pubkey, proof)?;
+            total = total.safe_add(*count)?;
+        }
+        require!(total == poll.total_votes, VotingError::InvalidBallotProof);
+
+        for (candidate, count) in poll.candidates.iter_mut().zip(&counts) {
+            candidate.votes = *count;
+            candidate.weight = count.safe_mul(BALLOT_WEIGHT)?;
+        }
+        poll.tallies_aggregated = true;
+
+        msg!("Private tally of poll '{}' posted", poll.title);
+        emit_cpi!(PrivateTallyPosted {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            counts,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Casts a ballot on a poll created with a `ballot_key`, encrypted to that
+    /// key so nobody but the admin can read it before the reveal. The voter
+    /// picks a random scalar `r` and sends `ephemeral_key = r·G` (Ristretto)
+    /// and their candidate index XORed with the first byte of
+    /// `EncryptedBallot::mask(r·ballot_key, poll, voter)`. The ballot waits
+    /// in an `EncryptedBallot` PDA, paid by the voter, until
+    /// `tally_encrypted_votes` decrypts and counts it.
+    pub fn vote_encrypted(
+        ctx: Context<VoteEncrypted>,
+        _poll_id: u64,
+        ephemeral_key: [u8; 32],
+        masked_choice: u8,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        let voter = ctx.accounts.voter.key();
+        let poll = &mut ctx.accounts.poll;
+
+        if poll.expire()? {
+            ctx.accounts
+                .ballot
+                .close(ctx.accounts.voter.to_account_info())?;
+            msg!(
+                "Poll '{}' closed at its deadline; vote by {} not counted",
+                poll.title,
+                voter
+            );
+            emit_cpi!(PollClosed {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                total_votes: poll.total_votes,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+            return Ok(());
+        }
+
+        poll.check_encrypted_ballot(ctx.accounts.membership.as_deref())?;
+        poll.throttle()?;
+        require!(
+            validate_ristretto(&PodRistrettoPoint(ephemeral_key)),
+            VotingError::InvalidEncryptedBallot
+        );
+        poll.sealed_ballots = poll.sealed_ballots.safe_add(1)?;
+
+        let ballot = &mut ctx.accounts.ballot;
+        ballot.poll = poll.key();
+        ballot.voter = voter;
+        ballot.ephemeral_key = ephemeral_key;
+        ballot.masked_choice = masked_choice;
+        ballot.bump = ctx.bumps.ballot;
+
+        if poll.emits(EventVerbosity::Standard) {
+            msg!("Encrypted ballot cast by {} in poll '{}'", voter, poll.title);
+            emit_cpi!(EncryptedVoteCast {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                voter,
+                sealed_ballots: poll.sealed_ballots,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        Ok(())
+    }
+
+    /// Publishes the secret scalar of a closed poll's `ballot_key` so its
+    /// encrypted ballots can be counted, and returns the `reveal_bond` to the
+    /// admin. Must happen by `reveal_deadline`; after that the bond can be
+    /// slashed with `slash_reveal_bond` instead. Cancelled polls can still be
+    /// revealed in time, to get the bond back.
+    pub fn reveal_ballot_key(
+        ctx: Context<RevealBallotKey>,
+        _poll_id: u64,
+        secret: [u8; 32],
+    ) -> Result<()> {
+        let poll_info = ctx.accounts.poll.to_account_info();
+        let poll = &mut ctx.accounts.poll;
+        require!(
+            poll.ballot_key != Pubkey::default(),
+            VotingError::EncryptedBallotsDisabled
+        );
+        require!(
+            matches!(poll.status, PollStatus::Closed | PollStatus::Cancelled),
+            VotingError::PollNotClosed
+        );
+        require!(!poll.ballot_key_revealed(), VotingError::BallotKeyRevealed);
+        require!(
+            poll.now()? <= poll.reveal_deadline()?,
+            VotingError::RevealDeadlinePassed
+        );
+        require!(
+            multiply_ristretto(&PodScalar(secret), &PodRistrettoPoint(RISTRETTO_BASEPOINT))
+                .is_some_and(|key| key.0 == poll.ballot_key.to_bytes()),
+            VotingError::InvalidBallotKey
+        );
+
+        poll.ballot_secret = secret;
+        let bond = poll.reveal_bond;
+        poll.reveal_bond = 0;
+        move_lamports(&poll_info, &ctx.accounts.admin.to_account_info(), bond)?;
+        if poll.sealed_ballots == 0 {
+            poll.tallies_aggregated = true;
+        }
+
+        msg!("Ballot key of poll '{}' revealed", poll.title);
+        emit_cpi!(BallotKeyRevealed {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            secret,
+            sealed_ballots: poll.sealed_ballots,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Crank that decrypts and counts encrypted ballots once the ballot key
+    /// is revealed. Anyone may call it with `(EncryptedBallot, voter)`
+    /// account pairs as remaining accounts; each ballot is closed to its
+    /// voter. Ballots that don't decrypt to a candidate are spoiled. When
+    /// the last one is counted the poll can be finalized. On cancelled polls
+    /// ballots are closed without counting, returning voters' rent.
+    pub fn tally_encrypted_votes<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TallyEncryptedVotes<'info>>,
+        _poll_id: u64,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(
+            poll.ballot_key != Pubkey::default(),
+            VotingError::EncryptedBallotsDisabled
+        );
+        let cancelled = poll.status == PollStatus::Cancelled;
+        require!(
+            cancelled || poll.status == PollStatus::Closed,
+            VotingError::PollNotClosed
+        );
+        require!(
+            cancelled || poll.ballot_key_revealed(),
+            VotingError::BallotKeyNotRevealed
+        );
+        require!(
+            ctx.remaining_accounts.len().is_multiple_of(2),
+            VotingError::InvalidEncryptedBallot
+        );
+
+        let (mut counted, mut spoiled) = (0u32, 0u32);
+        for pair in ctx.remaining_accounts.chunks(2) {
+            let ballot = Account::<EncryptedBallot>::try_from(&pair[0])?;
+            require!(
+                ballot.poll == poll.key() && ballot.voter == pair[1].key(),
+                VotingError::InvalidEncryptedBallot
+            );
+            if !cancelled {
+                match ballot.decrypt(&poll.ballot_secret, poll.candidates.len()) {
+                    Some(index) => {
+                        poll.count_ballot(index, BALLOT_WEIGHT)?;
+                        counted += 1;
+                    }
+                    None => spoiled += 1,
+                }
+            }
+            poll.sealed_ballots = poll.sealed_ballots.safe_sub(1)?;
+            ballot.close(pair[1].clone())?;
+        }
+        if !cancelled && poll.sealed_ballots == 0 {
+            poll.tallies_aggregated = true;
+        }
+
+        msg!(
+            "Tallied {} encrypted ballots of poll '{}' ({} spoiled, {} left)",
+            counted,
+            poll.title,
+            spoiled,
+            poll.sealed_ballots
+        );
+        emit_cpi!(EncryptedVotesTallied {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            counted,
+            spoiled,
+            remaining: poll.sealed_ballots,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Slashes the `reveal_bond` of a poll whose ballot key wasn't revealed
+    /// by its `reveal_deadline`, moving it to the treasury, and cancels the
+    /// poll, which then can never be finalized. Anyone may call this (paying
+    /// for the treasury if no creation fee has created it yet).
+    pub fn slash_reveal_bond(ctx: Context<SlashRevealBond>, _poll_id: u64) -> Result<()> {
+        let poll_info = ctx.accounts.poll.to_account_info();
+        let poll = &mut ctx.accounts.poll;
+        require!(
+            poll.ballot_key != Pubkey::default(),
+            VotingError::EncryptedBallotsDisabled
+        );
+        require!(!poll.ballot_key_revealed(), VotingError::BallotKeyRevealed);
+        poll.expire()?;
+        require!(
+            matches!(poll.status, PollStatus::Closed | PollStatus::Cancelled),
+            VotingError::PollNotClosed
+        );
+        require!(
+            poll.now()? > poll.reveal_deadline()?,
+            VotingError::RevealDeadlineNotPassed
+        );
+
+        let amount = poll.reveal_bond;
+        poll.reveal_bond = 0;
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.bump = ctx.bumps.treasury;
+        move_lamports(&poll_info, &treasury.to_account_info(), amount)?;
+        treasury.total_collected = treasury.total_collected.safe_add(amount)?;
+        if poll.status != PollStatus::Cancelled {
+            poll.status = PollStatus::Cancelled;
+            poll.cancel_reason = "Ballot key not revealed".to_string();
+        }
+
+        msg!(
+            "Reveal bond of {} lamports slashed from poll '{}'",
+            amount,
+            poll.title
+        );
+        emit_cpi!(RevealBondSlashed {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Votes for a write-in candidate on a poll with `allow_write_ins`. Names
+    /// match case-insensitively, ignoring surrounding whitespace: the first
+    /// ballot for a name creates its `WriteInCandidate` PDA (paid by the
+    /// voter) and appends it to the poll's candidates, and later ballots are
+    /// counted there. Each voter still votes once per poll.
+    pub fn vote_write_in(ctx: Context<VoteWriteIn>, _poll_id: u64, name: String) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        let poll_info = ctx.accounts.poll.to_account_info();
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.allow_write_ins, VotingError::WriteInsDisabled);
+
+        let write_in = &mut ctx.accounts.write_in;
+        if write_in.poll == Pubkey::default() {
+            let name = name.trim();
+            let budget = poll.string_limits(&ctx.accounts.config.limits).max_candidate_name_len;
+            require!(
+                !name.is_empty() && name.len() <= budget as usize,
+                VotingError::InvalidWriteIn
+            );
+            require!(
+                !poll
+                    .candidates
+                    .iter()
+                    .any(|c| c.name.trim().eq_ignore_ascii_case(name)),
+                VotingError::WriteInMatchesCandidate
+            );
+            require!(
+                (poll.write_in_count as usize) < MAX_WRITE_INS,
+                VotingError::TooManyWriteIns
+            );
+            write_in.poll = poll.key();
+            write_in.name = name.to_string();
+            write_in.candidate_index = poll.candidates.len() as u8;
+            write_in.bump = ctx.bumps.write_in;
+            poll.candidates.push(Candidate {
+                name: name.to_string(),
+                votes: 0,
+                weight: 0,
+                disqualified: false,
+            });
+            poll.write_in_count = poll.write_in_count.safe_add(1)?;
+            msg!("Write-in '{}' added to poll '{}'", name, poll.title);
+            emit_cpi!(WriteInAdded {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                candidate_index: write_in.candidate_index,
+                name: write_in.name.clone(),
+                proposer: ctx.accounts.voter.key(),
+            });
+        }
+
+        let candidate_index = write_in.candidate_index;
+        let weight = poll.check_ballot(candidate_index, ctx.accounts.membership.as_deref(), None)?;
+        poll.throttle()?;
+        poll.count_ballot(candidate_index, weight)?;
+
+        let vote_record = &mut ctx.accounts.vote_record;
+        vote_record.voter = poll.record_voter(&ctx.accounts.voter.key());
+        vote_record.poll_id = poll.poll_id;
+        vote_record.candidate_index = candidate_index;
+        vote_record.bump = ctx.bumps.vote_record;
+        vote_record.weight = weight;
+        vote_record.delegated_bps = 0;
+        vote_record.changes = Vec::new();
+        vote_record.stamp()?;
+        vote_record.memo = String::new();
+        vote_record.hold_deposit(
+            poll.vote_deposit,
+            ctx.accounts.voter.to_account_info(),
+            poll_info,
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        if poll.emits(EventVerbosity::Standard) {
+            msg!(
+                "Write-in vote cast by {} for '{}' in poll '{}'",
+                ctx.accounts.voter.key(),
+                poll.candidates[candidate_index as usize].name,
+                poll.title
+            );
+            emit_cpi!(VoteCast {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                voter: ctx.accounts.voter.key(),
+                candidate_index,
+                weight,
+                candidate_votes: poll.candidates[candidate_index as usize].votes,
+                total_votes: poll.total_votes,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        if poll.emits(EventVerbosity::Verbose) {
+            emit_cpi!(BallotDetail {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                voter: ctx.accounts.voter.key(),
+                candidate_index,
+                weight,
+                cohort: poll.direct_cohort(),
+                candidate_weight: poll.candidates[candidate_index as usize].weight,
+                total_weight: poll.total_weight,
+            });
+        }
+        Ok(())
+    }
+
+    /// Casts one direct ballot in each of several polls, all or nothing.
+    /// Remaining accounts are `(poll, vote_record)` pairs, writable, with
+    /// the vote record at its usual PDA; `candidate_indices[i]` is the choice
+    /// in the i-th poll. Each ballot passes the same checks as `vote`, except
+    /// that expired polls fail instead of being closed, and voter roll polls
+    /// aren't supported.
+    pub fn vote_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, VoteBatch<'info>>,
+        candidate_indices: Vec<u8>,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        require!(
+            !candidate_indices.is_empty()
+                && candidate_indices.len() <= MAX_BATCH_VOTES
+                && ctx.remaining_accounts.len() == candidate_indices.len() * 2,
+            VotingError::InvalidBatch
+        );
+
+        let voter = ctx.accounts.voter.key();
+        let clock = Clock::get()?;
+        for (pair, &candidate_index) in ctx.remaining_accounts.chunks(2).zip(&candidate_indices) {
+            let (poll_info, record_info) = (&pair[0], &pair[1]);
+            require!(
+                poll_info.owner == &crate::ID && poll_info.is_writable,
+                VotingError::InvalidBatch
+            );
+            let mut poll = Poll::try_deserialize(&mut &poll_info.try_borrow_data()?[..])?;
+            require!(!poll.parallel_tallies, VotingError::ParallelTalliesEnabled);
+            let weight = poll.check_ballot(candidate_index, None, None)?;
+            poll.throttle()?;
+
+            let poll_id = poll.poll_id.to_le_bytes();
+            let (record_key, bump) = Pubkey::find_program_address(
+                &[b"vote", poll.namespace_seed(), &poll_id, voter.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(record_info.key(), record_key, VotingError::InvalidVoteRecord);
+            let space = VoteRecord::space(poll.allow_memos);
+            system_program::create_account(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::CreateAccount {
+                        from: ctx.accounts.voter.to_account_info(),
+                        to: record_info.clone(),
+                    },
+                    &[&[b"vote", poll.namespace_seed(), &poll_id, voter.as_ref(), &[bump]]],
+                ),
+                Rent::get()?.minimum_balance(space),
+                space as u64,
+                &crate::ID,
+            )?;
+            let mut record = VoteRecord {
+                voter: poll.record_voter(&voter),
+                poll_id: poll.poll_id,
+                candidate_index,
+                bump,
+                weight,
+                delegated_bps: 0,
+                changes: Vec::new(),
+                voted_at: clock.unix_timestamp,
+                slot: clock.slot,
+                memo: String::new(),
+                deposit: 0,
+                reward_claimed: false,
+                version: VOTE_RECORD_VERSION,
+                reputation_counted: false,
+                receipt_minted: false,
+                invalidated: false,
+                invalidation_reason: 0,
+                tier: 0,
+            };
+            record.hold_deposit(
+                poll.vote_deposit,
+                ctx.accounts.voter.to_account_info(),
+                poll_info.clone(),
+                ctx.accounts.system_program.to_account_info(),
+            )?;
+            record.try_serialize(&mut &mut record_info.try_borrow_mut_data()?[..])?;
+
+            poll.count_ballot(candidate_index, weight)?;
+            poll.try_serialize(&mut &mut poll_info.try_borrow_mut_data()?[..])?;
+
+            if poll.emits(EventVerbosity::Standard) {
+                emit_cpi!(VoteCast {
+                    poll: poll_info.key(),
+                    poll_id: poll.poll_id,
+                    voter,
+                    candidate_index,
+                    weight,
+                    candidate_votes: poll.candidates[candidate_index as usize].votes,
+                    total_votes: poll.total_votes,
+                    timestamp: clock.unix_timestamp,
+                });
+            }
+        }
+
+        msg!("Batch of {} votes cast by {}", candidate_indices.len(), voter);
+        Ok(())
+    }
+
+    /// Casts `voter`'s ballot from a relayer-submitted transaction; the
+    /// relayer pays fees and rent. The instruction right before this one must
+    /// be an ed25519 program instruction verifying `voter`'s signature over
+    /// `VoteNonce::message(poll, poll_id, candidate_index, nonce)`. `nonce`
+    /// must exceed the voter's last used nonce, so a signed ballot can't be
+    /// replayed; the vote record still allows one ballot per voter.
+    pub fn vote_with_signature(
+        ctx: Context<VoteWithSignature>,
+        _poll_id: u64,
+        voter: Pubkey,
+        candidate_index: u8,
+        nonce: u64,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        let poll_info = ctx.accounts.poll.to_account_info();
+        let poll = &mut ctx.accounts.poll;
+
+        let instructions = ctx.accounts.instructions.to_account_info();
+        let signature_ix = ix_sysvar::get_instruction_relative(-1, &instructions)
+            .map_err(|_| VotingError::InvalidVoteSignature)?;
+        VoteNonce::check_signature(
+            &signature_ix,
+            &voter,
+            &VoteNonce::message(&poll.key(), poll.poll_id, candidate_index, nonce),
+        )?;
+
+        let vote_nonce = &mut ctx.accounts.vote_nonce;
+        require!(nonce > vote_nonce.last_nonce, VotingError::StaleNonce);
+        vote_nonce.voter = voter;
+        vote_nonce.last_nonce = nonce;
+        vote_nonce.bump = ctx.bumps.vote_nonce;
+
+        if poll.expire()? {
+            ctx.accounts
+                .vote_record
+                .close(ctx.accounts.relayer.to_account_info())?;
+            msg!(
+                "Poll '{}' closed at its deadline; vote by {} not counted",
+                poll.title,
+                voter
+            );
+            emit_cpi!(PollClosed {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                total_votes: poll.total_votes,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+            return Ok(());
+        }
+
+        require!(!poll.parallel_tallies, VotingError::ParallelTalliesEnabled);
+        let weight = poll.check_ballot(candidate_index, ctx.accounts.membership.as_deref(), None)?;
+        poll.throttle()?;
+
+        poll.count_ballot(candidate_index, weight)?;
+        let tier = poll.ballot_tier(ctx.accounts.membership.as_deref());
+        poll.tally_tier(tier, candidate_index, true)?;
+
+        let vote_record = &mut ctx.accounts.vote_record;
+        vote_record.tier = tier;
+        vote_record.voter = poll.record_voter(&voter);
+        vote_record.poll_id = poll.poll_id;
+        vote_record.candidate_index = candidate_index;
+        vote_record.bump = ctx.bumps.vote_record;
+        vote_record.weight = weight;
+        vote_record.delegated_bps = 0;
+        vote_record.changes = Vec::new();
+        vote_record.stamp()?;
+        vote_record.memo = String::new();
+        vote_record.hold_deposit(
+            poll.vote_deposit,
+            ctx.accounts.relayer.to_account_info(),
+            poll_info,
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        if poll.emits(EventVerbosity::Standard) {
+            msg!(
+                "Signed vote by {} relayed by {} for candidate '{}' in poll '{}'",
+                voter,
+                ctx.accounts.relayer.key(),
+                poll.candidates[candidate_index as usize].name,
+                poll.title
+            );
+            emit_cpi!(VoteCast {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                voter,
+                candidate_index,
+                weight,
+                candidate_votes: poll.candidates[candidate_index as usize].votes,
+                total_votes: poll.total_votes,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        if poll.emits(EventVerbosity::Verbose) {
+            emit_cpi!(BallotDetail {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                voter,
+                candidate_index,
+                weight,
+                cohort: poll.direct_cohort(),
+                candidate_weight: poll.candidates[candidate_index as usize].weight,
+                total_weight: poll.total_weight,
+            });
+        }
+        Ok(())
+    }
+
+    /// Authorizes `session_key` to sign `vote` for the calling wallet until
+    /// `expires_at` (unix time, at most `MAX_SESSION_DURATION` away), so a
+    /// multi-question ballot needs one wallet approval. The session key pays
+    /// its own vote record rent. Calling again renews or shortens a session.
+    pub fn create_session(
+        ctx: Context<CreateSession>,
+        session_key: Pubkey,
+        expires_at: i64,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            expires_at > now && expires_at - now <= MAX_SESSION_DURATION,
+            VotingError::InvalidSessionExpiry
+        );
+
+        let session_token = &mut ctx.accounts.session_token;
+        session_token.authority = ctx.accounts.authority.key();
+        session_token.session_key = session_key;
+        session_token.expires_at = expires_at;
+        session_token.bump = ctx.bumps.session_token;
+
+        msg!(
+            "Session key {} may vote for {} until {}",
+            session_key,
+            session_token.authority,
+            expires_at
+        );
+        Ok(())
+    }
+
+    /// Runs a ballot through the same eligibility and weight checks as `vote`
+    /// and emits a `PracticeBallot` event, but records nothing. Lets organizers
+    /// run voter-education dry runs against a live poll's real configuration.
+    pub fn practice_vote(ctx: Context<PracticeVote>, _poll_id: u64, candidate_index: u8) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        let weight = poll.check_ballot(candidate_index, ctx.accounts.membership.as_deref(), None)?;
+        let already_voted = !ctx.accounts.vote_record.data_is_empty();
+
+        msg!(
+            "Practice ballot by {} for candidate '{}' in poll '{}' (weight {})",
+            ctx.accounts.voter.key(),
+            poll.candidates[candidate_index as usize].name,
+            poll.title,
+            weight
+        );
+        emit_cpi!(PracticeBallot {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            voter: ctx.accounts.voter.key(),
+            candidate_index,
+            weight,
+            already_voted,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Moves the signer's direct ballot to another candidate on a poll that
+    /// allows vote changes. Each change is appended to the vote record's
+    /// audit history (previous choice, new choice, slot) so tallies stay
+    /// explainable against earlier `VoteCast` events; once the history is
+    /// full, the vote is final. Older vote records are grown to fit.
+    pub fn change_vote(ctx: Context<ChangeVote>, _poll_id: u64, candidate_index: u8) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.allow_vote_changes, VotingError::VoteChangesDisabled);
+        require!(!poll.parallel_tallies, VotingError::ParallelTalliesEnabled);
+        // The existing vote record already proves eligibility
+        poll.assert_accepting_votes()?;
+        poll.check_candidate(candidate_index)?;
+        require!(
+            !poll.candidates[candidate_index as usize].disqualified,
+            VotingError::CandidateDisqualified
+        );
+
+        let vote_record = &mut ctx.accounts.vote_record;
+        require!(!vote_record.invalidated, VotingError::VoteInvalidated);
+        require!(
+            vote_record.candidate_index != SPLIT_BALLOT && vote_record.delegated_bps == 0,
+            VotingError::BallotNotChangeable
+        );
+        require!(
+            vote_record.changes.len() < MAX_VOTE_CHANGES,
+            VotingError::VoteChangeLimitReached
+        );
+        let previous_index = vote_record.candidate_index;
+        require!(previous_index != candidate_index, VotingError::VoteUnchanged);
+
+        let weight = vote_record.weight;
+        poll.move_ballot(previous_index, candidate_index, weight)?;
+        poll.tally_tier(vote_record.tier, previous_index, false)?;
+        poll.tally_tier(vote_record.tier, candidate_index, true)?;
+
+        let slot = Clock::get()?.slot;
+        vote_record.candidate_index = candidate_index;
+        vote_record.changes.push(VoteChange {
+            previous_index,
+            new_index: candidate_index,
+            slot,
+        });
+
+        if poll.emits(EventVerbosity::Standard) {
+            msg!(
+                "Vote by {} in poll '{}' changed from '{}' to '{}'",
+                ctx.accounts.voter.key(),
+                poll.title,
+                poll.candidates[previous_index as usize].name,
+                poll.candidates[candidate_index as usize].name
+            );
+            emit_cpi!(VoteChanged {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                voter: ctx.accounts.voter.key(),
+                previous_index,
+                candidate_index,
+                weight,
+                slot,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        Ok(())
+    }
+
+    /// Attaches a short memo to the signer's ballot, replacing any earlier
+    /// one, on polls created with `allow_memos`. Send it in the same
+    /// transaction as the vote to submit both together; it can be edited
+    /// while the poll accepts votes.
+    pub fn set_vote_memo(ctx: Context<SetVoteMemo>, _poll_id: u64, memo: String) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(poll.allow_memos, VotingError::MemosDisabled);
+        poll.assert_accepting_votes()?;
+        require!(memo.len() <= MAX_VOTE_MEMO_LEN, VotingError::MemoTooLong);
+        ctx.accounts.vote_record.memo = memo;
+        Ok(())
+    }
+
+    /// Closes the signer's vote record once the poll is finalized or
+    /// cancelled, refunding its rent and any vote deposit to the signer. The
+    /// ballot stays in the poll's tallies but drops out of later recounts and
+    /// attendance pages.
+    pub fn close_vote_record(ctx: Context<CloseVoteRecord>, _poll_id: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(
+            poll.is_sealed() || poll.status == PollStatus::Cancelled,
+            VotingError::PollNotOver
+        );
+
+        let deposit = ctx.accounts.vote_record.deposit;
+        if deposit > 0 {
+            move_lamports(
+                &poll.to_account_info(),
+                &ctx.accounts.voter.to_account_info(),
+                deposit,
+            )?;
+        }
+        msg!(
+            "Vote record of {} in poll '{}' closed; {} lamport deposit refunded",
+            ctx.accounts.voter.key(),
+            poll.title,
+            deposit
+        );
+        Ok(())
+    }
+
+    /// Sets (or replaces) how the signer's vote is split across delegates.
+    /// Each share is a percentage in basis points; shares may total less than
+    /// 100%, in which case the remainder is simply not cast by anyone.
+    pub fn set_delegation(ctx: Context<SetDelegation>, shares: Vec<DelegateShare>) -> Result<()> {
+        let delegator = ctx.accounts.delegator.key();
+        require!(shares.len() <= MAX_DELEGATE_SHARES, VotingError::TooManyDelegates);
+
+        let mut total_bps: u32 = 0;
+        for (i, share) in shares.iter().enumerate() {
+            require!(share.bps > 0, VotingError::InvalidDelegation);
+            require!(share.delegate != delegator, VotingError::InvalidDelegation);
+            require!(
+                shares[..i].iter().all(|other| other.delegate != share.delegate),
+                VotingError::InvalidDelegation
+            );
+            total_bps = total_bps.safe_add(share.bps as u32)?;
+        }
+        require!(total_bps <= BPS_DENOMINATOR as u32, VotingError::InvalidDelegation);
+
+        let delegation = &mut ctx.accounts.delegation;
+        delegation.delegator = delegator;
+        delegation.shares = shares;
+        delegation.bump = ctx.bumps.delegation;
+        Ok(())
+    }
+
+    /// Casts the signer's share of a delegator's vote. The share's weight is
+    /// added to the chosen candidate; the delegator's `VoteRecord` tracks how
+    /// much of their ballot has been cast so shares can never exceed 100%,
+    /// even if the split is changed mid-poll. A delegator who already voted
+    /// directly cannot also be voted for.
+    pub fn vote_delegated(
+        ctx: Context<VoteDelegated>,
+        _poll_id: u64,
+        candidate_index: u8,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        let poll_info = ctx.accounts.poll.to_account_info();
+        let poll = &mut ctx.accounts.poll;
+
+        require!(!poll.parallel_tallies, VotingError::ParallelTalliesEnabled);
+        // Split ballots aren't rebuilt by `tally_page`
+        require!(!poll.hide_results_until_close, VotingError::TalliesHidden);
+        // nor do they have a single voter's tier
+        require!(poll.tier_weights.is_empty(), VotingError::InvalidVoterTiers);
+        let ballot_weight =
+            poll.check_ballot(candidate_index, ctx.accounts.membership.as_deref(), None)?;
+        poll.throttle()?;
+
+        let delegate = ctx.accounts.delegate.key();
+        let share_bps = ctx
+            .accounts
+            .delegation
+            .share_of(&delegate)
+            .ok_or(VotingError::NotADelegate)?;
+
+        let ballot = &mut ctx.accounts.delegator_vote_record;
+        if ballot.voter == Pubkey::default() {
+            ballot.voter = poll.record_voter(&ctx.accounts.delegation.delegator);
+            ballot.poll_id = poll.poll_id;
+            ballot.candidate_index = SPLIT_BALLOT;
+            ballot.bump = ctx.bumps.delegator_vote_record;
+            ballot.stamp()?;
+            ballot.hold_deposit(
+                poll.vote_deposit,
+                ctx.accounts.delegate.to_account_info(),
+                poll_info,
+                ctx.accounts.system_program.to_account_info(),
+            )?;
+        } else {
+            // A direct vote leaves nothing for delegates to cast
+            require!(ballot.delegated_bps > 0, VotingError::DelegatorAlreadyVoted);
+        }
+        let used_bps = ballot.delegated_bps.safe_add(share_bps)?;
+        require!(used_bps <= BPS_DENOMINATOR, VotingError::DelegationExhausted);
+
+        let weight = bps_share(ballot_weight, share_bps);
+        ballot.delegated_bps = used_bps;
+        ballot.weight = ballot.weight.safe_add(weight)?;
+
+        let candidate = &mut poll.candidates[candidate_index as usize];
+        candidate.weight = candidate.weight.safe_add(weight)?;
+        poll.total_weight = poll.total_weight.safe_add(weight)?;
+
+        let delegated_vote = &mut ctx.accounts.delegated_vote;
+        delegated_vote.poll_id = poll.poll_id;
+        delegated_vote.delegator = ballot.voter;
+        delegated_vote.delegate = delegate;
+        delegated_vote.candidate_index = candidate_index;
+        delegated_vote.weight = weight;
+        delegated_vote.bump = ctx.bumps.delegated_vote;
+
+        if poll.emits(EventVerbosity::Standard) {
+            msg!(
+                "Delegate {} cast {} bps of {}'s vote for '{}' in poll '{}'",
+                delegate,
+                share_bps,
+                ballot.voter,
+                poll.candidates[candidate_index as usize].name,
+                poll.title
+            );
+            emit_cpi!(DelegatedVoteCast {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                delegator: ballot.voter,
+                delegate,
+                candidate_index,
+                weight,
+                candidate_weight: poll.candidates[candidate_index as usize].weight,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        if poll.emits(EventVerbosity::Verbose) {
+            emit_cpi!(BallotDetail {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                voter: delegate,
+                candidate_index,
+                weight,
+                cohort: VoterCohort::Delegated,
+                candidate_weight: poll.candidates[candidate_index as usize].weight,
+                total_weight: poll.total_weight,
+            });
+        }
+        Ok(())
+    }
+
+    /// Names `proxy` to cast the signer's whole ballot in one poll, replacing
+    /// any earlier proxy for it. Unlike `set_delegation`, this applies to a
+    /// single poll and can be revoked until the proxy votes.
+    pub fn set_poll_proxy(ctx: Context<SetPollProxy>, _poll_id: u64, proxy: Pubkey) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(!poll.parallel_tallies, VotingError::ParallelTalliesEnabled);
+        let delegator = ctx.accounts.delegator.key();
+        require!(proxy != delegator, VotingError::InvalidDelegation);
+
+        let poll_proxy = &mut ctx.accounts.poll_proxy;
+        require!(!poll_proxy.voted, VotingError::ProxyAlreadyVoted);
+        poll_proxy.poll = poll.key();
+        poll_proxy.delegator = delegator;
+        poll_proxy.proxy = proxy;
+        poll_proxy.bump = ctx.bumps.poll_proxy;
+
+        msg!("{} named {} as proxy in poll '{}'", delegator, proxy, poll.title);
+        Ok(())
+    }
+
+    /// Withdraws the signer's proxy for a poll, refunding its rent. Fails
+    /// once the proxy has voted; `override_proxy_vote` replaces that ballot.
+    pub fn revoke_delegation(ctx: Context<RevokeDelegation>, _poll_id: u64) -> Result<()> {
+        msg!(
+            "{} revoked proxy {} in poll '{}'",
+            ctx.accounts.delegator.key(),
+            ctx.accounts.poll_proxy.proxy,
+            ctx.accounts.poll.title
+        );
+        Ok(())
+    }
+
+    /// Casts a delegator's whole ballot as their poll-scoped proxy. The
+    /// ballot lands in the delegator's own vote record, so a delegator who
+    /// already voted blocks the proxy.
+    pub fn vote_as_proxy(
+        ctx: Context<VoteAsProxy>,
+        _poll_id: u64,
+        candidate_index: u8,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        let poll_info = ctx.accounts.poll.to_account_info();
+        let poll = &mut ctx.accounts.poll;
+        require!(!poll.parallel_tallies, VotingError::ParallelTalliesEnabled);
+        let weight = poll.check_ballot(candidate_index, ctx.accounts.membership.as_deref(), None)?;
+        poll.throttle()?;
+
+        poll.count_ballot(candidate_index, weight)?;
+        let tier = poll.ballot_tier(ctx.accounts.membership.as_deref());
+        poll.tally_tier(tier, candidate_index, true)?;
+
+        let poll_proxy = &mut ctx.accounts.poll_proxy;
+        poll_proxy.voted = true;
+        let vote_record = &mut ctx.accounts.vote_record;
+        vote_record.tier = tier;
+        vote_record.voter = poll.record_voter(&poll_proxy.delegator);
+        vote_record.poll_id = poll.poll_id;
+        vote_record.candidate_index = candidate_index;
+        vote_record.bump = ctx.bumps.vote_record;
+        vote_record.weight = weight;
+        vote_record.delegated_bps = 0;
+        vote_record.changes = Vec::new();
+        vote_record.stamp()?;
+        vote_record.memo = String::new();
+        vote_record.hold_deposit(
+            poll.vote_deposit,
+            ctx.accounts.proxy.to_account_info(),
+            poll_info,
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        if poll.emits(EventVerbosity::Standard) {
+            msg!(
+                "Proxy {} voted for {} for candidate '{}' in poll '{}'",
+                poll_proxy.proxy,
+                poll_proxy.delegator,
+                poll.candidates[candidate_index as usize].name,
+                poll.title
+            );
+            emit_cpi!(DelegatedVoteCast {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                delegator: poll_proxy.delegator,
+                delegate: poll_proxy.proxy,
+                candidate_index,
+                weight,
+                candidate_weight: poll.candidates[candidate_index as usize].weight,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        Ok(())
+    }
+
+    /// Replaces the ballot the signer's proxy cast with the signer's own
+    /// choice while the poll accepts votes, and retires the proxy. This is
+    /// not a `change_vote`: it works without `allow_vote_changes` and isn't
+    /// counted in the record's change history.
+    pub fn override_proxy_vote(
+        ctx: Context<OverrideProxyVote>,
+        _poll_id: u64,
+        candidate_index: u8,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        let poll = &mut ctx.accounts.poll;
+        // The proxy's ballot already proved eligibility
+        poll.assert_accepting_votes()?;
+        poll.check_candidate(candidate_index)?;
+        require!(
+            !poll.candidates[candidate_index as usize].disqualified,
+            VotingError::CandidateDisqualified
+        );
+
+        let vote_record = &mut ctx.accounts.vote_record;
+        require!(!vote_record.invalidated, VotingError::VoteInvalidated);
+        let previous_index = vote_record.candidate_index;
+        let weight = vote_record.weight;
+        poll.move_ballot(previous_index, candidate_index, weight)?;
+        poll.tally_tier(vote_record.tier, previous_index, false)?;
+        poll.tally_tier(vote_record.tier, candidate_index, true)?;
+        vote_record.candidate_index = candidate_index;
+
+        if poll.emits(EventVerbosity::Standard) {
+            msg!(
+                "{} overrode proxy {}'s vote in poll '{}'",
+                vote_record.voter,
+                ctx.accounts.poll_proxy.proxy,
+                poll.title
+            );
+            emit_cpi!(VoteChanged {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                voter: vote_record.voter,
+                previous_index,
+                candidate_index,
+                weight,
+                slot: Clock::get()?.slot,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        Ok(())
+    }
+
+    /// Creates the tally counter for one candidate of a parallel-tally poll,
+    /// or on polls with `tally_shards` one of its `shard`s (otherwise 0).
+    /// The admin must initialize every counter of a candidate before votes
+    /// for that candidate can be cast.
+    pub fn init_candidate_tally(
+        ctx: Context<InitCandidateTally>,
+        _poll_id: u64,
+        candidate_index: u8,
+        shard: u8,
+    ) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(
+            poll.admin == ctx.accounts.admin.key(),
+            VotingError::Unauthorized
+        );
+        require!(poll.parallel_tallies, VotingError::ParallelTalliesDisabled);
+        require!(
+            (candidate_index as usize) < poll.candidates.len(),
+            VotingError::InvalidCandidate
+        );
+        require!(
+            (shard as usize) < poll.tally_shard_count(),
+            VotingError::InvalidTallyShard
+        );
+
+        let tally = &mut ctx.accounts.tally;
+        tally.poll = poll.key();
+        tally.candidate_index = candidate_index;
+        tally.votes = 0;
+        tally.weight = 0;
+        tally.bump = ctx.bumps.tally;
+        tally.shard = shard;
+        Ok(())
+    }
+
+    /// Casts a vote on a parallel-tally poll. The poll account is only read,
+    /// so votes for different candidates can land in the same slot; with
+    /// `tally_shards`, so can votes for the same candidate from voters
+    /// counted by different shards (see `Poll::tally_shard`). `VoteCast`
+    /// then reports the shard's count for the candidate.
+    pub fn vote_parallel(
+        ctx: Context<VoteParallel>,
+        _poll_id: u64,
+        candidate_index: u8,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        let poll = &ctx.accounts.poll;
+
+        require!(poll.parallel_tallies, VotingError::ParallelTalliesDisabled);
+        let weight = poll.check_ballot(candidate_index, ctx.accounts.membership.as_deref(), None)?;
+
+        let tally = &mut ctx.accounts.tally;
+        tally.votes = tally.votes.safe_add(1)?;
+        tally.weight = tally.weight.safe_add(weight)?;
+
+        let vote_record = &mut ctx.accounts.vote_record;
+        vote_record.voter = poll.record_voter(&ctx.accounts.voter.key());
+        vote_record.poll_id = poll.poll_id;
+        vote_record.candidate_index = candidate_index;
+        vote_record.bump = ctx.bumps.vote_record;
+        vote_record.weight = weight;
+        vote_record.delegated_bps = 0;
+        vote_record.changes = Vec::new();
+        vote_record.stamp()?;
+        vote_record.memo = String::new();
+        vote_record.deposit = 0;
+
+        if poll.emits(EventVerbosity::Standard) {
+            msg!(
+                "Vote cast by {} for candidate '{}' in poll '{}'",
+                ctx.accounts.voter.key(),
+                poll.candidates[candidate_index as usize].name,
+                poll.title
+            );
+            emit_cpi!(VoteCast {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                voter: ctx.accounts.voter.key(),
+                candidate_index,
+                weight,
+                candidate_votes: ctx.accounts.tally.votes,
+                total_votes: poll.total_votes,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        if poll.emits(EventVerbosity::Verbose) {
+            emit_cpi!(BallotDetail {
+                poll: poll.key(),
+                poll_id: poll.poll_id,
+                voter: ctx.accounts.voter.key(),
+                candidate_index,
+                weight,
+                cohort: poll.direct_cohort(),
+                candidate_weight: ctx.accounts.tally.weight,
+                total_weight: poll.total_weight,
+            });
+        }
+        Ok(())
+    }
+
+    /// Folds the per-candidate counters of a closed parallel-tally poll back
+    /// into `poll.candidates` and `poll.total_votes`. The tally accounts must be
+    /// passed as remaining accounts in candidate index order, each candidate's
+    /// shards in order on polls with `tally_shards`. Anyone may call this; the
+    /// result only depends on the (frozen) counters.
+    pub fn aggregate_tallies(ctx: Context<AggregateTallies>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+
+        require!(poll.status == PollStatus::Closed, VotingError::PollNotClosed);
+        require!(poll.parallel_tallies, VotingError::ParallelTalliesDisabled);
+        let shards = poll.tally_shard_count();
+        require!(
+            ctx.remaining_accounts.len() == poll.candidates.len() * shards,
+            VotingError::InvalidTallyAccount
+        );
+
+        let poll_key = poll.key();
+        let mut total_votes = 0u64;
+        let mut total_weight = 0u64;
+        for candidate in poll.candidates.iter_mut() {
+            candidate.votes = 0;
+            candidate.weight = 0;
+        }
+        for (position, info) in ctx.remaining_accounts.iter().enumerate() {
+            let (index, shard) = (position / shards, position % shards);
+            require!(info.owner == &crate::ID, VotingError::InvalidTallyAccount);
+            let tally = CandidateTally::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+            require!(
+                tally.poll == poll_key
+                    && tally.candidate_index as usize == index
+                    && tally.shard as usize == shard,
+                VotingError::InvalidTallyAccount
+            );
+            let candidate = &mut poll.candidates[index];
+            candidate.votes = candidate.votes.safe_add(tally.votes)?;
+            candidate.weight = candidate.weight.safe_add(tally.weight)?;
+            total_votes = total_votes.safe_add(tally.votes)?;
+            total_weight = total_weight.safe_add(tally.weight)?;
+        }
+        poll.total_votes = total_votes;
+        poll.total_weight = total_weight;
+        poll.tallies_aggregated = true;
+
+        msg!("Tallies aggregated for poll '{}': {} votes", poll.title, total_votes);
+        Ok(())
+    }
+
+    /// Starts an independent recount of a closed poll's `VoteRecord`s.
+    /// Anyone may call this and then crank `tally_page` until every record is
+    /// processed. Each cranker keeps their own `TallyState`, so a page that
+    /// skips records only spoils the recount of whoever signed it; calling
+    /// this again restarts the cranker's recount from scratch.
+    pub fn begin_tally(ctx: Context<BeginTally>, _poll_id: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(
+            poll.status == PollStatus::Closed || poll.is_sealed(),
+            VotingError::PollNotClosed
+        );
+
+        let tally_state = &mut ctx.accounts.tally_state;
+        tally_state.poll = poll.key();
+        tally_state.votes = vec![0; poll.candidates.len()];
+        tally_state.weights = vec![0; poll.candidates.len()];
+        tally_state.processed = 0;
+        tally_state.last_voter = Pubkey::default();
+        tally_state.checkpoint = [0; 32];
+        tally_state.bump = ctx.bumps.tally_state;
+        tally_state.cranker = ctx.accounts.cranker.key();
+        Ok(())
+    }
+
+    /// Processes one page of `VoteRecord`s, passed as remaining accounts in
+    /// strictly ascending voter order, continuing from the previous page.
+    /// Each record is folded into `checkpoint`, so the final hash commits to
+    /// exactly which records were counted and in what order; anyone can
+    /// recompute it from the poll's vote records sorted by voter. Records
+    /// struck by `invalidate_vote` are folded in but not counted. A failed
+    /// page changes nothing and can simply be retried. Only the cranker who
+    /// began the recount can add pages to it.
+    pub fn tally_page(ctx: Context<TallyPage>, _poll_id: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        let tally_state = &mut ctx.accounts.tally_state;
+
+        for info in ctx.remaining_accounts.iter() {
+            let record = poll.load_vote_record(info)?;
+            require!(
+                record.voter > tally_state.last_voter,
+                VotingError::TallyOutOfOrder
+            );
+
+            tally_state.checkpoint = TallyState::next_checkpoint(
+                &tally_state.checkpoint,
+                &record.voter,
+                record.candidate_index,
+                record.weight,
+            );
+            // Split ballots are counted by their `DelegatedVote`s, not here,
+            // and invalidated ones not at all
+            if record.candidate_index != SPLIT_BALLOT && !record.invalidated {
+                let index = record.candidate_index as usize;
+                require!(
+                    index < tally_state.votes.len(),
+                    VotingError::InvalidVoteRecord
+                );
+                tally_state.votes[index] = tally_state.votes[index].safe_add(1)?;
+                tally_state.weights[index] = tally_state.weights[index].safe_add(record.weight)?;
+            }
+            tally_state.processed = tally_state.processed.safe_add(1)?;
+            tally_state.last_voter = record.voter;
+        }
+
+        msg!(
+            "Tallied {} records for poll '{}' ({} total)",
+            ctx.remaining_accounts.len(),
+            poll.title,
+            tally_state.processed
+        );
+        Ok(())
+    }
+
+    /// Rebuilds the candidate tallies of a closed poll created with
+    /// `hide_results_until_close` from a completed `tally_page` recount, so
+    /// it can be finalized. The recount, whoever cranked it, must account for
+    /// every ballot in the poll's totals. Anyone may call this.
+    pub fn reveal_tallies(ctx: Context<RevealTallies>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.status == PollStatus::Closed, VotingError::PollNotClosed);
+        require!(poll.tallies_hidden(), VotingError::TalliesNotHidden);
+        let tally_state = &ctx.accounts.tally_state;
+        let votes = tally_state.votes.iter().try_fold(0u64, |sum, n| sum.safe_add(*n))?;
+        let weight = tally_state.weights.iter().try_fold(0u64, |sum, w| sum.safe_add(*w))?;
+        require!(
+            tally_state.votes.len() == poll.candidates.len()
+                && votes == poll.total_votes
+                && weight == poll.total_weight,
+            VotingError::RecountIncomplete
+        );
+
+        for ((candidate, votes), weight) in poll
+            .candidates
+            .iter_mut()
+            .zip(&tally_state.votes)
+            .zip(&tally_state.weights)
+        {
+            candidate.votes = *votes;
+            candidate.weight = *weight;
+        }
+        poll.tallies_aggregated = true;
+
+        msg!("Tallies of poll '{}' revealed: {} votes", poll.title, votes);
+        emit_cpi!(TalliesRevealed {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            votes: tally_state.votes.clone(),
+            checkpoint: tally_state.checkpoint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Publishes one page of a finalized poll's attendance: the voters of the
+    /// `VoteRecord`s passed as remaining accounts, in strictly ascending voter
+    /// order continuing from the previous page, emitted without their
+    /// choices. Only for polls created with `publish_attendance`; anyone may
+    /// crank it.
+    pub fn publish_attendance(ctx: Context<PublishAttendance>, _poll_id: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(poll.publish_attendance, VotingError::AttendanceNotPublished);
+        require!(poll.is_sealed(), VotingError::PollNotFinalized);
+
+        let roll = &mut ctx.accounts.attendance;
+        if roll.poll == Pubkey::default() {
+            roll.poll = poll.key();
+            roll.bump = ctx.bumps.attendance;
+        }
+        let mut voters = Vec::with_capacity(ctx.remaining_accounts.len());
+        for info in ctx.remaining_accounts.iter() {
+            let record = poll.load_vote_record(info)?;
+            require!(record.voter > roll.last_voter, VotingError::TallyOutOfOrder);
+            roll.last_voter = record.voter;
+            voters.push(record.voter);
+        }
+        roll.published = roll.published.safe_add(voters.len() as u64)?;
+        let page = roll.pages;
+        roll.pages = page.safe_add(1)?;
+
+        msg!(
+            "Published {} voters of poll '{}' ({} total)",
+            voters.len(),
+            poll.title,
+            roll.published
+        );
+        emit_cpi!(AttendancePublished {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            page,
+            voters,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Closes a poll so no more votes can be cast. By default the admin, or a
+    /// co-admin holding `PERMISSION_CLOSE`, can close, as can the poll's
+    /// operations key. Before the config's minimum duration has passed, only
+    /// polls without votes, or multisig polls whose approvers signed off, can
+    /// be closed.
+    pub fn close_poll(ctx: Context<ClosePoll>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        if !poll.is_operations_key(&ctx.accounts.admin.key()) {
+            ctx.accounts.config.assert_permission(
+                &ctx.accounts.admin.key(),
+                Some(poll),
+                ctx.accounts.role.as_deref(),
+                ACTION_CLOSE,
+            )?;
+        }
+        require!(poll.status == PollStatus::Active, VotingError::PollClosed);
+        poll.assert_approved(MultisigAction::Close)?;
+        require!(
+            poll.total_votes == 0 || poll.approval_threshold > 0 || poll.now()? >= poll.earliest_close,
+            VotingError::MinDurationNotMet
+        );
+        let timestamp = Clock::get()?.unix_timestamp;
+        poll.status = PollStatus::Closed;
+        poll.closed_at = timestamp;
+        poll.audit(
+            ctx.accounts.audit_log.as_mut(),
+            ctx.accounts.admin.key(),
+            AuditAction::Close,
+            &[],
+        )?;
+        msg!("Poll '{}' has been closed", poll.title);
+        emit_cpi!(PollClosed {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            total_votes: poll.total_votes,
+            timestamp,
+        });
+        Ok(())
+    }
+
+    /// Opens the current page of a poll's audit log: the first one before
+    /// any admin action is logged, and each later one once the previous page
+    /// is full. Anyone may pay for it.
+    pub fn open_audit_page(ctx: Context<OpenAuditPage>, _poll_id: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(poll.audit_log, VotingError::AuditLogDisabled);
+        let log = &mut ctx.accounts.audit_log;
+        log.poll = poll.key();
+        log.page = poll.audit_page();
+        log.bump = ctx.bumps.audit_log;
+        msg!("Audit log page {} of poll '{}' opened", log.page, poll.title);
+        Ok(())
+    }
+
+    /// Closes a poll whose deadline has passed. Anyone may call this so
+    /// results freeze without the admin; multisig approvals are not needed
+    /// because the deadline was agreed at creation.
+    pub fn crank_close_expired(ctx: Context<CrankCloseExpired>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.expire()?, VotingError::PollNotExpired);
+        msg!("Poll '{}' closed at its deadline", poll.title);
+        emit_cpi!(PollClosed {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            total_votes: poll.total_votes,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Suspends voting on an active poll, e.g. while irregularities are
+    /// investigated, without closing it: ballots fail with `PollFrozen`
+    /// until `unfreeze_poll`, while the deadline keeps running. Needs the
+    /// same permission as closing.
+    pub fn freeze_poll(ctx: Context<FreezePoll>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            ctx.accounts.role.as_deref(),
+            ACTION_CLOSE,
+        )?;
+        require!(poll.status == PollStatus::Active, VotingError::PollClosed);
+        require!(!poll.frozen, VotingError::PollFrozen);
+
+        poll.frozen = true;
+        poll.audit(
+            ctx.accounts.audit_log.as_mut(),
+            ctx.accounts.admin.key(),
+            AuditAction::Freeze,
+            &[],
+        )?;
+        msg!("Poll '{}' has been frozen", poll.title);
+        emit_cpi!(PollFrozen {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            frozen_by: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Lets a frozen poll take ballots again.
+    pub fn unfreeze_poll(ctx: Context<FreezePoll>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            ctx.accounts.role.as_deref(),
+            ACTION_CLOSE,
+        )?;
+        require!(poll.frozen, VotingError::PollNotFrozen);
+
+        poll.frozen = false;
+        poll.audit(
+            ctx.accounts.audit_log.as_mut(),
+            ctx.accounts.admin.key(),
+            AuditAction::Unfreeze,
+            &[],
+        )?;
+        msg!("Poll '{}' has been unfrozen", poll.title);
+        emit_cpi!(PollUnfrozen {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            unfrozen_by: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Puts a poll closed by mistake back into voting. Only polls closed with
+    /// `close_poll` (not ones whose deadline has passed) can be reopened,
+    /// before finalization or aggregation and within the `reopen_window`
+    /// chosen at creation. Needs the same permission as closing; approvals
+    /// already given for the close still count if it is closed again.
+    pub fn reopen_poll(ctx: Context<ReopenPoll>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            ctx.accounts.role.as_deref(),
+            ACTION_CLOSE,
+        )?;
+        require!(
+            poll.status == PollStatus::Closed
+                && poll.closed_at != 0
+                && !poll.tallies_aggregated
+                && !poll.ballot_key_revealed()
+                && (poll.end_ts == 0 || poll.now()? < poll.end_ts),
+            VotingError::PollNotReopenable
+        );
+        let timestamp = Clock::get()?.unix_timestamp;
+        require!(
+            timestamp - poll.closed_at < poll.reopen_window,
+            VotingError::ReopenWindowElapsed
+        );
+
+        poll.status = PollStatus::Active;
+        poll.closed_at = 0;
+        poll.tie_break_randomness = Pubkey::default();
+        poll.audit(
+            ctx.accounts.audit_log.as_mut(),
+            ctx.accounts.admin.key(),
+            AuditAction::Reopen,
+            &[],
+        )?;
+        msg!("Poll '{}' has been reopened", poll.title);
+        emit_cpi!(PollReopened {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            reopened_by: ctx.accounts.admin.key(),
+            total_votes: poll.total_votes,
+            timestamp,
+        });
+        Ok(())
+    }
+
+    /// Pushes back the deadline of an active poll. `new_end_ts` is on the
+    /// poll's clock, must be later than the current deadline, and may be at
+    /// most `max_extension` past the deadline the poll was created with.
+    pub fn extend_deadline(ctx: Context<ExtendDeadline>, new_end_ts: i64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            None,
+            ACTION_MODERATE,
+        )?;
+        require!(poll.status == PollStatus::Active, VotingError::PollClosed);
+        require!(
+            poll.end_ts == 0 || poll.now()? < poll.end_ts,
+            VotingError::VotingEnded
+        );
+        require!(
+            poll.end_ts != 0
+                && new_end_ts > poll.end_ts
+                && new_end_ts - poll.original_end_ts <= poll.max_extension,
+            VotingError::InvalidDeadlineExtension
+        );
+
+        let previous_end_ts = poll.end_ts;
+        poll.end_ts = new_end_ts;
+        poll.audit(
+            ctx.accounts.audit_log.as_mut(),
+            ctx.accounts.admin.key(),
+            AuditAction::ExtendDeadline,
+            &new_end_ts.to_le_bytes(),
+        )?;
+        msg!(
+            "Deadline of poll '{}' extended from {} to {}",
+            poll.title,
+            previous_end_ts,
+            new_end_ts
+        );
+        emit_cpi!(DeadlineExtended {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            previous_end_ts,
+            new_end_ts,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Opens (publishes) a poll created with `start_as_draft` for voting.
+    /// Until then the admin can rework it freely with `edit_draft`; from here
+    /// on its candidates and settings are locked, leaving only the pre-vote
+    /// title and metadata fixes. Activating is a moderation action
+    /// (admin-only by default).
+    pub fn activate_poll(ctx: Context<ActivatePoll>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            None,
+            ACTION_MODERATE,
+        )?;
+        require!(poll.status == PollStatus::Draft, VotingError::PollNotDraft);
+        poll.status = PollStatus::Active;
+        if poll.decay_bps > 0 {
+            // Weight decays from when voting actually opens
+            poll.decay_start = poll.decay_start.max(poll.now()?);
+        }
+        msg!("Poll '{}' is now open for voting", poll.title);
+        emit_cpi!(PollActivated {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Reworks a draft poll's title, candidates and settings, as if it had
+    /// been created with them, so a complex ballot can be assembled and
+    /// corrected before `activate_poll` publishes it. The poll keeps its
+    /// address, metadata, voter roll, audit trail and snapshot, and stays a
+    /// draft whatever `start_as_draft` says. v2 polls, whose address commits
+    /// to their creation parameters, can't be reworked, nor can drafts that
+    /// already carry compressed votes or private ballots; the reveal bond
+    /// can't change since it's already held. A moderation action, like
+    /// activating.
+    pub fn edit_draft(
+        ctx: Context<EditDraft>,
+        _poll_id: u64,
+        title: String,
+        candidates: Vec<String>,
+        settings: PollSettings,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            None,
+            ACTION_MODERATE,
+        )?;
+        require!(poll.status == PollStatus::Draft, VotingError::PollNotDraft);
+        require!(
+            !poll.is_content_addressed()
+                && poll.vote_tree == Pubkey::default()
+                && poll.encrypted_tally == Pubkey::default()
+                && settings.reveal_bond == poll.reveal_bond,
+            VotingError::DraftNotReconfigurable
+        );
+
+        let settings_hash = Poll::settings_hash(&poll.admin, &title, &candidates, &settings);
+        poll.audit(
+            ctx.accounts.audit_log.as_mut(),
+            ctx.accounts.admin.key(),
+            AuditAction::EditDraft,
+            &settings_hash,
+        )?;
+        // configure() starts from a blank poll; carry over what was attached
+        // to the draft since it was created
+        let previous = Poll::clone(poll);
+        poll.configure(title, candidates, &settings, &ctx.accounts.config.limits)?;
+        poll.apply_min_duration(ctx.accounts.config.min_poll_duration)?;
+        poll.status = PollStatus::Draft;
+        poll.description = previous.description;
+        poll.metadata_uri = previous.metadata_uri;
+        poll.metadata_hash = previous.metadata_hash;
+        poll.organization = previous.organization;
+        poll.pending_admin = previous.pending_admin;
+        poll.eligible_voter_count = previous.eligible_voter_count;
+        poll.parent_poll = previous.parent_poll;
+        poll.round = previous.round;
+        poll.roll_source = previous.roll_source;
+        poll.vote_filter = previous.vote_filter;
+        poll.operations_key = previous.operations_key;
+        poll.receipt_tree = previous.receipt_tree;
+        poll.audit_entries = previous.audit_entries;
+        poll.snapshot_slot = previous.snapshot_slot;
+
+        msg!(
+            "Draft poll '{}' now has {} candidates",
+            poll.title,
+            poll.candidates.len()
+        );
+        emit_cpi!(DraftEdited {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            title: poll.title.clone(),
+            candidate_count: poll.candidates.len() as u8,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Cancels a poll. Unlike closing, a cancelled poll is invalid: it never
+    /// produces a winner and cannot be finalized. Cancelling is a moderation
+    /// action (admin-only by default) and only possible before finalization.
+    /// `reason` is stored on the poll and emitted for indexers.
+    pub fn cancel_poll(ctx: Context<CancelPoll>, _poll_id: u64, reason: String) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            None,
+            ACTION_MODERATE,
+        )?;
+        require!(
+            matches!(
+                poll.status,
+                PollStatus::Draft | PollStatus::Active | PollStatus::Closed
+            ),
+            VotingError::PollNotCancellable
+        );
+        require!(
+            reason.len() <= MAX_CANCEL_REASON_LEN,
+            VotingError::CancelReasonTooLong
+        );
+        poll.status = PollStatus::Cancelled;
+        poll.cancel_reason = reason.clone();
+        poll.audit(
+            ctx.accounts.audit_log.as_mut(),
+            ctx.accounts.admin.key(),
+            AuditAction::Cancel,
+            reason.as_bytes(),
+        )?;
+        msg!("Poll '{}' has been cancelled: {}", poll.title, reason);
+        emit_cpi!(PollCancelled {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            reason,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Finalizes a closed poll, sealing its tallies and declaring the winner.
+    /// Parallel-tally polls must be aggregated first. By default the admin, or
+    /// a co-admin holding `PERMISSION_FINALIZE`, can finalize, as can the
+    /// poll's operations key.
+    pub fn finalize_poll(ctx: Context<FinalizePoll>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        if !poll.is_operations_key(&ctx.accounts.admin.key()) {
+            ctx.accounts.config.assert_permission(
+                &ctx.accounts.admin.key(),
+                Some(poll),
+                ctx.accounts.role.as_deref(),
+                ACTION_FINALIZE,
+            )?;
+        }
+        poll.seal()?;
+
+        if !poll.release_results()? {
+            msg!(
+                "Poll '{}' sealed; results are embargoed until slot {}",
+                poll.title,
+                poll.embargo_slot
+            );
+            return Ok(());
+        }
+        match poll.winner() {
+            Some(index) => msg!(
+                "Poll '{}' finalized, winner: '{}'",
+                poll.title,
+                poll.candidates[index as usize].name
+            ),
+            None if poll.status == PollStatus::QuorumNotMet => msg!(
+                "Poll '{}' finalized below its quorum; the result is non-binding",
+                poll.title
+            ),
+            None => msg!("Poll '{}' finalized without a single winner", poll.title),
+        }
+        emit_cpi!(poll_finalized_event(poll)?);
+        Ok(())
+    }
+
+    /// First half of a randomness tie-break: binds a closed, tied poll to a
+    /// Switchboard on-demand randomness account committed in the previous
+    /// slot, before its value is known. Same permission as finalizing.
+    pub fn commit_tie_break(ctx: Context<CommitTieBreak>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            ctx.accounts.role.as_deref(),
+            ACTION_FINALIZE,
+        )?;
+        require!(poll.status == PollStatus::Closed, VotingError::PollNotClosed);
+        require!(poll.tied_candidates().len() > 1, VotingError::NotATie);
+
+        let randomness = SwitchboardRandomness::load(&ctx.accounts.randomness)?;
+        require!(
+            randomness.seed_slot == Clock::get()?.slot.saturating_sub(1),
+            VotingError::RandomnessNotFresh
+        );
+        poll.tie_break_randomness = ctx.accounts.randomness.key();
+        poll.tie_break_seed_slot = randomness.seed_slot;
+        msg!("Poll '{}' committed to a tie-break", poll.title);
+        Ok(())
+    }
+
+    /// Second half of a randomness tie-break: finalizes the poll like
+    /// `finalize_poll`, then picks among the tied leaders using the committed
+    /// account's value, which must be revealed in this slot.
+    pub fn finalize_poll_with_randomness(
+        ctx: Context<FinalizePollWithRandomness>,
+        _poll_id: u64,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            ctx.accounts.role.as_deref(),
+            ACTION_FINALIZE,
+        )?;
+        require!(
+            poll.tie_break_randomness != Pubkey::default()
+                && poll.tie_break_randomness == ctx.accounts.randomness.key(),
+            VotingError::TieBreakNotCommitted
+        );
+        let randomness = SwitchboardRandomness::load(&ctx.accounts.randomness)?;
+        require!(
+            randomness.seed_slot == poll.tie_break_seed_slot
+                && randomness.reveal_slot == Clock::get()?.slot,
+            VotingError::RandomnessNotFresh
+        );
+        poll.seal()?;
+
+        if poll.status == PollStatus::Finalized {
+            let tied = poll.tied_candidates();
+            let roll = u64::from_le_bytes(randomness.value[..8].try_into().unwrap());
+            let index = tied[(roll % tied.len() as u64) as usize];
+            poll.tie_winner = Some(index);
+            poll.record_outcome();
+        }
+        if !poll.release_results()? {
+            msg!(
+                "Poll '{}' sealed; results are embargoed until slot {}",
+                poll.title,
+                poll.embargo_slot
+            );
+            return Ok(());
+        }
+        if let Some(index) = poll.tie_winner {
+            msg!(
+                "Poll '{}' finalized, tie broken in favour of '{}'",
+                poll.title,
+                poll.candidates[index as usize].name
+            );
+        }
+        emit_cpi!(poll_finalized_event(poll)?);
+        Ok(())
+    }
+
+    /// Adds lamports to a poll's fee vault, which pays `auto_finalize`
+    /// tips. Anyone may fund it.
+    pub fn fund_fee_vault(ctx: Context<FundFeeVault>, _poll_id: u64, amount: u64) -> Result<()> {
+        let fee_vault = &mut ctx.accounts.fee_vault;
+        if fee_vault.poll == Pubkey::default() {
+            fee_vault.poll = ctx.accounts.poll.key();
+            fee_vault.bump = ctx.bumps.fee_vault;
+        }
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.funder.to_account_info(),
+                    to: fee_vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        Ok(())
+    }
+
+    /// Finalizes a poll once its deadline has passed, for automation networks
+    /// (Clockwork threads or similar). Anyone may call it; the cranker is paid
+    /// the poll's `finalize_tip` from its fee vault, or whatever the vault
+    /// holds above rent if that is less. Parallel-tally polls must be
+    /// aggregated and multisig polls approved first, as with `finalize_poll`.
+    pub fn auto_finalize(ctx: Context<AutoFinalize>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(
+            poll.end_ts != 0 && poll.now()? >= poll.end_ts,
+            VotingError::DeadlineNotReached
+        );
+        poll.expire()?;
+        poll.seal()?;
+
+        let vault = ctx.accounts.fee_vault.to_account_info();
+        let rent = Rent::get()?.minimum_balance(vault.data_len());
+        let tip = poll.finalize_tip.min(vault.lamports().saturating_sub(rent));
+        move_lamports(&vault, &ctx.accounts.cranker.to_account_info(), tip)?;
+
+        msg!(
+            "Poll '{}' auto-finalized; {} lamports paid to {}",
+            poll.title,
+            tip,
+            ctx.accounts.cranker.key()
+        );
+        if poll.release_results()? {
+            emit_cpi!(poll_finalized_event(poll)?);
+        }
+        Ok(())
+    }
+
+    /// Compute-minimal finalization for congested periods: the same checks
+    /// and sealed state as `finalize_poll`, but no logs, no self-CPI and only
+    /// a compact `PollSealed` event, so results can be sealed on time even
+    /// when full finalization won't fit.
+    pub fn finalize_minimal(ctx: Context<FinalizeMinimal>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            ctx.accounts.role.as_deref(),
+            ACTION_FINALIZE,
+        )?;
+        poll.seal()?;
+        let winner = if poll.release_results()? { poll.winner() } else { None };
+        emit!(PollSealed {
+            poll: poll.key(),
+            winner,
+        });
+        Ok(())
+    }
+
+    /// Emits the `PollFinalized` result of a poll sealed during its embargo,
+    /// once the embargo slot is reached. Anyone may call it.
+    pub fn publish_results(ctx: Context<PublishResults>, _poll_id: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.is_sealed(), VotingError::PollNotFinalized);
+        require!(!poll.results_published, VotingError::ResultsAlreadyPublished);
+        require!(poll.release_results()?, VotingError::ResultsEmbargoed);
+
+        msg!("Results of poll '{}' published", poll.title);
+        emit_cpi!(poll_finalized_event(poll)?);
+        Ok(())
+    }
+
+    /// Challenges a sealed poll's result within its `challenge_window`. The
+    /// challenger must have a vote record in the poll and puts the poll's
+    /// `challenge_deposit` into a `Challenge` PDA; until every challenge is
+    /// resolved the result can't be certified or archived. Each voter can
+    /// hold one open challenge per poll.
+    pub fn challenge_result(ctx: Context<ChallengeResult>, _poll_id: u64, reason: String) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.challenge_window > 0, VotingError::ChallengesDisabled);
+        require!(poll.is_sealed(), VotingError::PollNotFinalized);
+        require!(
+            poll.now()? < poll.challenge_deadline()?,
+            VotingError::ChallengeWindowClosed
+        );
+        require!(
+            reason.len() <= MAX_CHALLENGE_REASON_LEN,
+            VotingError::ChallengeReasonTooLong
+        );
+
+        let challenge = &mut ctx.accounts.challenge;
+        if poll.challenge_deposit > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.challenger.to_account_info(),
+                        to: challenge.to_account_info(),
+                    },
+                ),
+                poll.challenge_deposit,
+            )?;
+        }
+        challenge.poll = poll.key();
+        challenge.challenger = ctx.accounts.challenger.key();
+        challenge.reason = reason.clone();
+        challenge.deposit = poll.challenge_deposit;
+        challenge.created_at = Clock::get()?.unix_timestamp;
+        challenge.bump = ctx.bumps.challenge;
+        poll.open_challenges = poll.open_challenges.safe_add(1)?;
+
+        msg!("Result of poll '{}' challenged: {}", poll.title, reason);
+        emit_cpi!(ResultChallenged {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            challenger: challenge.challenger,
+            reason,
+            deposit: challenge.deposit,
+            timestamp: challenge.created_at,
+        });
+        Ok(())
+    }
+
+    /// Resolves a challenge, closing it to the challenger. An upheld
+    /// challenge refunds the deposit and marks the result disputed, so it
+    /// can never be certified; a rejected one forfeits the deposit to the
+    /// treasury. Only the poll's admin (which may be a multisig) can resolve
+    /// challenges, paying for the treasury if it doesn't exist yet.
+    pub fn resolve_challenge(ctx: Context<ResolveChallenge>, _poll_id: u64, upheld: bool) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let challenge = &ctx.accounts.challenge;
+        if upheld {
+            poll.result_disputed = true;
+        } else {
+            let treasury = &mut ctx.accounts.treasury;
+            treasury.bump = ctx.bumps.treasury;
+            move_lamports(
+                &challenge.to_account_info(),
+                &treasury.to_account_info(),
+                challenge.deposit,
+            )?;
+            treasury.total_collected = treasury.total_collected.safe_add(challenge.deposit)?;
+        }
+        poll.open_challenges = poll.open_challenges.safe_sub(1)?;
+
+        msg!(
+            "Challenge by {} to poll '{}' {}",
+            challenge.challenger,
+            poll.title,
+            if upheld { "upheld" } else { "rejected" }
+        );
+        emit_cpi!(ChallengeResolved {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            challenger: challenge.challenger,
+            upheld,
+            deposit: challenge.deposit,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Ratifies a sealed poll's published result: the admin, or the poll's
+    /// `certifier` if one was set at creation, records its
+    /// `canonical_result_hash` as the certified `result_hash`, so off-chain
+    /// systems can treat the election as official. A result is certified once, after its challenge
+    /// window has passed with no challenge open or upheld.
+    pub fn certify_result(ctx: Context<CertifyResult>, _poll_id: u64) -> Result<()> {
+        let signer = ctx.accounts.certifier.key();
+        let poll = &mut ctx.accounts.poll;
+        require!(
+            signer == poll.admin
+                || (poll.certifier != Pubkey::default() && signer == poll.certifier),
+            VotingError::Unauthorized
+        );
+        require!(poll.is_sealed(), VotingError::PollNotFinalized);
+        require!(poll.results_published, VotingError::ResultsEmbargoed);
+        require!(poll.certified_at == 0, VotingError::ResultAlreadyCertified);
+        poll.assert_unchallenged()?;
+        require!(!poll.result_disputed, VotingError::ResultDisputed);
+
+        poll.certified_by = signer;
+        poll.certified_at = Clock::get()?.unix_timestamp;
+        poll.result_hash = poll.canonical_result_hash;
+
+        msg!("Result of poll '{}' certified by {}", poll.title, signer);
+        emit_cpi!(ResultCertified {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            certified_by: signer,
+            result_hash: poll.result_hash,
+            timestamp: poll.certified_at,
+        });
+        Ok(())
+    }
+
+    /// Moves a sealed poll's published result into a compact `PollResult`
+    /// and closes the poll, returning its rent to the admin. The result keeps
+    /// the winner, the per-candidate tallies, the totals and a hash of the
+    /// poll's full account data, so an archived copy of the poll can still
+    /// be checked against it, along with any certification. Prediction
+    /// polls, polls holding vote deposits or conviction locks, with an open
+    /// prize pool, reward vault, rent sponsor or funding round, or whose
+    /// result can still be challenged can't be archived; leftover vote
+    /// records are closed with `close_archived_vote_record`.
+    pub fn archive_poll(ctx: Context<ArchivePoll>, _poll_id: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        poll.assert_archivable([
+            &ctx.accounts.prize_pool,
+            &ctx.accounts.reward_vault,
+            &ctx.accounts.rent_sponsor,
+            &ctx.accounts.funding_round,
+        ])?;
+        let result = &mut ctx.accounts.result;
+        result.archive(poll, ctx.bumps.result)?;
+
+        msg!("Poll '{}' archived", poll.title);
+        emit_cpi!(PollArchived {
+            poll: result.poll,
+            poll_id: result.poll_id,
+            result: result.key(),
+            winner: result.winner,
+            poll_hash: result.poll_hash,
+            timestamp: result.archived_at,
+        });
+        Ok(())
+    }
+
+    /// Cleans up a poll once the config's `retention_period` has passed since
+    /// it was sealed. Anyone may crank it: `VoteRecord`s passed as remaining
+    /// accounts, each followed by its voter's wallet, are closed with their
+    /// rent and any vote deposit refunded to the voter. Passing `result`
+    /// also archives the poll as `archive_poll` would, under the same
+    /// conditions, with the cranker paying for the `PollResult` and the
+    /// poll's rent going to its admin; archive once the records are
+    /// collected, since deposits are refunded from the poll.
+    pub fn gc_expired_poll<'info>(
+        ctx: Context<'_, '_, '_, 'info, GcExpiredPoll<'info>>,
+        _poll_id: u64,
+    ) -> Result<()> {
+        let retention_period = ctx.accounts.config.retention_period;
+        require!(retention_period > 0, VotingError::GarbageCollectionDisabled);
+        let poll = &ctx.accounts.poll;
+        require!(poll.is_sealed(), VotingError::PollNotFinalized);
+        let collectable_at = poll
+            .sealed_at
+            .checked_add(poll.clock_source.from_seconds(retention_period))
+            .ok_or(VotingError::ArithmeticOverflow)?;
+        require!(poll.now()? >= collectable_at, VotingError::RetentionPeriodActive);
+
+        require!(
+            ctx.remaining_accounts.len().is_multiple_of(2),
+            VotingError::InvalidVoteRecord
+        );
+        let poll_info = poll.to_account_info();
+        let poll_id = poll.poll_id.to_le_bytes();
+        let mut records_closed: u32 = 0;
+        for pair in ctx.remaining_accounts.chunks(2) {
+            let (record_info, voter) = (&pair[0], &pair[1]);
+            require!(record_info.owner == &crate::ID, VotingError::InvalidVoteRecord);
+            let record = VoteRecord::try_deserialize(&mut &record_info.try_borrow_data()?[..])?;
+            let address = Pubkey::create_program_address(
+                &[b"vote", poll.namespace_seed(), &poll_id, voter.key.as_ref(), &[record.bump]],
+                &crate::ID,
+            )
+            .map_err(|_| VotingError::InvalidVoteRecord)?;
+            require_keys_eq!(address, record_info.key(), VotingError::InvalidVoteRecord);
+
+            if record.deposit > 0 {
+                move_lamports(&poll_info, voter, record.deposit)?;
+            }
+            close_account(record_info, voter)?;
+            records_closed = records_closed.safe_add(1)?;
+        }
+
+        let archived = if let Some(result) = ctx.accounts.result.as_mut() {
+            poll.assert_archivable([
+                &ctx.accounts.prize_pool,
+                &ctx.accounts.reward_vault,
+                &ctx.accounts.rent_sponsor,
+                &ctx.accounts.funding_round,
+            ])?;
+            result.archive(poll, ctx.bumps.result)?;
+            emit_cpi!(PollArchived {
+                poll: result.poll,
+                poll_id: result.poll_id,
+                result: result.key(),
+                winner: result.winner,
+                poll_hash: result.poll_hash,
+                timestamp: result.archived_at,
+            });
+            close_account(&poll_info, &ctx.accounts.admin)?;
+            true
+        } else {
+            false
+        };
+
+        msg!(
+            "Collected {} vote records of poll '{}'{}",
+            records_closed,
+            poll.title,
+            if archived { " and archived it" } else { "" }
+        );
+        emit_cpi!(ExpiredPollCollected {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            records_closed,
+            archived,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Closes the signer's vote record in an archived poll, refunding its
+    /// rent. Archived polls never held vote deposits.
+    pub fn close_archived_vote_record(ctx: Context<CloseArchivedVoteRecord>) -> Result<()> {
+        msg!(
+            "Vote record of {} in archived poll {} closed",
+            ctx.accounts.voter.key(),
+            ctx.accounts.result.poll
+        );
+        Ok(())
+    }
+
+    /// Records the signer's approval of `action` on a multisig poll. Close
+    /// approvals are only accepted while the poll is active and finalize
+    /// approvals only once it is closed, so approvers sign off on the state
+    /// they can see. Each approver can approve each action once.
+    pub fn approve_action(ctx: Context<ApproveAction>, action: MultisigAction) -> Result<()> {
+        let approver = ctx.accounts.approver.key();
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.approvers.contains(&approver), VotingError::NotAnApprover);
+        let expected_status = match action {
+            MultisigAction::Close => PollStatus::Active,
+            MultisigAction::Finalize => PollStatus::Closed,
+        };
+        require!(poll.status == expected_status, VotingError::ApprovalOutOfOrder);
+
+        let approvals = match action {
+            MultisigAction::Close => &mut poll.close_approvals,
+            MultisigAction::Finalize => &mut poll.finalize_approvals,
+        };
+        *approvals = approvals.safe_add(1)?;
+        let approvals = *approvals;
+
+        let approval = &mut ctx.accounts.approval;
+        approval.poll = poll.key();
+        approval.approver = approver;
+        approval.action = action;
+        approval.slot = Clock::get()?.slot;
+        approval.bump = ctx.bumps.approval;
+
+        msg!(
+            "{:?} of poll '{}' approved by {} ({}/{})",
+            action,
+            poll.title,
+            approver,
+            approvals,
+            poll.approval_threshold
+        );
+        emit_cpi!(ActionApproved {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            approver,
+            action,
+            approvals,
+            threshold: poll.approval_threshold,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Sets the poll's description and external metadata URI, so frontends
+    /// can show basic poll info without an off-chain database. Only allowed
+    /// before the first vote; hash-only polls can't take a description and
+    /// should point `metadata_uri` at hashed content instead.
+    pub fn update_poll_metadata(
+        ctx: Context<UpdatePollMetadata>,
+        description: String,
+        metadata_uri: String,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            None,
+            ACTION_MODERATE,
+        )?;
+        poll.assert_no_votes_yet()?;
+        require!(
+            description.is_empty() || poll.content_mode == ContentMode::Inline,
+            VotingError::InlineContentDisabled
+        );
+        require!(
+            description.len() <= MAX_DESCRIPTION_LEN && metadata_uri.len() <= MAX_CONTENT_URI_LEN,
+            VotingError::ContentTooLong
+        );
+
+        let payload = (&description, &metadata_uri).try_to_vec()?;
+        poll.description = description;
+        poll.metadata_uri = metadata_uri;
+        poll.audit(
+            ctx.accounts.audit_log.as_mut(),
+            ctx.accounts.admin.key(),
+            AuditAction::UpdateMetadata,
+            &payload,
+        )?;
+        msg!("Metadata of poll '{}' updated", poll.title);
+        Ok(())
+    }
+
+    /// Sets the SHA-256 of the canonical JSON document at `metadata_uri`
+    /// (candidate bios, rules, images), so clients can check the off-chain
+    /// content is what the admin committed to. Like the metadata itself it
+    /// is locked from the first vote on.
+    pub fn set_metadata_hash(ctx: Context<UpdatePollMetadata>, metadata_hash: [u8; 32]) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            None,
+            ACTION_MODERATE,
+        )?;
+        poll.assert_no_votes_yet()?;
+
+        poll.metadata_hash = metadata_hash;
+        poll.audit(
+            ctx.accounts.audit_log.as_mut(),
+            ctx.accounts.admin.key(),
+            AuditAction::SetMetadataHash,
+            &metadata_hash,
+        )?;
+        msg!("Metadata hash of poll '{}' set", poll.title);
+        Ok(())
+    }
+
+    /// Fixes the poll's title before anyone has voted. The account is grown
+    /// if the new title doesn't fit its original allocation, e.g. after the
+    /// config's title limit was raised.
+    pub fn update_poll_title(ctx: Context<UpdatePollTitle>, title: String) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            None,
+            ACTION_MODERATE,
+        )?;
+        poll.assert_no_votes_yet()?;
+        let budget = poll.string_limits(&ctx.accounts.config.limits).max_title_len;
+        if title.len() > budget as usize {
+            msg!("Title is {} bytes; this poll allows {}", title.len(), budget);
+            return err!(VotingError::TitleTooLong);
+        }
+
+        poll.audit(
+            ctx.accounts.audit_log.as_mut(),
+            ctx.accounts.admin.key(),
+            AuditAction::UpdateTitle,
+            title.as_bytes(),
+        )?;
+        let previous_title = std::mem::replace(&mut poll.title, title);
+        msg!("Poll '{}' renamed to '{}'", previous_title, poll.title);
+        emit_cpi!(PollTitleUpdated {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            title: poll.title.clone(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Sets or replaces a candidate's description. Polls in
+    /// `ContentMode::HashOnly` only accept `Content::Hashed`, keeping the
+    /// on-chain footprint to a hash and URI while the text stays verifiable.
+    pub fn set_candidate_description(
+        ctx: Context<SetCandidateDescription>,
+        candidate_index: u8,
+        content: Content,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            ctx.accounts.role.as_deref(),
+            ACTION_MANAGE_CANDIDATES,
+        )?;
+        poll.check_candidate(candidate_index)?;
+        content.validate(poll.content_mode)?;
+        poll.audit(
+            ctx.accounts.audit_log.as_mut(),
+            ctx.accounts.admin.key(),
+            AuditAction::SetCandidateDescription,
+            &(candidate_index, &content).try_to_vec()?,
+        )?;
+
+        let description = &mut ctx.accounts.description;
+        description.poll = poll.key();
+        description.candidate_index = candidate_index;
+        description.content = content;
+        description.bump = ctx.bumps.description;
+        Ok(())
+    }
+
+    /// Registers the address that receives the poll's prize if
+    /// `candidate_index` wins. Fixed once the poll holds votes.
+    pub fn set_candidate_payout(
+        ctx: Context<SetCandidatePayout>,
+        candidate_index: u8,
+        payout: Pubkey,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            ctx.accounts.role.as_deref(),
+            ACTION_MANAGE_CANDIDATES,
+        )?;
+        poll.check_candidate(candidate_index)?;
+        poll.assert_no_votes_yet()?;
+        poll.audit(
+            ctx.accounts.audit_log.as_mut(),
+            ctx.accounts.admin.key(),
+            AuditAction::SetCandidatePayout,
+            &(candidate_index, payout).try_to_vec()?,
+        )?;
+
+        let candidate_payout = &mut ctx.accounts.candidate_payout;
+        candidate_payout.poll = poll.key();
+        candidate_payout.candidate_index = candidate_index;
+        candidate_payout.payout = payout;
+        candidate_payout.bump = ctx.bumps.candidate_payout;
+        Ok(())
+    }
+
+    /// Adds `amount` to the poll's prize pool, in lamports or, when the token
+    /// accounts are passed, in tokens of `mint` held by the pool's associated
+    /// token account (which the caller creates). A pool holds one asset; only
+    /// the poll admin funds it, until the poll is finalized.
+    pub fn fund_prize_pool(ctx: Context<FundPrizePool>, _poll_id: u64, amount: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(amount > 0, VotingError::InvalidFundingAmount);
+        require!(
+            !poll.is_sealed() && poll.status != PollStatus::Cancelled,
+            VotingError::PollAlreadyFinalized
+        );
+
+        let tokens = EscrowTokens {
+            mint: ctx.accounts.mint.as_ref(),
+            vault: ctx.accounts.prize_vault.as_ref(),
+            token: ctx.accounts.funder_token.as_ref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let pool = &mut ctx.accounts.prize_pool;
+        let (mint, vault, received) = tokens.deposit(
+            &pool.to_account_info(),
+            ctx.accounts.admin.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            amount,
+        )?;
+        if pool.poll == Pubkey::default() {
+            pool.poll = poll.key();
+            pool.mint = mint;
+            pool.vault = vault;
+            pool.bump = ctx.bumps.prize_pool;
+        }
+        require!(
+            pool.mint == mint && pool.vault == vault,
+            VotingError::InvalidEscrowAccounts
+        );
+        pool.funded = pool.funded.safe_add(received)?;
+
+        msg!("Prize pool of poll '{}' funded with {}", poll.title, amount);
+        Ok(())
+    }
+
+    /// Pays the whole prize pool to the winning candidate's registered
+    /// payout address, which must sign, once the poll is finalized with a
+    /// published winner that can no longer be challenged.
+    pub fn claim_prize(ctx: Context<ClaimPrize>, _poll_id: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(poll.status == PollStatus::Finalized, VotingError::PollNotFinalized);
+        require!(!poll.embargoed()?, VotingError::ResultsEmbargoed);
+        poll.assert_unchallenged()?;
+        let winner = poll.winner().ok_or(VotingError::NoWinner)?;
+        require!(
+            ctx.accounts.candidate_payout.candidate_index == winner,
+            VotingError::NotPrizeRecipient
+        );
+
+        let pool = &ctx.accounts.prize_pool;
+        let tokens = EscrowTokens {
+            mint: ctx.accounts.mint.as_ref(),
+            vault: ctx.accounts.prize_vault.as_ref(),
+            token: ctx.accounts.recipient_token.as_ref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let amount = tokens.withdraw(
+            pool.to_account_info(),
+            (pool.mint, pool.vault),
+            &[b"prize", poll.key().as_ref(), &[pool.bump]],
+            ctx.accounts.recipient.to_account_info(),
+            None,
+        )?;
+        emit_cpi!(PrizeReleased {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            recipient: ctx.accounts.recipient.key(),
+            mint: pool.mint,
+            amount,
+            winner: Some(winner),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Returns the prize pool to the poll admin when the poll is cancelled
+    /// or ends without meeting quorum. A finalized poll's prize comes back
+    /// once the result can't be challenged if nobody can claim it: the poll
+    /// has no winner, or `winner_payout` shows the winner never registered a
+    /// payout address. Otherwise the winner has `PAYOUT_CLAIM_WINDOW` to
+    /// claim it first.
+    pub fn reclaim_prize(ctx: Context<ReclaimPrize>, _poll_id: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        let reclaimable = match poll.status {
+            PollStatus::Cancelled | PollStatus::QuorumNotMet => true,
+            PollStatus::Finalized => {
+                require!(!poll.embargoed()?, VotingError::ResultsEmbargoed);
+                poll.assert_unchallenged()?;
+                match poll.winner() {
+                    None => true,
+                    Some(winner) => {
+                        let (payout, _) = Pubkey::find_program_address(
+                            &[b"payout", poll.key().as_ref(), &[winner]],
+                            &crate::ID,
+                        );
+                        let unregistered = ctx
+                            .accounts
+                            .winner_payout
+                            .as_ref()
+                            .is_some_and(|info| info.key() == payout && info.data_is_empty());
+                        unregistered || poll.payout_claims_expired()?
+                    }
+                }
+            }
+            _ => false,
+        };
+        require!(reclaimable, VotingError::EscrowNotReclaimable);
+
+        let pool = &ctx.accounts.prize_pool;
+        let tokens = EscrowTokens {
+            mint: ctx.accounts.mint.as_ref(),
+            vault: ctx.accounts.prize_vault.as_ref(),
+            token: ctx.accounts.admin_token.as_ref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let amount = tokens.withdraw(
+            pool.to_account_info(),
+            (pool.mint, pool.vault),
+            &[b"prize", poll.key().as_ref(), &[pool.bump]],
+            ctx.accounts.admin.to_account_info(),
+            None,
+        )?;
+        emit_cpi!(PrizeReleased {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            recipient: ctx.accounts.admin.key(),
+            mint: pool.mint,
+            amount,
+            winner: None,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Adds `amount` to the poll's voter reward vault, in lamports or tokens
+    /// as with `fund_prize_pool`. Each voter may claim `per_voter` once the
+    /// poll is finalized, or an equal share of the vault when it is 0; the
+    /// latest value set before finalization applies.
+    pub fn fund_reward_vault(
+        ctx: Context<FundRewardVault>,
+        _poll_id: u64,
+        amount: u64,
+        per_voter: u64,
+    ) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(amount > 0, VotingError::InvalidFundingAmount);
+        require!(
+            !poll.is_sealed() && poll.status != PollStatus::Cancelled,
+            VotingError::PollAlreadyFinalized
+        );
+
+        let tokens = EscrowTokens {
+            mint: ctx.accounts.mint.as_ref(),
+            vault: ctx.accounts.reward_token_vault.as_ref(),
+            token: ctx.accounts.funder_token.as_ref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let rewards = &mut ctx.accounts.reward_vault;
+        let (mint, vault, received) = tokens.deposit(
+            &rewards.to_account_info(),
+            ctx.accounts.admin.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            amount,
+        )?;
+        if rewards.poll == Pubkey::default() {
+            rewards.poll = poll.key();
+            rewards.mint = mint;
+            rewards.vault = vault;
+            rewards.bump = ctx.bumps.reward_vault;
+        }
+        require!(
+            rewards.mint == mint && rewards.vault == vault,
+            VotingError::InvalidEscrowAccounts
+        );
+        rewards.funded = rewards.funded.safe_add(received)?;
+        rewards.per_voter = per_voter;
+
+        msg!("Reward vault of poll '{}' funded with {}", poll.title, amount);
+        Ok(())
+    }
+
+    /// Pays the signer's participation reward from the poll's reward vault,
+    /// once per vote record, after the poll is finalized and its challenge
+    /// window has passed. The equal share is fixed at the first claim as the
+    /// vault's balance over the poll's ballots; claims are capped at what the
+    /// vault still holds.
+    pub fn claim_reward(ctx: Context<ClaimReward>, _poll_id: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(poll.status == PollStatus::Finalized, VotingError::PollNotFinalized);
+        poll.assert_unchallenged()?;
+        let record = &mut ctx.accounts.vote_record;
+        require!(!record.invalidated, VotingError::VoteInvalidated);
+        require!(!record.reward_claimed, VotingError::RewardAlreadyClaimed);
+
+        let rewards = &mut ctx.accounts.reward_vault;
+        let tokens = EscrowTokens {
+            mint: ctx.accounts.mint.as_ref(),
+            vault: ctx.accounts.reward_token_vault.as_ref(),
+            token: ctx.accounts.voter_token.as_ref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let asset = (rewards.mint, rewards.vault);
+        if rewards.share == 0 {
+            rewards.share = match rewards.per_voter {
+                0 => tokens.held(&rewards.to_account_info(), asset)? / poll.total_votes.max(1),
+                per_voter => per_voter,
+            };
+        }
+        let amount = tokens.withdraw(
+            rewards.to_account_info(),
+            asset,
+            &[b"reward", poll.key().as_ref(), &[rewards.bump]],
+            ctx.accounts.voter.to_account_info(),
+            Some(rewards.share),
+        )?;
+        require!(amount > 0, VotingError::RewardVaultEmpty);
+        record.reward_claimed = true;
+        rewards.claims = rewards.claims.safe_add(1)?;
+
+        emit_cpi!(RewardClaimed {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            voter: ctx.accounts.voter.key(),
+            mint: rewards.mint,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Returns the reward vault to the poll admin when the poll is cancelled
+    /// or ends without meeting quorum. On a finalized poll the admin takes
+    /// back what's left, unclaimed shares and rounding dust included, once
+    /// every ballot's reward is claimed or `PAYOUT_CLAIM_WINDOW` has passed.
+    pub fn reclaim_reward(ctx: Context<ReclaimReward>, _poll_id: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        let rewards = &ctx.accounts.reward_vault;
+        let reclaimable = match poll.status {
+            PollStatus::Cancelled | PollStatus::QuorumNotMet => true,
+            PollStatus::Finalized => {
+                poll.assert_unchallenged()?;
+                rewards.claims >= poll.total_votes || poll.payout_claims_expired()?
+            }
+            _ => false,
+        };
+        require!(reclaimable, VotingError::EscrowNotReclaimable);
+
+        let tokens = EscrowTokens {
+            mint: ctx.accounts.mint.as_ref(),
+            vault: ctx.accounts.reward_token_vault.as_ref(),
+            token: ctx.accounts.admin_token.as_ref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let amount = tokens.withdraw(
+            rewards.to_account_info(),
+            (rewards.mint, rewards.vault),
+            &[b"reward", poll.key().as_ref(), &[rewards.bump]],
+            ctx.accounts.admin.to_account_info(),
+            None,
+        )?;
+        msg!("Reward vault of poll '{}' reclaimed: {}", poll.title, amount);
+        Ok(())
+    }
+
+    /// Creates the signer's `Reputation`, which `vote` credits with each
+    /// ballot it is passed to and `record_participation` with each finalized
+    /// poll voted in.
+    pub fn init_reputation(ctx: Context<InitReputation>) -> Result<()> {
+        let reputation = &mut ctx.accounts.reputation;
+        reputation.voter = ctx.accounts.voter.key();
+        reputation.votes_cast = 0;
+        reputation.polls_completed = 0;
+        reputation.bump = ctx.bumps.reputation;
+        Ok(())
+    }
+
+    /// Credits a voter's reputation with a finalized poll they voted in,
+    /// once per vote record. Anyone may crank it.
+    pub fn record_participation(ctx: Context<RecordParticipation>, _poll_id: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(poll.status == PollStatus::Finalized, VotingError::PollNotFinalized);
+        let record = &mut ctx.accounts.vote_record;
+        require!(!record.invalidated, VotingError::VoteInvalidated);
+        require!(!record.reputation_counted, VotingError::ParticipationRecorded);
+        record.reputation_counted = true;
+
+        let reputation = &mut ctx.accounts.reputation;
+        reputation.polls_completed = reputation.polls_completed.safe_add(1)?;
+        msg!(
+            "Participation of {} in poll '{}' recorded (score {})",
+            reputation.voter,
+            poll.title,
+            reputation.score()
+        );
+        Ok(())
+    }
+
+    /// Escrows `amount` in a quadratic funding poll's matching pool, in
+    /// lamports or, with the token accounts, in tokens of `mint` held by the
+    /// `FundingRound`'s associated token account (which the caller creates).
+    /// Contributions are made in the same asset. Only the poll admin funds
+    /// it, and only before the first contribution.
+    pub fn fund_matching_pool(
+        ctx: Context<FundMatchingPool>,
+        _poll_id: u64,
+        amount: u64,
+    ) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(poll.quadratic_funding, VotingError::QuadraticFundingDisabled);
+        require!(amount > 0, VotingError::InvalidFundingAmount);
+
+        let tokens = EscrowTokens {
+            mint: ctx.accounts.mint.as_ref(),
+            vault: ctx.accounts.round_vault.as_ref(),
+            token: ctx.accounts.funder_token.as_ref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let round = &mut ctx.accounts.funding_round;
+        let (mint, vault, received) = tokens.deposit(
+            &round.to_account_info(),
+            ctx.accounts.admin.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            amount,
+        )?;
+        if round.poll == Pubkey::default() {
+            round.poll = poll.key();
+            round.mint = mint;
+            round.vault = vault;
+            round.projects = vec![ProjectFunding::default(); poll.candidates.len()];
+            round.bump = ctx.bumps.funding_round;
+        }
+        require!(
+            round.mint == mint && round.vault == vault,
+            VotingError::InvalidEscrowAccounts
+        );
+        require!(
+            round.projects.iter().all(|project| project.contributors == 0),
+            VotingError::FundingRoundStarted
+        );
+        round.matching_pool = round.matching_pool.safe_add(received)?;
+
+        msg!("Matching pool of poll '{}' funded with {}", poll.title, amount);
+        Ok(())
+    }
+
+    /// Contributes `amount` to candidate `candidate_index`'s project in a
+    /// quadratic funding poll while it accepts votes, in the round's asset.
+    /// A contributor may give to several projects and top up; each project
+    /// tracks its contributors, their total and the sum of the square roots
+    /// of each contributor's total, which decides its share of the match.
+    pub fn contribute(
+        ctx: Context<Contribute>,
+        _poll_id: u64,
+        candidate_index: u8,
+        amount: u64,
+    ) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(poll.quadratic_funding, VotingError::QuadraticFundingDisabled);
+        poll.assert_accepting_votes()?;
+        poll.check_candidate(candidate_index)?;
+        require!(
+            !poll.candidates[candidate_index as usize].disqualified,
+            VotingError::CandidateDisqualified
+        );
+        require!(amount > 0, VotingError::InvalidFundingAmount);
+
+        let tokens = EscrowTokens {
+            mint: ctx.accounts.mint.as_ref(),
+            vault: ctx.accounts.round_vault.as_ref(),
+            token: ctx.accounts.contributor_token.as_ref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let round = &mut ctx.accounts.funding_round;
+        let (mint, vault, received) = tokens.deposit(
+            &round.to_account_info(),
+            ctx.accounts.contributor.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            amount,
+        )?;
+        require!(
+            round.mint == mint && round.vault == vault,
+            VotingError::InvalidEscrowAccounts
+        );
+
+        let contribution = &mut ctx.accounts.contribution;
+        let project = &mut round.projects[candidate_index as usize];
+        if contribution.poll == Pubkey::default() {
+            contribution.poll = poll.key();
+            contribution.contributor = ctx.accounts.contributor.key();
+            contribution.candidate_index = candidate_index;
+            contribution.bump = ctx.bumps.contribution;
+            project.contributors = project.contributors.safe_add(1)?;
+        }
+        let previous = contribution.amount;
+        contribution.amount = previous.safe_add(received)?;
+        project.contributed = project.contributed.safe_add(received)?;
+        project.sqrt_sum = project.sqrt_sum - ProjectFunding::root(previous)
+            + ProjectFunding::root(contribution.amount);
+
+        emit_cpi!(ContributionMade {
+            poll: poll.key(),
+            contributor: contribution.contributor,
+            candidate_index,
+            amount: received,
+            project_contributed: project.contributed,
+            project_contributors: project.contributors,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Computes each project's quadratic funding match once the poll is
+    /// finalized and its challenge window has passed: `(Σ √contribution)² −
+    /// Σ contribution` per project, scaled down proportionally when the
+    /// ideal matches exceed the matching pool. Disqualified projects get no
+    /// match. Anyone may call it.
+    pub fn finalize_funding_round(
+        ctx: Context<FinalizeFundingRound>,
+        _poll_id: u64,
+    ) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(poll.status == PollStatus::Finalized, VotingError::PollNotFinalized);
+        poll.assert_unchallenged()?;
+        let round = &mut ctx.accounts.funding_round;
+        require!(!round.matched, VotingError::FundingRoundMatched);
+
+        let ideal: Vec<u128> = round
+            .projects
+            .iter()
+            .zip(&poll.candidates)
+            .map(|(project, candidate)| {
+                if candidate.disqualified {
+                    0
+                } else {
+                    project.ideal_match()
+                }
+            })
+            .collect();
+        let total = ideal.iter().fold(0u128, |sum, &m| sum.saturating_add(m));
+        // Shift both sides until the total fits in 64 bits, so scaling by a
+        // u64 pool can't overflow.
+        let shift = (128 - total.leading_zeros()).saturating_sub(64);
+        let pool = round.matching_pool as u128;
+        let mut matched = 0u64;
+        for (project, ideal) in round.projects.iter_mut().zip(ideal) {
+            project.matched = if total <= pool {
+                ideal as u64
+            } else {
+                ((ideal >> shift) * pool / (total >> shift)) as u64
+            };
+            matched = matched.safe_add(project.matched)?;
+        }
+        round.matched = true;
+
+        emit_cpi!(FundingRoundFinalized {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            matching_pool: round.matching_pool,
+            matched,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Pays a project its contributions plus its match to the candidate's
+    /// registered payout address, which must sign, once the round is
+    /// finalized.
+    pub fn claim_funding(
+        ctx: Context<ClaimFunding>,
+        _poll_id: u64,
+        candidate_index: u8,
+    ) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        let round = &mut ctx.accounts.funding_round;
+        require!(round.matched, VotingError::FundingRoundNotMatched);
+        let project = round
+            .projects
+            .get(candidate_index as usize)
+            .ok_or(VotingError::InvalidCandidate)?;
+        require!(!project.claimed, VotingError::FundingAlreadyClaimed);
+
+        let tokens = EscrowTokens {
+            mint: ctx.accounts.mint.as_ref(),
+            vault: ctx.accounts.round_vault.as_ref(),
+            token: ctx.accounts.recipient_token.as_ref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let amount = tokens.withdraw(
+            round.to_account_info(),
+            (round.mint, round.vault),
+            &[b"funding", poll.key().as_ref(), &[round.bump]],
+            ctx.accounts.recipient.to_account_info(),
+            Some(project.contributed.safe_add(project.matched)?),
+        )?;
+        round.projects[candidate_index as usize].claimed = true;
+
+        emit_cpi!(FundingClaimed {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            candidate_index,
+            recipient: ctx.accounts.recipient.key(),
+            mint: round.mint,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Refunds a contribution, closing its record, when the poll is
+    /// cancelled or ends without meeting quorum.
+    pub fn refund_contribution(
+        ctx: Context<RefundContribution>,
+        _poll_id: u64,
+        _candidate_index: u8,
+    ) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(
+            matches!(poll.status, PollStatus::Cancelled | PollStatus::QuorumNotMet),
+            VotingError::EscrowNotReclaimable
+        );
+
+        let round = &ctx.accounts.funding_round;
+        let tokens = EscrowTokens {
+            mint: ctx.accounts.mint.as_ref(),
+            vault: ctx.accounts.round_vault.as_ref(),
+            token: ctx.accounts.contributor_token.as_ref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let amount = tokens.withdraw(
+            round.to_account_info(),
+            (round.mint, round.vault),
+            &[b"funding", poll.key().as_ref(), &[round.bump]],
+            ctx.accounts.contributor.to_account_info(),
+            Some(ctx.accounts.contribution.amount),
+        )?;
+        msg!("Contribution of {} to poll '{}' refunded", amount, poll.title);
+        Ok(())
+    }
+
+    /// Returns the matching pool to the poll admin when the poll is
+    /// cancelled or ends without meeting quorum; contributions stay in the
+    /// round for `refund_contribution`. Once a matched round is settled,
+    /// with every project that is owed anything paid, the admin takes
+    /// whatever is left (the unmatched pool and rounding dust) and the
+    /// round closes.
+    pub fn reclaim_matching_pool(ctx: Context<ReclaimMatchingPool>, _poll_id: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        let round = &mut ctx.accounts.funding_round;
+        let settled = round.matched
+            && round
+                .projects
+                .iter()
+                .all(|project| project.claimed || project.contributed == 0 && project.matched == 0);
+        require!(
+            settled || matches!(poll.status, PollStatus::Cancelled | PollStatus::QuorumNotMet),
+            VotingError::EscrowNotReclaimable
+        );
+
+        let tokens = EscrowTokens {
+            mint: ctx.accounts.mint.as_ref(),
+            vault: ctx.accounts.round_vault.as_ref(),
+            token: ctx.accounts.admin_token.as_ref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let amount = tokens.withdraw(
+            round.to_account_info(),
+            (round.mint, round.vault),
+            &[b"funding", poll.key().as_ref(), &[round.bump]],
+            ctx.accounts.admin.to_account_info(),
+            if settled { None } else { Some(round.matching_pool) },
+        )?;
+        round.matching_pool = 0;
+        if settled {
+            close_account(&round.to_account_info(), &ctx.accounts.admin)?;
+        }
+        msg!("Matching pool of poll '{}' reclaimed: {}", poll.title, amount);
+        Ok(())
+    }
+
+    /// Stakes `amount` on candidate `candidate_index` of a prediction poll,
+    /// as the signer's one ballot: the stake, in lamports or the poll's
+    /// `prediction_mint` (held by the `PredictionPool`'s associated token
+    /// account, which the caller creates), becomes the ballot's weight, so
+    /// candidate weights are the amounts staked on each outcome.
+    pub fn place_stake(
+        ctx: Context<PlaceStake>,
+        _poll_id: u64,
+        candidate_index: u8,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.config.assert_not_paused()?;
+        let poll_info = ctx.accounts.poll.to_account_info();
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.prediction_market, VotingError::PredictionMarketDisabled);
+        require!(amount > 0, VotingError::InvalidFundingAmount);
+
+        let tokens = EscrowTokens {
+            mint: ctx.accounts.mint.as_ref(),
+            vault: ctx.accounts.pool_vault.as_ref(),
+            token: ctx.accounts.voter_token.as_ref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let pool = &mut ctx.accounts.prediction_pool;
+        let (mint, vault, received) = tokens.deposit(
+            &pool.to_account_info(),
+            ctx.accounts.voter.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            amount,
+        )?;
+        require!(mint == poll.prediction_mint, VotingError::InvalidEscrowAccounts);
+        if pool.poll == Pubkey::default() {
+            pool.poll = poll.key();
+            pool.mint = mint;
+            pool.vault = vault;
+            pool.bump = ctx.bumps.prediction_pool;
+        }
+        require!(pool.vault == vault, VotingError::InvalidEscrowAccounts);
+        pool.staked = pool.staked.safe_add(received)?;
+
+        let passes = VoterPasses {
+            prediction_stake: Some(received),
+            ..Default::default()
+        };
+        let weight = poll.check_ballot(candidate_index, None, Some(&passes))?;
+        poll.throttle()?;
+        poll.count_ballot(candidate_index, weight)?;
+
+        let voter = ctx.accounts.voter.key();
+        let vote_record = &mut ctx.accounts.vote_record;
+        vote_record.voter = poll.record_voter(&voter);
+        vote_record.poll_id = poll.poll_id;
+        vote_record.candidate_index = candidate_index;
+        vote_record.bump = ctx.bumps.vote_record;
+        vote_record.weight = weight;
+        vote_record.delegated_bps = 0;
+        vote_record.changes = Vec::new();
+        vote_record.stamp()?;
+        vote_record.memo = String::new();
+        vote_record.hold_deposit(
+            poll.vote_deposit,
+            ctx.accounts.voter.to_account_info(),
+            poll_info,
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        emit_cpi!(StakePlaced {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            voter,
+            candidate_index,
+            amount: weight,
+            candidate_staked: poll.candidates[candidate_index as usize].weight,
+            total_staked: pool.staked,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Records the real-world outcome of a prediction poll once it no longer
+    /// takes stakes. Only the admin or the poll's `oracle` can resolve it,
+    /// once.
+    pub fn resolve_outcome(
+        ctx: Context<ResolveOutcome>,
+        _poll_id: u64,
+        outcome: u8,
+    ) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        let resolver = ctx.accounts.resolver.key();
+        require!(
+            resolver == poll.admin || (poll.oracle != Pubkey::default() && resolver == poll.oracle),
+            VotingError::Unauthorized
+        );
+        require!(
+            !matches!(
+                poll.status,
+                PollStatus::Draft | PollStatus::Active | PollStatus::Cancelled
+            ),
+            VotingError::PollNotClosed
+        );
+        poll.check_candidate(outcome)?;
+        let pool = &mut ctx.accounts.prediction_pool;
+        require!(pool.outcome.is_none(), VotingError::OutcomeAlreadyResolved);
+        pool.outcome = Some(outcome);
+
+        emit_cpi!(OutcomeResolved {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            outcome,
+            resolver,
+            winning_staked: poll.candidates[outcome as usize].weight,
+            total_staked: pool.staked,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Pays a staker on the resolved outcome their stake plus a pro-rata
+    /// share of everything staked on other outcomes, and closes their vote
+    /// record, refunding its rent and any vote deposit as
+    /// `close_vote_record` does. Stakes are refunded instead if the poll was
+    /// cancelled or nobody backed the outcome.
+    pub fn claim_winnings(ctx: Context<ClaimWinnings>, _poll_id: u64) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        let pool = &ctx.accounts.prediction_pool;
+        let stake = ctx.accounts.vote_record.weight;
+        let amount = match pool.outcome {
+            _ if poll.status == PollStatus::Cancelled => stake,
+            None => return err!(VotingError::OutcomeNotResolved),
+            Some(outcome) => {
+                let winning = poll.candidates[outcome as usize].weight;
+                if winning == 0 {
+                    stake
+                } else {
+                    require!(
+                        ctx.accounts.vote_record.candidate_index == outcome,
+                        VotingError::LosingStake
+                    );
+                    let losing = pool.staked.saturating_sub(winning) as u128;
+                    stake.safe_add((losing * stake as u128 / winning as u128) as u64)?
+                }
+            }
+        };
+
+        let tokens = EscrowTokens {
+            mint: ctx.accounts.mint.as_ref(),
+            vault: ctx.accounts.pool_vault.as_ref(),
+            token: ctx.accounts.voter_token.as_ref(),
+            token_program: ctx.accounts.token_program.as_ref(),
+        };
+        let amount = tokens.withdraw(
+            pool.to_account_info(),
+            (pool.mint, pool.vault),
+            &[b"prediction", poll.key().as_ref(), &[pool.bump]],
+            ctx.accounts.voter.to_account_info(),
+            Some(amount),
+        )?;
+        let deposit = ctx.accounts.vote_record.deposit;
+        if deposit > 0 {
+            move_lamports(
+                &poll.to_account_info(),
+                &ctx.accounts.voter.to_account_info(),
+                deposit,
+            )?;
+        }
+
+        emit_cpi!(WinningsClaimed {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            voter: ctx.accounts.voter.key(),
+            mint: pool.mint,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Disqualifies a candidate before the poll is finalized. Further ballots
+    /// for it are rejected, it can't win, and the ballots it already holds
+    /// are kept or dropped from the totals at finalization according to the
+    /// poll's `DisqualificationPolicy`.
+    pub fn disqualify_candidate(
+        ctx: Context<DisqualifyCandidate>,
+        candidate_index: u8,
+        reason: String,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            ctx.accounts.role.as_deref(),
+            ACTION_MANAGE_CANDIDATES,
+        )?;
+        require!(
+            matches!(
+                poll.status,
+                PollStatus::Draft | PollStatus::Active | PollStatus::Closed
+            ),
+            VotingError::PollAlreadyFinalized
+        );
+        poll.check_candidate(candidate_index)?;
+        require!(
+            reason.len() <= MAX_DISQUALIFY_REASON_LEN,
+            VotingError::DisqualifyReasonTooLong
+        );
+        let candidate = &mut poll.candidates[candidate_index as usize];
+        require!(!candidate.disqualified, VotingError::CandidateDisqualified);
+        candidate.disqualified = true;
+        poll.audit(
+            ctx.accounts.audit_log.as_mut(),
+            ctx.accounts.admin.key(),
+            AuditAction::DisqualifyCandidate,
+            &(candidate_index, &reason).try_to_vec()?,
+        )?;
+
+        msg!(
+            "Candidate '{}' disqualified from poll '{}': {}",
+            poll.candidates[candidate_index as usize].name,
+            poll.title,
+            reason
+        );
+        emit_cpi!(CandidateDisqualified {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            candidate_index,
+            policy: poll.disqualification_policy,
+            reason,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Lets a candidate step down before voting ends, signed by the payout
+    /// address registered for it with `set_candidate_payout`, which stands
+    /// for the candidate. A withdrawn candidate is treated like a
+    /// disqualified one: further ballots for it are rejected, it can't win,
+    /// and the ballots it already holds follow the poll's
+    /// `DisqualificationPolicy` at finalization.
+    pub fn withdraw_candidacy(
+        ctx: Context<WithdrawCandidacy>,
+        _poll_id: u64,
+        candidate_index: u8,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(
+            matches!(poll.status, PollStatus::Draft | PollStatus::Active)
+                && (poll.end_ts == 0 || poll.now()? < poll.end_ts),
+            VotingError::VotingEnded
+        );
+        poll.check_candidate(candidate_index)?;
+        let candidate = &mut poll.candidates[candidate_index as usize];
+        require!(!candidate.disqualified, VotingError::CandidateDisqualified);
+        candidate.disqualified = true;
+        poll.withdrawn_candidates |= 1 << candidate_index;
+
+        msg!(
+            "Candidate '{}' withdrew from poll '{}'",
+            poll.candidates[candidate_index as usize].name,
+            poll.title
+        );
+        emit_cpi!(CandidateWithdrawn {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            candidate_index,
+            policy: poll.disqualification_policy,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Strikes a fraudulent ballot, e.g. from a compromised or bot wallet:
+    /// its vote and weight leave the tallies and its `VoteRecord` is kept,
+    /// marked invalidated with the admin's `reason` code, for an auditable
+    /// trail. Allowed while the poll is active or closed, and on sealed
+    /// polls within their challenge window, where quorum and the outcome
+    /// are recomputed. A moderation action (admin-only by default); on
+    /// multisig polls `approval_threshold` of the approvers must also sign,
+    /// passed as remaining accounts. Only direct ballots tallied on the poll
+    /// itself can be struck.
+    pub fn invalidate_vote(ctx: Context<InvalidateVote>, voter: Pubkey, reason: u8) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            ctx.accounts.role.as_deref(),
+            ACTION_MODERATE,
+        )?;
+        poll.assert_cosigned(ctx.remaining_accounts)?;
+        let in_challenge_window = matches!(
+            poll.status,
+            PollStatus::Finalized | PollStatus::QuorumNotMet
+        ) && poll.challenge_window > 0
+            && poll.now()? < poll.challenge_deadline()?;
+        require!(
+            matches!(poll.status, PollStatus::Active | PollStatus::Closed) || in_challenge_window,
+            VotingError::InvalidationWindowClosed
+        );
+        // A recount may already be under way; strike after `reveal_tallies`
+        require!(
+            !(poll.tallies_hidden() && poll.status == PollStatus::Closed),
+            VotingError::TalliesHidden
+        );
+        require!(
+            !(poll.parallel_tallies
+                || poll.prediction_market
+                || poll.encrypted_tally != Pubkey::default()
+                || poll.ballot_key != Pubkey::default()),
+            VotingError::BallotNotInvalidatable
+        );
+
+        let vote_record = &mut ctx.accounts.vote_record;
+        require!(!vote_record.invalidated, VotingError::VoteInvalidated);
+        require!(
+            vote_record.candidate_index != SPLIT_BALLOT && vote_record.delegated_bps == 0,
+            VotingError::BallotNotInvalidatable
+        );
+        poll.check_candidate(vote_record.candidate_index)?;
+        poll.strike_ballot(vote_record.candidate_index, vote_record.weight)?;
+        poll.tally_tier(vote_record.tier, vote_record.candidate_index, false)?;
+        vote_record.invalidated = true;
+        vote_record.invalidation_reason = reason;
+        poll.audit(
+            ctx.accounts.audit_log.as_mut(),
+            ctx.accounts.admin.key(),
+            AuditAction::InvalidateVote,
+            &(voter, reason).try_to_vec()?,
+        )?;
+
+        msg!(
+            "Vote by {} in poll '{}' invalidated (reason {})",
+            voter,
+            poll.title,
+            reason
+        );
+        emit_cpi!(VoteInvalidated {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            voter,
+            candidate_index: vote_record.candidate_index,
+            weight: vote_record.weight,
+            reason,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Adds a hashed identity to the poll's voter roll, in voter `tier` (0
+    /// on polls without `tier_weights`). The hash is
+    /// `RollEntry::identity_hash(poll, voter, salt)`; the admin keeps the
+    /// voter-to-salt mapping off-chain, so the roll can't be enumerated.
+    pub fn add_roll_entry(
+        ctx: Context<AddRollEntry>,
+        identity_hash: [u8; 32],
+        tier: u8,
+    ) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            ctx.accounts.role.as_deref(),
+            ACTION_MANAGE_WHITELIST,
+        )?;
+        require!(poll.voter_roll, VotingError::VoterRollDisabled);
+        require!(poll.roll_source == Pubkey::default(), VotingError::VoterRollInherited);
+        require!(
+            (tier as usize) < poll.tier_weights.len().max(1),
+            VotingError::InvalidVoterTier
+        );
+        poll.eligible_voter_count = poll.eligible_voter_count.safe_add(1)?;
+
+        let entry = &mut ctx.accounts.roll_entry;
+        entry.poll = poll.key();
+        entry.identity_hash = identity_hash;
+        entry.bump = ctx.bumps.roll_entry;
+        entry.tier = tier;
+        Ok(())
+    }
+
+    /// Proves the signer is on the poll's voter roll by revealing their salt,
+    /// and records a `RollMembership` that the voting instructions accept.
+    /// Only the signer's own membership is disclosed.
+    pub fn prove_membership(ctx: Context<ProveMembership>, _salt: [u8; 32]) -> Result<()> {
+        let membership = &mut ctx.accounts.membership;
+        membership.poll = ctx.accounts.poll.key();
+        membership.voter = ctx.accounts.voter.key();
+        membership.bump = ctx.bumps.membership;
+        membership.tier = ctx.accounts.roll_entry.tier;
+        msg!(
+            "{} proved membership of the voter roll of poll '{}'",
+            membership.voter,
+            ctx.accounts.poll.title
+        );
+        Ok(())
+    }
+
+    /// Grants `grantee` a co-admin role on the poll with the given
+    /// `PERMISSION_*` bits, replacing any permissions it already held.
+    /// Managing roles is a moderation action (admin-only by default).
+    /// Organization polls take their organization's roles instead.
+    pub fn grant_role(ctx: Context<GrantRole>, grantee: Pubkey, permissions: u8) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            None,
+            ACTION_MODERATE,
+        )?;
+        require!(!poll.in_organization(), VotingError::OrganizationPoll);
+        require!(
+            permissions != 0 && permissions & !PERMISSION_ALL == 0,
+            VotingError::InvalidPermissions
+        );
+
+        let role = &mut ctx.accounts.role;
+        role.poll = poll.key();
+        role.grantee = grantee;
+        role.permissions = permissions;
+        role.bump = ctx.bumps.role;
+
+        msg!("Granted permissions {:#06b} on poll '{}' to {}", permissions, poll.title, grantee);
+        emit_cpi!(RoleUpdated {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            grantee,
+            permissions,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Revokes a co-admin role, closing its account and refunding the rent to
+    /// the signer. Managing roles is a moderation action (admin-only by default).
+    pub fn revoke_role(ctx: Context<RevokeRole>) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            None,
+            ACTION_MODERATE,
+        )?;
+        let grantee = ctx.accounts.role.grantee;
+        msg!("Revoked role on poll '{}' from {}", poll.title, grantee);
+        emit_cpi!(RoleUpdated {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            grantee,
+            permissions: 0,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Sets the poll's operations key, which may `close_poll` and
+    /// `finalize_poll` like the admin but nothing else, so lifecycle bots
+    /// don't need the admin key. `Pubkey::default()` removes it. Managing it
+    /// is a moderation action (admin-only by default).
+    pub fn set_operations_key(ctx: Context<SetOperationsKey>, operations_key: Pubkey) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        ctx.accounts.config.assert_permission(
+            &ctx.accounts.admin.key(),
+            Some(poll),
+            None,
+            ACTION_MODERATE,
+        )?;
+        poll.operations_key = operations_key;
+        msg!("Operations key of poll '{}' set to {}", poll.title, operations_key);
+        emit_cpi!(OperationsKeyUpdated {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            operations_key,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// First step of an admin handover: records `new_admin` as the poll's
+    /// pending admin. Proposing `Pubkey::default()` withdraws a pending
+    /// proposal. Only the current admin can propose.
+    pub fn propose_admin_transfer(ctx: Context<ProposeAdminTransfer>, new_admin: Pubkey) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(
+            poll.admin == ctx.accounts.admin.key(),
+            VotingError::Unauthorized
+        );
+        poll.pending_admin = new_admin;
+        msg!("Admin transfer of poll '{}' proposed to {}", poll.title, new_admin);
+        emit_cpi!(AdminTransferProposed {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            admin: poll.admin,
+            pending_admin: new_admin,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Second step of an admin handover: the pending admin signs to take over
+    /// the poll. The poll keeps its address and namespace, and stays listed
+    /// under its creator's `AdminPollEntry` index.
+    pub fn accept_admin_transfer(ctx: Context<AcceptAdminTransfer>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(
+            poll.pending_admin != Pubkey::default(),
+            VotingError::NoPendingAdminTransfer
+        );
+        require!(
+            poll.pending_admin == ctx.accounts.new_admin.key(),
+            VotingError::NotPendingAdmin
+        );
+        let previous_admin = poll.admin;
+        poll.admin = poll.pending_admin;
+        poll.pending_admin = Pubkey::default();
+        msg!("Poll '{}' is now administered by {}", poll.title, poll.admin);
+        emit_cpi!(AdminTransferred {
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            previous_admin,
+            new_admin: poll.admin,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Succeeds only if the poll is finalized with `expected_winner` as its
+    /// winner. Intended to be called via CPI by programs that act on a result
+    /// (e.g. releasing a treasury transfer). Each consumption is recorded in a
+    /// `ConsumptionReceipt` keyed by the consuming (top-level) program, so
+    /// organizers can see on-chain which downstream systems acted on it.
+    pub fn assert_winner(ctx: Context<AssertWinner>, expected_winner: u8) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(poll.status == PollStatus::Finalized, VotingError::PollNotFinalized);
+        require!(!poll.embargoed()?, VotingError::ResultsEmbargoed);
+        require!(
+            poll.winner() == Some(expected_winner),
+            VotingError::WinnerMismatch
+        );
+
+        let instructions = ctx.accounts.instructions.to_account_info();
+        let outer = ix_sysvar::get_instruction_relative(0, &instructions)?;
+        let consumer = ctx.accounts.consumer_program.key();
+        require!(outer.program_id == consumer, VotingError::ConsumerMismatch);
+
+        let slot = Clock::get()?.slot;
+        let receipt = &mut ctx.accounts.receipt;
+        if receipt.consumptions == 0 {
+            receipt.poll = poll.key();
+            receipt.consumer_program = consumer;
+            receipt.first_slot = slot;
+            receipt.bump = ctx.bumps.receipt;
+        }
+        receipt.last_slot = slot;
+        receipt.consumptions = receipt.consumptions.safe_add(1)?;
+
+        msg!(
+            "Result of poll '{}' consumed by program {}",
+            poll.title,
+            consumer
+        );
+        Ok(())
+    }
+
+    /// Upgrades a poll written by an earlier program version to the current
+    /// layout: fills in defaults for fields its version lacked, stamps
+    /// `POLL_VERSION` and grows the account if the new layout no longer fits,
+    /// with the payer covering the extra rent. Anyone may call it.
+    pub fn migrate_poll(ctx: Context<MigratePoll>, _namespace: Pubkey, _poll_id: u64) -> Result<()> {
+        let info = ctx.accounts.poll.to_account_info();
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+        let mut poll = Poll::decode_any_version(&info.try_borrow_data()?)?;
+        require!(poll.version < POLL_VERSION, VotingError::PollUpToDate);
+        let from_version = poll.version;
+        poll.upgrade();
+
+        let mut data = Vec::with_capacity(info.data_len());
+        poll.try_serialize(&mut data)?;
+        if data.len() > info.data_len() {
+            let rent = Rent::get()?
+                .minimum_balance(data.len())
+                .saturating_sub(info.lamports());
+            if rent > 0 {
+                system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: ctx.accounts.payer.to_account_info(),
+                            to: info.clone(),
+                        },
+                    ),
+                    rent,
+                )?;
+            }
+            info.realloc(data.len(), true)?;
+        }
+        info.try_borrow_mut_data()?[..data.len()].copy_from_slice(&data);
+
+        msg!(
+            "Poll '{}' migrated from version {} to {}",
+            poll.title,
+            from_version,
+            POLL_VERSION
+        );
+        Ok(())
+    }
+}
+
+// ─── Account Contexts ────────────────────────────────────────────────────────
+
+#[derive(Accounts)]
+pub struct InitializeCounter<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = PollCounter::SPACE,
+        seeds = [b"poll_counter"],
+        bump
+    )]
+    pub poll_counter: Account<'info, PollCounter>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::SolanaVoting>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ VotingError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = ProgramConfig::SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::SolanaVoting>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ VotingError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPollCreationFee<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = Treasury::SPACE,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+
+    /// CHECK: Any account may receive the withdrawal.
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetProgramCapabilities<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(namespace: Pubkey, poll_id: u64)]
+pub struct MigratePoll<'info> {
+    /// CHECK: Older layouts may not decode as `Poll`; the handler checks the
+    /// owner and discriminator.
+    #[account(
+        mut,
+        seeds = [b"poll", Poll::seed_for(&namespace), poll_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub poll: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetResults<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(title: String, candidates: Vec<String>, settings: PollSettings)]
+pub struct CreatePoll<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"poll_counter"],
+        bump = poll_counter.bump,
+    )]
+    pub poll_counter: Account<'info, PollCounter>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = Poll::space_for(candidates.len(), &settings, &config.limits),
+        seeds = [b"poll", poll_counter.next_poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = AdminProfile::SPACE,
+        seeds = [b"admin_profile", admin.key().as_ref()],
+        bump
+    )]
+    pub admin_profile: Account<'info, AdminProfile>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = AdminPollEntry::SPACE,
+        seeds = [
+            b"admin_poll",
+            admin.key().as_ref(),
+            admin_profile.polls_created.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub admin_poll_entry: Account<'info, AdminPollEntry>,
+
+    /// The program treasury; needed while `config.poll_creation_fee` is set.
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Option<Account<'info, Treasury>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64, title: String, candidates: Vec<String>, settings: PollSettings)]
+pub struct CreateNamespacedPoll<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = Poll::space_for(candidates.len(), &settings, &config.limits),
+        seeds = [b"poll", admin.key().as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = AdminProfile::SPACE,
+        seeds = [b"admin_profile", admin.key().as_ref()],
+        bump
+    )]
+    pub admin_profile: Account<'info, AdminProfile>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = AdminPollEntry::SPACE,
+        seeds = [
+            b"admin_poll",
+            admin.key().as_ref(),
+            admin_profile.polls_created.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub admin_poll_entry: Account<'info, AdminPollEntry>,
+
+    /// The program treasury; needed while `config.poll_creation_fee` is set.
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Option<Account<'info, Treasury>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for `create_governed_poll`. From a Realms proposal the admin is
+/// the governance account and the payer its native treasury, both signed
+/// for by spl-governance when the proposal executes. The instruction's
+/// account metas are, in order:
+///
+/// 0. `config`: `[b"config"]`
+/// 1. `poll`: `[b"poll", governance, poll_id]`, writable
+/// 2. `admin_profile`: `[b"admin_profile", governance]`, writable
+/// 3. `admin_poll_entry`: `[b"admin_poll", governance, polls_created]`,
+///    writable, where `polls_created` is read from the admin profile when
+///    the proposal is drafted (0 if it doesn't exist yet)
+/// 4. `treasury`: `[b"treasury"]`, writable, while a creation fee is set;
+///    otherwise this program's ID
+/// 5. `admin`: the governance account, signer
+/// 6. `payer`: `[b"native-treasury", governance]` under the governance
+///    program, writable signer
+/// 7. `system_program`
+/// 8. `event_authority`: `[b"__event_authority"]`
+/// 9. `program`: this program
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64, title: String, candidates: Vec<String>, settings: PollSettings)]
+pub struct CreateGovernedPoll<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = Poll::space_for(candidates.len(), &settings, &config.limits),
+        seeds = [b"poll", admin.key().as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = AdminProfile::SPACE,
+        seeds = [b"admin_profile", admin.key().as_ref()],
+        bump
+    )]
+    pub admin_profile: Account<'info, AdminProfile>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = AdminPollEntry::SPACE,
+        seeds = [
+            b"admin_poll",
+            admin.key().as_ref(),
+            admin_profile.polls_created.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub admin_poll_entry: Account<'info, AdminPollEntry>,
+
+    /// The program treasury; needed while `config.poll_creation_fee` is set.
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Option<Account<'info, Treasury>>,
+
+    pub admin: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CreateOrganization<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = Organization::SPACE,
+        seeds = [b"org", owner.key().as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub organization: Account<'info, Organization>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(member: Pubkey)]
+pub struct SetOrgMember<'info> {
+    #[account(
+        seeds = [b"org", organization.owner.as_ref(), organization.name.as_bytes()],
+        bump = organization.bump,
+        has_one = owner,
+    )]
+    pub organization: Account<'info, Organization>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = PollRole::SPACE,
+        seeds = [b"role", organization.key().as_ref(), member.as_ref()],
+        bump
+    )]
+    pub role: Account<'info, PollRole>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RemoveOrgMember<'info> {
+    #[account(
+        seeds = [b"org", organization.owner.as_ref(), organization.name.as_bytes()],
+        bump = organization.bump,
+        has_one = owner,
+    )]
+    pub organization: Account<'info, Organization>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"role", organization.key().as_ref(), role.grantee.as_ref()],
+        bump = role.bump,
+    )]
+    pub role: Account<'info, PollRole>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64, title: String, candidates: Vec<String>, settings: PollSettings)]
+pub struct CreateOrgPoll<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        seeds = [b"org", organization.owner.as_ref(), organization.name.as_bytes()],
+        bump = organization.bump,
+    )]
+    pub organization: Account<'info, Organization>,
+
+    /// Only needed when the creator is a member rather than the owner.
+    #[account(
+        seeds = [b"role", organization.key().as_ref(), creator.key().as_ref()],
+        bump = role.bump,
+    )]
+    pub role: Option<Account<'info, PollRole>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = Poll::space_for(candidates.len(), &settings, &config.limits),
+        seeds = [b"poll", organization.key().as_ref(), poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+
+    /// The organization's poll listing.
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = AdminProfile::SPACE,
+        seeds = [b"admin_profile", organization.key().as_ref()],
+        bump
+    )]
+    pub admin_profile: Account<'info, AdminProfile>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = AdminPollEntry::SPACE,
+        seeds = [
+            b"admin_poll",
+            organization.key().as_ref(),
+            admin_profile.polls_created.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub admin_poll_entry: Account<'info, AdminPollEntry>,
+
+    /// The program treasury; needed while `config.poll_creation_fee` is set.
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Option<Account<'info, Treasury>>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(title: String, candidates: Vec<String>, settings: PollSettings)]
+pub struct CreatePollV2<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = Poll::space_for(candidates.len(), &settings, &config.limits),
+        seeds = [
+            b"poll",
+            Poll::settings_hash(&admin.key(), &title, &candidates, &settings).as_ref(),
+            0u64.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = AdminProfile::SPACE,
+        seeds = [b"admin_profile", admin.key().as_ref()],
+        bump
+    )]
+    pub admin_profile: Account<'info, AdminProfile>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = AdminPollEntry::SPACE,
+        seeds = [
+            b"admin_poll",
+            admin.key().as_ref(),
+            admin_profile.polls_created.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub admin_poll_entry: Account<'info, AdminPollEntry>,
+
+    /// The program treasury; needed while `config.poll_creation_fee` is set.
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Option<Account<'info, Treasury>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(new_poll_id: u64, new_start_ts: i64, new_end_ts: i64)]
+pub struct ClonePoll<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        seeds = [b"poll", source_poll.namespace_seed(), source_poll.poll_id.to_le_bytes().as_ref()],
+        bump = source_poll.bump,
+    )]
+    pub source_poll: Box<Account<'info, Poll>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = Poll::space_for(
+            source_poll.listed_candidates(),
+            &source_poll.template_settings(new_start_ts, new_end_ts),
+            &config.limits,
+        ),
+        seeds = [b"poll", admin.key().as_ref(), new_poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Box<Account<'info, Poll>>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = AdminProfile::SPACE,
+        seeds = [b"admin_profile", admin.key().as_ref()],
+        bump
+    )]
+    pub admin_profile: Account<'info, AdminProfile>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = AdminPollEntry::SPACE,
+        seeds = [
+            b"admin_poll",
+            admin.key().as_ref(),
+            admin_profile.polls_created.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub admin_poll_entry: Account<'info, AdminPollEntry>,
+
+    /// The program treasury; needed while `config.poll_creation_fee` is set.
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Option<Account<'info, Treasury>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(_poll_id: u64, top_k: u8, settings: PollSettings)]
+pub struct StartRunoff<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        seeds = [b"poll", parent_poll.namespace_seed(), parent_poll.poll_id.to_le_bytes().as_ref()],
+        bump = parent_poll.bump,
+    )]
+    pub parent_poll: Box<Account<'info, Poll>>,
+
+    /// One runoff per poll; later rounds are runoffs of the runoff.
+    #[account(
+        init,
+        payer = admin,
+        space = Poll::space_for(parent_poll.candidates.len(), &settings, &config.limits),
+        seeds = [b"poll", parent_poll.key().as_ref(), parent_poll.poll_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub poll: Box<Account<'info, Poll>>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = AdminProfile::SPACE,
+        seeds = [b"admin_profile", admin.key().as_ref()],
+        bump
+    )]
+    pub admin_profile: Account<'info, AdminProfile>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = AdminPollEntry::SPACE,
+        seeds = [
+            b"admin_poll",
+            admin.key().as_ref(),
+            admin_profile.polls_created.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub admin_poll_entry: Account<'info, AdminPollEntry>,
+
+    /// The program treasury; needed while `config.poll_creation_fee` is set.
+    #[account(mut, seeds = [b"treasury"], bump = treasury.bump)]
+    pub treasury: Option<Account<'info, Treasury>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundRentSponsor<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init_if_needed,
+        payer = funder,
+        space = RentSponsor::SPACE,
+        seeds = [b"rent_sponsor", poll.key().as_ref()],
+        bump
+    )]
+    pub rent_sponsor: Account<'info, RentSponsor>,
+
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SponsorVoteRecord<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"rent_sponsor", poll.key().as_ref()],
+        bump = rent_sponsor.bump,
+    )]
+    pub rent_sponsor: Account<'info, RentSponsor>,
+
+    /// CHECK: The voter's vote record address, not yet created; only its
+    /// lamports are checked and credited.
+    #[account(
+        mut,
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            voter.key().as_ref(),
+        ],
+        bump
+    )]
+    pub vote_record: UncheckedAccount<'info>,
+
+    pub voter: Signer<'info>,
+
+    /// CHECK: Address is pinned to the instructions sysvar.
+    #[account(address = ix_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimRentSponsor<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+        has_one = admin @ VotingError::Unauthorized,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"rent_sponsor", poll.key().as_ref()],
+        bump = rent_sponsor.bump,
+        close = admin,
+    )]
+    pub rent_sponsor: Account<'info, RentSponsor>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EnableVoteReceipts<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+        has_one = admin @ VotingError::Unauthorized,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    /// CHECK: A Bubblegum tree; Bubblegum checks the poll is its delegate
+    /// when receipts are minted.
+    #[account(owner = ACCOUNT_COMPRESSION_PROGRAM_ID)]
+    pub receipt_tree: UncheckedAccount<'info>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MintVoteReceipt<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            voter.key().as_ref(),
+        ],
+        bump = vote_record.bump,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    #[account(
+        mut,
+        seeds = [b"rent_sponsor", poll.key().as_ref()],
+        bump = rent_sponsor.bump,
+    )]
+    pub rent_sponsor: Account<'info, RentSponsor>,
+
+    /// CHECK: Bubblegum's config for `receipt_tree`, checked by Bubblegum.
+    #[account(mut)]
+    pub tree_authority: UncheckedAccount<'info>,
+
+    /// CHECK: The poll's receipt tree.
+    #[account(mut, address = poll.receipt_tree @ VotingError::VoteReceiptsDisabled)]
+    pub receipt_tree: UncheckedAccount<'info>,
+
+    pub voter: Signer<'info>,
+
+    /// CHECK: The Bubblegum program.
+    #[account(address = BUBBLEGUM_PROGRAM_ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+
+    /// CHECK: The spl-account-compression program.
+    #[account(address = ACCOUNT_COMPRESSION_PROGRAM_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    /// CHECK: The noop program the compression program logs through.
+    #[account(address = NOOP_PROGRAM_ID)]
+    pub noop_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct LockConviction<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = ConvictionLock::SPACE,
+        seeds = [b"conviction", poll.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub conviction_lock: Account<'info, ConvictionLock>,
+
+    #[account(address = poll.conviction_mint @ VotingError::InvalidEscrowAccounts)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// The conviction lock's associated token account for `mint`.
+    #[account(mut)]
+    pub lock_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub voter_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnlockConviction<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"conviction", poll.key().as_ref(), voter.key().as_ref()],
+        bump = conviction_lock.bump,
+        close = voter,
+    )]
+    pub conviction_lock: Account<'info, ConvictionLock>,
+
+    #[account(address = poll.conviction_mint @ VotingError::InvalidEscrowAccounts)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub lock_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub voter_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64, voter: Pubkey)]
+pub struct AttestSnapshotBalance<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+        constraint = poll.snapshot_oracle != Pubkey::default() @ VotingError::SnapshotDisabled,
+        constraint = poll.snapshot_oracle == oracle.key() @ VotingError::Unauthorized,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init,
+        payer = oracle,
+        space = SnapshotBalance::SPACE,
+        seeds = [b"snapshot", poll.key().as_ref(), voter.as_ref()],
+        bump
+    )]
+    pub snapshot_balance: Account<'info, SnapshotBalance>,
+
+    #[account(mut)]
+    pub oracle: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Vote<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Box<Account<'info, Poll>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Lets `voter` be a session key voting for the token's authority; the
+    /// vote record and membership then belong to the authority.
+    #[account(
+        seeds = [b"session", session_token.authority.as_ref(), voter.key().as_ref()],
+        bump = session_token.bump,
+    )]
+    pub session_token: Option<Account<'info, SessionToken>>,
+
+    /// The vote_record PDA ensures each wallet can only vote once per poll.
+    /// If a wallet tries to vote again, account initialization will fail.
+    #[account(
+        init,
+        payer = voter,
+        space = VoteRecord::space(poll.allow_memos),
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            SessionToken::ballot_owner(&session_token, &voter).as_ref(),
+        ],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    /// The ballot owner's voter roll membership; only needed on polls with a
+    /// voter roll.
+    #[account(
+        seeds = [
+            b"member",
+            poll.key().as_ref(),
+            SessionToken::ballot_owner(&session_token, &voter).as_ref(),
+        ],
+        bump = membership.bump,
+    )]
+    pub membership: Option<Account<'info, RollMembership>>,
+
+    /// CHECK: The ballot owner's Civic gateway token, verified by the
+    /// handler; only needed on polls with a gatekeeper network.
+    pub gateway_token: Option<UncheckedAccount<'info>>,
+
+    /// The poll's badge mint, verified by the handler; only needed on polls
+    /// with a badge mint.
+    pub badge_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// The ballot owner's token account for `badge_mint`, verified by the
+    /// handler.
+    pub badge_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The ballot owner's associated token account for the poll's
+    /// `token_gate_mint`, under SPL Token or Token-2022, verified by the
+    /// handler; only needed on polls with a minimum token balance.
+    pub gate_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The poll's `token_gate_mint`; needed with `gate_token` when it is a
+    /// Token-2022 mint, so interest-bearing balances count their accrued
+    /// interest.
+    pub gate_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// The ballot owner's attested balance at the poll's snapshot; replaces
+    /// `gate_token` on polls with a `snapshot_oracle`.
+    #[account(
+        seeds = [
+            b"snapshot",
+            poll.key().as_ref(),
+            SessionToken::ballot_owner(&session_token, &voter).as_ref(),
+        ],
+        bump = snapshot_balance.bump,
+    )]
+    pub snapshot_balance: Option<Account<'info, SnapshotBalance>>,
+
+    /// CHECK: Address is pinned to the instructions sysvar; only needed on
+    /// polls with `direct_votes_only`.
+    #[account(address = ix_sysvar::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+
+    /// The ballot owner's conviction lock; only needed on conviction polls.
+    #[account(
+        seeds = [
+            b"conviction",
+            poll.key().as_ref(),
+            SessionToken::ballot_owner(&session_token, &voter).as_ref(),
+        ],
+        bump = conviction_lock.bump,
+    )]
+    pub conviction_lock: Option<Account<'info, ConvictionLock>>,
+
+    /// The ballot owner's reputation, credited with the ballot; needed on
+    /// polls with a minimum reputation.
+    #[account(
+        mut,
+        seeds = [
+            b"reputation",
+            SessionToken::ballot_owner(&session_token, &voter).as_ref(),
+        ],
+        bump = reputation.bump,
+    )]
+    pub reputation: Option<Account<'info, Reputation>>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EnableCompressedVotes<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+        has_one = admin @ VotingError::Unauthorized,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    /// CHECK: Allocated by the admin for the compression program, which
+    /// initializes it.
+    #[account(mut, owner = ACCOUNT_COMPRESSION_PROGRAM_ID)]
+    pub vote_tree: UncheckedAccount<'info>,
+
+    /// CHECK: Allocated zeroed by the admin; `VoteFilter::init` writes its
+    /// header.
+    #[account(mut, owner = crate::ID)]
+    pub vote_filter: UncheckedAccount<'info>,
+
+    pub admin: Signer<'info>,
+
+    /// CHECK: The spl-account-compression program.
+    #[account(address = ACCOUNT_COMPRESSION_PROGRAM_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    /// CHECK: The noop program the compression program logs through.
+    #[account(address = NOOP_PROGRAM_ID)]
+    pub noop_program: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct VoteCompressed<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// The voter's roll membership; only needed on polls with a voter roll.
+    #[account(
+        seeds = [b"member", poll.key().as_ref(), voter.key().as_ref()],
+        bump = membership.bump,
+    )]
+    pub membership: Option<Account<'info, RollMembership>>,
+
+    /// CHECK: The poll's concurrent merkle tree.
+    #[account(mut, address = poll.vote_tree @ VotingError::CompressedVotesDisabled)]
+    pub vote_tree: UncheckedAccount<'info>,
+
+    /// CHECK: The poll's nullifier filter, decoded by `VoteFilter::insert`.
+    #[account(mut, address = poll.vote_filter @ VotingError::CompressedVotesDisabled)]
+    pub vote_filter: UncheckedAccount<'info>,
+
+    pub voter: Signer<'info>,
+
+    /// CHECK: The spl-account-compression program.
+    #[account(address = ACCOUNT_COMPRESSION_PROGRAM_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    /// CHECK: The noop program the compression program logs through.
+    #[account(address = NOOP_PROGRAM_ID)]
+    pub noop_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EnablePrivateBallots<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+        has_one = admin @ VotingError::Unauthorized,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = EncryptedTally::space(poll.candidates.len()),
+        seeds = [b"encrypted_tally", poll.key().as_ref()],
+        bump
+    )]
+    pub encrypted_tally: Account<'info, EncryptedTally>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct VotePrivate<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        mut,
+        address = poll.encrypted_tally @ VotingError::PrivateBallotsDisabled,
+    )]
+    pub encrypted_tally: Account<'info, EncryptedTally>,
+
+    /// Marks the voter as having voted; creating it twice fails.
+    #[account(
+        init,
+        payer = voter,
+        space = BallotNullifier::SPACE,
+        seeds = [b"nullifier", poll.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub nullifier: Account<'info, BallotNullifier>,
+
+    /// The voter's roll membership; only needed on polls with a voter roll.
+    #[account(
+        seeds = [b"member", poll.key().as_ref(), voter.key().as_ref()],
+        bump = membership.bump,
+    )]
+    pub membership: Option<Account<'info, RollMembership>>,
+
+    /// CHECK: The ballot's range proof context, checked by
+    /// `BallotProofs::verify_ballot`.
+    pub range_proof: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PostPrivateTally<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        address = poll.encrypted_tally @ VotingError::PrivateBallotsDisabled,
+        has_one = tally_authority @ VotingError::Unauthorized,
+    )]
+    pub encrypted_tally: Account<'info, EncryptedTally>,
+
+    pub tally_authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct VoteEncrypted<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = EncryptedBallot::SPACE,
+        seeds = [b"encrypted_ballot", poll.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub ballot: Account<'info, EncryptedBallot>,
+
+    /// The voter's roll membership; only needed on polls with a voter roll.
+    #[account(
+        seeds = [b"member", poll.key().as_ref(), voter.key().as_ref()],
+        bump = membership.bump,
+    )]
+    pub membership: Option<Account<'info, RollMembership>>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevealBallotKey<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+        has_one = admin @ VotingError::Unauthorized,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TallyEncryptedVotes<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SlashRevealBond<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init_if_needed,
+        payer = caller,
+        space = Treasury::SPACE,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(_poll_id: u64, name: String)]
+pub struct VoteWriteIn<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = voter,
+        space = WriteInCandidate::SPACE,
+        seeds = [b"write_in", poll.key().as_ref(), WriteInCandidate::name_hash(&name).as_ref()],
+        bump
+    )]
+    pub write_in: Account<'info, WriteInCandidate>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = VoteRecord::space(poll.allow_memos),
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            voter.key().as_ref(),
+        ],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    /// The ballot owner's voter roll membership; only needed on polls with a
+    /// voter roll.
+    #[account(
+        seeds = [b"member", poll.key().as_ref(), voter.key().as_ref()],
+        bump = membership.bump,
+    )]
+    pub membership: Option<Account<'info, RollMembership>>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ChangeVote<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            voter.key().as_ref(),
+        ],
+        bump = vote_record.bump,
+        realloc = VoteRecord::space(poll.allow_memos),
+        realloc::payer = voter,
+        realloc::zero = false,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetVoteMemo<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            voter.key().as_ref(),
+        ],
+        bump = vote_record.bump,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    pub voter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVoteRecord<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            voter.key().as_ref(),
+        ],
+        bump = vote_record.bump,
+        close = voter,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct VoteBatch<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(_poll_id: u64, voter: Pubkey)]
+pub struct VoteWithSignature<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Same PDA as a directly cast ballot, so a voter gets one vote either way.
+    #[account(
+        init,
+        payer = relayer,
+        space = VoteRecord::space(poll.allow_memos),
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            voter.as_ref(),
+        ],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = VoteNonce::SPACE,
+        seeds = [b"nonce", voter.as_ref()],
+        bump
+    )]
+    pub vote_nonce: Account<'info, VoteNonce>,
+
+    /// The voter's roll membership; only needed on polls with a voter roll.
+    #[account(
+        seeds = [b"member", poll.key().as_ref(), voter.as_ref()],
+        bump = membership.bump,
+    )]
+    pub membership: Option<Account<'info, RollMembership>>,
+
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    /// CHECK: Address is pinned to the instructions sysvar.
+    #[account(address = ix_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(session_key: Pubkey)]
+pub struct CreateSession<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = SessionToken::SPACE,
+        seeds = [b"session", authority.key().as_ref(), session_key.as_ref()],
+        bump
+    )]
+    pub session_token: Account<'info, SessionToken>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PracticeVote<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    /// CHECK: Only inspected to report whether the voter has already voted;
+    /// the seeds pin it to the voter's real vote record address.
+    #[account(
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            voter.key().as_ref(),
+        ],
+        bump
+    )]
+    pub vote_record: UncheckedAccount<'info>,
+
+    /// The ballot owner's voter roll membership; only needed on polls with a
+    /// voter roll.
+    #[account(
+        seeds = [b"member", poll.key().as_ref(), voter.key().as_ref()],
+        bump = membership.bump,
+    )]
+    pub membership: Option<Account<'info, RollMembership>>,
+
+    pub voter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetDelegation<'info> {
+    #[account(
+        init_if_needed,
+        payer = delegator,
+        space = Delegation::SPACE,
+        seeds = [b"delegation", delegator.key().as_ref()],
+        bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct VoteDelegated<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        seeds = [b"delegation", delegation.delegator.as_ref()],
+        bump = delegation.bump,
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    /// The delegator's own vote record, created by the first delegate to
+    /// vote. Sharing it with direct voting makes the two mutually exclusive.
+    #[account(
+        init_if_needed,
+        payer = delegate,
+        space = VoteRecord::space(poll.allow_memos),
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            delegation.delegator.as_ref(),
+        ],
+        bump
+    )]
+    pub delegator_vote_record: Account<'info, VoteRecord>,
+
+    /// Ensures each delegate casts a given delegator's share only once per poll.
+    #[account(
+        init,
+        payer = delegate,
+        space = DelegatedVote::SPACE,
+        seeds = [
+            b"delegated_vote",
+            poll.key().as_ref(),
+            delegation.delegator.as_ref(),
+            delegate.key().as_ref(),
+        ],
+        bump
+    )]
+    pub delegated_vote: Account<'info, DelegatedVote>,
+
+    /// The ballot owner's voter roll membership; only needed on polls with a
+    /// voter roll.
+    #[account(
+        seeds = [b"member", poll.key().as_ref(), delegation.delegator.as_ref()],
+        bump = membership.bump,
+    )]
+    pub membership: Option<Account<'info, RollMembership>>,
+
+    #[account(mut)]
+    pub delegate: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPollProxy<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init_if_needed,
+        payer = delegator,
+        space = PollProxy::SPACE,
+        seeds = [b"proxy", poll.key().as_ref(), delegator.key().as_ref()],
+        bump
+    )]
+    pub poll_proxy: Account<'info, PollProxy>,
+
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeDelegation<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"proxy", poll.key().as_ref(), delegator.key().as_ref()],
+        bump = poll_proxy.bump,
+        constraint = !poll_proxy.voted @ VotingError::ProxyAlreadyVoted,
+        close = delegator,
+    )]
+    pub poll_proxy: Account<'info, PollProxy>,
+
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct VoteAsProxy<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"proxy", poll.key().as_ref(), poll_proxy.delegator.as_ref()],
+        bump = poll_proxy.bump,
+        has_one = proxy @ VotingError::NotADelegate,
+    )]
+    pub poll_proxy: Account<'info, PollProxy>,
+
+    /// The delegator's own vote record; initialization fails if they already
+    /// voted.
+    #[account(
+        init,
+        payer = proxy,
+        space = VoteRecord::space(poll.allow_memos),
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            poll_proxy.delegator.as_ref(),
+        ],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    /// The delegator's voter roll membership; only needed on polls with a
+    /// voter roll.
+    #[account(
+        seeds = [b"member", poll.key().as_ref(), poll_proxy.delegator.as_ref()],
+        bump = membership.bump,
+    )]
+    pub membership: Option<Account<'info, RollMembership>>,
+
+    #[account(mut)]
+    pub proxy: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct OverrideProxyVote<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"proxy", poll.key().as_ref(), delegator.key().as_ref()],
+        bump = poll_proxy.bump,
+        constraint = poll_proxy.voted @ VotingError::ProxyNotVoted,
+        close = delegator,
+    )]
+    pub poll_proxy: Account<'info, PollProxy>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            delegator.key().as_ref(),
+        ],
+        bump = vote_record.bump,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(poll_id: u64, candidate_index: u8, shard: u8)]
+pub struct InitCandidateTally<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = CandidateTally::SPACE,
+        seeds = [
+            b"tally",
+            poll.key().as_ref(),
+            &[candidate_index],
+            CandidateTally::shard_seed(shard).as_ref(),
+        ],
+        bump
+    )]
+    pub tally: Account<'info, CandidateTally>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64, candidate_index: u8)]
+pub struct VoteParallel<'info> {
+    /// Read-only: parallel votes never write-lock the poll.
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// The candidate's counter in the voter's shard.
+    #[account(
+        mut,
+        seeds = [
+            b"tally",
+            poll.key().as_ref(),
+            &[candidate_index],
+            CandidateTally::shard_seed(poll.tally_shard(&voter.key())).as_ref(),
+        ],
+        bump = tally.bump,
+    )]
+    pub tally: Account<'info, CandidateTally>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = VoteRecord::space(poll.allow_memos),
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            voter.key().as_ref(),
+        ],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    /// The ballot owner's voter roll membership; only needed on polls with a
+    /// voter roll.
+    #[account(
+        seeds = [b"member", poll.key().as_ref(), voter.key().as_ref()],
+        bump = membership.bump,
+    )]
+    pub membership: Option<Account<'info, RollMembership>>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AggregateTallies<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+}
+
+#[derive(Accounts)]
+pub struct BeginTally<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        space = TallyState::space(poll.candidates.len()),
+        seeds = [b"tally", poll.key().as_ref(), cranker.key().as_ref()],
+        bump
+    )]
+    pub tally_state: Account<'info, TallyState>,
+
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevealTallies<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"tally", poll.key().as_ref(), tally_state.cranker.as_ref()],
+        bump = tally_state.bump,
+    )]
+    pub tally_state: Account<'info, TallyState>,
+}
+
+#[derive(Accounts)]
+pub struct TallyPage<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"tally", poll.key().as_ref(), cranker.key().as_ref()],
+        bump = tally_state.bump,
+    )]
+    pub tally_state: Account<'info, TallyState>,
+
+    pub cranker: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PublishAttendance<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = AttendanceRoll::SPACE,
+        seeds = [b"attendance", poll.key().as_ref()],
+        bump
+    )]
+    pub attendance: Account<'info, AttendanceRoll>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundFeeVault<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init_if_needed,
+        payer = funder,
+        space = FeeVault::SPACE,
+        seeds = [b"fee_vault", poll.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AutoFinalize<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_vault", poll.key().as_ref()],
+        bump = fee_vault.bump,
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+}
+
+/// From a Realms proposal (see `CreateGovernedPoll`) the account metas are
+/// `poll` (writable), `config`, `role` (this program's ID), `audit_log`
+/// (this program's ID, or the current page, writable, on polls with an
+/// audit log), `admin` (the governance account, signer), `event_authority`
+/// and `program`.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClosePoll<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Only needed when the signer is a co-admin rather than the admin.
+    #[account(
+        seeds = [b"role", poll.role_scope(poll.key()).as_ref(), admin.key().as_ref()],
+        bump = role.bump,
+    )]
+    pub role: Option<Account<'info, PollRole>>,
+
+    /// Required on polls with `audit_log`: the log's current page.
+    #[account(
+        mut,
+        seeds = [b"audit_log", poll.key().as_ref(), poll.audit_page().to_le_bytes().as_ref()],
+        bump = audit_log.bump,
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenAuditPage<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = AuditLog::SPACE,
+        seeds = [b"audit_log", poll.key().as_ref(), poll.audit_page().to_le_bytes().as_ref()],
+        bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CrankCloseExpired<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FreezePoll<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Only needed when the signer is a co-admin rather than the admin.
+    #[account(
+        seeds = [b"role", poll.role_scope(poll.key()).as_ref(), admin.key().as_ref()],
+        bump = role.bump,
+    )]
+    pub role: Option<Account<'info, PollRole>>,
+
+    /// Required on polls with `audit_log`: the log's current page.
+    #[account(
+        mut,
+        seeds = [b"audit_log", poll.key().as_ref(), poll.audit_page().to_le_bytes().as_ref()],
+        bump = audit_log.bump,
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReopenPoll<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Only needed when the signer is a co-admin rather than the admin.
+    #[account(
+        seeds = [b"role", poll.role_scope(poll.key()).as_ref(), admin.key().as_ref()],
+        bump = role.bump,
+    )]
+    pub role: Option<Account<'info, PollRole>>,
+
+    /// Required on polls with `audit_log`: the log's current page.
+    #[account(
+        mut,
+        seeds = [b"audit_log", poll.key().as_ref(), poll.audit_page().to_le_bytes().as_ref()],
+        bump = audit_log.bump,
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExtendDeadline<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Required on polls with `audit_log`: the log's current page.
+    #[account(
+        mut,
+        seeds = [b"audit_log", poll.key().as_ref(), poll.audit_page().to_le_bytes().as_ref()],
+        bump = audit_log.bump,
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ActivatePoll<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(poll_id: u64, title: String, candidates: Vec<String>, settings: PollSettings)]
+pub struct EditDraft<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+        realloc = Poll::space_for(candidates.len(), &settings, &config.limits)
+            .max(poll.to_account_info().data_len()),
+        realloc::payer = admin,
+        realloc::zero = false,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Required on polls with `audit_log`: the log's current page.
+    #[account(
+        mut,
+        seeds = [b"audit_log", poll.key().as_ref(), poll.audit_page().to_le_bytes().as_ref()],
+        bump = audit_log.bump,
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelPoll<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Required on polls with `audit_log`: the log's current page.
+    #[account(
+        mut,
+        seeds = [b"audit_log", poll.key().as_ref(), poll.audit_page().to_le_bytes().as_ref()],
+        bump = audit_log.bump,
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub admin: Signer<'info>,
+}
+
+/// From a Realms proposal the account metas are the same as `ClosePoll`'s,
+/// without `audit_log`.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FinalizePoll<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Only needed when the signer is a co-admin rather than the admin.
+    #[account(
+        seeds = [b"role", poll.role_scope(poll.key()).as_ref(), admin.key().as_ref()],
+        bump = role.bump,
+    )]
+    pub role: Option<Account<'info, PollRole>>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CommitTieBreak<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Only needed when the signer is a co-admin rather than the admin.
+    #[account(
+        seeds = [b"role", poll.role_scope(poll.key()).as_ref(), admin.key().as_ref()],
+        bump = role.bump,
+    )]
+    pub role: Option<Account<'info, PollRole>>,
+
+    /// CHECK: Owner and layout are checked by `SwitchboardRandomness::load`.
+    pub randomness: UncheckedAccount<'info>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FinalizePollWithRandomness<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Only needed when the signer is a co-admin rather than the admin.
+    #[account(
+        seeds = [b"role", poll.role_scope(poll.key()).as_ref(), admin.key().as_ref()],
+        bump = role.bump,
+    )]
+    pub role: Option<Account<'info, PollRole>>,
+
+    /// CHECK: Must be the committed account; layout checked by
+    /// `SwitchboardRandomness::load`.
+    pub randomness: UncheckedAccount<'info>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeMinimal<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Only needed when the signer is a co-admin rather than the admin.
+    #[account(
+        seeds = [b"role", poll.role_scope(poll.key()).as_ref(), admin.key().as_ref()],
+        bump = role.bump,
+    )]
+    pub role: Option<Account<'info, PollRole>>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PublishResults<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ChallengeResult<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            challenger.key().as_ref(),
+        ],
+        bump = vote_record.bump,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    #[account(
+        init,
+        payer = challenger,
+        space = Challenge::SPACE,
+        seeds = [b"challenge", poll.key().as_ref(), challenger.key().as_ref()],
+        bump
+    )]
+    pub challenge: Account<'info, Challenge>,
+
+    #[account(mut)]
+    pub challenger: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ResolveChallenge<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+        has_one = admin @ VotingError::Unauthorized,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"challenge", poll.key().as_ref(), challenger.key().as_ref()],
+        bump = challenge.bump,
+        close = challenger,
+    )]
+    pub challenge: Account<'info, Challenge>,
+
+    /// CHECK: Only receives the challenge's rent, and its deposit if upheld.
+    #[account(mut)]
+    pub challenger: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = Treasury::SPACE,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CertifyResult<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    /// The poll's admin or its designated certifier.
+    pub certifier: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ArchivePoll<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+        has_one = admin @ VotingError::Unauthorized,
+        close = admin,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = PollResult::SPACE,
+        seeds = [b"result", poll.key().as_ref()],
+        bump
+    )]
+    pub result: Account<'info, PollResult>,
+
+    /// CHECK: Only checked to be empty; prize pools close once paid out.
+    #[account(seeds = [b"prize", poll.key().as_ref()], bump)]
+    pub prize_pool: UncheckedAccount<'info>,
+
+    /// CHECK: Only checked to be empty.
+    #[account(seeds = [b"reward", poll.key().as_ref()], bump)]
+    pub reward_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Only checked to be empty.
+    #[account(seeds = [b"rent_sponsor", poll.key().as_ref()], bump)]
+    pub rent_sponsor: UncheckedAccount<'info>,
+
+    /// CHECK: Only checked to be empty.
+    #[account(seeds = [b"funding", poll.key().as_ref()], bump)]
+    pub funding_round: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct GcExpiredPoll<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    /// CHECK: Receives the poll's rent when it is archived.
+    #[account(mut, address = poll.admin @ VotingError::Unauthorized)]
+    pub admin: UncheckedAccount<'info>,
+
+    /// Only passed to archive the poll.
+    #[account(
+        init,
+        payer = cranker,
+        space = PollResult::SPACE,
+        seeds = [b"result", poll.key().as_ref()],
+        bump
+    )]
+    pub result: Option<Account<'info, PollResult>>,
+
+    /// CHECK: Only checked to be empty when archiving.
+    #[account(seeds = [b"prize", poll.key().as_ref()], bump)]
+    pub prize_pool: UncheckedAccount<'info>,
+
+    /// CHECK: Only checked to be empty when archiving.
+    #[account(seeds = [b"reward", poll.key().as_ref()], bump)]
+    pub reward_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Only checked to be empty when archiving.
+    #[account(seeds = [b"rent_sponsor", poll.key().as_ref()], bump)]
+    pub rent_sponsor: UncheckedAccount<'info>,
+
+    /// CHECK: Only checked to be empty when archiving.
+    #[account(seeds = [b"funding", poll.key().as_ref()], bump)]
+    pub funding_round: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseArchivedVoteRecord<'info> {
+    #[account(
+        seeds = [b"result", result.poll.as_ref()],
+        bump = result.bump,
+    )]
+    pub result: Account<'info, PollResult>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vote",
+            Poll::seed_for(&result.namespace),
+            result.poll_id.to_le_bytes().as_ref(),
+            voter.key().as_ref(),
+        ],
+        bump = vote_record.bump,
+        close = voter,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(action: MultisigAction)]
+pub struct ApproveAction<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    /// One approval per approver per action; a second `init` fails.
+    #[account(
+        init,
+        payer = approver,
+        space = Approval::SPACE,
+        seeds = [
+            b"approval",
+            poll.key().as_ref(),
+            &[action as u8],
+            approver.key().as_ref(),
+        ],
+        bump
+    )]
+    pub approval: Account<'info, Approval>,
+
+    #[account(mut)]
+    pub approver: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePollMetadata<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Required on polls with `audit_log`: the log's current page.
+    #[account(
+        mut,
+        seeds = [b"audit_log", poll.key().as_ref(), poll.audit_page().to_le_bytes().as_ref()],
+        bump = audit_log.bump,
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(title: String)]
+pub struct UpdatePollTitle<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+        realloc = poll.space_with_title(poll.to_account_info().data_len(), &title),
+        realloc::payer = admin,
+        realloc::zero = false,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Required on polls with `audit_log`: the log's current page.
+    #[account(
+        mut,
+        seeds = [b"audit_log", poll.key().as_ref(), poll.audit_page().to_le_bytes().as_ref()],
+        bump = audit_log.bump,
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(candidate_index: u8)]
+pub struct SetCandidateDescription<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Only needed when the signer is a co-admin rather than the admin.
+    #[account(
+        seeds = [b"role", poll.role_scope(poll.key()).as_ref(), admin.key().as_ref()],
+        bump = role.bump,
+    )]
+    pub role: Option<Account<'info, PollRole>>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = CandidateDescription::SPACE,
+        seeds = [b"description", poll.key().as_ref(), &[candidate_index]],
+        bump
+    )]
+    pub description: Account<'info, CandidateDescription>,
+
+    /// Required on polls with `audit_log`: the log's current page.
+    #[account(
+        mut,
+        seeds = [b"audit_log", poll.key().as_ref(), poll.audit_page().to_le_bytes().as_ref()],
+        bump = audit_log.bump,
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(candidate_index: u8)]
+pub struct SetCandidatePayout<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Only needed when the signer is a co-admin rather than the admin.
+    #[account(
+        seeds = [b"role", poll.role_scope(poll.key()).as_ref(), admin.key().as_ref()],
+        bump = role.bump,
+    )]
+    pub role: Option<Account<'info, PollRole>>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = CandidatePayout::SPACE,
+        seeds = [b"payout", poll.key().as_ref(), &[candidate_index]],
+        bump
+    )]
+    pub candidate_payout: Account<'info, CandidatePayout>,
+
+    /// Required on polls with `audit_log`: the log's current page.
+    #[account(
+        mut,
+        seeds = [b"audit_log", poll.key().as_ref(), poll.audit_page().to_le_bytes().as_ref()],
+        bump = audit_log.bump,
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Token prizes need all of `mint`, `prize_vault`, `funder_token` and
+/// `token_program`; lamport prizes need none of them.
+#[derive(Accounts)]
+pub struct FundPrizePool<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+        has_one = admin @ VotingError::Unauthorized,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = PrizePool::SPACE,
+        seeds = [b"prize", poll.key().as_ref()],
+        bump
+    )]
+    pub prize_pool: Account<'info, PrizePool>,
+
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// The prize pool's associated token account for `mint`.
+    #[account(mut)]
+    pub prize_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub funder_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Token prizes need `mint`, `prize_vault`, `recipient_token` and
+/// `token_program`.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimPrize<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"prize", poll.key().as_ref()],
+        bump = prize_pool.bump,
+        close = recipient,
+    )]
+    pub prize_pool: Account<'info, PrizePool>,
+
+    #[account(
+        seeds = [b"payout", poll.key().as_ref(), &[candidate_payout.candidate_index]],
+        bump = candidate_payout.bump,
+        constraint = candidate_payout.payout == recipient.key() @ VotingError::NotPrizeRecipient,
+    )]
+    pub candidate_payout: Account<'info, CandidatePayout>,
+
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub prize_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub recipient_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+/// Token prizes need `mint`, `prize_vault`, `admin_token` and
+/// `token_program`.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReclaimPrize<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+        has_one = admin @ VotingError::Unauthorized,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"prize", poll.key().as_ref()],
+        bump = prize_pool.bump,
+        close = admin,
+    )]
+    pub prize_pool: Account<'info, PrizePool>,
+
+    /// CHECK: The winner's `CandidatePayout` address, checked by the
+    /// handler; only needed to reclaim a finalized poll's prize before the
+    /// claim window ends.
+    pub winner_payout: Option<UncheckedAccount<'info>>,
+
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub prize_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub admin_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+/// Token rewards need all of `mint`, `reward_token_vault`, `funder_token`
+/// and `token_program`; lamport rewards need none of them.
+#[derive(Accounts)]
+pub struct FundRewardVault<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+        has_one = admin @ VotingError::Unauthorized,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = RewardVault::SPACE,
+        seeds = [b"reward", poll.key().as_ref()],
+        bump
+    )]
+    pub reward_vault: Account<'info, RewardVault>,
+
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// The reward vault's associated token account for `mint`.
+    #[account(mut)]
+    pub reward_token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub funder_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Token rewards need `mint`, `reward_token_vault`, `voter_token` and
+/// `token_program`.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimReward<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"reward", poll.key().as_ref()],
+        bump = reward_vault.bump,
+    )]
+    pub reward_vault: Account<'info, RewardVault>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            voter.key().as_ref(),
+        ],
+        bump = vote_record.bump,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub reward_token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub voter_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+/// Token rewards need `mint`, `reward_token_vault`, `admin_token` and
+/// `token_program`.
+#[derive(Accounts)]
+pub struct ReclaimReward<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+        has_one = admin @ VotingError::Unauthorized,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"reward", poll.key().as_ref()],
+        bump = reward_vault.bump,
+        close = admin,
+    )]
+    pub reward_vault: Account<'info, RewardVault>,
+
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub reward_token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub admin_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct InitReputation<'info> {
+    #[account(
+        init,
+        payer = voter,
+        space = Reputation::SPACE,
+        seeds = [b"reputation", voter.key().as_ref()],
+        bump
+    )]
+    pub reputation: Account<'info, Reputation>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecordParticipation<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"reputation", reputation.voter.as_ref()],
+        bump = reputation.bump,
+    )]
+    pub reputation: Account<'info, Reputation>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            reputation.voter.as_ref(),
+        ],
+        bump = vote_record.bump,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+}
+
+/// Token stakes need `mint`, `pool_vault`, `voter_token` and
+/// `token_program`.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PlaceStake<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = voter,
+        space = PredictionPool::SPACE,
+        seeds = [b"prediction", poll.key().as_ref()],
+        bump
+    )]
+    pub prediction_pool: Account<'info, PredictionPool>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = VoteRecord::space(poll.allow_memos),
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            voter.key().as_ref(),
+        ],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// The prediction pool's associated token account for `mint`.
+    #[account(mut)]
+    pub pool_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub voter_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ResolveOutcome<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"prediction", poll.key().as_ref()],
+        bump = prediction_pool.bump,
+    )]
+    pub prediction_pool: Account<'info, PredictionPool>,
+
+    pub resolver: Signer<'info>,
+}
+
+/// Token stakes need `mint`, `pool_vault`, `voter_token` and
+/// `token_program`.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimWinnings<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"prediction", poll.key().as_ref()],
+        bump = prediction_pool.bump,
+    )]
+    pub prediction_pool: Account<'info, PredictionPool>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            voter.key().as_ref(),
+        ],
+        bump = vote_record.bump,
+        close = voter,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub pool_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub voter_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+/// Token rounds need `mint`, `round_vault`, `funder_token` and
+/// `token_program`.
+#[derive(Accounts)]
+pub struct FundMatchingPool<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+        has_one = admin @ VotingError::Unauthorized,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = FundingRound::space(poll.candidates.len()),
+        seeds = [b"funding", poll.key().as_ref()],
+        bump
+    )]
+    pub funding_round: Account<'info, FundingRound>,
+
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// The funding round's associated token account for `mint`.
+    #[account(mut)]
+    pub round_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub funder_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Token rounds need `mint`, `round_vault`, `contributor_token` and
+/// `token_program`.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(_poll_id: u64, candidate_index: u8)]
+pub struct Contribute<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"funding", poll.key().as_ref()],
+        bump = funding_round.bump,
+    )]
+    pub funding_round: Account<'info, FundingRound>,
+
+    #[account(
+        init_if_needed,
+        payer = contributor,
+        space = Contribution::SPACE,
+        seeds = [
+            b"contribution",
+            poll.key().as_ref(),
+            contributor.key().as_ref(),
+            &[candidate_index],
+        ],
+        bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub round_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub contributor_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FinalizeFundingRound<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"funding", poll.key().as_ref()],
+        bump = funding_round.bump,
+    )]
+    pub funding_round: Account<'info, FundingRound>,
+}
+
+/// Token rounds need `mint`, `round_vault`, `recipient_token` and
+/// `token_program`.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(_poll_id: u64, candidate_index: u8)]
+pub struct ClaimFunding<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"funding", poll.key().as_ref()],
+        bump = funding_round.bump,
+    )]
+    pub funding_round: Account<'info, FundingRound>,
+
+    #[account(
+        seeds = [b"payout", poll.key().as_ref(), &[candidate_index]],
+        bump = candidate_payout.bump,
+        constraint = candidate_payout.payout == recipient.key() @ VotingError::NotPrizeRecipient,
+    )]
+    pub candidate_payout: Account<'info, CandidatePayout>,
+
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub round_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub recipient_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+/// Token rounds need `mint`, `round_vault`, `contributor_token` and
+/// `token_program`.
+#[derive(Accounts)]
+#[instruction(_poll_id: u64, candidate_index: u8)]
+pub struct RefundContribution<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"funding", poll.key().as_ref()],
+        bump = funding_round.bump,
+    )]
+    pub funding_round: Account<'info, FundingRound>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"contribution",
+            poll.key().as_ref(),
+            contributor.key().as_ref(),
+            &[candidate_index],
+        ],
+        bump = contribution.bump,
+        close = contributor,
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub round_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub contributor_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+/// Token rounds need `mint`, `round_vault`, `admin_token` and
+/// `token_program`.
+#[derive(Accounts)]
+pub struct ReclaimMatchingPool<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+        has_one = admin @ VotingError::Unauthorized,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        mut,
+        seeds = [b"funding", poll.key().as_ref()],
+        bump = funding_round.bump,
+    )]
+    pub funding_round: Account<'info, FundingRound>,
+
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub round_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub admin_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(_poll_id: u64, candidate_index: u8)]
+pub struct WithdrawCandidacy<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        seeds = [b"payout", poll.key().as_ref(), &[candidate_index]],
+        bump = candidate_payout.bump,
+        constraint = candidate_payout.payout == candidate.key() @ VotingError::Unauthorized,
+    )]
+    pub candidate_payout: Account<'info, CandidatePayout>,
+
+    pub candidate: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DisqualifyCandidate<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Only needed when the signer is a co-admin rather than the admin.
+    #[account(
+        seeds = [b"role", poll.role_scope(poll.key()).as_ref(), admin.key().as_ref()],
+        bump = role.bump,
+    )]
+    pub role: Option<Account<'info, PollRole>>,
+
+    /// Required on polls with `audit_log`: the log's current page.
+    #[account(
+        mut,
+        seeds = [b"audit_log", poll.key().as_ref(), poll.audit_page().to_le_bytes().as_ref()],
+        bump = audit_log.bump,
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(voter: Pubkey)]
+pub struct InvalidateVote<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Only needed when the signer is a co-admin rather than the admin.
+    #[account(
+        seeds = [b"role", poll.role_scope(poll.key()).as_ref(), admin.key().as_ref()],
+        bump = role.bump,
+    )]
+    pub role: Option<Account<'info, PollRole>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"vote",
+            poll.namespace_seed(),
+            poll.poll_id.to_le_bytes().as_ref(),
+            voter.as_ref(),
+        ],
+        bump = vote_record.bump,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    /// Required on polls with `audit_log`: the log's current page.
+    #[account(
+        mut,
+        seeds = [b"audit_log", poll.key().as_ref(), poll.audit_page().to_le_bytes().as_ref()],
+        bump = audit_log.bump,
+    )]
+    pub audit_log: Option<Account<'info, AuditLog>>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(identity_hash: [u8; 32])]
+pub struct AddRollEntry<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Only needed when the signer is a co-admin rather than the admin.
+    #[account(
+        seeds = [b"role", poll.role_scope(poll.key()).as_ref(), admin.key().as_ref()],
+        bump = role.bump,
+    )]
+    pub role: Option<Account<'info, PollRole>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = RollEntry::SPACE,
+        seeds = [b"roll", poll.key().as_ref(), identity_hash.as_ref()],
+        bump
+    )]
+    pub roll_entry: Account<'info, RollEntry>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(salt: [u8; 32])]
+pub struct ProveMembership<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    /// Only exists if the admin added the hash the revealed salt produces.
+    /// Runoffs check the first round's roll.
+    #[account(
+        seeds = [
+            b"roll",
+            poll.roll_poll(poll.key()).as_ref(),
+            RollEntry::identity_hash(&poll.roll_poll(poll.key()), &voter.key(), &salt).as_ref(),
+        ],
+        bump = roll_entry.bump,
+    )]
+    pub roll_entry: Account<'info, RollEntry>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = RollMembership::SPACE,
+        seeds = [b"member", poll.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub membership: Account<'info, RollMembership>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(grantee: Pubkey)]
+pub struct GrantRole<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = PollRole::SPACE,
+        seeds = [b"role", poll.key().as_ref(), grantee.as_ref()],
+        bump
+    )]
+    pub role: Account<'info, PollRole>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevokeRole<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"role", poll.key().as_ref(), role.grantee.as_ref()],
+        bump = role.bump,
+    )]
+    pub role: Account<'info, PollRole>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetOperationsKey<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ProposeAdminTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptAdminTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    pub new_admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AssertWinner<'info> {
+    #[account(
+        seeds = [b"poll", poll.namespace_seed(), poll.poll_id.to_le_bytes().as_ref()],
+        bump = poll.bump,
+    )]
+    pub poll: Account<'info, Poll>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = ConsumptionReceipt::SPACE,
+        seeds = [b"receipt", poll.key().as_ref(), consumer_program.key().as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, ConsumptionReceipt>,
+
+    /// CHECK: Must be the program of the top-level instruction, which the
+    /// handler verifies through the instructions sysvar.
+    pub consumer_program: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Address is pinned to the instructions sysvar.
+    #[account(address = ix_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// ─── Account Data Structures ─────────────────────────────────────────────────
+
+/// spl-account-compression (cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK),
+/// which owns compressed polls' vote trees.
+pub const ACCOUNT_COMPRESSION_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    9, 42, 19, 238, 149, 196, 28, 186, 8, 166, 127, 90, 198, 126, 141, 247, 225, 218, 17, 98, 94,
+    29, 100, 19, 127, 143, 79, 35, 131, 3, 127, 20,
+]);
+
+/// The noop program (noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV) the
+/// compression program logs tree changes through.
+pub const NOOP_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    11, 188, 15, 192, 187, 71, 202, 47, 116, 196, 17, 46, 148, 171, 19, 207, 163, 198, 52, 229,
+    220, 23, 234, 203, 3, 205, 26, 35, 205, 126, 120, 124,
+]);
+
+/// CPIs into spl-account-compression for a poll's vote tree, built by hand
+/// to avoid depending on its crate.
+pub struct VoteTree;
+
+impl VoteTree {
+    /// Anchor discriminators of `init_empty_merkle_tree` and `append`.
+    pub const INIT_DISCRIMINATOR: [u8; 8] = [191, 11, 119, 7, 180, 107, 220, 110];
+    pub const APPEND_DISCRIMINATOR: [u8; 8] = [149, 120, 18, 222, 236, 225, 88, 203];
+
+    /// The leaf a compressed ballot appends.
+    pub fn leaf(
+        poll: &Pubkey,
+        voter: &Pubkey,
+        candidate_index: u8,
+        weight: u64,
+        slot: u64,
+    ) -> [u8; 32] {
+        hashv(&[
+            b"ballot",
+            poll.as_ref(),
+            voter.as_ref(),
+            &[candidate_index],
+            &weight.to_le_bytes(),
+            &slot.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
+
+    /// Sends `data` to the compression program for `tree`, signed by the
+    /// poll as the tree's authority.
+    pub fn invoke<'info>(
+        data: Vec<u8>,
+        tree: &AccountInfo<'info>,
+        poll: &Account<'info, Poll>,
+        noop_program: &AccountInfo<'info>,
+    ) -> Result<()> {
+        let instruction = Instruction {
+            program_id: ACCOUNT_COMPRESSION_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(tree.key(), false),
+                AccountMeta::new_readonly(poll.key(), true),
+                AccountMeta::new_readonly(noop_program.key(), false),
+            ],
+            data,
+        };
+        let poll_id = poll.poll_id.to_le_bytes();
+        invoke_signed(
+            &instruction,
+            &[tree.clone(), poll.to_account_info(), noop_program.clone()],
+            &[&[b"poll", poll.namespace_seed(), &poll_id, &[poll.bump]]],
+        )?;
+        Ok(())
+    }
+}
+
+/// Metaplex Bubblegum (BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY), which
+/// mints compressed vote receipts.
+pub const BUBBLEGUM_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    152, 139, 128, 235, 121, 53, 40, 105, 178, 36, 116, 95, 89, 221, 191, 138, 38, 88, 202, 19,
+    220, 104, 129, 33, 38, 53, 28, 174, 7, 193, 165, 165,
+]);
+
+/// Bubblegum's `MetadataArgs`, with its enums and nested structs flattened
+/// to their borsh encodings.
+#[derive(AnchorSerialize)]
+pub struct ReceiptMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub primary_sale_happened: bool,
+    pub is_mutable: bool,
+    pub edition_nonce: Option<u8>,
+    /// `TokenStandard`, 0 for `NonFungible`.
+    pub token_standard: Option<u8>,
+    /// `Collection { verified, key }`.
+    pub collection: Option<(bool, Pubkey)>,
+    /// `Uses { use_method, remaining, total }`.
+    pub uses: Option<(u8, u64, u64)>,
+    /// `TokenProgramVersion`, 0 for `Original`.
+    pub token_program_version: u8,
+    /// `Creator { address, verified, share }`.
+    pub creators: Vec<(Pubkey, bool, u8)>,
+}
+
+/// "I voted" receipts minted through Bubblegum's `mint_v1`, built by hand
+/// to avoid depending on its crate.
+pub struct VoteReceipt;
+
+impl VoteReceipt {
+    /// Anchor discriminator of `mint_v1`.
+    pub const MINT_DISCRIMINATOR: [u8; 8] = [145, 98, 192, 118, 184, 147, 118, 104];
+    /// Longest name and symbol Metaplex metadata takes.
+    pub const MAX_NAME_LEN: usize = 32;
+    pub const SYMBOL: &'static str = "VOTED";
+
+    /// The receipt's metadata: "I voted: <title>", cut to fit, and the
+    /// poll's metadata URI.
+    pub fn metadata(poll: &Poll) -> ReceiptMetadata {
+        let mut name = format!("I voted: {}", poll.title);
+        let mut len = name.len().min(Self::MAX_NAME_LEN);
+        while !name.is_char_boundary(len) {
+            len -= 1;
+        }
+        name.truncate(len);
+        ReceiptMetadata {
+            name,
+            symbol: Self::SYMBOL.to_string(),
+            uri: poll.metadata_uri.clone(),
+            seller_fee_basis_points: 0,
+            primary_sale_happened: false,
+            is_mutable: false,
+            edition_nonce: None,
+            token_standard: Some(0),
+            collection: None,
+            uses: None,
+            token_program_version: 0,
+            creators: Vec::new(),
+        }
+    }
+
+    /// Mints `voter` a receipt into `tree`, signed by the poll as the tree's
+    /// delegate and by its rent sponsor as payer. `programs` are Bubblegum,
+    /// the noop program and spl-account-compression.
+    pub fn mint<'info>(
+        poll: &Account<'info, Poll>,
+        rent_sponsor: &Account<'info, RentSponsor>,
+        voter: &Signer<'info>,
+        tree_authority: &AccountInfo<'info>,
+        tree: &AccountInfo<'info>,
+        programs: [&AccountInfo<'info>; 3],
+        system_program: &AccountInfo<'info>,
+    ) -> Result<()> {
+        let [bubblegum, noop, compression] = programs;
+        let mut data = Self::MINT_DISCRIMINATOR.to_vec();
+        Self::metadata(poll).serialize(&mut data)?;
+        let instruction = Instruction {
+            program_id: BUBBLEGUM_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(tree_authority.key(), false),
+                AccountMeta::new_readonly(voter.key(), false),
+                AccountMeta::new_readonly(voter.key(), false),
+                AccountMeta::new(tree.key(), false),
+                AccountMeta::new(rent_sponsor.key(), true),
+                AccountMeta::new_readonly(poll.key(), true),
+                AccountMeta::new_readonly(noop.key(), false),
+                AccountMeta::new_readonly(compression.key(), false),
+                AccountMeta::new_readonly(system_program.key(), false),
+            ],
+            data,
+        };
+        let poll_id = poll.poll_id.to_le_bytes();
+        let poll_key = poll.key();
+        invoke_signed(
+            &instruction,
+            &[
+                tree_authority.clone(),
+                voter.to_account_info(),
+                tree.clone(),
+                rent_sponsor.to_account_info(),
+                poll.to_account_info(),
+                noop.clone(),
+                compression.clone(),
+                system_program.clone(),
+                bubblegum.clone(),
+            ],
+            &[
+                &[b"poll", poll.namespace_seed(), &poll_id, &[poll.bump]],
+                &[b"rent_sponsor", poll_key.as_ref(), &[rent_sponsor.bump]],
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+/// Switchboard on-demand program IDs, the only owners a tie-break
+/// randomness account may have.
+pub const SWITCHBOARD_ON_DEMAND_PROGRAM_IDS: [Pubkey; 2] = [
+    // SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv (mainnet)
+    Pubkey::new_from_array([
+        6, 115, 189, 70, 242, 228, 126, 4, 241, 43, 217, 47, 183, 49, 150, 142, 205, 157, 151,
+        87, 194, 116, 218, 135, 71, 111, 70, 92, 4, 12, 101, 115,
+    ]),
+    // Aio4gaXjXzJNVLtzwtNVmSqGKpANtXhybbkhtAC94ji2 (devnet)
+    Pubkey::new_from_array([
+        144, 110, 20, 100, 197, 248, 183, 99, 60, 192, 90, 66, 76, 221, 179, 174, 205, 109, 171,
+        184, 174, 199, 71, 188, 79, 62, 17, 48, 30, 64, 99, 203,
+    ]),
+];
+
+/// Gate requirements a ballot owner has proven, each already verified
+/// against the owner. `check_ballot` matches them to the poll's gates.
+#[derive(Default)]
+pub struct VoterPasses {
+    /// Network of the owner's active gateway token.
+    pub gatekeeper_network: Option<Pubkey>,
+    /// Non-transferable mint the owner holds a unit of.
+    pub badge_mint: Option<Pubkey>,
+    /// Mint and balance of the owner's associated token account.
+    pub token_balance: Option<(Pubkey, u64)>,
+    /// Active stake, in lamports, the owner counted on a stake-weighted poll.
+    pub stake_weight: Option<u64>,
+    /// NFTs of the poll's collection the owner counted on an NFT-weighted
+    /// poll.
+    pub nft_count: Option<u64>,
+    /// Weight of the owner's conviction lock on the poll.
+    pub conviction_weight: Option<u64>,
+    /// What the owner staked with `place_stake` on a prediction poll.
+    pub prediction_stake: Option<u64>,
+    /// The owner's reputation score before this ballot.
+    pub reputation: Option<u64>,
+    /// The ballot is a top-level instruction of this program, not a CPI.
+    pub direct_call: bool,
+}
+
+impl VoterPasses {
+    /// Whether the instruction being executed was called directly by the
+    /// transaction rather than through another program: the top-level
+    /// instruction in `instructions` (the sysvar) must be this program's.
+    pub fn verify_direct_call(instructions: &AccountInfo) -> Result<bool> {
+        let outer = ix_sysvar::get_instruction_relative(0, instructions)?;
+        Ok(outer.program_id == crate::ID)
+    }
+
+    /// Returns `mint`'s address if it is a Token-2022 mint with the
+    /// non-transferable extension and `token` is `owner`'s account holding
+    /// at least one unit of it.
+    pub fn verify_badge(
+        mint: &InterfaceAccount<Mint>,
+        token: &InterfaceAccount<TokenAccount>,
+        owner: &Pubkey,
+    ) -> Result<Pubkey> {
+        let mint_info = mint.to_account_info();
+        require!(
+            mint_info.owner == &spl_token_2022::ID,
+            VotingError::InvalidBadge
+        );
+        let mint_data = mint_info.try_borrow_data()?;
+        let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)
+            .map_err(|_| VotingError::InvalidBadge)?;
+        require!(
+            mint_state.get_extension::<NonTransferable>().is_ok(),
+            VotingError::InvalidBadge
+        );
+        require!(
+            token.mint == mint.key() && token.owner == *owner && token.amount >= 1,
+            VotingError::InvalidBadge
+        );
+        Ok(mint.key())
+    }
+
+    /// Returns the mint and balance of `token`, which must be `owner`'s
+    /// associated token account under SPL Token or Token-2022. Token-2022
+    /// balances need their `mint` and include any interest accrued under
+    /// the interest-bearing extension.
+    pub fn verify_token_balance(
+        token: &InterfaceAccount<TokenAccount>,
+        mint: Option<&InterfaceAccount<Mint>>,
+        owner: &Pubkey,
+    ) -> Result<(Pubkey, u64)> {
+        let token_program = *token.to_account_info().owner;
+        require!(
+            token.owner == *owner
+                && token.key()
+                    == get_associated_token_address_with_program_id(
+                        owner,
+                        &token.mint,
+                        &token_program,
+                    ),
+            VotingError::InvalidTokenAccount
+        );
+        if token_program == token::ID {
+            return Ok((token.mint, token.amount));
+        }
+        let mint = mint
+            .filter(|mint| mint.key() == token.mint)
+            .ok_or(VotingError::InvalidTokenAccount)?;
+        Ok((token.mint, Self::accrued_amount(mint, token.amount)?))
+    }
+
+    /// `amount` of a Token-2022 `mint` with the interest accrued so far
+    /// added, or unchanged if the mint isn't interest-bearing.
+    pub fn accrued_amount(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+        let mint_info = mint.to_account_info();
+        let mint_data = mint_info.try_borrow_data()?;
+        let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)
+            .map_err(|_| VotingError::InvalidTokenAccount)?;
+        let Ok(config) = mint_state.get_extension::<InterestBearingConfig>() else {
+            return Ok(amount);
+        };
+        // At zero decimals the UI amount is the balance in base units.
+        let accrued = config
+            .amount_to_ui_amount(amount, 0, Clock::get()?.unix_timestamp)
+            .and_then(|ui| ui.parse::<f64>().ok())
+            .ok_or(VotingError::InvalidTokenAccount)?;
+        Ok(accrued as u64)
+    }
+}
+
+/// Civic gateway program (gatem74V238djXdzWnJf94Wo1DcnuGkfijbf3AuBhfs),
+/// the owner of gateway tokens.
+pub const GATEWAY_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    10, 35, 248, 193, 156, 10, 77, 255, 245, 245, 47, 38, 174, 200, 84, 58, 98, 42, 12, 197, 198,
+    30, 81, 25, 62, 157, 73, 19, 220, 196, 171, 94,
+]);
+
+/// Metaplex Token Metadata program
+/// (metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s), the owner of NFT metadata.
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    11, 112, 101, 177, 227, 209, 124, 69, 56, 157, 82, 127, 107, 4, 195, 205, 88, 184, 108, 115,
+    26, 160, 253, 181, 73, 182, 209, 188, 3, 248, 41, 70,
+]);
+
+/// The fields of a Metaplex `Metadata` account used for NFT-weighted
+/// ballots, decoded here to avoid depending on the Metaplex crates.
+pub struct NftMetadata {
+    pub mint: Pubkey,
+    /// The collection and whether its authority verified the NFT as a member.
+    pub collection: Option<(Pubkey, bool)>,
+}
+
+impl NftMetadata {
+    const KEY_METADATA_V1: u8 = 4;
+    const CREATOR_SIZE: usize = 32 + 1 + 1;
+
+    pub fn load(info: &AccountInfo) -> Result<Self> {
+        require!(info.owner == &TOKEN_METADATA_PROGRAM_ID, VotingError::InvalidNft);
+        Self::parse(&info.try_borrow_data()?).ok_or_else(|| error!(VotingError::InvalidNft))
+    }
+
+    /// Walks the borsh layout up to the collection: key, update authority,
+    /// mint, name, symbol, uri, seller fee, creators, primary sale and
+    /// mutability flags, edition nonce and token standard.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let data = &mut &data[..];
+        if Self::take(data, 1)?[0] != Self::KEY_METADATA_V1 {
+            return None;
+        }
+        Self::take(data, 32)?;
+        let mint = Pubkey::try_from(Self::take(data, 32)?).ok()?;
+        for _ in 0..3 {
+            let len = Self::take_u32(data)?;
+            Self::take(data, len)?;
+        }
+        Self::take(data, 2)?;
+        if Self::take_flag(data)? {
+            let creators = Self::take_u32(data)?;
+            Self::take(data, creators.checked_mul(Self::CREATOR_SIZE)?)?;
+        }
+        Self::take(data, 2)?;
+        for _ in 0..2 {
+            if Self::take_flag(data)? {
+                Self::take(data, 1)?;
+            }
+        }
+        let collection = if Self::take_flag(data)? {
+            let verified = Self::take_flag(data)?;
+            Some((Pubkey::try_from(Self::take(data, 32)?).ok()?, verified))
+        } else {
+            None
+        };
+        Some(Self { mint, collection })
+    }
+
+    fn take<'a>(data: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+        let (head, rest) = data.split_at_checked(len)?;
+        *data = rest;
+        Some(head)
+    }
+
+    fn take_u32(data: &mut &[u8]) -> Option<usize> {
+        Some(u32::from_le_bytes(Self::take(data, 4)?.try_into().ok()?) as usize)
+    }
+
+    fn take_flag(data: &mut &[u8]) -> Option<bool> {
+        Some(Self::take(data, 1)?[0] == 1)
+    }
+}
+
+/// A Civic gateway token account, decoded from the borsh layout of
+/// `solana_gateway::state::GatewayToken` rather than through the
+/// `solana-gateway` crate, which would pull in its own pinned Solana
+/// version for one struct. `gateway_token_decodes_the_published_layout`
+/// pins the layout.
+#[derive(AnchorDeserialize)]
+pub struct GatewayToken {
+    pub features: u8,
+    pub parent_gateway_token: Option<Pubkey>,
+    pub owner_wallet: Pubkey,
+    pub owner_identity: Option<Pubkey>,
+    pub gatekeeper_network: Pubkey,
+    pub issuing_gatekeeper: Pubkey,
+    pub state: GatewayTokenState,
+    pub expire_time: Option<i64>,
+}
+
+#[derive(AnchorDeserialize, PartialEq)]
+pub enum GatewayTokenState {
+    Active,
+    Frozen,
+    Revoked,
+}
+
+impl GatewayToken {
+    /// Decodes `info` as a gateway token, failing unless it belongs to
+    /// `owner`, is active and hasn't expired.
+    pub fn load(info: &AccountInfo, owner: &Pubkey) -> Result<Self> {
+        require!(info.owner == &GATEWAY_PROGRAM_ID, VotingError::InvalidGatewayToken);
+        let token = Self::deserialize(&mut &info.try_borrow_data()?[..])
+            .map_err(|_| VotingError::InvalidGatewayToken)?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            token.owner_wallet == *owner
+                && token.state == GatewayTokenState::Active
+                && token.expire_time.is_none_or(|expiry| now < expiry),
+            VotingError::InvalidGatewayToken
+        );
+        Ok(token)
+    }
+}
+
+/// The fields of a Switchboard on-demand `RandomnessAccountData` used for
+/// tie-breaks, read by offset to avoid depending on the Switchboard SDK.
+pub struct SwitchboardRandomness {
+    /// Slot whose slothash seeds the randomness (set by its commit).
+    pub seed_slot: u64,
+    /// Slot in which `value` was revealed.
+    pub reveal_slot: u64,
+    pub value: [u8; 32],
+}
+
+impl SwitchboardRandomness {
+    pub const DISCRIMINATOR: [u8; 8] = [10, 66, 229, 135, 220, 239, 217, 114];
+    // Offsets past the discriminator: authority 32, queue 32, seed_slothash 32
+    const SEED_SLOT_OFFSET: usize = 8 + 96;
+    // ... seed_slot 8, oracle 32
+    const REVEAL_SLOT_OFFSET: usize = Self::SEED_SLOT_OFFSET + 40;
+    const VALUE_OFFSET: usize = Self::REVEAL_SLOT_OFFSET + 8;
+
+    pub fn load(info: &AccountInfo) -> Result<Self> {
+        require!(
+            SWITCHBOARD_ON_DEMAND_PROGRAM_IDS.contains(info.owner),
+            VotingError::InvalidRandomnessAccount
+        );
+        let data = info.try_borrow_data()?;
+        require!(
+            data.len() >= Self::VALUE_OFFSET + 32 && data[..8] == Self::DISCRIMINATOR,
+            VotingError::InvalidRandomnessAccount
+        );
+        let read_u64 = |offset: usize| {
+            u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+        };
+        Ok(Self {
+            seed_slot: read_u64(Self::SEED_SLOT_OFFSET),
+            reveal_slot: read_u64(Self::REVEAL_SLOT_OFFSET),
+            value: data[Self::VALUE_OFFSET..Self::VALUE_OFFSET + 32]
+                .try_into()
+                .unwrap(),
+        })
+    }
+}
+
+/// Poll creation fees collected for the program operator, at
+/// `[b"treasury"]`. Fees are held as the account's lamports above rent.
+#[account]
+pub struct Treasury {
+    pub total_collected: u64, // 8, lamports
+    pub total_withdrawn: u64, // 8, lamports
+    pub bump: u8,             // 1
+}
+
+impl Treasury {
+    pub const SPACE: usize = 8 + 8 + 8 + 1; // discriminator + fields
+}
+
+/// Program-wide settings, at `[b"config"]`.
+#[account]
+pub struct ProgramConfig {
+    pub authority: Pubkey,                    // 32
+    pub permissions: [u8; CONFIG_ROLE_COUNT], // 4, ACTION_* bits per ConfigRole
+    pub bump: u8,                             // 1
+    pub limits: ConfigLimits,                 // ConfigLimits::SIZE
+    pub paused: bool,                         // 1
+    pub poll_creation_fee: u64,               // 8, lamports
+    pub latest_poll: Pubkey,                  // 32, head of the poll list, Pubkey::default() while empty
+    pub polls_listed: u64,                    // 8
+    pub min_poll_duration: i64,               // 8, seconds new polls must accept votes for (0 = any)
+    pub retention_period: i64,                // 8, seconds sealed polls are kept before gc_expired_poll (0 = forever)
+}
+
+impl ProgramConfig {
+    pub const SPACE: usize = 8 + 32 + CONFIG_ROLE_COUNT + 1 + ConfigLimits::SIZE + 1 + 8 + 32 + 8 + 8 + 8 + 8; // discriminator + fields + padding
+
+    /// Anyone may create polls; poll admins run their own polls; co-admins
+    /// may act where their `PollRole` allows it.
+    pub const DEFAULT_PERMISSIONS: [u8; CONFIG_ROLE_COUNT] = [
+        // Anyone
+        ACTION_CREATE,
+        // CoAdmin
+        ACTION_CLOSE | ACTION_FINALIZE | ACTION_MANAGE_WHITELIST | ACTION_MANAGE_CANDIDATES,
+        // PollAdmin
+        ACTION_ALL & !ACTION_CREATE,
+        // ConfigAuthority
+        ACTION_CREATE,
+    ];
+
+    pub fn assert_not_paused(&self) -> Result<()> {
+        require!(!self.paused, VotingError::ProgramPaused);
+        Ok(())
+    }
+
+    /// Pushes a newly created poll onto the on-chain poll list: the poll
+    /// points at the previous head through `prev_poll`, so clients and other
+    /// programs can walk every poll from `latest_poll` without
+    /// `getProgramAccounts`. Polls are never deleted, so the list only grows.
+    pub fn list_poll(&mut self, poll: &mut Account<Poll>) -> Result<()> {
+        poll.prev_poll = self.latest_poll;
+        self.latest_poll = poll.key();
+        self.polls_listed = self.polls_listed.safe_add(1)?;
+        Ok(())
+    }
+
+    /// Moves `poll_creation_fee` from `payer` into the treasury. Free, and
+    /// the treasury optional, while the fee is 0.
+    pub fn charge_creation_fee<'info>(
+        &self,
+        treasury: Option<&mut Account<'info, Treasury>>,
+        payer: AccountInfo<'info>,
+        system_program: AccountInfo<'info>,
+    ) -> Result<()> {
+        if self.poll_creation_fee == 0 {
+            return Ok(());
+        }
+        let treasury = treasury.ok_or(VotingError::TreasuryRequired)?;
+        system_program::transfer(
+            CpiContext::new(
+                system_program,
+                system_program::Transfer {
+                    from: payer,
+                    to: treasury.to_account_info(),
+                },
+            ),
+            self.poll_creation_fee,
+        )?;
+        treasury.total_collected = treasury.total_collected.safe_add(self.poll_creation_fee)?;
+        Ok(())
+    }
+
+    /// Whether the matrix lets `role` perform `action`.
+    pub fn allows(&self, role: ConfigRole, action: u8) -> bool {
+        self.permissions[role as usize] & action == action
+    }
+
+    /// Fails unless some role `signer` holds is permitted `action`. Poll-level
+    /// roles are only considered when `poll` is given; a co-admin `role`
+    /// only counts if its `PERMISSION_*` bits cover the action.
+    pub fn assert_permission(
+        &self,
+        signer: &Pubkey,
+        poll: Option<&Poll>,
+        role: Option<&PollRole>,
+        action: u8,
+    ) -> Result<()> {
+        let is_admin = poll.is_some_and(|poll| &poll.admin == signer);
+        let is_co_admin = role.is_some_and(|role| {
+            &role.grantee == signer
+                && role.permissions & ConfigRole::poll_permission(action) != 0
+        });
+        let held = [
+            (ConfigRole::Anyone, true),
+            (ConfigRole::CoAdmin, is_co_admin),
+            (ConfigRole::PollAdmin, is_admin),
+            (ConfigRole::ConfigAuthority, &self.authority == signer),
+        ];
+        if held.iter().any(|&(r, holds)| holds && self.allows(r, action)) {
+            return Ok(());
+        }
+        if poll.is_some() && !is_admin && !is_co_admin {
+            return err!(VotingError::Unauthorized);
+        }
+        err!(VotingError::ActionNotPermitted)
+    }
+}
+
+/// What `get_results` returns. Per-candidate vectors follow the poll's
+/// candidate order.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PollResults {
+    pub poll: Pubkey,
+    pub status: PollStatus,
+    pub tallies: Vec<u64>,
+    pub weights: Vec<u64>,
+    /// Disqualified candidates keep their tallies but can't win.
+    pub disqualified: Vec<bool>,
+    /// Net of disqualified candidates once finalized under the `Exclude`
+    /// policy.
+    pub total_votes: u64,
+    pub total_weight: u64,
+    pub quorum_met: bool,
+    /// `None` until results are sealed, and on a tie or missed quorum.
+    pub winner: Option<u8>,
+    /// Ballots per candidate from each voter tier, on polls with tiers.
+    pub tier_tallies: Vec<Vec<u64>>,
+}
+
+/// What `get_program_capabilities` returns.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ProgramCapabilities {
+    pub version: String,
+    /// `FEATURE_*` bits this build supports.
+    pub features: u64,
+    /// Limits new polls are currently validated against.
+    pub limits: ConfigLimits,
+    /// The most any config may allow.
+    pub hard_limits: ConfigLimits,
+    pub permissions: [u8; CONFIG_ROLE_COUNT],
+    pub paused: bool,
+    /// Lamports charged into the treasury per poll creation.
+    pub poll_creation_fee: u64,
+    /// Seconds new polls must accept votes for.
+    pub min_poll_duration: i64,
+    /// Seconds sealed polls are kept before `gc_expired_poll`, 0 for never.
+    pub retention_period: i64,
+}
+
+/// Tunable bounds on what `create_poll` accepts.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct ConfigLimits {
+    pub max_candidates: u8,
+    pub max_title_len: u16,
+    pub max_candidate_name_len: u16,
+}
+
+impl ConfigLimits {
+    pub const SIZE: usize = 1 + 2 + 2;
+
+    /// The most any config may allow.
+    pub const HARD_MAX: Self = Self {
+        max_candidates: HARD_MAX_CANDIDATES,
+        max_title_len: HARD_MAX_TITLE_LEN,
+        max_candidate_name_len: HARD_MAX_CANDIDATE_NAME_LEN,
+    };
+
+    /// Checks each limit against its hard cap, logging the first one out of
+    /// range, and that the largest poll they allow can still be allocated.
+    pub fn validate(&self) -> Result<()> {
+        let out_of_range = if !(2..=HARD_MAX_CANDIDATES).contains(&self.max_candidates) {
+            Some(("max_candidates", 2, HARD_MAX_CANDIDATES as u16))
+        } else if !(1..=HARD_MAX_TITLE_LEN).contains(&self.max_title_len) {
+            Some(("max_title_len", 1, HARD_MAX_TITLE_LEN))
+        } else if !(1..=HARD_MAX_CANDIDATE_NAME_LEN).contains(&self.max_candidate_name_len) {
+            Some(("max_candidate_name_len", 1, HARD_MAX_CANDIDATE_NAME_LEN))
+        } else {
+            None
+        };
+        if let Some((parameter, min, hard_max)) = out_of_range {
+            msg!("Config limit {} must be between {} and {}", parameter, min, hard_max);
+            return err!(VotingError::InvalidConfig);
+        }
+
+        let space = Poll::space(self.max_candidates as usize + MAX_WRITE_INS, self)
+            + PollSettings::max_tier_space(self.max_candidates as usize);
+        if space > MAX_ACCOUNT_ALLOCATION {
+            msg!(
+                "max_candidates x max_candidate_name_len needs {} byte polls, over the {} byte limit",
+                space,
+                MAX_ACCOUNT_ALLOCATION
+            );
+            return err!(VotingError::PollAccountTooLarge);
+        }
+        Ok(())
+    }
+}
+
+impl Default for ConfigLimits {
+    /// The limits the program originally hard-coded.
+    fn default() -> Self {
+        Self {
+            max_candidates: 10,
+            max_title_len: 100,
+            max_candidate_name_len: 50,
+        }
+    }
+}
+
+/// Rows of the `ProgramConfig` permission matrix.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConfigRole {
+    /// Any signer.
+    Anyone,
+    /// Holder of a `PollRole` on the poll being acted on.
+    CoAdmin,
+    /// The admin of the poll being acted on.
+    PollAdmin,
+    /// `ProgramConfig::authority`.
+    ConfigAuthority,
+}
+
+impl ConfigRole {
+    /// The `PollRole` bits that make a co-admin eligible for `action`.
+    pub fn poll_permission(action: u8) -> u8 {
+        match action {
+            ACTION_CLOSE => PERMISSION_CLOSE,
+            ACTION_FINALIZE => PERMISSION_FINALIZE,
+            ACTION_MANAGE_WHITELIST => PERMISSION_MANAGE_WHITELIST,
+            ACTION_MANAGE_CANDIDATES => PERMISSION_MANAGE_CANDIDATES,
+            _ => 0,
+        }
+    }
+}
+
+/// Global source of poll IDs, so callers can't collide on or front-run an ID.
+#[account]
+pub struct PollCounter {
+    pub next_poll_id: u64, // 8
+    pub bump: u8,          // 1
+}
+
+impl PollCounter {
+    pub const SPACE: usize = 8 + 8 + 1; // discriminator + fields
+}
+
+/// Per-creator poll count. Together with `AdminPollEntry` this lets clients
+/// list an admin's polls by index instead of scanning `getProgramAccounts`.
+#[account]
+pub struct AdminProfile {
+    pub admin: Pubkey,      // 32
+    pub polls_created: u64, // 8
+    pub bump: u8,           // 1
+}
+
+impl AdminProfile {
+    pub const SPACE: usize = 8 + 32 + 8 + 1; // discriminator + fields
+
+    /// Claims the next poll index for `admin`, initializing a fresh profile.
+    pub fn register(&mut self, admin: Pubkey, bump: u8) -> Result<u64> {
+        self.admin = admin;
+        self.bump = bump;
+        let index = self.polls_created;
+        self.polls_created = index.safe_add(1)?;
+        Ok(index)
+    }
+}
+
+/// The `index`-th poll created by `admin`, at `[b"admin_poll", admin, index]`.
+#[account]
+pub struct AdminPollEntry {
+    pub admin: Pubkey, // 32
+    pub index: u64,    // 8
+    pub poll: Pubkey,  // 32
+    pub poll_id: u64,  // 8
+    pub bump: u8,      // 1
+}
+
+impl AdminPollEntry {
+    pub const SPACE: usize = 8 + 32 + 8 + 32 + 8 + 1; // discriminator + fields
+
+    pub fn new(poll: &Account<Poll>, index: u64, bump: u8) -> Self {
+        Self {
+            admin: poll.admin,
+            index,
+            poll: poll.key(),
+            poll_id: poll.poll_id,
+            bump,
+        }
+    }
+}
+
+#[account]
+pub struct Poll {
+    pub admin: Pubkey,        // 32
+    pub poll_id: u64,         // 8
+    pub title: String,        // 4 + len
+    pub candidates: Vec<Candidate>, // 4 + (len * Candidate::size(max name))
+    pub total_votes: u64,     // 8
+    pub status: PollStatus,   // 1
+    pub bump: u8,             // 1
+    pub parallel_tallies: bool,   // 1
+    pub tallies_aggregated: bool, // 1
+    pub clock_source: ClockSource, // 1
+    pub start_ts: i64,            // 8
+    pub end_ts: i64,              // 8
+    pub total_weight: u64,        // 8
+    pub namespace: Pubkey,        // 32, Pubkey::default() for global polls, settings hash for v2
+    pub pending_admin: Pubkey,    // 32, Pubkey::default() when no transfer is pending
+    pub approvers: Vec<Pubkey>,   // 4 + (MAX_APPROVERS * 32)
+    pub approval_threshold: u8,   // 1, 0 = no multisig
+    pub close_approvals: u8,      // 1
+    pub finalize_approvals: u8,   // 1
+    pub allow_vote_changes: bool, // 1
+    pub voter_roll: bool,         // 1
+    pub content_mode: ContentMode, // 1
+    pub description: String,      // 4 + MAX_DESCRIPTION_LEN
+    pub metadata_uri: String,     // 4 + MAX_CONTENT_URI_LEN
+    pub cancel_reason: String,    // 4 + MAX_CANCEL_REASON_LEN
+    pub event_verbosity: EventVerbosity, // 1
+    pub reopen_window: i64,       // 8, seconds after a close during which it can be undone
+    pub closed_at: i64,           // 8, unix time of the last close_poll, 0 otherwise
+    pub original_end_ts: i64,     // 8, end_ts at creation
+    pub max_extension: i64,       // 8, furthest extend_deadline may move end_ts past original_end_ts
+    pub publish_attendance: bool, // 1
+    pub finalize_tip: u64,        // 8, lamports paid to the auto_finalize cranker
+    pub reporting_threshold_bps: u16, // 2
+    pub quorum: u64,              // 8, minimum total_votes for a binding result
+    pub quorum_bps: u16,          // 2, minimum turnout of the voter roll
+    pub eligible_voter_count: u64, // 8, voter roll entries added
+    pub tie_break_randomness: Pubkey, // 32, Pubkey::default() until commit_tie_break
+    pub tie_break_seed_slot: u64, // 8
+    pub tie_winner: Option<u8>,   // 2, set by finalize_poll_with_randomness
+    pub parent_poll: Pubkey,      // 32, previous round, Pubkey::default() for first rounds
+    pub round: u8,                // 1, 1 for first rounds
+    pub roll_source: Pubkey,      // 32, poll whose roll entries admit voters, Pubkey::default() for this one
+    pub embargo_slot: u64,        // 8, result events are withheld before this slot (0 = none); tallies stay readable
+    pub results_published: bool,  // 1
+    pub allow_write_ins: bool,    // 1
+    pub write_in_count: u8,       // 1
+    pub disqualification_policy: DisqualificationPolicy, // 1
+    pub allow_memos: bool,        // 1
+    pub gatekeeper_network: Pubkey, // 32, Civic gatekeeper network voters need a pass from, Pubkey::default() for none
+    pub badge_mint: Pubkey,       // 32, non-transferable Token-2022 mint voters must hold, Pubkey::default() for none
+    pub token_gate_mint: Pubkey,  // 32, mint voters need min_token_balance of, Pubkey::default() for none
+    pub min_token_balance: u64,   // 8, in base units of token_gate_mint
+    pub vote_deposit: u64,        // 8, lamports each new ballot leaves in the poll account
+    pub stake_weighted: bool,     // 1, ballots weigh the voter's active native stake
+    pub winner_index: Option<u8>, // 2, winner() as of finalization
+    pub winning_weight: u64,      // 8, leading eligible candidate's weight at finalization
+    pub runner_up_weight: u64,    // 8, next eligible candidate's weight (equal to winning_weight on a tie)
+    pub is_tie: bool,             // 1, more than one eligible candidate shared the lead
+    pub version: u8,              // 1, POLL_VERSION when written, 0 for polls from before versioning
+    pub vote_tree: Pubkey,        // 32, concurrent merkle tree of compressed ballots, Pubkey::default() for none
+    pub vote_filter: Pubkey,      // 32, VoteFilter of compressed ballots' nullifiers
+    pub encrypted_tally: Pubkey,  // 32, EncryptedTally of private ballots, Pubkey::default() for none
+    pub ballot_key: Pubkey,       // 32, Ristretto point ballots are encrypted to, Pubkey::default() for none
+    pub ballot_secret: [u8; 32],  // 32, its revealed secret scalar, zeroes until reveal_ballot_key
+    pub reveal_window: i64,       // 8, time after end_ts, in clock_source units, to reveal the key
+    pub reveal_bond: u64,         // 8, lamports held in the poll until the key is revealed or slashed
+    pub sealed_ballots: u64,      // 8, encrypted ballots cast but not yet tallied
+    pub direct_votes_only: bool,  // 1, reject ballots cast through CPI
+    pub rate_limit_votes: u32,    // 4, ballots allowed per rate_limit_slots, 0 for no limit
+    pub rate_limit_slots: u64,    // 8
+    pub rate_window_start: u64,   // 8, first slot of the current rate window
+    pub rate_window_votes: u32,   // 4, ballots in the current rate window
+    pub rate_previous_votes: u32, // 4, ballots in the window before it
+    pub max_votes: Option<u64>,   // 9, ballots after which the poll closes, None for no cap
+    pub category: u16,            // 2, a PollCategory
+    pub tags: Vec<String>,        // 4 + MAX_POLL_TAGS * (4 + MAX_TAG_LEN)
+    pub prev_poll: Pubkey,        // 32, poll created before this one, Pubkey::default() at the end of the list
+    pub certifier: Pubkey,        // 32, may certify_result besides the admin, Pubkey::default() for none
+    pub certified_by: Pubkey,     // 32, Pubkey::default() until certify_result
+    pub certified_at: i64,        // 8
+    pub result_hash: [u8; 32],    // 32, the canonical_result_hash that was certified
+    pub challenge_window: i64,    // 8, time after sealing, in clock_source units, to challenge the result (0 = none)
+    pub challenge_deposit: u64,   // 8, lamports each challenge puts up
+    pub sealed_at: i64,           // 8, in clock_source units, 0 until sealed
+    pub open_challenges: u32,     // 4
+    pub result_disputed: bool,    // 1, a challenge was upheld
+    pub audit_log: bool,          // 1, admin actions are appended to AuditLog pages
+    pub audit_entries: u64,       // 8, entries logged so far
+    pub frozen: bool,             // 1, voting suspended by freeze_poll
+    pub earliest_close: i64,      // 8, in clock_source units, before which close_poll needs no votes or a multisig (0 = none)
+    pub conviction_mint: Pubkey,  // 32, tokens locked for conviction-weighted ballots, Pubkey::default() if off
+    pub conviction_period: i64,   // 8, seconds of the 1x lock tier
+    pub conviction_locks: u32,    // 4, open ConvictionLocks
+    pub quadratic_funding: bool,  // 1, a funding round with a FundingRound escrow
+    pub prediction_market: bool,  // 1, ballots are stakes settled by resolve_outcome
+    pub prediction_mint: Pubkey,  // 32, staked asset, Pubkey::default() for lamports
+    pub oracle: Pubkey,           // 32, may resolve_outcome besides the admin, Pubkey::default() for none
+    pub petition_threshold: u64,  // 8, signatures that seal a petition, 0 for ordinary polls
+    pub withdrawn_candidates: u64, // 8, bit per candidate index that withdrew (and is disqualified)
+    pub operations_key: Pubkey,   // 32, may close and finalize, Pubkey::default() for none
+    pub record_salt: [u8; 32],    // 32, vote records store sha256(voter || record_salt), zeroes for raw keys
+    pub nft_collection: Pubkey,   // 32, collection whose NFTs weigh ballots, Pubkey::default() for none
+    pub min_reputation: u64,      // 8, Reputation score needed to vote, 0 for none
+    pub receipt_tree: Pubkey,     // 32, Bubblegum tree of "I voted" receipts, Pubkey::default() for none
+    pub metadata_hash: [u8; 32],  // 32, SHA-256 of the document at metadata_uri, zeroes if not committed
+    pub organization: Pubkey,     // 32, Organization owning the poll, Pubkey::default() for none
+    pub max_title_bytes: u16,     // 2, title budget in UTF-8 bytes, 0 on polls from before per-poll limits
+    pub max_candidate_name_bytes: u16, // 2, candidate name budget in UTF-8 bytes, 0 likewise
+    pub hide_results_until_close: bool, // 1, candidate tallies withheld until `reveal_tallies`
+    pub snapshot_slot: u64,       // 8, slot of creation, which attested balances are as of
+    pub snapshot_oracle: Pubkey,  // 32, attests snapshot balances, Pubkey::default() for none
+    pub decay_bps: u16,           // 2, weight lost linearly from decay_start to end_ts, 0 for none
+    pub decay_start: i64,         // 8, when voting opened, in clock_source units
+    pub tier_weights: Vec<u16>,   // 4 + (tiers * 2), ballot weight multiplier per voter tier
+    pub tier_votes: Vec<u64>,     // 4 + (tiers * candidate_count * 8), ballots per candidate per tier
+    pub tally_shards: u8,         // 1, counters per candidate on parallel-tally polls, 0 for one
+    pub canonical_result_hash: [u8; 32], // 32, compute_result_hash of the sealed result, zero until sealed
+}
+
+impl Poll {
+    /// Space a poll created with `candidates` listed candidates and
+    /// `settings` is allocated, so callers can budget its rent.
+    pub fn space_for(candidates: usize, settings: &PollSettings, limits: &ConfigLimits) -> usize {
+        Self::space(settings.candidate_slots(candidates), &settings.string_limits(limits))
+            + settings.tier_space(candidates)
+    }
+
+    /// Space for a poll with `candidate_count` candidates, leaving room for
+    /// the longest title and names `limits` allow.
+    pub const fn space(candidate_count: usize, limits: &ConfigLimits) -> usize {
+        8 +                            // discriminator
+        32 +                           // admin pubkey
+        8 +                            // poll_id
+        4 + limits.max_title_len as usize + // title
+        4 + (candidate_count * Candidate::size(limits.max_candidate_name_len)) + // candidates vec
+        8 +                            // total_votes
+        1 +                            // status
+        1 +                            // bump
+        1 +                            // parallel_tallies
+        1 +                            // tallies_aggregated
+        1 +                            // clock_source
+        8 +                            // start_ts
+        8 +                            // end_ts
+        8 +                            // total_weight
+        32 +                           // namespace
+        32 +                           // pending_admin
+        4 + (MAX_APPROVERS * 32) +     // approvers
+        1 +                            // approval_threshold
+        1 +                            // close_approvals
+        1 +                            // finalize_approvals
+        1 +                            // allow_vote_changes
+        1 +                            // voter_roll
+        1 +                            // content_mode
+        4 + MAX_DESCRIPTION_LEN +      // description
+        4 + MAX_CONTENT_URI_LEN +      // metadata_uri
+        4 + MAX_CANCEL_REASON_LEN +    // cancel_reason
+        1 +                            // event_verbosity
+        8 +                            // reopen_window
+        8 +                            // closed_at
+        8 +                            // original_end_ts
+        8 +                            // max_extension
+        1 +                            // publish_attendance
+        8 +                            // finalize_tip
+        2 +                            // reporting_threshold_bps
+        8 +                            // quorum
+        2 +                            // quorum_bps
+        8 +                            // eligible_voter_count
+        32 +                           // tie_break_randomness
+        8 +                            // tie_break_seed_slot
+        2 +                            // tie_winner
+        32 +                           // parent_poll
+        1 +                            // round
+        32 +                           // roll_source
+        8 +                            // embargo_slot
+        1 +                            // results_published
+        1 +                            // allow_write_ins
+        1 +                            // write_in_count
+        1 +                            // disqualification_policy
+        1 +                            // allow_memos
+        32 +                           // gatekeeper_network
+        32 +                           // badge_mint
+        32 +                           // token_gate_mint
+        8 +                            // min_token_balance
+        8 +                            // vote_deposit
+        1 +                            // stake_weighted
+        2 +                            // winner_index
+        8 +                            // winning_weight
+        8 +                            // runner_up_weight
+        1 +                            // is_tie
+        1 +                            // version
+        32 +                           // vote_tree
+        32 +                           // vote_filter
+        32 +                           // encrypted_tally
+        32 +                           // ballot_key
+        32 +                           // ballot_secret
+        8 +                            // reveal_window
+        8 +                            // reveal_bond
+        8 +                            // sealed_ballots
+        1 +                            // direct_votes_only
+        4 +                            // rate_limit_votes
+        8 +                            // rate_limit_slots
+        8 +                            // rate_window_start
+        4 +                            // rate_window_votes
+        4 +                            // rate_previous_votes
+        9 +                            // max_votes
+        2 +                            // category
+        4 + MAX_POLL_TAGS * (4 + MAX_TAG_LEN) + // tags
+        32 +                           // prev_poll
+        32 +                           // certifier
+        32 +                           // certified_by
+        8 +                            // certified_at
+        32 +                           // result_hash
+        8 +                            // challenge_window
+        8 +                            // challenge_deposit
+        8 +                            // sealed_at
+        4 +                            // open_challenges
+        1 +                            // result_disputed
+        1 +                            // audit_log
+        8 +                            // audit_entries
+        1 +                            // frozen
+        8 +                            // earliest_close
+        32 +                           // conviction_mint
+        8 +                            // conviction_period
+        4 +                            // conviction_locks
+        1 +                            // quadratic_funding
+        1 +                            // prediction_market
+        32 +                           // prediction_mint
+        32 +                           // oracle
+        8 +                            // petition_threshold
+        8 +                            // withdrawn_candidates
+        32 +                           // operations_key
+        32 +                           // record_salt
+        32 +                           // nft_collection
+        8 +                            // min_reputation
+        32 +                           // receipt_tree
+        32 +                           // metadata_hash
+        32 +                           // organization
+        2 +                            // max_title_bytes
+        2 +                            // max_candidate_name_bytes
+        1 +                            // hide_results_until_close
+        8 +                            // snapshot_slot
+        32 +                           // snapshot_oracle
+        2 +                            // decay_bps
+        8 +                            // decay_start
+        4 + 4 +                        // tier_weights, tier_votes (entries in PollSettings::tier_space)
+        1 +                            // tally_shards
+        32 +                           // canonical_result_hash
+        64                             // padding for safety
+    }
+
+    /// Identity of a v2 poll: `sha256("poll_v2" || admin || borsh(title) ||
+    /// borsh(candidates) || borsh(settings))`. Binding the admin stops others
+    /// from claiming an address someone else intends to create.
+    pub fn settings_hash(
+        admin: &Pubkey,
+        title: &String,
+        candidates: &Vec<String>,
+        settings: &PollSettings,
+    ) -> [u8; 32] {
+        let mut params = Vec::new();
+        (title, candidates, settings)
+            .serialize(&mut params)
+            .expect("serializing into a Vec cannot fail");
+        hashv(&[b"poll_v2", admin.as_ref(), &params]).to_bytes()
+    }
+
+    /// Namespace seed for this poll's PDAs. Global polls use an empty seed,
+    /// which derives exactly the same addresses as the original
+    /// `[b"poll", poll_id]` / `[b"vote", poll_id, voter]` scheme, so polls and
+    /// vote records created before namespacing keep working unchanged.
+    pub fn namespace_seed(&self) -> &[u8] {
+        Self::seed_for(&self.namespace)
+    }
+
+    /// The PDA seed for a poll stored under `namespace`.
+    pub fn seed_for(namespace: &Pubkey) -> &[u8] {
+        if *namespace == Pubkey::default() {
+            &[]
+        } else {
+            namespace.as_ref()
+        }
+    }
+
+    /// Decodes a poll account written by any program version. Fields an
+    /// older layout lacks read as zero, as they would from the account's
+    /// unused tail.
+    pub fn decode_any_version(data: &[u8]) -> Result<Self> {
+        let mut padded = data.to_vec();
+        padded.resize(data.len() + Self::LAYOUT_SLACK, 0);
+        Self::try_deserialize(&mut padded.as_slice())
+    }
+
+    /// Zero bytes `decode_any_version` appends: the current layout's whole
+    /// size without a title or candidates, which no older layout can fall
+    /// short of it by, so it keeps up as fields are added.
+    const LAYOUT_SLACK: usize = Self::space(
+        0,
+        &ConfigLimits {
+            max_candidates: 0,
+            max_title_len: 0,
+            max_candidate_name_len: 0,
+        },
+    );
+
+    /// Fills in defaults for fields added after `self.version` whose zero
+    /// value would be wrong, and stamps `POLL_VERSION`.
+    fn upgrade(&mut self) {
+        if self.version < 1 {
+            // Layouts from before runoffs and deadline extensions
+            if self.round == 0 {
+                self.round = 1;
+            }
+            if self.original_end_ts == 0 {
+                self.original_end_ts = self.end_ts;
+            }
+        }
+        if self.version < 2 && self.is_sealed() {
+            // Layouts from before canonical result hashes
+            self.canonical_result_hash =
+                compute_result_hash(&self.namespace, self.poll_id, self.status, &self.candidates);
+        }
+        self.version = POLL_VERSION;
+    }
+
+    /// Validates creation parameters against `limits` and initializes
+    /// everything except the poll's identity (admin, namespace, ID and bump).
+    pub fn configure(
+        &mut self,
+        title: String,
+        candidates: Vec<String>,
+        settings: &PollSettings,
+        limits: &ConfigLimits,
+    ) -> Result<()> {
+        if settings.petition_threshold > 0 {
+            require!(
+                candidates.len() == 1
+                    && !settings.parallel_tallies
+                    && !settings.stake_weighted
+                    && !settings.quadratic_funding
+                    && !settings.prediction_market
+                    && settings.conviction_mint == Pubkey::default()
+                    && settings.ballot_key == Pubkey::default(),
+                VotingError::InvalidPetition
+            );
+        } else {
+            require!(candidates.len() >= 2, VotingError::TooFewCandidates);
+        }
+        require!(
+            candidates.len() <= limits.max_candidates as usize,
+            VotingError::TooManyCandidates
+        );
+        require!(
+            settings.max_title_bytes <= limits.max_title_len
+                && settings.max_candidate_name_bytes <= limits.max_candidate_name_len,
+            VotingError::InvalidStringLimits
+        );
+        // Lengths are UTF-8 bytes, which is what the account stores
+        let budget = settings.string_limits(limits);
+        if title.len() > budget.max_title_len as usize {
+            msg!("Title is {} bytes; this poll allows {}", title.len(), budget.max_title_len);
+            return err!(VotingError::TitleTooLong);
+        }
+        if let Some(name) = candidates
+            .iter()
+            .find(|name| name.len() > budget.max_candidate_name_len as usize)
+        {
+            msg!(
+                "Candidate '{}' is {} bytes; this poll allows {}",
+                name,
+                name.len(),
+                budget.max_candidate_name_len
+            );
+            return err!(VotingError::CandidateNameTooLong);
+        }
+        require!(
+            settings.approvers.len() <= MAX_APPROVERS
+                && (settings.approval_threshold as usize) <= settings.approvers.len()
+                && (settings.approval_threshold == 0) == settings.approvers.is_empty()
+                && settings
+                    .approvers
+                    .iter()
+                    .enumerate()
+                    .all(|(i, approver)| !settings.approvers[..i].contains(approver)),
+            VotingError::InvalidMultisig
+        );
+        require!(
+            !(settings.allow_write_ins && settings.parallel_tallies),
+            VotingError::WriteInsNeedDirectTallies
+        );
+        require!(
+            !(settings.vote_deposit > 0 && settings.parallel_tallies),
+            VotingError::DepositsNeedDirectTallies
+        );
+        require!(
+            PollCategory::from_u16(settings.category).is_some(),
+            VotingError::InvalidCategory
+        );
+        require!(
+            settings.tags.len() <= MAX_POLL_TAGS
+                && settings.tags.iter().enumerate().all(|(i, tag)| {
+                    PollCategory::valid_tag(tag) && !settings.tags[..i].contains(tag)
+                }),
+            VotingError::InvalidTags
+        );
+        require!(
+            !(settings.quadratic_funding
+                && (settings.allow_write_ins || settings.quorum > 0 || settings.quorum_bps > 0)),
+            VotingError::InvalidQuadraticFundingSettings
+        );
+        require!(
+            if settings.prediction_market {
+                !settings.stake_weighted
+                    && !settings.parallel_tallies
+                    && !settings.quadratic_funding
+                    && settings.ballot_key == Pubkey::default()
+                    && settings.conviction_mint == Pubkey::default()
+            } else {
+                settings.prediction_mint == Pubkey::default() && settings.oracle == Pubkey::default()
+            },
+            VotingError::InvalidPredictionSettings
+        );
+        require!(
+            if settings.conviction_mint == Pubkey::default() {
+                settings.conviction_period == 0
+            } else {
+                settings.conviction_period > 0
+                    && settings.conviction_period <= i64::MAX >> CONVICTION_TIERS
+                    && !settings.stake_weighted
+            },
+            VotingError::InvalidConvictionSettings
+        );
+        require!(
+            settings.challenge_window >= 0
+                && (settings.challenge_deposit == 0 || settings.challenge_window > 0),
+            VotingError::InvalidChallengeSettings
+        );
+        require!(
+            settings.max_votes.is_none_or(|max| {
+                max > 0 && !settings.parallel_tallies && settings.ballot_key == Pubkey::default()
+            }),
+            VotingError::InvalidMaxVotes
+        );
+        require!(
+            (settings.rate_limit_votes == 0) == (settings.rate_limit_slots == 0)
+                && !(settings.rate_limit_votes > 0 && settings.parallel_tallies),
+            VotingError::InvalidRateLimit
+        );
+        require!(
+            settings.nft_collection == Pubkey::default()
+                || !(settings.stake_weighted
+                    || settings.parallel_tallies
+                    || settings.prediction_market
+                    || settings.petition_threshold > 0
+                    || settings.conviction_mint != Pubkey::default()
+                    || settings.ballot_key != Pubkey::default()),
+            VotingError::InvalidNftSettings
+        );
+        require!(
+            settings.record_salt == [0; 32] || !settings.publish_attendance,
+            VotingError::VoteRecordsPseudonymous
+        );
+        require!(
+            !settings.hide_results_until_close
+                || !(settings.parallel_tallies
+                    || settings.prediction_market
+                    || settings.quadratic_funding
+                    || settings.petition_threshold > 0
+                    || settings.ballot_key != Pubkey::default()
+                    || settings.record_salt != [0; 32]),
+            VotingError::InvalidHiddenTallySettings
+        );
+        require!(
+            settings.snapshot_oracle == Pubkey::default()
+                || settings.token_gate_mint != Pubkey::default(),
+            VotingError::InvalidSnapshotSettings
+        );
+        require!(
+            settings.decay_bps == 0
+                || (settings.decay_bps <= BPS_DENOMINATOR
+                    && settings.end_ts != 0
+                    && !settings.prediction_market
+                    && !settings.quadratic_funding
+                    && settings.petition_threshold == 0
+                    && settings.ballot_key == Pubkey::default()),
+            VotingError::InvalidDecaySettings
+        );
+        require!(
+            settings.tier_weights.is_empty()
+                || (settings.tier_weights.len() <= MAX_VOTER_TIERS
+                    && settings.tier_weights.iter().all(|weight| *weight > 0)
+                    && settings.voter_roll
+                    && !settings.allow_write_ins
+                    && !settings.parallel_tallies
+                    && !settings.hide_results_until_close
+                    && !settings.prediction_market
+                    && !settings.quadratic_funding
+                    && settings.petition_threshold == 0
+                    && settings.ballot_key == Pubkey::default()),
+            VotingError::InvalidVoterTiers
+        );
+        require!(
+            settings.tally_shards <= 1
+                || (settings.parallel_tallies
+                    && settings.tally_shards <= MAX_TALLY_SHARDS
+                    && candidates.len() * settings.tally_shards as usize
+                        <= HARD_MAX_CANDIDATES as usize),
+            VotingError::InvalidTallyShards
+        );
+        require!(
+            settings.reporting_threshold_bps <= BPS_DENOMINATOR,
+            VotingError::InvalidReportingThreshold
+        );
+        require!(
+            settings.quorum_bps <= BPS_DENOMINATOR
+                && (settings.quorum_bps == 0 || settings.voter_roll),
+            VotingError::InvalidQuorum
+        );
+        require!(
+            if settings.ballot_key == Pubkey::default() {
+                settings.reveal_window == 0 && settings.reveal_bond == 0
+            } else {
+                validate_ristretto(&PodRistrettoPoint(settings.ballot_key.to_bytes()))
+                    && settings.end_ts != 0
+                    && settings.reveal_window > 0
+                    && !settings.parallel_tallies
+                    && !settings.stake_weighted
+                    && !settings.allow_write_ins
+                    && settings.vote_deposit == 0
+                    && !settings.direct_votes_only
+                    && settings.gatekeeper_network == Pubkey::default()
+                    && settings.badge_mint == Pubkey::default()
+                    && settings.token_gate_mint == Pubkey::default()
+            },
+            VotingError::InvalidEncryptedBallotSettings
+        );
+        let mut decay_start = 0;
+        if settings.end_ts != 0 {
+            let now = settings.clock_source.now(&Clock::get()?);
+            require!(
+                settings.end_ts > settings.start_ts && settings.end_ts > now,
+                VotingError::InvalidSchedule
+            );
+            decay_start = settings.start_ts.max(now);
+        }
+
+        self.title = title;
+        self.candidates = candidates
+            .into_iter()
+            .map(|name| Candidate {
+                name,
+                votes: 0,
+                weight: 0,
+                disqualified: false,
+            })
+            .collect();
+        self.total_votes = 0;
+        self.total_weight = 0;
+        self.status = if settings.start_as_draft {
+            PollStatus::Draft
+        } else {
+            PollStatus::Active
+        };
+        self.parallel_tallies = settings.parallel_tallies;
+        self.tallies_aggregated = false;
+        self.clock_source = settings.clock_source;
+        self.start_ts = settings.start_ts;
+        self.end_ts = settings.end_ts;
+        self.pending_admin = Pubkey::default();
+        self.approvers = settings.approvers.clone();
+        self.approval_threshold = settings.approval_threshold;
+        self.close_approvals = 0;
+        self.finalize_approvals = 0;
+        self.allow_vote_changes = settings.allow_vote_changes;
+        self.voter_roll = settings.voter_roll;
+        self.content_mode = settings.content_mode;
+        self.description = String::new();
+        self.metadata_uri = String::new();
+        self.cancel_reason = String::new();
+        self.event_verbosity = settings.event_verbosity;
+        self.reopen_window = settings.reopen_window;
+        self.closed_at = 0;
+        self.original_end_ts = settings.end_ts;
+        self.max_extension = settings.max_extension;
+        self.publish_attendance = settings.publish_attendance;
+        self.finalize_tip = settings.finalize_tip;
+        self.reporting_threshold_bps = settings.reporting_threshold_bps;
+        self.quorum = settings.quorum;
+        self.quorum_bps = settings.quorum_bps;
+        self.eligible_voter_count = 0;
+        self.tie_break_randomness = Pubkey::default();
+        self.tie_break_seed_slot = 0;
+        self.tie_winner = None;
+        self.parent_poll = Pubkey::default();
+        self.round = 1;
+        self.roll_source = Pubkey::default();
+        self.embargo_slot = settings.embargo_slot;
+        self.results_published = false;
+        self.allow_write_ins = settings.allow_write_ins;
+        self.write_in_count = 0;
+        self.disqualification_policy = settings.disqualification_policy;
+        self.allow_memos = settings.allow_memos;
+        self.gatekeeper_network = settings.gatekeeper_network;
+        self.badge_mint = settings.badge_mint;
+        self.token_gate_mint = settings.token_gate_mint;
+        self.min_token_balance = settings.min_token_balance;
+        self.vote_deposit = settings.vote_deposit;
+        self.stake_weighted = settings.stake_weighted;
+        self.winner_index = None;
+        self.winning_weight = 0;
+        self.runner_up_weight = 0;
+        self.is_tie = false;
+        self.version = POLL_VERSION;
+        self.vote_tree = Pubkey::default();
+        self.vote_filter = Pubkey::default();
+        self.encrypted_tally = Pubkey::default();
+        self.ballot_key = settings.ballot_key;
+        self.ballot_secret = [0; 32];
+        self.reveal_window = settings.reveal_window;
+        self.reveal_bond = settings.reveal_bond;
+        self.sealed_ballots = 0;
+        self.direct_votes_only = settings.direct_votes_only;
+        self.rate_limit_votes = settings.rate_limit_votes;
+        self.rate_limit_slots = settings.rate_limit_slots;
+        self.rate_window_start = 0;
+        self.rate_window_votes = 0;
+        self.rate_previous_votes = 0;
+        self.max_votes = settings.max_votes;
+        self.category = settings.category;
+        self.tags = settings.tags.clone();
+        self.certifier = settings.certifier;
+        self.certified_by = Pubkey::default();
+        self.certified_at = 0;
+        self.result_hash = [0; 32];
+        self.challenge_window = settings.challenge_window;
+        self.challenge_deposit = settings.challenge_deposit;
+        self.sealed_at = 0;
+        self.open_challenges = 0;
+        self.result_disputed = false;
+        self.audit_log = settings.audit_log;
+        self.audit_entries = 0;
+        self.frozen = false;
+        self.earliest_close = 0;
+        self.conviction_mint = settings.conviction_mint;
+        self.conviction_period = settings.conviction_period;
+        self.conviction_locks = 0;
+        self.quadratic_funding = settings.quadratic_funding;
+        self.prediction_market = settings.prediction_market;
+        self.prediction_mint = settings.prediction_mint;
+        self.oracle = settings.oracle;
+        self.petition_threshold = settings.petition_threshold;
+        self.withdrawn_candidates = 0;
+        self.operations_key = Pubkey::default();
+        self.record_salt = settings.record_salt;
+        self.nft_collection = settings.nft_collection;
+        self.min_reputation = settings.min_reputation;
+        self.receipt_tree = Pubkey::default();
+        self.metadata_hash = [0; 32];
+        self.organization = Pubkey::default();
+        self.max_title_bytes = budget.max_title_len;
+        self.max_candidate_name_bytes = budget.max_candidate_name_len;
+        self.hide_results_until_close = settings.hide_results_until_close;
+        self.snapshot_slot = Clock::get()?.slot;
+        self.snapshot_oracle = settings.snapshot_oracle;
+        self.decay_bps = settings.decay_bps;
+        self.decay_start = decay_start;
+        self.tier_votes = vec![0; settings.tier_weights.len() * self.candidates.len()];
+        self.tier_weights = settings.tier_weights.clone();
+        self.tally_shards = settings.tally_shards;
+        self.canonical_result_hash = [0; 32];
+        Ok(())
+    }
+
+    /// The poll whose `RollEntry`s admit voters to this one, given this
+    /// poll's own address: itself, or the first round for runoffs.
+    pub fn roll_poll(&self, key: Pubkey) -> Pubkey {
+        if self.roll_source == Pubkey::default() {
+            key
+        } else {
+            self.roll_source
+        }
+    }
+
+    /// What a `VoteRecord` stores as `voter`: the key itself, or
+    /// `sha256(voter || record_salt)` on polls with pseudonymous records.
+    pub fn record_voter(&self, voter: &Pubkey) -> Pubkey {
+        if self.record_salt == [0; 32] {
+            *voter
+        } else {
+            Pubkey::new_from_array(hashv(&[voter.as_ref(), &self.record_salt]).to_bytes())
+        }
+    }
+
+    /// The title and name budgets, in bytes, this poll's account was sized
+    /// for; polls from before per-poll limits go by the config's `limits`.
+    pub fn string_limits(&self, limits: &ConfigLimits) -> ConfigLimits {
+        let budget = |own: u16, config: u16| if own == 0 { config } else { own };
+        ConfigLimits {
+            max_candidates: limits.max_candidates,
+            max_title_len: budget(self.max_title_bytes, limits.max_title_len),
+            max_candidate_name_len: budget(self.max_candidate_name_bytes, limits.max_candidate_name_len),
+        }
+    }
+
+    /// Whether an organization owns this poll.
+    pub fn in_organization(&self) -> bool {
+        self.organization != Pubkey::default()
+    }
+
+    /// Whether this looks like a v2 poll, addressed by its settings hash
+    /// rather than an ID. Errs on the side of yes for a poll 0 namespaced
+    /// under a previous admin.
+    pub fn is_content_addressed(&self) -> bool {
+        self.poll_id == 0
+            && self.namespace != Pubkey::default()
+            && self.namespace != self.admin
+            && !self.in_organization()
+    }
+
+    /// Where co-admin roles on this poll live, given the poll's own address:
+    /// its organization for organization polls, otherwise the poll itself.
+    pub fn role_scope(&self, poll: Pubkey) -> Pubkey {
+        if self.in_organization() {
+            self.organization
+        } else {
+            poll
+        }
+    }
+
+    /// Whether `signer` is this poll's operations key.
+    pub fn is_operations_key(&self, signer: &Pubkey) -> bool {
+        self.operations_key != Pubkey::default() && &self.operations_key == signer
+    }
+
+    /// Candidates listed at creation or since, ahead of any write-ins.
+    pub fn listed_candidates(&self) -> usize {
+        self.candidates.len() - self.write_in_count as usize
+    }
+
+    /// Settings that recreate this poll's configuration on a new schedule,
+    /// for `clone_poll`. The ballot key, record salt and embargo slot only
+    /// make sense for one election, so they are left unset.
+    pub fn template_settings(&self, start_ts: i64, end_ts: i64) -> PollSettings {
+        PollSettings {
+            parallel_tallies: self.parallel_tallies,
+            clock_source: self.clock_source,
+            start_ts,
+            end_ts,
+            approvers: self.approvers.clone(),
+            approval_threshold: self.approval_threshold,
+            allow_vote_changes: self.allow_vote_changes,
+            voter_roll: self.voter_roll,
+            content_mode: self.content_mode,
+            start_as_draft: false,
+            event_verbosity: self.event_verbosity,
+            reopen_window: self.reopen_window,
+            max_extension: self.max_extension,
+            publish_attendance: self.publish_attendance,
+            finalize_tip: self.finalize_tip,
+            reporting_threshold_bps: self.reporting_threshold_bps,
+            quorum: self.quorum,
+            quorum_bps: self.quorum_bps,
+            embargo_slot: 0,
+            allow_write_ins: self.allow_write_ins,
+            disqualification_policy: self.disqualification_policy,
+            allow_memos: self.allow_memos,
+            gatekeeper_network: self.gatekeeper_network,
+            badge_mint: self.badge_mint,
+            token_gate_mint: self.token_gate_mint,
+            min_token_balance: self.min_token_balance,
+            vote_deposit: self.vote_deposit,
+            stake_weighted: self.stake_weighted,
+            ballot_key: Pubkey::default(),
+            reveal_window: 0,
+            reveal_bond: 0,
+            direct_votes_only: self.direct_votes_only,
+            rate_limit_votes: self.rate_limit_votes,
+            rate_limit_slots: self.rate_limit_slots,
+            max_votes: self.max_votes,
+            category: self.category,
+            tags: self.tags.clone(),
+            certifier: self.certifier,
+            challenge_window: self.challenge_window,
+            challenge_deposit: self.challenge_deposit,
+            audit_log: self.audit_log,
+            conviction_mint: self.conviction_mint,
+            conviction_period: self.conviction_period,
+            quadratic_funding: self.quadratic_funding,
+            prediction_market: self.prediction_market,
+            prediction_mint: self.prediction_mint,
+            oracle: self.oracle,
+            petition_threshold: self.petition_threshold,
+            record_salt: [0; 32],
+            nft_collection: self.nft_collection,
+            min_reputation: self.min_reputation,
+            max_title_bytes: self.max_title_bytes,
+            max_candidate_name_bytes: self.max_candidate_name_bytes,
+            hide_results_until_close: self.hide_results_until_close,
+            snapshot_oracle: self.snapshot_oracle,
+            decay_bps: self.decay_bps,
+            tier_weights: self.tier_weights.clone(),
+            tally_shards: self.tally_shards,
+        }
+    }
+
+    /// Whether per-ballot logs and events at `level` are emitted for this
+    /// poll. Lifecycle events are always emitted.
+    pub fn emits(&self, level: EventVerbosity) -> bool {
+        // Per-ballot events would give hidden tallies away
+        self.event_verbosity >= level && !self.tallies_hidden()
+    }
+
+    /// Whether candidate tallies are still withheld: the poll hides results
+    /// until close and `reveal_tallies` hasn't rebuilt them yet.
+    pub fn tallies_hidden(&self) -> bool {
+        self.hide_results_until_close && !self.tallies_aggregated
+    }
+
+    /// Cohort of a ballot cast directly by the voter.
+    pub fn direct_cohort(&self) -> VoterCohort {
+        if self.voter_roll {
+            VoterCohort::Roll
+        } else {
+            VoterCohort::Open
+        }
+    }
+
+    /// Current time on the poll's clock (unix seconds or slot).
+    pub fn now(&self) -> Result<i64> {
+        Ok(self.clock_source.now(&Clock::get()?))
+    }
+
+    /// Deserializes one of this poll's `VoteRecord`s passed as a remaining
+    /// account, checking its owner and address.
+    pub fn load_vote_record(&self, info: &AccountInfo) -> Result<VoteRecord> {
+        require!(self.record_salt == [0; 32], VotingError::VoteRecordsPseudonymous);
+        require!(info.owner == &crate::ID, VotingError::InvalidVoteRecord);
+        let record = VoteRecord::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        let expected = Pubkey::create_program_address(
+            &[
+                b"vote",
+                self.namespace_seed(),
+                self.poll_id.to_le_bytes().as_ref(),
+                record.voter.as_ref(),
+                &[record.bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| VotingError::InvalidVoteRecord)?;
+        require!(
+            info.key() == expected && record.poll_id == self.poll_id,
+            VotingError::InvalidVoteRecord
+        );
+        Ok(record)
+    }
+
+    /// Closes an active poll whose deadline has passed, returning whether it
+    /// did. `closed_at` stays 0 so the close can't be undone by `reopen_poll`.
+    pub fn expire(&mut self) -> Result<bool> {
+        let expired =
+            self.status == PollStatus::Active && self.end_ts != 0 && self.now()? >= self.end_ts;
+        if expired {
+            self.status = PollStatus::Closed;
+            self.closed_at = 0;
+        }
+        Ok(expired)
+    }
+
+    /// Fails unless the poll is active and inside its voting window.
+    pub fn assert_accepting_votes(&self) -> Result<()> {
+        require!(self.status == PollStatus::Active, VotingError::PollClosed);
+        require!(!self.frozen, VotingError::PollFrozen);
+        let now = self.now()?;
+        require!(now >= self.start_ts, VotingError::VotingNotStarted);
+        require!(
+            self.end_ts == 0 || now < self.end_ts,
+            VotingError::VotingEnded
+        );
+        Ok(())
+    }
+
+    /// Checks that a ballot for `candidate_index` would be accepted right now
+    /// and returns the weight it carries. Shared by every voting path,
+    /// including practice ballots. `membership` is the ballot owner's proven
+    /// voter roll membership, required when the poll has a voter roll;
+    /// `passes` are the ballot owner's verified proofs for the poll's gates
+    /// (gatekeeper network, badge mint, token balance); only `vote` collects
+    /// them, so gated polls take no ballots elsewhere. Compressed polls only
+    /// take `vote_compressed` ballots, checked by `check_compressed_ballot`,
+    /// private-ballot polls only `vote_private` ones and polls with a
+    /// `ballot_key` only `vote_encrypted` ones.
+    pub fn check_ballot(
+        &self,
+        candidate_index: u8,
+        membership: Option<&RollMembership>,
+        passes: Option<&VoterPasses>,
+    ) -> Result<u64> {
+        require!(
+            self.vote_tree == Pubkey::default(),
+            VotingError::CompressedVotesOnly
+        );
+        require!(
+            self.encrypted_tally == Pubkey::default(),
+            VotingError::PrivateBallotsOnly
+        );
+        require!(
+            self.ballot_key == Pubkey::default(),
+            VotingError::EncryptedBallotsOnly
+        );
+        let weight = self.admit_ballot(candidate_index, membership, passes)?;
+        self.decayed_weight(weight.safe_mul(self.tier_weight(membership)?)?)
+    }
+
+    /// `check_ballot` for `vote_private` ballots, whose choice is hidden;
+    /// the proofs `vote_private` checks stand in for the candidate checks.
+    pub fn check_private_ballot(&self, membership: Option<&RollMembership>) -> Result<u64> {
+        require!(
+            self.encrypted_tally != Pubkey::default(),
+            VotingError::PrivateBallotsDisabled
+        );
+        self.admit_hidden_ballot(membership)
+    }
+
+    /// `check_ballot` for `vote_encrypted` ballots, which are counted (with
+    /// `BALLOT_WEIGHT`) only once decrypted.
+    pub fn check_encrypted_ballot(&self, membership: Option<&RollMembership>) -> Result<u64> {
+        require!(
+            self.ballot_key != Pubkey::default(),
+            VotingError::EncryptedBallotsDisabled
+        );
+        self.admit_hidden_ballot(membership)
+    }
+
+    fn admit_hidden_ballot(&self, membership: Option<&RollMembership>) -> Result<u64> {
+        require!(!self.is_full(), VotingError::PollFull);
+        self.assert_accepting_votes()?;
+        require!(
+            !self.voter_roll || membership.is_some(),
+            VotingError::NotOnVoterRoll
+        );
+        Ok(BALLOT_WEIGHT)
+    }
+
+    /// Whether the admin has published the secret of `ballot_key`. A valid
+    /// key is never the identity, so its secret is never zero.
+    pub fn ballot_key_revealed(&self) -> bool {
+        self.ballot_secret != [0; 32]
+    }
+
+    /// Last moment, on the poll's clock, at which `reveal_ballot_key` is
+    /// accepted.
+    pub fn reveal_deadline(&self) -> Result<i64> {
+        Ok(self
+            .end_ts
+            .checked_add(self.reveal_window)
+            .ok_or(VotingError::ArithmeticOverflow)?)
+    }
+
+    /// Moves the `reveal_bond` chosen at creation from `payer` into the
+    /// poll, where it stays until the ballot key is revealed or slashed.
+    pub fn hold_reveal_bond<'info>(
+        &self,
+        payer: AccountInfo<'info>,
+        poll: AccountInfo<'info>,
+        system_program: AccountInfo<'info>,
+    ) -> Result<()> {
+        if self.reveal_bond == 0 {
+            return Ok(());
+        }
+        system_program::transfer(
+            CpiContext::new(
+                system_program,
+                system_program::Transfer {
+                    from: payer,
+                    to: poll,
+                },
+            ),
+            self.reveal_bond,
+        )
+    }
+
+    /// `check_ballot` for `vote_compressed` ballots.
+    pub fn check_compressed_ballot(
+        &self,
+        candidate_index: u8,
+        membership: Option<&RollMembership>,
+    ) -> Result<u64> {
+        require!(
+            self.vote_tree != Pubkey::default(),
+            VotingError::CompressedVotesDisabled
+        );
+        let weight = self.admit_ballot(candidate_index, membership, None)?;
+        self.decayed_weight(weight)
+    }
+
+    /// Tier of the ballot owner with `membership`; 0 on polls without tiers.
+    pub fn ballot_tier(&self, membership: Option<&RollMembership>) -> u8 {
+        if self.tier_weights.is_empty() {
+            return 0;
+        }
+        membership.map_or(0, |membership| membership.tier)
+    }
+
+    /// Weight multiplier of the ballot owner's tier; 1 on polls without
+    /// tiers.
+    pub fn tier_weight(&self, membership: Option<&RollMembership>) -> Result<u64> {
+        if self.tier_weights.is_empty() {
+            return Ok(1);
+        }
+        let tier = self.ballot_tier(membership) as usize;
+        let weight = self.tier_weights.get(tier).ok_or(VotingError::InvalidVoterTier)?;
+        Ok(*weight as u64)
+    }
+
+    /// Adds a ballot for candidate `index` to `tier`'s tallies, or with
+    /// `counted` false takes one away. Polls without tiers keep none.
+    pub fn tally_tier(&mut self, tier: u8, index: u8, counted: bool) -> Result<()> {
+        let tiers = self.tier_weights.len();
+        if tiers == 0 {
+            return Ok(());
+        }
+        let votes = self
+            .tier_votes
+            .get_mut(index as usize * tiers + tier as usize)
+            .ok_or(VotingError::InvalidVoterTier)?;
+        *votes = if counted { votes.safe_add(1)? } else { votes.safe_sub(1)? };
+        Ok(())
+    }
+
+    /// Counters each candidate's tally is split across on a parallel-tally
+    /// poll.
+    pub fn tally_shard_count(&self) -> usize {
+        self.tally_shards.max(1) as usize
+    }
+
+    /// The shard counting `voter`'s ballots, picked by a hash of their key
+    /// so a busy candidate's ballots spread evenly over its counters.
+    pub fn tally_shard(&self, voter: &Pubkey) -> u8 {
+        if self.tally_shards <= 1 {
+            return 0;
+        }
+        hashv(&[b"tally_shard", voter.as_ref()]).to_bytes()[0] % self.tally_shards
+    }
+
+    /// Ballots per candidate cast by voters of `tier`.
+    pub fn tier_tallies(&self, tier: u8) -> Vec<u64> {
+        let tiers = self.tier_weights.len();
+        if tier as usize >= tiers {
+            return Vec::new();
+        }
+        self.tier_votes
+            .iter()
+            .skip(tier as usize)
+            .step_by(tiers)
+            .copied()
+            .collect()
+    }
+
+    /// `weight` less the share of `decay_bps` that has elapsed of the
+    /// window from `decay_start` to `end_ts`.
+    pub fn decayed_weight(&self, weight: u64) -> Result<u64> {
+        if self.decay_bps == 0 {
+            return Ok(weight);
+        }
+        let window = (self.end_ts - self.decay_start).max(1) as u128;
+        let elapsed = (self.now()? - self.decay_start).clamp(0, window as i64) as u128;
+        let lost_bps = self.decay_bps as u128 * elapsed / window;
+        let kept = weight as u128 * (BPS_DENOMINATOR as u128 - lost_bps) / BPS_DENOMINATOR as u128;
+        Ok(kept as u64)
+    }
+
+    fn admit_ballot(
+        &self,
+        candidate_index: u8,
+        membership: Option<&RollMembership>,
+        passes: Option<&VoterPasses>,
+    ) -> Result<u64> {
+        require!(!self.is_full(), VotingError::PollFull);
+        self.assert_accepting_votes()?;
+        require!(
+            !self.voter_roll || membership.is_some(),
+            VotingError::NotOnVoterRoll
+        );
+        require!(
+            self.gatekeeper_network == Pubkey::default()
+                || passes.is_some_and(|passes| {
+                    passes.gatekeeper_network == Some(self.gatekeeper_network)
+                }),
+            VotingError::GatewayTokenRequired
+        );
+        require!(
+            self.badge_mint == Pubkey::default()
+                || passes.is_some_and(|passes| passes.badge_mint == Some(self.badge_mint)),
+            VotingError::BadgeRequired
+        );
+        require!(
+            self.token_gate_mint == Pubkey::default()
+                || passes.is_some_and(|passes| {
+                    passes.token_balance.is_some_and(|(mint, amount)| {
+                        mint == self.token_gate_mint && amount >= self.min_token_balance
+                    })
+                }),
+            VotingError::InsufficientTokenBalance
+        );
+        require!(
+            !self.direct_votes_only || passes.is_some_and(|passes| passes.direct_call),
+            VotingError::DirectVoteRequired
+        );
+        require!(
+            self.min_reputation == 0
+                || passes.is_some_and(|passes| {
+                    passes.reputation.is_some_and(|score| score >= self.min_reputation)
+                }),
+            VotingError::ReputationTooLow
+        );
+        self.check_candidate(candidate_index)?;
+        require!(
+            !self.candidates[candidate_index as usize].disqualified,
+            VotingError::CandidateDisqualified
+        );
+        if self.prediction_market {
+            return match passes.and_then(|passes| passes.prediction_stake) {
+                Some(stake) if stake > 0 => Ok(stake),
+                _ => err!(VotingError::StakeOnOutcomeRequired),
+            };
+        }
+        if self.conviction_mint != Pubkey::default() {
+            return match passes.and_then(|passes| passes.conviction_weight) {
+                Some(weight) if weight > 0 => Ok(weight),
+                _ => err!(VotingError::ConvictionLockRequired),
+            };
+        }
+        if self.nft_collection != Pubkey::default() {
+            return match passes.and_then(|passes| passes.nft_count) {
+                Some(count) if count > 0 => Ok(count),
+                _ => err!(VotingError::NftRequired),
+            };
+        }
+        if !self.stake_weighted {
+            return Ok(BALLOT_WEIGHT);
+        }
+        match passes.and_then(|passes| passes.stake_weight) {
+            Some(stake) if stake > 0 => Ok(stake),
+            _ => err!(VotingError::StakeRequired),
+        }
+    }
+
+    /// Account size needed once the title is replaced by `title`; never
+    /// less than `current_len`, so the account only ever grows.
+    pub fn space_with_title(&self, current_len: usize, title: &str) -> usize {
+        let used = self
+            .try_to_vec()
+            .map_or(current_len, |data| 8 + data.len());
+        (used + title.len()).saturating_sub(self.title.len()).max(current_len)
+    }
+
+    /// Fails once the poll may hold votes. Parallel-tally polls keep their
+    /// counts off the poll account, so for them this means before voting
+    /// opens at `start_ts`.
+    pub fn assert_no_votes_yet(&self) -> Result<()> {
+        if self.parallel_tallies {
+            require!(self.now()? < self.start_ts, VotingError::PollHasVotes);
+        } else {
+            require!(
+                self.total_votes == 0 && self.total_weight == 0,
+                VotingError::PollHasVotes
+            );
+        }
+        Ok(())
+    }
+
+    /// Fails unless `candidate_index` names one of the poll's candidates.
+    pub fn check_candidate(&self, candidate_index: u8) -> Result<()> {
+        require!(
+            (candidate_index as usize) < self.candidates.len(),
+            VotingError::InvalidCandidate
+        );
+        Ok(())
+    }
+
+    /// Holds a freshly configured poll to the config's minimum duration of
+    /// `min_duration` seconds: it must end at least that long after voting
+    /// opens, and `earliest_close` records when that is.
+    pub fn apply_min_duration(&mut self, min_duration: i64) -> Result<()> {
+        if min_duration == 0 {
+            return Ok(());
+        }
+        let opens = self.start_ts.max(self.now()?);
+        let earliest_close = opens
+            .checked_add(self.clock_source.from_seconds(min_duration))
+            .ok_or(VotingError::ArithmeticOverflow)?;
+        require!(
+            self.end_ts != 0 && self.end_ts >= earliest_close,
+            VotingError::PollTooShort
+        );
+        self.earliest_close = earliest_close;
+        Ok(())
+    }
+
+    /// Fails unless a multisig poll has collected enough approvals for
+    /// `action`. Polls without a multisig always pass.
+    pub fn assert_approved(&self, action: MultisigAction) -> Result<()> {
+        let approvals = match action {
+            MultisigAction::Close => self.close_approvals,
+            MultisigAction::Finalize => self.finalize_approvals,
+        };
+        require!(
+            approvals >= self.approval_threshold,
+            VotingError::ApprovalThresholdNotMet
+        );
+        Ok(())
+    }
+
+    /// On multisig polls, checks that `approval_threshold` distinct approvers
+    /// signed the transaction among `signers`.
+    pub fn assert_cosigned(&self, signers: &[AccountInfo]) -> Result<()> {
+        let mut cosigners: Vec<&Pubkey> = signers
+            .iter()
+            .filter(|info| info.is_signer && self.approvers.contains(info.key))
+            .map(|info| info.key)
+            .collect();
+        cosigners.sort_unstable();
+        cosigners.dedup();
+        require!(
+            cosigners.len() >= self.approval_threshold as usize,
+            VotingError::ApprovalThresholdNotMet
+        );
+        Ok(())
+    }
+
+    /// Checks that a sealed poll can be archived: its results are published
+    /// and unchallengeable, and it holds nothing still owed to anyone. The
+    /// escrow PDAs (prize pool, reward vault, rent sponsor and funding
+    /// round) must all be closed.
+    pub fn assert_archivable(&self, escrows: [&AccountInfo; 4]) -> Result<()> {
+        require!(self.is_sealed(), VotingError::PollNotFinalized);
+        require!(self.results_published, VotingError::ResultsEmbargoed);
+        require!(
+            self.vote_deposit == 0
+                && self.conviction_locks == 0
+                && !self.prediction_market
+                && escrows.iter().all(|escrow| escrow.data_is_empty()),
+            VotingError::PollNotArchivable
+        );
+        self.assert_unchallenged()
+    }
+
+    /// Checks that a closed poll is ready to finalize and seals it. Shared
+    /// by `finalize_poll` and `finalize_minimal`.
+    pub fn seal(&mut self) -> Result<()> {
+        require!(self.status == PollStatus::Closed, VotingError::PollNotClosed);
+        require!(
+            !(self.parallel_tallies
+                || self.hide_results_until_close
+                || self.encrypted_tally != Pubkey::default()
+                || self.ballot_key != Pubkey::default())
+                || self.tallies_aggregated,
+            VotingError::TalliesNotAggregated
+        );
+        self.assert_approved(MultisigAction::Finalize)?;
+        if self.disqualification_policy == DisqualificationPolicy::Exclude {
+            // Candidate tallies stay as cast; only the totals drop them
+            for candidate in self.candidates.iter().filter(|c| c.disqualified) {
+                self.total_votes = self.total_votes.safe_sub(candidate.votes)?;
+                self.total_weight = self.total_weight.safe_sub(candidate.weight)?;
+            }
+        }
+        self.status = if self.quorum_met() {
+            PollStatus::Finalized
+        } else {
+            PollStatus::QuorumNotMet
+        };
+        self.sealed_at = self.now()?;
+        self.record_outcome();
+        Ok(())
+    }
+
+    /// Last moment, on the poll's clock, before which a sealed result can be
+    /// challenged.
+    pub fn challenge_deadline(&self) -> Result<i64> {
+        Ok(self
+            .sealed_at
+            .checked_add(self.challenge_window)
+            .ok_or(VotingError::ArithmeticOverflow)?)
+    }
+
+    /// Checks that a sealed result can no longer be challenged: its challenge
+    /// window has passed and no challenge is open.
+    pub fn assert_unchallenged(&self) -> Result<()> {
+        require!(self.open_challenges == 0, VotingError::ResultChallenged);
+        require!(
+            self.challenge_window == 0 || self.now()? >= self.challenge_deadline()?,
+            VotingError::ChallengeWindowOpen
+        );
+        Ok(())
+    }
+
+    /// Whether `PAYOUT_CLAIM_WINDOW` has passed since the challenge
+    /// deadline, so unclaimed prizes and rewards go back to the admin.
+    pub fn payout_claims_expired(&self) -> Result<bool> {
+        let expires_at = self
+            .challenge_deadline()?
+            .checked_add(self.clock_source.from_seconds(PAYOUT_CLAIM_WINDOW))
+            .ok_or(VotingError::ArithmeticOverflow)?;
+        Ok(self.now()? >= expires_at)
+    }
+
+    /// Index of the `AuditLog` page the next entry goes to.
+    pub fn audit_page(&self) -> u32 {
+        (self.audit_entries / AUDIT_LOG_PAGE_ENTRIES as u64) as u32
+    }
+
+    /// Appends `action` by `actor` to the poll's audit log, if it keeps one,
+    /// with a hash of the action's arguments as `payload`.
+    pub fn audit(
+        &mut self,
+        log: Option<&mut Account<AuditLog>>,
+        actor: Pubkey,
+        action: AuditAction,
+        payload: &[u8],
+    ) -> Result<()> {
+        if !self.audit_log {
+            return Ok(());
+        }
+        let log = log.ok_or(VotingError::AuditLogRequired)?;
+        log.entries.push(AuditEntry {
+            actor,
+            action,
+            timestamp: Clock::get()?.unix_timestamp,
+            payload_hash: hashv(&[payload]).to_bytes(),
+        });
+        self.audit_entries = self.audit_entries.safe_add(1)?;
+        Ok(())
+    }
+
+    /// Admits a new ballot under the poll's rate limit. The rate over the
+    /// last `rate_limit_slots` slots is estimated from a rolling pair of
+    /// fixed windows, weighting the previous window's count by how much of
+    /// it still overlaps.
+    pub fn throttle(&mut self) -> Result<()> {
+        if self.rate_limit_votes == 0 {
+            return Ok(());
+        }
+        let slot = Clock::get()?.slot;
+        let windows = slot.saturating_sub(self.rate_window_start) / self.rate_limit_slots;
+        if windows > 0 {
+            self.rate_previous_votes = if windows == 1 {
+                self.rate_window_votes
+            } else {
+                0
+            };
+            self.rate_window_votes = 0;
+            self.rate_window_start = slot - (slot - self.rate_window_start) % self.rate_limit_slots;
+        }
+
+        let elapsed = slot.saturating_sub(self.rate_window_start);
+        let overlap = (self.rate_limit_slots - elapsed) as u128;
+        let carried = self.rate_previous_votes as u128 * overlap / self.rate_limit_slots as u128;
+        require!(
+            carried + (self.rate_window_votes as u128) < self.rate_limit_votes as u128,
+            VotingError::RateLimited
+        );
+        self.rate_window_votes = self.rate_window_votes.safe_add(1)?;
+        Ok(())
+    }
+
+    /// Counts a new ballot of `weight` for candidate `index`. While tallies
+    /// are hidden only the totals move.
+    pub fn count_ballot(&mut self, index: u8, weight: u64) -> Result<()> {
+        if !self.tallies_hidden() {
+            let candidate = &mut self.candidates[index as usize];
+            candidate.votes = candidate.votes.safe_add(1)?;
+            candidate.weight = candidate.weight.safe_add(weight)?;
+        }
+        self.total_votes = self.total_votes.safe_add(1)?;
+        self.total_weight = self.total_weight.safe_add(weight)?;
+        self.reach_threshold()?;
+        self.close_if_full();
+        Ok(())
+    }
+
+    /// Seals an active petition once it holds `petition_threshold`
+    /// signatures.
+    pub fn reach_threshold(&mut self) -> Result<()> {
+        if self.status == PollStatus::Active
+            && self.petition_threshold > 0
+            && self.total_votes >= self.petition_threshold
+        {
+            self.status = PollStatus::ThresholdReached;
+            self.sealed_at = self.now()?;
+            self.record_outcome();
+            self.release_results()?;
+            msg!("Petition '{}' reached its threshold", self.title);
+        }
+        Ok(())
+    }
+
+    /// Whether the poll has taken its `max_votes` ballots.
+    pub fn is_full(&self) -> bool {
+        self.max_votes.is_some_and(|max| self.total_votes >= max)
+    }
+
+    /// Closes an active poll once it is full. Like expiry, the close can't
+    /// be undone by `reopen_poll`.
+    pub fn close_if_full(&mut self) {
+        if self.status == PollStatus::Active && self.is_full() {
+            self.status = PollStatus::Closed;
+            self.closed_at = 0;
+            msg!("Poll '{}' is full and has closed", self.title);
+        }
+    }
+
+    /// Removes a ballot of `weight` for candidate `index` from the tallies.
+    /// On sealed polls the totals already exclude disqualified candidates
+    /// under the `Exclude` policy, and quorum and the outcome are
+    /// recomputed as `seal` would. A randomness tie-break only stands while
+    /// its winner is still among the tied leaders.
+    pub fn strike_ballot(&mut self, index: u8, weight: u64) -> Result<()> {
+        let sealed = self.is_sealed();
+        let exclude = self.disqualification_policy == DisqualificationPolicy::Exclude;
+        let hidden = self.tallies_hidden();
+        let candidate = &mut self.candidates[index as usize];
+        if !hidden {
+            candidate.votes = candidate.votes.safe_sub(1)?;
+            candidate.weight = candidate.weight.safe_sub(weight)?;
+        }
+        if !(sealed && exclude && candidate.disqualified) {
+            self.total_votes = self.total_votes.safe_sub(1)?;
+            self.total_weight = self.total_weight.safe_sub(weight)?;
+        }
+        if sealed {
+            self.status = if self.quorum_met() {
+                PollStatus::Finalized
+            } else {
+                PollStatus::QuorumNotMet
+            };
+            if let Some(tie_winner) = self.tie_winner {
+                let tied = self.tied_candidates();
+                if tied.len() < 2 || !tied.contains(&tie_winner) {
+                    self.tie_winner = None;
+                }
+            }
+            self.record_outcome();
+        }
+        Ok(())
+    }
+
+    /// Moves a ballot of `weight` from candidate `from` to candidate `to`.
+    /// While tallies are hidden the vote record alone carries the move.
+    pub fn move_ballot(&mut self, from: u8, to: u8, weight: u64) -> Result<()> {
+        if self.tallies_hidden() {
+            return Ok(());
+        }
+        let previous = &mut self.candidates[from as usize];
+        previous.votes = previous.votes.safe_sub(1)?;
+        previous.weight = previous.weight.safe_sub(weight)?;
+        let candidate = &mut self.candidates[to as usize];
+        candidate.votes = candidate.votes.safe_add(1)?;
+        candidate.weight = candidate.weight.safe_add(weight)?;
+        Ok(())
+    }
+
+    /// Stores the winner, margin and `canonical_result_hash` on the poll, so
+    /// CPI consumers can read the outcome without recomputing it. Called
+    /// whenever results are sealed or a tie is broken.
+    pub fn record_outcome(&mut self) {
+        let mut weights: Vec<u64> = self.eligible_candidates().map(|(_, c)| c.weight).collect();
+        weights.sort_unstable_by(|a, b| b.cmp(a));
+        self.winning_weight = weights.first().copied().unwrap_or(0);
+        self.runner_up_weight = weights.get(1).copied().unwrap_or(0);
+        self.is_tie = weights.len() > 1 && self.winning_weight == self.runner_up_weight;
+        self.winner_index = self.winner();
+        self.canonical_result_hash =
+            compute_result_hash(&self.namespace, self.poll_id, self.status, &self.candidates);
+    }
+
+    /// Whether the poll's results must still be withheld.
+    pub fn embargoed(&self) -> Result<bool> {
+        Ok(Clock::get()?.slot < self.embargo_slot)
+    }
+
+    /// Marks sealed results as published unless they are embargoed, and
+    /// returns whether they may be emitted now.
+    pub fn release_results(&mut self) -> Result<bool> {
+        if self.embargoed()? {
+            return Ok(false);
+        }
+        self.results_published = true;
+        Ok(true)
+    }
+
+    /// Whether turnout reached both the absolute `quorum` and `quorum_bps`
+    /// of the voter roll.
+    pub fn quorum_met(&self) -> bool {
+        self.total_votes >= self.quorum
+            && self.total_votes as u128 * BPS_DENOMINATOR as u128
+                >= self.quorum_bps as u128 * self.eligible_voter_count as u128
+    }
+
+    /// Whether results are sealed, binding or not.
+    pub fn is_sealed(&self) -> bool {
+        matches!(
+            self.status,
+            PollStatus::Finalized | PollStatus::QuorumNotMet | PollStatus::ThresholdReached
+        )
+    }
+
+    /// Indices of the candidates sharing the most weight.
+    pub fn tied_candidates(&self) -> Vec<u8> {
+        let max = self.eligible_candidates().map(|(_, c)| c.weight).max().unwrap_or(0);
+        self.eligible_candidates()
+            .filter(|(_, c)| c.weight == max)
+            .map(|(index, _)| index as u8)
+            .collect()
+    }
+
+    /// Candidates that can still win, with their indices.
+    pub fn eligible_candidates(&self) -> impl Iterator<Item = (usize, &Candidate)> {
+        self.candidates.iter().enumerate().filter(|(_, c)| !c.disqualified)
+    }
+
+    /// Index of the eligible candidate with strictly the most weight, or of
+    /// the randomly chosen leader when a tie was broken. `None` on an
+    /// unbroken tie or when the poll missed its quorum. Petitions only have
+    /// a winner once they reach their threshold.
+    pub fn winner(&self) -> Option<u8> {
+        if self.status == PollStatus::QuorumNotMet
+            || (self.petition_threshold > 0 && self.status != PollStatus::ThresholdReached)
+        {
+            return None;
+        }
+        if self.tie_winner.is_some() {
+            return self.tie_winner;
+        }
+        let max = self.eligible_candidates().map(|(_, c)| c.weight).max()?;
+        let mut leaders = self.eligible_candidates().filter(|(_, c)| c.weight == max);
+        let (index, _) = leaders.next()?;
+        match leaders.next() {
+            Some(_) => None,
+            None => Some(index as u8),
+        }
+    }
+}
+
+/// Lifecycle of a poll. `Closed` and `Active` keep the byte values of the
+/// former `is_active: bool` field so existing accounts still deserialize.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PollStatus {
+    /// Voting has ended; results can be aggregated and finalized.
+    Closed,
+    /// Accepting votes.
+    Active,
+    /// Invalidated by the admin. Never finalizes and has no winner.
+    Cancelled,
+    /// Results are sealed.
+    Finalized,
+    /// Created but not yet open; becomes `Active` with `activate_poll`.
+    Draft,
+    /// Finalized with fewer than `quorum` votes. Results are sealed but
+    /// non-binding, so there is no winner.
+    QuorumNotMet,
+    /// A petition collected `petition_threshold` signatures. It takes no
+    /// more and its results are sealed, with the petition as the winner.
+    ThresholdReached,
+}
+
+/// Options chosen once at poll creation.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct PollSettings {
+    /// Keep live tallies in per-candidate `CandidateTally` accounts instead of
+    /// on the poll, so concurrent votes don't contend for one write lock.
+    pub parallel_tallies: bool,
+    /// Unit in which `start_ts` and `end_ts` are expressed.
+    pub clock_source: ClockSource,
+    /// Votes are rejected before this point (0 = open immediately).
+    pub start_ts: i64,
+    /// Votes are rejected from this point on (0 = no deadline).
+    pub end_ts: i64,
+    /// Keys whose approvals count towards `approval_threshold`.
+    pub approvers: Vec<Pubkey>,
+    /// Approvals (`approve_action`) required before the poll can be closed,
+    /// and again before it can be finalized (0 = no multisig).
+    pub approval_threshold: u8,
+    /// Let voters move their direct ballot with `change_vote` while the poll
+    /// accepts votes.
+    pub allow_vote_changes: bool,
+    /// Only accept ballots from voters who proved membership of the poll's
+    /// hashed voter roll with `prove_membership`.
+    pub voter_roll: bool,
+    /// How user-generated content such as candidate descriptions is stored.
+    pub content_mode: ContentMode,
+    /// Create the poll as a `Draft` that accepts no votes until
+    /// `activate_poll`.
+    pub start_as_draft: bool,
+    /// How much per-ballot detail is logged and emitted as events.
+    pub event_verbosity: EventVerbosity,
+    /// Seconds after `close_poll` during which `reopen_poll` can undo the
+    /// close (0 = never).
+    pub reopen_window: i64,
+    /// How far past `end_ts`, in `clock_source` units, `extend_deadline` may
+    /// push the deadline in total (0 = no extensions).
+    pub max_extension: i64,
+    /// Allow `publish_attendance` to emit who voted, without their choices,
+    /// once the poll is finalized.
+    pub publish_attendance: bool,
+    /// Lamports paid from the poll's fee vault to whoever calls
+    /// `auto_finalize` after the deadline.
+    pub finalize_tip: u64,
+    /// Share of the vote, in basis points, below which published result
+    /// feeds group a candidate under "Other". Raw tallies are unaffected.
+    pub reporting_threshold_bps: u16,
+    /// Minimum `total_votes` for the result to be binding (0 = none). Polls
+    /// finalized below it end as `QuorumNotMet`.
+    pub quorum: u64,
+    /// Minimum turnout, in basis points of the voter roll's size, for the
+    /// result to be binding (0 = none). Needs `voter_roll`.
+    pub quorum_bps: u16,
+    /// Slot before which finalizing seals the result without emitting it;
+    /// `publish_results` emits it from this slot on (0 = no embargo). The
+    /// embargo is advisory: the sealed tallies and winner stay readable by
+    /// anyone who fetches the poll account.
+    pub embargo_slot: u64,
+    /// Let voters add up to `MAX_WRITE_INS` candidates with `vote_write_in`.
+    /// Not available with `parallel_tallies`.
+    pub allow_write_ins: bool,
+    /// How `disqualify_candidate` treats ballots already cast.
+    pub disqualification_policy: DisqualificationPolicy,
+    /// Size vote records to hold a `MAX_VOTE_MEMO_LEN`-byte memo, which
+    /// voters set with `set_vote_memo`.
+    pub allow_memos: bool,
+    /// Civic gatekeeper network whose gateway token (proof of personhood)
+    /// `vote` requires from each voter; `Pubkey::default()` for none. Gated
+    /// polls take ballots only through `vote`.
+    pub gatekeeper_network: Pubkey,
+    /// Token-2022 mint with the non-transferable extension (a membership
+    /// badge) of which `vote` requires the voter to hold at least one unit;
+    /// `Pubkey::default()` for none.
+    pub badge_mint: Pubkey,
+    /// SPL Token or Token-2022 mint of which `vote` requires the voter's
+    /// associated token account to hold at least `min_token_balance` (in
+    /// base units); `Pubkey::default()` for none. Unlike weighting, every
+    /// qualifying ballot still counts once.
+    pub token_gate_mint: Pubkey,
+    pub min_token_balance: u64,
+    /// Lamports each new ballot leaves in the poll account, refunded by
+    /// `close_vote_record` once the poll is over (0 = none). Deters spam
+    /// wallets in open polls. Not available with `parallel_tallies`, which
+    /// keeps the poll account out of ballots.
+    pub vote_deposit: u64,
+    /// Weigh each `vote` ballot by the voter's active native stake, in
+    /// lamports, proven by passing their stake accounts; other ballot paths
+    /// are rejected. For validator-community polls.
+    pub stake_weighted: bool,
+    /// Ristretto point (`secret·G`) voters encrypt ballots to with
+    /// `vote_encrypted`, the only ballot path then; `Pubkey::default()` for
+    /// none. Needs an `end_ts` and no write-ins, gates, deposits, stake
+    /// weighting, parallel tallies or `direct_votes_only`.
+    pub ballot_key: Pubkey,
+    /// Time after `end_ts`, in `clock_source` units, the admin has to reveal
+    /// the key's secret with `reveal_ballot_key`. Needs `ballot_key`.
+    pub reveal_window: i64,
+    /// Lamports the creator leaves in the poll until the key is revealed,
+    /// slashed to the treasury if it isn't revealed in time. Needs
+    /// `ballot_key`.
+    pub reveal_bond: u64,
+    /// Reject ballots from `vote` calls made through CPI, so only wallets
+    /// signing the transaction themselves can vote, not smart-contract
+    /// wallets or vote-buying escrows. Voters pass the instructions sysvar;
+    /// other ballot paths are rejected, as on gated polls.
+    pub direct_votes_only: bool,
+    /// Most ballots the poll takes in any `rate_limit_slots` slots, so open
+    /// polls can dampen bot bursts (0 = no limit); ballots over it fail with
+    /// `RateLimited` and can be retried later. Both or neither must be set.
+    /// Not available with `parallel_tallies`, which keeps ballots off the
+    /// poll account.
+    pub rate_limit_votes: u32,
+    pub rate_limit_slots: u64,
+    /// Close the poll once it holds this many ballots, for "first N
+    /// responses" polls; later ballots fail with `PollFull`. Not available
+    /// with `parallel_tallies` or a `ballot_key`, whose ballots are counted
+    /// only after voting.
+    pub max_votes: Option<u64>,
+    /// What kind of poll this is, as a `PollCategory`, so explorers can
+    /// filter without an off-chain registry.
+    pub category: u16,
+    /// Up to `MAX_POLL_TAGS` distinct tags of at most `MAX_TAG_LEN` lowercase
+    /// letters, digits and dashes.
+    pub tags: Vec<String>,
+    /// Key that may `certify_result` besides the admin, such as an election
+    /// commission (`Pubkey::default()` for the admin only).
+    pub certifier: Pubkey,
+    /// Time after finalization, in `clock_source` units, during which voters
+    /// can `challenge_result` (0 = no challenges). Certification waits for
+    /// it to pass.
+    pub challenge_window: i64,
+    /// Lamports each challenge puts up, refunded if the challenge is upheld
+    /// and forfeited to the treasury otherwise. Needs a `challenge_window`.
+    pub challenge_deposit: u64,
+    /// Append every close, reopen, extension, cancellation, metadata or
+    /// candidate change and disqualification to paged `AuditLog` PDAs, so
+    /// observers can reconstruct what administrators did. Those actions then
+    /// need the log's current page, opened with `open_audit_page`.
+    pub audit_log: bool,
+    /// Weigh each ballot by tokens of this mint the voter locks with
+    /// `lock_conviction`, multiplied by the lock tier (`Pubkey::default()`
+    /// for none). Not combinable with stake weighting.
+    pub conviction_mint: Pubkey,
+    /// Seconds the 1x conviction tier locks tokens for; higher tiers lock
+    /// them 2x and 4x as long.
+    pub conviction_period: i64,
+    /// Run the poll as a quadratic funding round: contributors fund
+    /// candidates' projects with `contribute`, and a matching pool escrowed
+    /// with `fund_matching_pool` is split by quadratic funding after
+    /// finalization. Not combinable with write-ins.
+    pub quadratic_funding: bool,
+    /// Run the poll as a prediction market: ballots are stakes placed with
+    /// `place_stake`, and once the admin or `oracle` calls
+    /// `resolve_outcome`, stakers on the right outcome split the rest with
+    /// `claim_winnings`. Not combinable with other weighting or parallel
+    /// tallies.
+    pub prediction_market: bool,
+    /// Mint staked on prediction polls (`Pubkey::default()` for lamports).
+    pub prediction_mint: Pubkey,
+    /// Key that may `resolve_outcome` besides the admin
+    /// (`Pubkey::default()` for the admin only).
+    pub oracle: Pubkey,
+    /// Run the poll as a petition with this many signatures to reach (0 for
+    /// an ordinary poll): it has a single candidate, each `vote` signs it,
+    /// and it becomes `ThresholdReached` on the last signature needed.
+    pub petition_threshold: u64,
+    /// Verified Metaplex collection whose NFTs weigh `vote` ballots, one
+    /// each, proven by passing the voter's token and metadata accounts;
+    /// other ballot paths are rejected. `Pubkey::default()` for none. Not
+    /// combinable with other ballot weights, parallel tallies, encrypted
+    /// ballots or petitions.
+    pub nft_collection: Pubkey,
+    /// Reputation score a voter's `Reputation` must have reached for `vote`
+    /// to take their ballot (0 = none); other ballot paths are rejected.
+    pub min_reputation: u64,
+    /// Nonzero to keep voters' keys out of their vote records, which store
+    /// `sha256(voter || record_salt)` instead. Record addresses are still
+    /// derived from the real key, so each voter gets one, but records can't
+    /// be tied back to voters for `tally_page` or `publish_attendance`.
+    pub record_salt: [u8; 32],
+    /// Longest title the poll takes, in UTF-8 bytes (0 = the config's
+    /// `max_title_len`, which it can't exceed). Smaller budgets make for
+    /// smaller poll accounts and less rent.
+    pub max_title_bytes: u16,
+    /// Longest candidate or write-in name, in UTF-8 bytes (0 = the config's
+    /// `max_candidate_name_len`, which it can't exceed).
+    pub max_candidate_name_bytes: u16,
+    /// Withholds candidate tallies while the poll is open, so early results
+    /// can't sway later voters: ballots only add to the totals, per-ballot
+    /// events are suppressed, and the tallies are rebuilt from the vote
+    /// records (`begin_tally`/`tally_page`, then `reveal_tallies`) before
+    /// the poll can be finalized. The records themselves stay readable;
+    /// for secret ballots use `ballot_key` instead.
+    pub hide_results_until_close: bool,
+    /// Key attesting voters' `token_gate_mint` balances as of the poll's
+    /// `snapshot_slot` with `attest_snapshot_balance`; token-gated polls
+    /// with one ignore live balances, so tokens bought after creation don't
+    /// count. Pubkey::default() for none.
+    pub snapshot_oracle: Pubkey,
+    /// Share of a ballot's weight lost by `end_ts`, in basis points: weight
+    /// decays linearly from full when voting opens, so early ballots count
+    /// for more. Needs an `end_ts`; 0 for none.
+    pub decay_bps: u16,
+    /// Ballot weight multiplier of each voter tier, for council-style
+    /// electorates where member classes have set influence. Voters' tiers
+    /// come from their voter roll entries, so tiers need a `voter_roll`;
+    /// each tier's ballots are also tallied separately. Empty for none.
+    pub tier_weights: Vec<u16>,
+    /// Counters to split each candidate's tally across with
+    /// `parallel_tallies`, so ballots for a popular candidate don't all
+    /// queue on one account. Voters are assigned a shard by a hash of their
+    /// key. 0 or 1 for a single counter.
+    pub tally_shards: u8,
+}
+
+impl PollSettings {
+    /// Candidate entries to reserve space for, given `listed` candidates.
+    pub fn candidate_slots(&self, listed: usize) -> usize {
+        listed + if self.allow_write_ins { MAX_WRITE_INS } else { 0 }
+    }
+
+    /// Bytes the poll needs for per-tier weights and tallies of
+    /// `candidates`.
+    pub fn tier_space(&self, candidates: usize) -> usize {
+        self.tier_weights.len() * (2 + candidates * 8)
+    }
+
+    /// `tier_space` with the most tiers a poll can have.
+    pub const fn max_tier_space(candidates: usize) -> usize {
+        MAX_VOTER_TIERS * (2 + candidates * 8)
+    }
+
+    /// The config's `limits` with this poll's own title and name budgets
+    /// applied.
+    pub fn string_limits(&self, limits: &ConfigLimits) -> ConfigLimits {
+        let budget = |own: u16, max: u16| if own == 0 { max } else { own.min(max) };
+        ConfigLimits {
+            max_candidates: limits.max_candidates,
+            max_title_len: budget(self.max_title_bytes, limits.max_title_len),
+            max_candidate_name_len: budget(self.max_candidate_name_bytes, limits.max_candidate_name_len),
+        }
+    }
+}
+
+/// Per-ballot logging level. Lifecycle events (created, closed, finalized,
+/// ...) are emitted at every level.
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default,
+)]
+pub enum EventVerbosity {
+    /// No per-ballot logs or events, for high-throughput polls.
+    Essential,
+    /// A log line and `VoteCast`-style event per ballot.
+    #[default]
+    Standard,
+    /// `Standard` plus a `BallotDetail` event with running weights and the
+    /// voter's cohort, for analytics.
+    Verbose,
+}
+
+/// How a ballot reached the poll, reported in `BallotDetail`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VoterCohort {
+    /// Direct vote on an open poll.
+    Open,
+    /// Direct vote by a proven member of the poll's voter roll.
+    Roll,
+    /// Share of a delegator's vote cast by a delegate.
+    Delegated,
+}
+
+/// What happens to the ballots a candidate already holds when it is
+/// disqualified. Either way the candidate can't win.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DisqualificationPolicy {
+    /// Its ballots stay in the totals, frozen at what they were.
+    #[default]
+    Freeze,
+    /// Its ballots are dropped from the totals at finalization; its own
+    /// tally is kept for audit.
+    Exclude,
+}
+
+/// Admin actions recorded in a poll's audit log.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AuditAction {
+    Close,
+    Reopen,
+    ExtendDeadline,
+    Cancel,
+    UpdateMetadata,
+    UpdateTitle,
+    SetCandidateDescription,
+    SetCandidatePayout,
+    DisqualifyCandidate,
+    Freeze,
+    Unfreeze,
+    SetMetadataHash,
+    InvalidateVote,
+    EditDraft,
+}
+
+/// One audit log entry. `payload_hash` is a SHA-256 of the action's
+/// arguments: the new `end_ts` as little-endian bytes for extensions, the
+/// UTF-8 reason or title for cancellations and title changes, the borsh
+/// encoding of the remaining arguments otherwise, and nothing for closes,
+/// reopens, freezes and unfreezes.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct AuditEntry {
+    pub actor: Pubkey,
+    pub action: AuditAction,
+    pub timestamp: i64,
+    pub payload_hash: [u8; 32],
+}
+
+impl AuditEntry {
+    pub const SIZE: usize = 32 + 1 + 8 + 32;
+}
+
+/// Kinds of poll, stored on the poll as their `u16` value so new kinds can
+/// be added without a layout change.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[repr(u16)]
+pub enum PollCategory {
+    #[default]
+    General = 0,
+    /// Binding decisions of a DAO, protocol or organization.
+    Governance = 1,
+    /// Non-binding sentiment of a community.
+    Community = 2,
+    /// Elections of people to roles.
+    Election = 3,
+    /// Questionnaires and feedback.
+    Survey = 4,
+    /// Just for fun.
+    Fun = 5,
+}
+
+impl PollCategory {
+    pub fn from_u16(value: u16) -> Option<Self> {
+        Some(match value {
+            0 => Self::General,
+            1 => Self::Governance,
+            2 => Self::Community,
+            3 => Self::Election,
+            4 => Self::Survey,
+            5 => Self::Fun,
+            _ => return None,
+        })
+    }
+
+    /// Whether `tag` is non-empty, at most `MAX_TAG_LEN` bytes, and only
+    /// lowercase ASCII letters, digits and dashes, so tags match exactly.
+    pub fn valid_tag(tag: &str) -> bool {
+        !tag.is_empty()
+            && tag.len() <= MAX_TAG_LEN
+            && tag
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+    }
+}
+
+/// Storage mode for user-generated content.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ContentMode {
+    /// Short text may be stored inline.
+    #[default]
+    Inline,
+    /// Only content hashes and URIs are stored, bounding account growth.
+    HashOnly,
+}
+
+/// A piece of user-generated content: short inline text, or a hash of
+/// off-chain content and where to fetch it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub enum Content {
+    Text(String),
+    Hashed { hash: [u8; 32], uri: String },
+}
+
+impl Content {
+    /// Largest serialized size: enum tag + the bigger variant.
+    pub const MAX_SIZE: usize = 1 + {
+        let text = 4 + MAX_INLINE_CONTENT_LEN;
+        let hashed = 32 + 4 + MAX_CONTENT_URI_LEN;
+        if text > hashed { text } else { hashed }
+    };
+
+    /// Fails if the content is too long or not allowed under `mode`.
+    pub fn validate(&self, mode: ContentMode) -> Result<()> {
+        match self {
+            Content::Text(text) => {
+                require!(mode == ContentMode::Inline, VotingError::InlineContentDisabled);
+                require!(text.len() <= MAX_INLINE_CONTENT_LEN, VotingError::ContentTooLong);
+            }
+            Content::Hashed { uri, .. } => {
+                require!(uri.len() <= MAX_CONTENT_URI_LEN, VotingError::ContentTooLong);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Admin actions that a multisig poll gates behind approvals.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MultisigAction {
+    Close,
+    Finalize,
+}
+
+/// How a poll measures its voting window. Slots are precise for short polls;
+/// unix time suits polls scheduled around human calendars.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ClockSource {
+    #[default]
+    UnixTimestamp,
+    Slot,
+}
+
+impl ClockSource {
+    pub fn now(&self, clock: &Clock) -> i64 {
+        match self {
+            ClockSource::UnixTimestamp => clock.unix_timestamp,
+            ClockSource::Slot => clock.slot as i64,
+        }
+    }
+
+    /// `seconds` in this clock's units, assuming the default slot time.
+    pub fn from_seconds(&self, seconds: i64) -> i64 {
+        match self {
+            ClockSource::UnixTimestamp => seconds,
+            ClockSource::Slot => seconds.saturating_mul(1000) / DEFAULT_MS_PER_SLOT as i64,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Candidate {
+    pub name: String, // 4 + len (max ConfigLimits::max_candidate_name_len)
+    pub votes: u64,   // 8, ballots cast directly for this candidate
+    pub weight: u64,  // 8, in basis points of a ballot, including delegated shares
+    pub disqualified: bool, // 1
+}
+
+impl Candidate {
+    pub const fn size(max_name_len: u16) -> usize {
+        4 + max_name_len as usize + 8 + 8 + 1 // string prefix + max name + votes + weight + disqualified
+    }
+}
+
+#[account]
+pub struct VoteRecord {
+    pub voter: Pubkey,        // 32
+    pub poll_id: u64,         // 8
+    pub candidate_index: u8,  // 1, SPLIT_BALLOT when cast by delegates
+    pub bump: u8,             // 1
+    pub weight: u64,          // 8
+    pub delegated_bps: u16,   // 2, share of the ballot cast by delegates
+    pub changes: Vec<VoteChange>, // 4 + (MAX_VOTE_CHANGES * VoteChange::SIZE)
+    pub voted_at: i64,        // 8, unix time the ballot landed (first share for split ballots)
+    pub slot: u64,            // 8, slot the ballot landed in
+    pub memo: String,         // 4 + MAX_VOTE_MEMO_LEN on polls with allow_memos, else 4
+    pub deposit: u64,         // 8, lamports held in the poll until close_vote_record
+    pub reward_claimed: bool, // 1, set by claim_reward
+    pub version: u8,          // 1, VOTE_RECORD_VERSION when written
+    pub reputation_counted: bool, // 1, set by record_participation
+    pub receipt_minted: bool, // 1, set by mint_vote_receipt
+    pub invalidated: bool,    // 1, struck from the tallies by invalidate_vote
+    pub invalidation_reason: u8, // 1, the admin's reason code when invalidated
+    pub tier: u8,             // 1, the voter's tier on polls with tier_weights
+}
+
+impl VoteRecord {
+    pub const SPACE: usize = 8 + 32 + 8 + 1 + 1 + 8 + 2
+        + 4 + MAX_VOTE_CHANGES * VoteChange::SIZE
+        + 8 + 8
+        + 4
+        + 8
+        + 1
+        + 1
+        + 1
+        + 1
+        + 1
+        + 1
+        + 1
+        + 1; // discriminator + fields + padding
+
+    /// Space for a record on a poll with or without memos.
+    pub const fn space(allow_memos: bool) -> usize {
+        Self::SPACE + if allow_memos { MAX_VOTE_MEMO_LEN } else { 0 }
+    }
+
+    /// Moves a `amount`-lamport anti-spam deposit from `payer` into the
+    /// poll account and records it for refund by `close_vote_record`.
+    pub fn hold_deposit<'info>(
+        &mut self,
+        amount: u64,
+        payer: AccountInfo<'info>,
+        poll: AccountInfo<'info>,
+        system_program: AccountInfo<'info>,
+    ) -> Result<()> {
+        self.deposit = amount;
+        if amount == 0 {
+            return Ok(());
+        }
+        system_program::transfer(
+            CpiContext::new(
+                system_program,
+                system_program::Transfer {
+                    from: payer,
+                    to: poll,
+                },
+            ),
+            amount,
+        )
+    }
+
+    /// Records when the ballot landed, from the Clock sysvar, and the
+    /// layout version it was written with.
+    pub fn stamp(&mut self) -> Result<()> {
+        let clock = Clock::get()?;
+        self.voted_at = clock.unix_timestamp;
+        self.slot = clock.slot;
+        self.version = VOTE_RECORD_VERSION;
+        Ok(())
+    }
+}
+
+/// One `change_vote` in a vote record's audit history.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct VoteChange {
+    pub previous_index: u8, // 1
+    pub new_index: u8,      // 1
+    pub slot: u64,          // 8
+}
+
+impl VoteChange {
+    pub const SIZE: usize = 1 + 1 + 8;
+}
+
+/// A voter's token balance at a poll's `snapshot_slot`, attested by its
+/// `snapshot_oracle`, at `[b"snapshot", poll, voter]`.
+#[account]
+pub struct SnapshotBalance {
+    pub poll: Pubkey,  // 32
+    pub voter: Pubkey, // 32
+    pub balance: u64,  // 8, base units of the poll's token_gate_mint
+    pub slot: u64,     // 8, the poll's snapshot_slot
+    pub bump: u8,      // 1
+}
+
+impl SnapshotBalance {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 1; // discriminator + fields
+}
+
+/// A voter's participation across all polls, at `[b"reputation", voter]`.
+/// Polls with a `min_reputation` only take ballots from voters whose score
+/// has reached it, which fresh wallets can't fake.
+#[account]
+pub struct Reputation {
+    pub voter: Pubkey,        // 32
+    pub votes_cast: u64,      // 8, ballots cast through `vote` with this account
+    pub polls_completed: u64, // 8, finalized polls credited by record_participation
+    pub bump: u8,             // 1
+}
+
+impl Reputation {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 1; // discriminator + fields
+
+    /// One point per ballot and one per finalized poll voted in.
+    pub fn score(&self) -> u64 {
+        self.votes_cast.saturating_add(self.polls_completed)
+    }
+}
+
+/// Marks a native stake account as counted in a stake-weighted poll, at
+/// `[b"stake_ballot", poll, stake_account]`, so moving its authority to
+/// another wallet can't count it twice.
+#[account]
+pub struct StakeBallot {
+    pub voter: Pubkey,  // 32
+    pub stake: u64,     // 8, lamports counted
+    pub bump: u8,       // 1
+}
+
+impl StakeBallot {
+    pub const SPACE: usize = 8 + 32 + 8 + 1; // discriminator + fields
+
+    /// Active stake of a delegated stake account `owner` is the staker or
+    /// withdrawer of. Only stake activated before the current epoch and not
+    /// deactivating counts; the stake history sysvar is too large to load
+    /// for partial warmup and cooldown.
+    pub fn active_stake(stake_account: &AccountInfo, owner: &Pubkey) -> Result<u64> {
+        require!(
+            stake_account.owner == &stake::program::ID,
+            VotingError::InvalidStakeAccount
+        );
+        let state = StakeStateV2::deserialize(&mut &stake_account.try_borrow_data()?[..])
+            .map_err(|_| VotingError::InvalidStakeAccount)?;
+        let StakeStateV2::Stake(meta, stake, _) = state else {
+            return err!(VotingError::InvalidStakeAccount);
+        };
+        require!(
+            meta.authorized.staker == *owner || meta.authorized.withdrawer == *owner,
+            VotingError::InvalidStakeAccount
+        );
+        let delegation = stake.delegation;
+        let activated =
+            delegation.is_bootstrap() || delegation.activation_epoch < Clock::get()?.epoch;
+        if activated && delegation.deactivation_epoch == u64::MAX {
+            Ok(delegation.stake)
+        } else {
+            Ok(0)
+        }
+    }
+
+    /// Sums `owner`'s active stake over `(stake_account, stake_ballot)`
+    /// pairs, creating each ballot (paid by `payer`) so no stake account
+    /// counts twice in `poll`.
+    pub fn count<'info>(
+        pairs: &[AccountInfo<'info>],
+        poll: &Pubkey,
+        owner: &Pubkey,
+        payer: AccountInfo<'info>,
+        system_program: AccountInfo<'info>,
+    ) -> Result<u64> {
+        require!(
+            !pairs.is_empty() && pairs.len().is_multiple_of(2) && pairs.len() <= MAX_STAKE_ACCOUNTS * 2,
+            VotingError::InvalidStakeAccount
+        );
+        let mut total: u64 = 0;
+        for pair in pairs.chunks(2) {
+            let (stake_account, ballot_info) = (&pair[0], &pair[1]);
+            let stake = Self::active_stake(stake_account, owner)?;
+
+            let (ballot_key, bump) = Pubkey::find_program_address(
+                &[b"stake_ballot", poll.as_ref(), stake_account.key.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(ballot_info.key(), ballot_key, VotingError::InvalidStakeAccount);
+            // Fails if the stake account was already counted in this poll.
+            system_program::create_account(
+                CpiContext::new_with_signer(
+                    system_program.clone(),
+                    system_program::CreateAccount {
+                        from: payer.clone(),
+                        to: ballot_info.clone(),
+                    },
+                    &[&[b"stake_ballot", poll.as_ref(), stake_account.key.as_ref(), &[bump]]],
+                ),
+                Rent::get()?.minimum_balance(Self::SPACE),
+                Self::SPACE as u64,
+                &crate::ID,
+            )?;
+            let ballot = StakeBallot {
+                voter: *owner,
+                stake,
+                bump,
+            };
+            ballot.try_serialize(&mut &mut ballot_info.try_borrow_mut_data()?[..])?;
+            total = total.safe_add(stake)?;
+        }
+        Ok(total)
+    }
+}
+
+/// Marks an NFT as counted in an NFT-weighted poll, at
+/// `[b"nft_ballot", poll, mint]`, so passing it to another wallet can't
+/// count it twice.
+#[account]
+pub struct NftBallot {
+    pub voter: Pubkey, // 32
+    pub bump: u8,      // 1
+}
+
+impl NftBallot {
+    pub const SPACE: usize = 8 + 32 + 1; // discriminator + fields
+
+    /// Counts `owner`'s NFTs of `collection` over `(token_account,
+    /// metadata, nft_ballot)` triples, creating each ballot (paid by
+    /// `payer`) so no NFT counts twice in `poll`.
+    pub fn count<'info>(
+        triples: &[AccountInfo<'info>],
+        poll: &Pubkey,
+        collection: &Pubkey,
+        owner: &Pubkey,
+        payer: AccountInfo<'info>,
+        system_program: AccountInfo<'info>,
+    ) -> Result<u64> {
+        require!(
+            !triples.is_empty()
+                && triples.len().is_multiple_of(3)
+                && triples.len() <= MAX_NFTS_PER_VOTE * 3,
+            VotingError::NftRequired
+        );
+        let mut total: u64 = 0;
+        for triple in triples.chunks(3) {
+            let (token_info, metadata_info, ballot_info) = (&triple[0], &triple[1], &triple[2]);
+            require!(
+                token_info.owner == &token::ID || token_info.owner == &spl_token_2022::ID,
+                VotingError::InvalidNft
+            );
+            let token = TokenAccount::try_deserialize(&mut &token_info.try_borrow_data()?[..])
+                .map_err(|_| VotingError::InvalidNft)?;
+            let metadata = NftMetadata::load(metadata_info)?;
+            require!(
+                token.owner == *owner
+                    && token.amount == 1
+                    && metadata.mint == token.mint
+                    && metadata.collection == Some((*collection, true)),
+                VotingError::InvalidNft
+            );
+
+            let (ballot_key, bump) = Pubkey::find_program_address(
+                &[b"nft_ballot", poll.as_ref(), token.mint.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(ballot_info.key(), ballot_key, VotingError::InvalidNft);
+            // Fails if the NFT was already counted in this poll.
+            system_program::create_account(
+                CpiContext::new_with_signer(
+                    system_program.clone(),
+                    system_program::CreateAccount {
+                        from: payer.clone(),
+                        to: ballot_info.clone(),
+                    },
+                    &[&[b"nft_ballot", poll.as_ref(), token.mint.as_ref(), &[bump]]],
+                ),
+                Rent::get()?.minimum_balance(Self::SPACE),
+                Self::SPACE as u64,
+                &crate::ID,
+            )?;
+            let ballot = NftBallot {
+                voter: *owner,
+                bump,
+            };
+            ballot.try_serialize(&mut &mut ballot_info.try_borrow_mut_data()?[..])?;
+            total = total.safe_add(1)?;
+        }
+        Ok(total)
+    }
+}
+
+/// A wallet's authorization for an ephemeral key to sign `vote` on its
+/// behalf, at `[b"session", authority, session_key]`. Being owned by this
+/// program, a token grants nothing outside it.
+#[account]
+pub struct SessionToken {
+    pub authority: Pubkey,   // 32
+    pub session_key: Pubkey, // 32
+    pub expires_at: i64,     // 8, unix time
+    pub bump: u8,            // 1
+}
+
+impl SessionToken {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 1; // discriminator + fields
+
+    /// Whose ballot `signer` casts: the token's authority when a session
+    /// token is given, otherwise the signer itself.
+    pub fn ballot_owner(token: &Option<Account<SessionToken>>, signer: &Signer) -> Pubkey {
+        token.as_ref().map_or(signer.key(), |token| token.authority)
+    }
+
+    /// Fails once the session has expired.
+    pub fn assert_active(&self) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp < self.expires_at,
+            VotingError::SessionExpired
+        );
+        Ok(())
+    }
+}
+
+/// The last nonce a voter signed a relayed ballot with, at `[b"nonce", voter]`.
+#[account]
+pub struct VoteNonce {
+    pub voter: Pubkey,    // 32
+    pub last_nonce: u64,  // 8
+    pub bump: u8,         // 1
+}
+
+impl VoteNonce {
+    pub const SPACE: usize = 8 + 32 + 8 + 1; // discriminator + fields
+
+    /// ed25519 program instruction data: a signature count and a padding
+    /// byte, then seven u16 offsets per signature.
+    const ED25519_HEADER_LEN: usize = 2;
+    const ED25519_OFFSETS_LEN: usize = 14;
+
+    /// The bytes a voter signs for `vote_with_signature`:
+    /// `poll || poll_id (LE) || candidate_index || nonce (LE)`.
+    pub fn message(poll: &Pubkey, poll_id: u64, candidate_index: u8, nonce: u64) -> Vec<u8> {
+        let mut message = Vec::with_capacity(32 + 8 + 1 + 8);
+        message.extend_from_slice(poll.as_ref());
+        message.extend_from_slice(&poll_id.to_le_bytes());
+        message.push(candidate_index);
+        message.extend_from_slice(&nonce.to_le_bytes());
+        message
+    }
+
+    /// Fails unless `ix` is an ed25519 program instruction verifying exactly
+    /// one signature, by `signer` over `message`, with all its data inline.
+    pub fn check_signature(ix: &Instruction, signer: &Pubkey, message: &[u8]) -> Result<()> {
+        require_keys_eq!(ix.program_id, ed25519_program::ID, VotingError::InvalidVoteSignature);
+        let data = &ix.data;
+        require!(
+            data.len() >= Self::ED25519_HEADER_LEN + Self::ED25519_OFFSETS_LEN && data[0] == 1,
+            VotingError::InvalidVoteSignature
+        );
+        let offsets: Vec<u16> = data[Self::ED25519_HEADER_LEN..][..Self::ED25519_OFFSETS_LEN]
+            .chunks(2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        // signature, public key and message offsets each pair with an
+        // instruction index; u16::MAX means this instruction's own data.
+        let [_, sig_ix, key_offset, key_ix, msg_offset, msg_len, msg_ix] = offsets[..] else {
+            return err!(VotingError::InvalidVoteSignature);
+        };
+        require!(
+            sig_ix == u16::MAX && key_ix == u16::MAX && msg_ix == u16::MAX,
+            VotingError::InvalidVoteSignature
+        );
+        let slice = |offset: u16, len: usize| data.get(offset as usize..offset as usize + len);
+        require!(
+            slice(key_offset, 32) == Some(signer.as_ref())
+                && slice(msg_offset, msg_len as usize) == Some(message),
+            VotingError::InvalidVoteSignature
+        );
+        Ok(())
+    }
+}
+
+/// One approver's sign-off on a multisig action, at
+/// `[b"approval", poll, action, approver]`.
+#[account]
+pub struct Approval {
+    pub poll: Pubkey,           // 32
+    pub approver: Pubkey,       // 32
+    pub action: MultisigAction, // 1
+    pub slot: u64,              // 8
+    pub bump: u8,               // 1
+}
+
+impl Approval {
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 8 + 1; // discriminator + fields
+}
+
+/// A candidate's description, at `[b"description", poll, candidate_index]`.
+#[account]
+pub struct CandidateDescription {
+    pub poll: Pubkey,        // 32
+    pub candidate_index: u8, // 1
+    pub content: Content,    // Content::MAX_SIZE
+    pub bump: u8,            // 1
+}
+
+impl CandidateDescription {
+    pub const SPACE: usize = 8 + 32 + 1 + Content::MAX_SIZE + 1; // discriminator + fields
+}
+
+/// Bloom filter of compressed ballots' nullifiers, one per compressed poll.
+/// This header is followed by the filter's bits, filling the rest of an
+/// account the admin allocates (owned by this program) for the expected
+/// turnout: about 10 bits per voter with 7 hashes keeps false positives near
+/// 1%. A false positive turns away a voter who hasn't voted, so size it
+/// generously.
+#[account]
+pub struct VoteFilter {
+    pub poll: Pubkey,  // 32
+    pub hashes: u8,    // 1, bits set per nullifier
+    pub inserted: u64, // 8, nullifiers added, i.e. leaves appended to the vote tree
+}
+
+impl VoteFilter {
+    pub const HEADER_SPACE: usize = 8 + 32 + 1 + 8; // discriminator + fields
+
+    /// The nullifier a ballot by `voter` in `poll` adds to the filter.
+    pub fn nullifier(poll: &Pubkey, voter: &Pubkey) -> [u8; 32] {
+        hashv(&[b"nullifier", poll.as_ref(), voter.as_ref()]).to_bytes()
+    }
+
+    /// Writes a fresh header for `poll` to `info`, which must be zeroed.
+    pub fn init(info: &AccountInfo, poll: Pubkey, hashes: u8) -> Result<()> {
+        let mut data = info.try_borrow_mut_data()?;
+        require!(
+            data.len() > Self::HEADER_SPACE && data[..8] == [0; 8],
+            VotingError::InvalidVoteFilter
+        );
+        Self {
+            poll,
+            hashes,
+            inserted: 0,
+        }
+        .try_serialize(&mut &mut data[..])
+    }
+
+    /// Adds `nullifier` to the filter in `info` and returns how many were
+    /// added before it, failing with `DuplicateVote` if it may be present.
+    pub fn insert(info: &AccountInfo, poll: &Pubkey, nullifier: &[u8; 32]) -> Result<u64> {
+        let mut data = info.try_borrow_mut_data()?;
+        let mut filter = Self::try_deserialize(&mut &data[..])?;
+        require_keys_eq!(filter.poll, *poll, VotingError::InvalidVoteFilter);
+        let (header, bits) = data.split_at_mut(Self::HEADER_SPACE);
+
+        // Double hashing over the nullifier, itself a hash
+        let len = bits.len() as u64 * 8;
+        let h1 = u64::from_le_bytes(nullifier[..8].try_into().unwrap());
+        let h2 = u64::from_le_bytes(nullifier[8..16].try_into().unwrap()) | 1;
+        let positions: Vec<u64> = (0..filter.hashes as u64)
+            .map(|i| h1.wrapping_add(i.wrapping_mul(h2)) % len)
+            .collect();
+        let is_set = |bits: &[u8], bit: u64| bits[(bit / 8) as usize] & (1 << (bit % 8)) != 0;
+        require!(
+            !positions.iter().all(|&bit| is_set(bits, bit)),
+            VotingError::DuplicateVote
+        );
+        for bit in positions {
+            bits[(bit / 8) as usize] |= 1 << (bit % 8);
+        }
+
+        let index = filter.inserted;
+        filter.inserted = index.safe_add(1)?;
+        filter.try_serialize(&mut &mut header[..])?;
+        Ok(index)
+    }
+}
+
+/// Homomorphic tally of a private-ballot poll, at
+/// `[b"encrypted_tally", poll]`: one ElGamal ciphertext per candidate,
+/// the sum of every ballot's ciphertext for it.
+#[account]
+pub struct EncryptedTally {
+    pub poll: Pubkey,             // 32
+    pub elgamal_pubkey: [u8; 32], // 32, twisted ElGamal key ballots are encrypted to
+    pub tally_authority: Pubkey,  // 32, holder of its secret, who posts the decrypted counts
+    pub tallies: Vec<[u8; 64]>,   // 4 + (candidates * 64)
+    pub bump: u8,                 // 1
+}
+
+impl EncryptedTally {
+    pub fn space(candidate_count: usize) -> usize {
+        8 + 32 + 32 + 32 + 4 + candidate_count * 64 + 1 // discriminator + fields
+    }
+}
+
+/// Marks that a voter cast a private ballot, at
+/// `[b"nullifier", poll, voter]`.
+#[account]
+pub struct BallotNullifier {
+    pub bump: u8, // 1
+}
+
+impl BallotNullifier {
+    pub const SPACE: usize = 8 + 1; // discriminator + fields
+}
+
+/// Checks of private ballots and tallies against zk-token-proof context
+/// state accounts, which the proof program only writes once it has
+/// verified the proof.
+pub struct BallotProofs;
+
+impl BallotProofs {
+    /// The context of a verified `proof_type` proof held in `info`, whose
+    /// context authority must be `authority` if given.
+    fn context<T: Pod>(info: &AccountInfo, proof_type: ProofType, authority: Option<&Pubkey>) -> Result<T> {
+        require_keys_eq!(
+            *info.owner,
+            zk_token_proof_program::id(),
+            VotingError::InvalidBallotProof
+        );
+        let data = info.try_borrow_data()?;
+        let state = ProofContextState::<T>::try_from_bytes(&data)
+            .map_err(|_| VotingError::InvalidBallotProof)?;
+        require!(
+            state.proof_type == proof_type.into()
+                && authority.is_none_or(|authority| state.context_state_authority == *authority),
+            VotingError::InvalidBallotProof
+        );
+        Ok(state.proof_context)
+    }
+
+    /// Checks that `ciphertexts` are well-formed encryptions under `key`
+    /// of 0 or 1 that sum to 1, from proofs `voter` verified.
+    pub fn verify_ballot(
+        ciphertexts: &[ElGamalCiphertext],
+        key: &[u8; 32],
+        voter: &Pubkey,
+        range_proof: &AccountInfo,
+        validity_proofs: &[AccountInfo],
+    ) -> Result<()> {
+        let invalid = || error!(VotingError::InvalidBallotProof);
+        let zero = ElGamalCiphertext([0; 64]);
+        let sum = ciphertexts
+            .iter()
+            .try_fold(zero, |sum, ciphertext| ops::add(&sum, ciphertext))
+            .ok_or_else(invalid)?;
+        let one = ops::add_to(&zero, 1).ok_or_else(invalid)?;
+        let above = ops::subtract_from(&sum, 1).ok_or_else(invalid)?;
+        let below = ops::subtract(&one, &sum).ok_or_else(invalid)?;
+
+        // Every choice is at least 0 and sum - 1 and 1 - sum both are, so
+        // exactly one choice is 1
+        let range: BatchedRangeProofContext =
+            Self::context(range_proof, ProofType::BatchedRangeProofU64, Some(voter))?;
+        let count = ciphertexts.len();
+        let commitments = ciphertexts.iter().chain([&above, &below]);
+        let mut bit_lengths = vec![1; count + 1];
+        bit_lengths.push(u64::BITS as u8 - 1 - count as u8);
+        bit_lengths.resize(range.bit_lengths.len(), 0);
+        require!(
+            commitments
+                .zip(&range.commitments)
+                .all(|(ciphertext, commitment)| ciphertext.0[..32] == commitment.0)
+                && range.bit_lengths[..] == bit_lengths[..],
+            VotingError::InvalidBallotProof
+        );
+
+        // Each ciphertext's decrypt handle matches its commitment
+        require!(
+            validity_proofs.len() == count.div_ceil(2),
+            VotingError::InvalidBallotProof
+        );
+        for (pair, proof) in ciphertexts.chunks(2).zip(validity_proofs) {
+            let validity: BatchedGroupedCiphertext2HandlesValidityProofContext = Self::context(
+                proof,
+                ProofType::BatchedGroupedCiphertext2HandlesValidity,
+                Some(voter),
+            )?;
+            let hi = pair.get(1).unwrap_or(&pair[0]);
+            require!(
+                validity.destination_pubkey.0 == *key
+                    && validity.auditor_pubkey.0 == *key
+                    && validity.grouped_ciphertext_lo.0[..64] == pair[0].0
+                    && validity.grouped_ciphertext_hi.0[..64] == hi.0,
+                VotingError::InvalidBallotProof
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks that `sum` decrypts to `count` under `key`, from a zero-balance
+    /// proof that `sum - count` encrypts zero.
+    pub fn verify_count(sum: &ElGamalCiphertext, count: u64, key: &[u8; 32], proof: &AccountInfo) -> Result<()> {
+        let zero_balance: ZeroBalanceProofContext =
+            Self::context(proof, ProofType::ZeroBalance, None)?;
+        let remainder = ops::subtract_from(sum, count).ok_or(VotingError::InvalidBallotProof)?;
+        require!(
+            zero_balance.pubkey.0 == *key && zero_balance.ciphertext == remainder,
+            VotingError::InvalidBallotProof
+        );
+        Ok(())
+    }
+}
+
+/// A ballot encrypted to its poll's `ballot_key`, at
+/// `[b"encrypted_ballot", poll, voter]`, until `tally_encrypted_votes`
+/// counts and closes it.
+#[account]
+pub struct EncryptedBallot {
+    pub poll: Pubkey,             // 32
+    pub voter: Pubkey,            // 32
+    pub ephemeral_key: [u8; 32],  // 32, r·G for the voter's random scalar r
+    pub masked_choice: u8,        // 1, candidate index XOR the first byte of the mask
+    pub bump: u8,                 // 1
+}
+
+impl EncryptedBallot {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 1 + 1; // discriminator + fields
+
+    /// Mask for `voter`'s ballot in `poll`, given the shared point
+    /// `r·ballot_key` (= `secret·ephemeral_key`). Binding the poll and voter
+    /// means a copied ballot decrypts to something else.
+    pub fn mask(shared: &[u8; 32], poll: &Pubkey, voter: &Pubkey) -> [u8; 32] {
+        hashv(&[b"encrypted_ballot", shared, poll.as_ref(), voter.as_ref()]).to_bytes()
+    }
+
+    /// The candidate index this ballot encrypts under the revealed `secret`,
+    /// or `None` if it isn't one of `candidate_count` candidates.
+    pub fn decrypt(&self, secret: &[u8; 32], candidate_count: usize) -> Option<u8> {
+        let shared = multiply_ristretto(&PodScalar(*secret), &PodRistrettoPoint(self.ephemeral_key))?;
+        let index = self.masked_choice ^ Self::mask(&shared.0, &self.poll, &self.voter)[0];
+        ((index as usize) < candidate_count).then_some(index)
+    }
+}
+
+/// A write-in candidate, at `[b"write_in", poll, name_hash(name)]`, mapping
+/// its name to its entry in the poll's candidates.
+#[account]
+pub struct WriteInCandidate {
+    pub poll: Pubkey,         // 32
+    pub name: String,         // 4 + HARD_MAX_CANDIDATE_NAME_LEN
+    pub candidate_index: u8,  // 1
+    pub bump: u8,             // 1
+}
+
+impl WriteInCandidate {
+    pub const SPACE: usize = 8 + 32 + 4 + HARD_MAX_CANDIDATE_NAME_LEN as usize + 1 + 1; // discriminator + fields
+
+    /// `sha256(lowercase(trim(name)))`, so spellings differing only in case
+    /// or surrounding whitespace share one candidate.
+    pub fn name_hash(name: &str) -> [u8; 32] {
+        hashv(&[name.trim().to_ascii_lowercase().as_bytes()]).to_bytes()
+    }
+}
+
+/// A hashed voter roll identity, at `[b"roll", poll, identity_hash]`.
+#[account]
+pub struct RollEntry {
+    pub poll: Pubkey,            // 32
+    pub identity_hash: [u8; 32], // 32
+    pub bump: u8,                // 1
+    pub tier: u8,                // 1, voter tier on polls with tier_weights
+}
+
+impl RollEntry {
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 1; // discriminator + fields
+
+    /// `sha256(poll || voter || salt)`. Binding the poll means the same voter
+    /// and salt can't be correlated across polls.
+    pub fn identity_hash(poll: &Pubkey, voter: &Pubkey, salt: &[u8; 32]) -> [u8; 32] {
+        hashv(&[poll.as_ref(), voter.as_ref(), salt]).to_bytes()
+    }
+}
+
+/// A voter's proven voter roll membership, at `[b"member", poll, voter]`.
+#[account]
+pub struct RollMembership {
+    pub poll: Pubkey,  // 32
+    pub voter: Pubkey, // 32
+    pub bump: u8,      // 1
+    pub tier: u8,      // 1, copied from the voter's roll entry
+}
+
+impl RollMembership {
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 1; // discriminator + fields
+}
+
+/// Co-admin permissions on one poll, at `[b"role", poll, grantee]`, or an
+/// organization member's role on all of its polls, at
+/// `[b"role", organization, grantee]` with the organization as `poll`.
+#[account]
+pub struct PollRole {
+    pub poll: Pubkey,     // 32
+    pub grantee: Pubkey,  // 32
+    pub permissions: u8,  // 1, PERMISSION_* bits
+    pub bump: u8,         // 1
+}
+
+impl PollRole {
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 1; // discriminator + fields
+}
+
+/// A team that owns polls, at `[b"org", owner, name]`. Members hold
+/// `PollRole`s at `[b"role", organization, member]`.
+#[account]
+pub struct Organization {
+    pub owner: Pubkey, // 32
+    pub name: String,  // 4 + MAX_NAME_LEN
+    pub bump: u8,      // 1
+}
+
+impl Organization {
+    /// Names are PDA seeds, so they fit in one.
+    pub const MAX_NAME_LEN: usize = 32;
+    pub const SPACE: usize = 8 + 32 + 4 + Self::MAX_NAME_LEN + 1; // discriminator + fields
+}
+
+/// Live vote counter for a single candidate of a parallel-tally poll, or
+/// for one shard of it.
+#[account]
+pub struct CandidateTally {
+    pub poll: Pubkey,         // 32
+    pub candidate_index: u8,  // 1
+    pub votes: u64,           // 8
+    pub bump: u8,             // 1
+    pub weight: u64,          // 8
+    pub shard: u8,            // 1
+}
+
+impl CandidateTally {
+    pub const SPACE: usize = 8 + 32 + 1 + 8 + 1 + 8 + 1; // discriminator + fields
+
+    /// Seed distinguishing `shard`'s counter. Shard 0 adds none, so it has
+    /// the address of an unsharded counter.
+    pub fn shard_seed(shard: u8) -> Vec<u8> {
+        if shard == 0 {
+            Vec::new()
+        } else {
+            vec![shard]
+        }
+    }
+}
+
+/// Progress of a paginated recount of a poll's vote records.
+#[account]
+pub struct TallyState {
+    pub poll: Pubkey,         // 32
+    pub votes: Vec<u64>,      // 4 + (candidate_count * 8), direct ballots per candidate
+    pub weights: Vec<u64>,    // 4 + (candidate_count * 8)
+    pub processed: u64,       // 8, records seen, including split ballots
+    pub last_voter: Pubkey,   // 32, cursor; pages continue above it
+    pub checkpoint: [u8; 32], // 32, running hash of processed records
+    pub bump: u8,             // 1
+    pub cranker: Pubkey,      // 32, who began the recount and signs its pages
+}
+
+impl TallyState {
+    pub fn space(candidate_count: usize) -> usize {
+        8 + 32 + 2 * (4 + candidate_count * 8) + 8 + 32 + 32 + 1 + 32
+    }
+
+    /// `sha256(checkpoint || voter || candidate_index || weight as u64 LE)`,
+    /// starting from 32 zero bytes.
+    pub fn next_checkpoint(
+        checkpoint: &[u8; 32],
+        voter: &Pubkey,
+        candidate_index: u8,
+        weight: u64,
+    ) -> [u8; 32] {
+        hashv(&[
+            checkpoint,
+            voter.as_ref(),
+            &[candidate_index],
+            &weight.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
+}
+
+/// Tokens a voter locked behind a conviction poll ballot, at
+/// `[b"conviction", poll, voter]`, held in its associated token account.
+#[account]
+pub struct ConvictionLock {
+    pub poll: Pubkey,   // 32
+    pub voter: Pubkey,  // 32
+    pub vault: Pubkey,  // 32
+    pub amount: u64,    // 8, tokens received
+    pub tier: u8,       // 1
+    pub weight: u64,    // 8, amount << tier, fixed at lock time
+    pub unlock_at: i64, // 8, unix time the lock period ends
+    pub bump: u8,       // 1
+}
+
+impl ConvictionLock {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 8 + 1 + 8 + 8 + 1; // discriminator + fields
+}
+
+/// Lamports set aside to pay voters' vote record rent, at
+/// `[b"rent_sponsor", poll]`.
+#[account]
+pub struct RentSponsor {
+    pub poll: Pubkey,   // 32
+    pub sponsored: u64, // 8, vote records paid for
+    pub bump: u8,       // 1
+}
+
+impl RentSponsor {
+    pub const SPACE: usize = 8 + 32 + 8 + 1; // discriminator + fields
+}
+
+/// Lamports set aside to reward whoever cranks `auto_finalize`.
+#[account]
+pub struct FeeVault {
+    pub poll: Pubkey, // 32
+    pub bump: u8,     // 1
+}
+
+impl FeeVault {
+    pub const SPACE: usize = 8 + 32 + 1; // discriminator + fields
+}
+
+/// Progress of publishing a finalized poll's attendance.
+#[account]
+pub struct AttendanceRoll {
+    pub poll: Pubkey,       // 32
+    pub published: u64,     // 8, voters published so far
+    pub pages: u32,         // 4
+    pub last_voter: Pubkey, // 32, cursor; pages continue above it
+    pub bump: u8,           // 1
+}
+
+impl AttendanceRoll {
+    pub const SPACE: usize = 8 + 32 + 8 + 4 + 32 + 1; // discriminator + fields
+}
+
+/// How a delegator's vote is split across delegates.
+#[account]
+pub struct Delegation {
+    pub delegator: Pubkey,          // 32
+    pub shares: Vec<DelegateShare>, // 4 + (MAX_DELEGATE_SHARES * DelegateShare::SIZE)
+    pub bump: u8,                   // 1
+}
+
+impl Delegation {
+    pub const SPACE: usize = 8 + 32 + 4 + MAX_DELEGATE_SHARES * DelegateShare::SIZE + 1;
+
+    /// The share (in basis points) assigned to `delegate`, if any.
+    pub fn share_of(&self, delegate: &Pubkey) -> Option<u16> {
+        self.shares
+            .iter()
+            .find(|share| &share.delegate == delegate)
+            .map(|share| share.bps)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DelegateShare {
+    pub delegate: Pubkey, // 32
+    pub bps: u16,         // 2
+}
+
+impl DelegateShare {
+    pub const SIZE: usize = 32 + 2;
+}
+
+/// A delegate's use of one delegator's share in one poll.
+#[account]
+pub struct DelegatedVote {
+    pub poll_id: u64,         // 8
+    pub delegator: Pubkey,    // 32
+    pub delegate: Pubkey,     // 32
+    pub candidate_index: u8,  // 1
+    pub weight: u64,          // 8
+    pub bump: u8,             // 1
+}
+
+impl DelegatedVote {
+    pub const SPACE: usize = 8 + 8 + 32 + 32 + 1 + 8 + 1; // discriminator + fields
+}
+
+/// A delegator's proxy for one poll, at `[b"proxy", poll, delegator]`.
+#[account]
+pub struct PollProxy {
+    pub poll: Pubkey,      // 32
+    pub delegator: Pubkey, // 32
+    pub proxy: Pubkey,     // 32
+    pub voted: bool,       // 1
+    pub bump: u8,          // 1
+}
+
+impl PollProxy {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 1 + 1; // discriminator + fields
+}
+
+/// Where a candidate's prize is paid if it wins, at
+/// `[b"payout", poll, candidate_index]`.
+#[account]
+pub struct CandidatePayout {
+    pub poll: Pubkey,         // 32
+    pub candidate_index: u8,  // 1
+    pub payout: Pubkey,       // 32
+    pub bump: u8,             // 1
+}
+
+impl CandidatePayout {
+    pub const SPACE: usize = 8 + 32 + 1 + 32 + 1; // discriminator + fields
+}
+
+/// A poll's prize escrow, at `[b"prize", poll]`. Lamport prizes are held as
+/// the account's lamports above rent; token prizes in `vault`, the pool's
+/// associated token account for `mint`.
+#[account]
+pub struct PrizePool {
+    pub poll: Pubkey,   // 32
+    pub mint: Pubkey,   // 32, Pubkey::default() for lamports
+    pub vault: Pubkey,  // 32, Pubkey::default() for lamports
+    pub funded: u64,    // 8, total received, net of transfer fees
+    pub bump: u8,       // 1
+}
+
+impl PrizePool {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 8 + 1; // discriminator + fields
+}
+
+/// A prediction poll's stakes, at `[b"prediction", poll]`, held like a
+/// `PrizePool`, and the outcome they are settled against.
+#[account]
+pub struct PredictionPool {
+    pub poll: Pubkey,        // 32
+    pub mint: Pubkey,        // 32, Pubkey::default() for lamports
+    pub vault: Pubkey,       // 32, Pubkey::default() for lamports
+    pub staked: u64,         // 8, received, net of transfer fees
+    pub outcome: Option<u8>, // 2, set by resolve_outcome
+    pub bump: u8,            // 1
+}
+
+impl PredictionPool {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 8 + 2 + 1; // discriminator + fields
+}
+
+/// A quadratic funding poll's escrow, at `[b"funding", poll]`, held like a
+/// `PrizePool`: the matching pool plus every contribution, tracked per
+/// project (candidate).
+#[account]
+pub struct FundingRound {
+    pub poll: Pubkey,                  // 32
+    pub mint: Pubkey,                  // 32, Pubkey::default() for lamports
+    pub vault: Pubkey,                 // 32, Pubkey::default() for lamports
+    pub matching_pool: u64,            // 8, received, net of transfer fees
+    pub projects: Vec<ProjectFunding>, // 4 + candidates * ProjectFunding::SIZE
+    pub matched: bool,                 // 1, matches computed by finalize_funding_round
+    pub bump: u8,                      // 1
+}
+
+impl FundingRound {
+    pub fn space(candidate_count: usize) -> usize {
+        8 + 32 + 32 + 32 + 8 + 4 + candidate_count * ProjectFunding::SIZE + 1 + 1
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug)]
+pub struct ProjectFunding {
+    pub contributors: u32,
+    pub contributed: u64,
+    /// Σ √(each contributor's total), in 16.16 fixed point.
+    pub sqrt_sum: u128,
+    pub matched: u64,
+    pub claimed: bool,
+}
+
+impl ProjectFunding {
+    pub const SIZE: usize = 4 + 8 + 16 + 8 + 1;
+
+    /// √`amount` in 16.16 fixed point.
+    pub fn root(amount: u64) -> u128 {
+        ((amount as u128) << 32).isqrt()
+    }
+
+    /// The unscaled quadratic funding match, `(Σ √c)² − Σ c`.
+    pub fn ideal_match(&self) -> u128 {
+        (self.sqrt_sum.saturating_mul(self.sqrt_sum) >> 32)
+            .saturating_sub(self.contributed as u128)
+    }
+}
+
+/// One contributor's running total to one project, at
+/// `[b"contribution", poll, contributor, candidate_index]`.
+#[account]
+pub struct Contribution {
+    pub poll: Pubkey,        // 32
+    pub contributor: Pubkey, // 32
+    pub candidate_index: u8, // 1
+    pub amount: u64,         // 8, received, net of transfer fees
+    pub bump: u8,            // 1
+}
+
+impl Contribution {
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 8 + 1; // discriminator + fields
+}
+
+/// A poll's voter reward escrow, at `[b"reward", poll]`, held like a
+/// `PrizePool`. Paid out per vote record by `claim_reward`.
+#[account]
+pub struct RewardVault {
+    pub poll: Pubkey,     // 32
+    pub mint: Pubkey,     // 32, Pubkey::default() for lamports
+    pub vault: Pubkey,    // 32, Pubkey::default() for lamports
+    pub funded: u64,      // 8, total received, net of transfer fees
+    pub per_voter: u64,   // 8, fixed reward per voter; 0 splits the vault equally
+    pub share: u64,       // 8, reward per voter, fixed at the first claim
+    pub claims: u64,      // 8
+    pub bump: u8,         // 1
+}
+
+impl RewardVault {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 1; // discriminator + fields
+}
+
+/// What's left of an archived poll, at `[b"result", poll]`. Tallies past
+/// `candidate_count` are zero.
+#[account]
+pub struct PollResult {
+    pub poll: Pubkey,             // 32, the closed poll's address
+    pub poll_id: u64,             // 8
+    pub namespace: Pubkey,        // 32
+    pub admin: Pubkey,            // 32
+    pub prev_poll: Pubkey,        // 32, the poll's link in the poll list
+    pub status: PollStatus,       // 1, Finalized or QuorumNotMet
+    pub winner: Option<u8>,       // 2
+    pub candidate_count: u8,      // 1
+    pub tallies: [u64; MAX_ARCHIVED_CANDIDATES], // 8 * MAX_ARCHIVED_CANDIDATES, ballots per candidate
+    pub total_votes: u64,         // 8
+    pub total_weight: u64,        // 8
+    pub poll_hash: [u8; 32],      // 32, SHA-256 of the poll's account data when archived
+    pub archived_at: i64,         // 8
+    pub certified_by: Pubkey,     // 32, the poll's certification, Pubkey::default() if uncertified
+    pub certified_at: i64,        // 8
+    pub result_hash: [u8; 32],    // 32
+    pub bump: u8,                 // 1
+    pub canonical_result_hash: [u8; 32], // 32, the poll's compute_result_hash
+}
+
+impl PollResult {
+    pub const SPACE: usize = 8 + 32 + 8 + 32 + 32 + 32 + 1 + 2 + 1 + 8 * MAX_ARCHIVED_CANDIDATES
+        + 8 + 8 + 32 + 8 + 32 + 8 + 32 + 1 + 32; // discriminator + fields
+
+    /// Fills in the result of `poll`, which is about to be closed.
+    pub fn archive(&mut self, poll: &Account<Poll>, bump: u8) -> Result<()> {
+        self.poll = poll.key();
+        self.poll_id = poll.poll_id;
+        self.namespace = poll.namespace;
+        self.admin = poll.admin;
+        self.prev_poll = poll.prev_poll;
+        self.status = poll.status;
+        self.winner = poll.winner();
+        self.candidate_count = poll.candidates.len() as u8;
+        for (tally, candidate) in self.tallies.iter_mut().zip(&poll.candidates) {
+            *tally = candidate.votes;
+        }
+        self.total_votes = poll.total_votes;
+        self.total_weight = poll.total_weight;
+        self.poll_hash = hashv(&[&poll.to_account_info().data.borrow()]).to_bytes();
+        self.certified_by = poll.certified_by;
+        self.certified_at = poll.certified_at;
+        self.result_hash = poll.result_hash;
+        self.canonical_result_hash = poll.canonical_result_hash;
+        self.archived_at = Clock::get()?.unix_timestamp;
+        self.bump = bump;
+        Ok(())
+    }
+}
+
+/// A voter's open challenge to a sealed result, at
+/// `[b"challenge", poll, challenger]`. Holds the challenge deposit above
+/// rent until `resolve_challenge`.
+#[account]
+pub struct Challenge {
+    pub poll: Pubkey,       // 32
+    pub challenger: Pubkey, // 32
+    pub reason: String,     // 4 + MAX_CHALLENGE_REASON_LEN
+    pub deposit: u64,       // 8, lamports
+    pub created_at: i64,    // 8
+    pub bump: u8,           // 1
+}
+
+impl Challenge {
+    pub const SPACE: usize = 8 + 32 + 32 + 4 + MAX_CHALLENGE_REASON_LEN + 8 + 8 + 1; // discriminator + fields
+}
+
+/// One page of a poll's append-only audit log, at
+/// `[b"audit_log", poll, page]` with `page` as little-endian `u32`, holding
+/// up to `AUDIT_LOG_PAGE_ENTRIES` entries in order.
+#[account]
+pub struct AuditLog {
+    pub poll: Pubkey,              // 32
+    pub page: u32,                 // 4
+    pub entries: Vec<AuditEntry>,  // 4 + AUDIT_LOG_PAGE_ENTRIES * AuditEntry::SIZE
+    pub bump: u8,                  // 1
+}
 
-        let poll = &mut ctx.accounts.poll;
-        poll.admin = ctx.accounts.admin.key();
-        poll.poll_id = poll_id;
-        poll.title = title;
-        poll.candidates = candidates
-            .iter()
-            .map(|name| Candidate {
-                name: name.clone(),
-                votes: 0,
-            })
-            .collect();
-        poll.total_votes = 0;
-        poll.is_active = true;
-        poll.bump = ctx.bumps.poll;
+impl AuditLog {
+    pub const SPACE: usize = 8 + 32 + 4 + 4 + AUDIT_LOG_PAGE_ENTRIES * AuditEntry::SIZE + 1; // discriminator + fields
+}
 
-        msg!("Poll '{}' created with {} candidates", poll.title, poll.candidates.len());
-        Ok(())
+/// Token accounts for moving an escrow's SPL or Token-2022 balance: its
+/// `vault` (the escrow's associated token account for `mint`) and the
+/// funder's or recipient's `token` account. All `None` for lamport escrows.
+pub struct EscrowTokens<'a, 'info> {
+    pub mint: Option<&'a InterfaceAccount<'info, Mint>>,
+    pub vault: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    pub token: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Option<&'a Interface<'info, TokenInterface>>,
+}
+
+impl<'a, 'info> EscrowTokens<'a, 'info> {
+    /// Moves `amount` from `funder` into `escrow`, as tokens into the vault
+    /// when the token accounts are passed or else as lamports. Returns the
+    /// `(mint, vault)` funded, both `Pubkey::default()` for lamports, and
+    /// the amount received net of any Token-2022 transfer fee.
+    pub fn deposit(
+        &self,
+        escrow: &AccountInfo<'info>,
+        funder: AccountInfo<'info>,
+        system_program: AccountInfo<'info>,
+        amount: u64,
+    ) -> Result<(Pubkey, Pubkey, u64)> {
+        match (self.mint, self.vault, self.token, self.token_program) {
+            (Some(mint), Some(vault), Some(from), Some(token_program)) => {
+                require!(
+                    vault.owner == escrow.key() && vault.mint == mint.key(),
+                    VotingError::InvalidEscrowAccounts
+                );
+                token_interface::transfer_checked(
+                    CpiContext::new(
+                        token_program.to_account_info(),
+                        token_interface::TransferChecked {
+                            from: from.to_account_info(),
+                            mint: mint.to_account_info(),
+                            to: vault.to_account_info(),
+                            authority: funder,
+                        },
+                    ),
+                    amount,
+                    mint.decimals,
+                )?;
+                Ok((mint.key(), vault.key(), amount - Self::transfer_fee(mint, amount)?))
+            }
+            (None, None, None, _) => {
+                system_program::transfer(
+                    CpiContext::new(
+                        system_program,
+                        system_program::Transfer {
+                            from: funder,
+                            to: escrow.clone(),
+                        },
+                    ),
+                    amount,
+                )?;
+                Ok((Pubkey::default(), Pubkey::default(), amount))
+            }
+            _ => err!(VotingError::InvalidEscrowAccounts),
+        }
     }
 
-    /// Casts a vote for a candidate in a poll. Each wallet can only vote once per poll.
-    pub fn vote(ctx: Context<Vote>, poll_id: u64, candidate_index: u8) -> Result<()> {
-        let poll = &mut ctx.accounts.poll;
+    /// Token-2022 transfer fee charged on moving `amount` of `mint` in the
+    /// current epoch; 0 for mints without the transfer-fee extension.
+    pub fn transfer_fee(mint: &InterfaceAccount<'info, Mint>, amount: u64) -> Result<u64> {
+        let mint_info = mint.to_account_info();
+        if mint_info.owner != &spl_token_2022::ID {
+            return Ok(0);
+        }
+        let mint_data = mint_info.try_borrow_data()?;
+        let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)
+            .map_err(|_| VotingError::InvalidEscrowAccounts)?;
+        let Ok(config) = mint_state.get_extension::<TransferFeeConfig>() else {
+            return Ok(0);
+        };
+        config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or_else(|| error!(VotingError::InvalidEscrowAccounts))
+    }
+
+    /// What `escrow` holds of its asset `(escrow_mint, escrow_vault)`:
+    /// lamports above rent, or the vault's token balance.
+    pub fn held(
+        &self,
+        escrow: &AccountInfo<'info>,
+        (escrow_mint, escrow_vault): (Pubkey, Pubkey),
+    ) -> Result<u64> {
+        if escrow_mint == Pubkey::default() {
+            return Ok(escrow
+                .lamports()
+                .saturating_sub(Rent::get()?.minimum_balance(escrow.data_len())));
+        }
+        match self.vault {
+            Some(vault) if vault.key() == escrow_vault => Ok(vault.amount),
+            _ => err!(VotingError::InvalidEscrowAccounts),
+        }
+    }
+
+    /// Pays `amount` (everything held when `None`) out of `escrow`, a PDA
+    /// signing with `seeds` whose asset is `(escrow_mint, escrow_vault)`, to
+    /// `recipient` for lamports or to the `token` account for tokens, and
+    /// returns what was paid. Lamport escrows keep their rent; callers that
+    /// are done with the escrow close it.
+    pub fn withdraw(
+        &self,
+        escrow: AccountInfo<'info>,
+        (escrow_mint, escrow_vault): (Pubkey, Pubkey),
+        seeds: &[&[u8]],
+        recipient: AccountInfo<'info>,
+        amount: Option<u64>,
+    ) -> Result<u64> {
+        let held = self.held(&escrow, (escrow_mint, escrow_vault))?;
+        let amount = amount.unwrap_or(held).min(held);
+        if escrow_mint == Pubkey::default() {
+            move_lamports(&escrow, &recipient, amount)?;
+            return Ok(amount);
+        }
 
-        require!(poll.is_active, VotingError::PollClosed);
+        let (Some(mint), Some(vault), Some(to), Some(token_program)) =
+            (self.mint, self.vault, self.token, self.token_program)
+        else {
+            return err!(VotingError::InvalidEscrowAccounts);
+        };
         require!(
-            (candidate_index as usize) < poll.candidates.len(),
-            VotingError::InvalidCandidate
+            mint.key() == escrow_mint && vault.key() == escrow_vault,
+            VotingError::InvalidEscrowAccounts
         );
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                token_interface::TransferChecked {
+                    from: vault.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: escrow,
+                },
+                &[seeds],
+            ),
+            amount,
+            mint.decimals,
+        )?;
+        Ok(amount)
+    }
+}
 
-        // Increment vote count for the selected candidate
-        poll.candidates[candidate_index as usize].votes += 1;
-        poll.total_votes += 1;
+/// Record of a downstream program acting on a poll's finalized result.
+#[account]
+pub struct ConsumptionReceipt {
+    pub poll: Pubkey,             // 32
+    pub consumer_program: Pubkey, // 32
+    pub first_slot: u64,          // 8
+    pub last_slot: u64,           // 8
+    pub consumptions: u64,        // 8
+    pub bump: u8,                 // 1
+}
 
-        // Record the voter's choice
-        let vote_record = &mut ctx.accounts.vote_record;
-        vote_record.voter = ctx.accounts.voter.key();
-        vote_record.poll_id = poll_id;
-        vote_record.candidate_index = candidate_index;
-        vote_record.bump = ctx.bumps.vote_record;
+impl ConsumptionReceipt {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 1; // discriminator + fields
+}
 
-        msg!(
-            "Vote cast by {} for candidate '{}' in poll '{}'",
-            ctx.accounts.voter.key(),
-            poll.candidates[candidate_index as usize].name,
-            poll.title
-        );
-        Ok(())
+// ─── Events ──────────────────────────────────────────────────────────────────
+
+/// Overflow-checked arithmetic for tallies, counters and escrow balances,
+/// failing with `ArithmeticOverflow` instead of wrapping or panicking.
+pub trait CheckedMath: Sized {
+    fn safe_add(self, rhs: Self) -> Result<Self>;
+    fn safe_sub(self, rhs: Self) -> Result<Self>;
+    fn safe_mul(self, rhs: Self) -> Result<Self>;
+}
+
+macro_rules! impl_checked_math {
+    ($($ty:ty),*) => {$(
+        impl CheckedMath for $ty {
+            fn safe_add(self, rhs: Self) -> Result<Self> {
+                self.checked_add(rhs).ok_or_else(|| error!(VotingError::ArithmeticOverflow))
+            }
+
+            fn safe_sub(self, rhs: Self) -> Result<Self> {
+                self.checked_sub(rhs).ok_or_else(|| error!(VotingError::ArithmeticOverflow))
+            }
+
+            fn safe_mul(self, rhs: Self) -> Result<Self> {
+                self.checked_mul(rhs).ok_or_else(|| error!(VotingError::ArithmeticOverflow))
+            }
+        }
+    )*};
+}
+
+impl_checked_math!(u8, u16, u32, u64);
+
+/// `bps` basis points of `amount`. Computed in u128, since stake weights
+/// times basis points can exceed u64 even though the share can't.
+fn bps_share(amount: u64, bps: u16) -> u64 {
+    (amount as u128 * bps.min(BPS_DENOMINATOR) as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+/// Canonical hash of a poll's result, which third parties can reproduce
+/// byte-for-byte from the published tallies: SHA-256 of
+/// `"solana_voting:result:v1" || namespace || poll_id (u64 LE) || status ||
+/// candidate count (u32 LE)`, then per candidate `name length (u32 LE) ||
+/// name (UTF-8) || votes (u64 LE) || weight (u64 LE) || disqualified (u8)`.
+/// `status` is the `PollStatus` variant's index as one byte. This is the borsh encoding of
+/// those values, so it stays stable as the poll account grows.
+pub fn compute_result_hash(
+    namespace: &Pubkey,
+    poll_id: u64,
+    status: PollStatus,
+    candidates: &[Candidate],
+) -> [u8; 32] {
+    let mut encoded = Vec::new();
+    encoded.extend_from_slice(namespace.as_ref());
+    encoded.extend_from_slice(&poll_id.to_le_bytes());
+    encoded.push(status as u8);
+    encoded.extend_from_slice(&(candidates.len() as u32).to_le_bytes());
+    for candidate in candidates {
+        encoded.extend_from_slice(&(candidate.name.len() as u32).to_le_bytes());
+        encoded.extend_from_slice(candidate.name.as_bytes());
+        encoded.extend_from_slice(&candidate.votes.to_le_bytes());
+        encoded.extend_from_slice(&candidate.weight.to_le_bytes());
+        encoded.push(candidate.disqualified as u8);
     }
+    hashv(&[b"solana_voting:result:v1", &encoded]).to_bytes()
+}
 
-    /// Closes a poll so no more votes can be cast. Only the admin can close.
-    pub fn close_poll(ctx: Context<ClosePoll>, _poll_id: u64) -> Result<()> {
-        let poll = &mut ctx.accounts.poll;
-        require!(
-            poll.admin == ctx.accounts.admin.key(),
-            VotingError::Unauthorized
-        );
-        poll.is_active = false;
-        msg!("Poll '{}' has been closed", poll.title);
-        Ok(())
+/// Moves `amount` lamports between two accounts, the first owned by this
+/// program.
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    let remaining = from.lamports().safe_sub(amount)?;
+    **from.try_borrow_mut_lamports()? = remaining;
+    let received = to.lamports().safe_add(amount)?;
+    **to.try_borrow_mut_lamports()? = received;
+    Ok(())
+}
+
+/// Closes an account owned by this program, sending its lamports to
+/// `destination`, as Anchor's `close` constraint does.
+fn close_account(info: &AccountInfo, destination: &AccountInfo) -> Result<()> {
+    move_lamports(info, destination, info.lamports())?;
+    info.assign(&system_program::ID);
+    info.realloc(0, false)?;
+    Ok(())
+}
+
+fn poll_results(poll: &Account<Poll>) -> PollResults {
+    PollResults {
+        poll: poll.key(),
+        status: poll.status,
+        tallies: poll.candidates.iter().map(|c| c.votes).collect(),
+        weights: poll.candidates.iter().map(|c| c.weight).collect(),
+        disqualified: poll.candidates.iter().map(|c| c.disqualified).collect(),
+        total_votes: poll.total_votes,
+        total_weight: poll.total_weight,
+        quorum_met: poll.quorum_met(),
+        winner: if poll.is_sealed() { poll.winner() } else { None },
+        tier_tallies: tier_tallies(poll),
     }
 }
 
-// ─── Account Contexts ────────────────────────────────────────────────────────
+fn poll_finalized_event(poll: &Account<Poll>) -> Result<PollFinalized> {
+    Ok(PollFinalized {
+        poll: poll.key(),
+        poll_id: poll.poll_id,
+        winner: poll.winner(),
+        quorum_met: poll.status == PollStatus::Finalized,
+        tallies: poll.candidates.iter().map(|c| c.votes).collect(),
+        weights: poll.candidates.iter().map(|c| c.weight).collect(),
+        total_votes: poll.total_votes,
+        total_weight: poll.total_weight,
+        tier_tallies: tier_tallies(poll),
+        canonical_result_hash: poll.canonical_result_hash,
+        timestamp: Clock::get()?.unix_timestamp,
+    })
+}
 
-#[derive(Accounts)]
-#[instruction(poll_id: u64, title: String, candidates: Vec<String>)]
-pub struct CreatePoll<'info> {
-    #[account(
-        init,
-        payer = admin,
-        space = Poll::space(&candidates),
-        seeds = [b"poll", poll_id.to_le_bytes().as_ref()],
-        bump
-    )]
-    pub poll: Account<'info, Poll>,
+fn tier_tallies(poll: &Poll) -> Vec<Vec<u64>> {
+    (0..poll.tier_weights.len() as u8)
+        .map(|tier| poll.tier_tallies(tier))
+        .collect()
+}
 
-    #[account(mut)]
-    pub admin: Signer<'info>,
+fn poll_created_event(poll: &Account<Poll>) -> Result<PollCreated> {
+    Ok(PollCreated {
+        poll: poll.key(),
+        poll_id: poll.poll_id,
+        admin: poll.admin,
+        namespace: poll.namespace,
+        title: poll.title.clone(),
+        candidate_count: poll.candidates.len() as u8,
+        parallel_tallies: poll.parallel_tallies,
+        clock_source: poll.clock_source,
+        start_ts: poll.start_ts,
+        end_ts: poll.end_ts,
+        approval_threshold: poll.approval_threshold,
+        category: poll.category,
+        tags: poll.tags.clone(),
+        timestamp: Clock::get()?.unix_timestamp,
+    })
+}
 
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct PollCreated {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub admin: Pubkey,
+    pub namespace: Pubkey,
+    pub title: String,
+    pub candidate_count: u8,
+    pub parallel_tallies: bool,
+    pub clock_source: ClockSource,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub approval_threshold: u8,
+    pub category: u16,
+    pub tags: Vec<String>,
+    pub timestamp: i64,
 }
 
-#[derive(Accounts)]
-#[instruction(poll_id: u64, candidate_index: u8)]
-pub struct Vote<'info> {
-    #[account(
-        mut,
-        seeds = [b"poll", poll_id.to_le_bytes().as_ref()],
-        bump = poll.bump,
-    )]
-    pub poll: Account<'info, Poll>,
+#[event]
+pub struct PollTitleUpdated {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub title: String,
+    pub timestamp: i64,
+}
 
-    /// The vote_record PDA ensures each wallet can only vote once per poll.
-    /// If a wallet tries to vote again, account initialization will fail.
-    #[account(
-        init,
-        payer = voter,
-        space = VoteRecord::SPACE,
-        seeds = [b"vote", poll_id.to_le_bytes().as_ref(), voter.key().as_ref()],
-        bump
-    )]
-    pub vote_record: Account<'info, VoteRecord>,
+#[event]
+pub struct VoteCast {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub candidate_index: u8,
+    pub weight: u64,
+    /// The candidate's tally after this vote.
+    pub candidate_votes: u64,
+    /// The poll's total after this vote. Parallel-tally polls only update
+    /// this at aggregation, so it stays 0 while voting is open.
+    pub total_votes: u64,
+    pub timestamp: i64,
+}
 
-    #[account(mut)]
-    pub voter: Signer<'info>,
+#[event]
+pub struct SnapshotBalanceAttested {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub balance: u64,
+    pub snapshot_slot: u64,
+    pub timestamp: i64,
+}
 
-    pub system_program: Program<'info, System>,
+/// A direct ballot moved between candidates by `change_vote`.
+/// Emitted alongside the per-ballot event on `Verbose` polls.
+#[event]
+pub struct BallotDetail {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    /// The signer who cast the ballot (the delegate for delegated shares).
+    pub voter: Pubkey,
+    pub candidate_index: u8,
+    pub weight: u64,
+    pub cohort: VoterCohort,
+    /// The candidate's weight after this ballot.
+    pub candidate_weight: u64,
+    /// The poll's total weight after this ballot. Like `VoteCast::total_votes`
+    /// it stays 0 on parallel-tally polls until aggregation.
+    pub total_weight: u64,
 }
 
-#[derive(Accounts)]
-#[instruction(poll_id: u64)]
-pub struct ClosePoll<'info> {
-    #[account(
-        mut,
-        seeds = [b"poll", poll_id.to_le_bytes().as_ref()],
-        bump = poll.bump,
-    )]
-    pub poll: Account<'info, Poll>,
+/// A ballot was struck from the tallies by `invalidate_vote`.
+#[event]
+pub struct VoteInvalidated {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub candidate_index: u8,
+    pub weight: u64,
+    pub reason: u8,
+    pub timestamp: i64,
+}
 
-    #[account(mut)]
-    pub admin: Signer<'info>,
+#[event]
+pub struct VoteChanged {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub previous_index: u8,
+    pub candidate_index: u8,
+    pub weight: u64,
+    pub slot: u64,
+    pub timestamp: i64,
 }
 
-// ─── Account Data Structures ─────────────────────────────────────────────────
+/// A dry-run ballot from `practice_vote`; no tally state was written.
+#[event]
+pub struct PracticeBallot {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub candidate_index: u8,
+    pub weight: u64,
+    /// Whether the voter already has a real ballot in this poll.
+    pub already_voted: bool,
+    pub timestamp: i64,
+}
 
-#[account]
-pub struct Poll {
-    pub admin: Pubkey,        // 32
-    pub poll_id: u64,         // 8
-    pub title: String,        // 4 + len
-    pub candidates: Vec<Candidate>, // 4 + (len * Candidate::SIZE)
-    pub total_votes: u64,     // 8
-    pub is_active: bool,      // 1
-    pub bump: u8,             // 1
+#[event]
+pub struct DelegatedVoteCast {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub delegator: Pubkey,
+    pub delegate: Pubkey,
+    pub candidate_index: u8,
+    pub weight: u64,
+    /// The candidate's weight after this vote.
+    pub candidate_weight: u64,
+    pub timestamp: i64,
 }
 
-impl Poll {
-    pub fn space(candidates: &[String]) -> usize {
-        8 +                            // discriminator
-        32 +                           // admin pubkey
-        8 +                            // poll_id
-        4 + 100 +                      // title (max 100 chars)
-        4 + (candidates.len() * Candidate::SIZE) + // candidates vec
-        8 +                            // total_votes
-        1 +                            // is_active
-        1 +                            // bump
-        64                             // padding for safety
-    }
+#[event]
+pub struct PollClosed {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub total_votes: u64,
+    pub timestamp: i64,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct Candidate {
-    pub name: String, // 4 + len (max 50)
-    pub votes: u64,   // 8
+#[event]
+pub struct PollReopened {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub reopened_by: Pubkey,
+    pub total_votes: u64,
+    pub timestamp: i64,
 }
 
-impl Candidate {
-    pub const SIZE: usize = 4 + 50 + 8; // string prefix + max name + votes
+#[event]
+pub struct PetitionThresholdReached {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub signatures: u64,
+    pub timestamp: i64,
 }
 
-#[account]
-pub struct VoteRecord {
-    pub voter: Pubkey,        // 32
-    pub poll_id: u64,         // 8
-    pub candidate_index: u8,  // 1
-    pub bump: u8,             // 1
+#[event]
+pub struct StakePlaced {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub candidate_index: u8,
+    pub amount: u64,
+    pub candidate_staked: u64,
+    pub total_staked: u64,
+    pub timestamp: i64,
 }
 
-impl VoteRecord {
-    pub const SPACE: usize = 8 + 32 + 8 + 1 + 1 + 16; // discriminator + fields + padding
+#[event]
+pub struct OutcomeResolved {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub outcome: u8,
+    pub resolver: Pubkey,
+    pub winning_staked: u64,
+    pub total_staked: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WinningsClaimed {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    /// `Pubkey::default()` for lamports.
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ContributionMade {
+    pub poll: Pubkey,
+    pub contributor: Pubkey,
+    pub candidate_index: u8,
+    pub amount: u64,
+    pub project_contributed: u64,
+    pub project_contributors: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FundingRoundFinalized {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub matching_pool: u64,
+    pub matched: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FundingClaimed {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub candidate_index: u8,
+    pub recipient: Pubkey,
+    /// `Pubkey::default()` for lamports.
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConvictionLocked {
+    pub poll: Pubkey,
+    pub voter: Pubkey,
+    pub amount: u64,
+    pub tier: u8,
+    pub weight: u64,
+    pub unlock_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PollFrozen {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub frozen_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PollUnfrozen {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub unfrozen_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DeadlineExtended {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub previous_end_ts: i64,
+    pub new_end_ts: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AttendancePublished {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    /// Pages are numbered from 0 in publication order.
+    pub page: u32,
+    pub voters: Vec<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PollCancelled {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub reason: String,
+    pub timestamp: i64,
+}
+
+/// A ballot appended to a compressed poll's vote tree. `leaf` is
+/// `VoteTree::leaf(poll, voter, candidate_index, weight, slot)` at
+/// `leaf_index`, so indexers can rebuild the tree and voters prove their
+/// ballot against its root.
+#[event]
+pub struct CompressedVoteCast {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub candidate_index: u8,
+    pub weight: u64,
+    pub leaf_index: u64,
+    pub leaf: [u8; 32],
+    pub slot: u64,
+    pub candidate_votes: u64,
+    pub total_votes: u64,
+    pub timestamp: i64,
+}
+
+/// A private ballot added to a poll's encrypted tally. The ciphertexts are
+/// the ballot as cast, one per candidate.
+#[event]
+pub struct PrivateVoteCast {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub ciphertexts: Vec<[u8; 64]>,
+    pub total_votes: u64,
+    pub timestamp: i64,
+}
+
+/// Candidate tallies of a `hide_results_until_close` poll, rebuilt from a
+/// recount whose final `checkpoint` commits to the records counted.
+#[event]
+pub struct TalliesRevealed {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub votes: Vec<u64>,
+    pub checkpoint: [u8; 32],
+    pub timestamp: i64,
+}
+
+/// The decrypted counts of a private-ballot poll, verified against its
+/// encrypted tally.
+#[event]
+pub struct PrivateTallyPosted {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub counts: Vec<u64>,
+    pub timestamp: i64,
+}
+
+/// An encrypted ballot, whose choice stays hidden until the ballot key is
+/// revealed.
+#[event]
+pub struct EncryptedVoteCast {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub sealed_ballots: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BallotKeyRevealed {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub secret: [u8; 32],
+    /// Encrypted ballots still to be tallied.
+    pub sealed_ballots: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EncryptedVotesTallied {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub counted: u32,
+    pub spoiled: u32,
+    /// Encrypted ballots still to be tallied.
+    pub remaining: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RevealBondSlashed {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WriteInAdded {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub candidate_index: u8,
+    pub name: String,
+    pub proposer: Pubkey,
+}
+
+#[event]
+pub struct CandidateWithdrawn {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub candidate_index: u8,
+    pub policy: DisqualificationPolicy,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CandidateDisqualified {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub candidate_index: u8,
+    pub policy: DisqualificationPolicy,
+    pub reason: String,
+    pub timestamp: i64,
+}
+
+/// A prize pool paid out: to the winner's payout address by `claim_prize`
+/// (`winner` set), or back to the admin by `reclaim_prize`.
+#[event]
+pub struct PrizeReleased {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub recipient: Pubkey,
+    /// `Pubkey::default()` for lamports.
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub winner: Option<u8>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VoteReceiptMinted {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    pub tree: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardClaimed {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub voter: Pubkey,
+    /// `Pubkey::default()` for lamports.
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RunoffStarted {
+    pub poll: Pubkey,
+    pub parent_poll: Pubkey,
+    pub round: u8,
+    pub candidates: Vec<String>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PollCloned {
+    pub poll: Pubkey,
+    pub source_poll: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PollActivated {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DraftEdited {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub title: String,
+    pub candidate_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PollFinalized {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    /// `None` when the top candidates are tied or the quorum was missed.
+    pub winner: Option<u8>,
+    pub quorum_met: bool,
+    pub tallies: Vec<u64>,
+    pub weights: Vec<u64>,
+    pub total_votes: u64,
+    pub total_weight: u64,
+    /// Ballots per candidate from each voter tier, on polls with tiers.
+    pub tier_tallies: Vec<Vec<u64>>,
+    /// See `compute_result_hash`.
+    pub canonical_result_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PauseChanged {
+    pub paused: bool,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub authority: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    /// Lamports still withdrawable after this withdrawal.
+    pub remaining: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminTransferProposed {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub admin: Pubkey,
+    /// `Pubkey::default()` when a pending proposal was withdrawn.
+    pub pending_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminTransferred {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ActionApproved {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub approver: Pubkey,
+    pub action: MultisigAction,
+    /// Approvals for `action` including this one.
+    pub approvals: u8,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+/// A co-admin role was granted or changed. `permissions` is 0 on revocation.
+#[event]
+pub struct RoleUpdated {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub grantee: Pubkey,
+    pub permissions: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrganizationCreated {
+    pub organization: Pubkey,
+    pub owner: Pubkey,
+    pub name: String,
+    pub timestamp: i64,
+}
+
+/// An organization member's role was set or changed. `permissions` is 0 on
+/// removal.
+#[event]
+pub struct OrgMemberUpdated {
+    pub organization: Pubkey,
+    pub member: Pubkey,
+    pub permissions: u8,
+    pub timestamp: i64,
+}
+
+/// A poll's operations key was set, or cleared to `Pubkey::default()`.
+#[event]
+pub struct OperationsKeyUpdated {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub operations_key: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ResultCertified {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub certified_by: Pubkey,
+    /// The certified `canonical_result_hash`.
+    pub result_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ResultChallenged {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub challenger: Pubkey,
+    pub reason: String,
+    pub deposit: u64,
+    pub timestamp: i64,
+}
+
+/// `deposit` went back to the challenger if `upheld`, to the treasury if not.
+#[event]
+pub struct ChallengeResolved {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub challenger: Pubkey,
+    pub upheld: bool,
+    pub deposit: u64,
+    pub timestamp: i64,
+}
+
+/// A sealed poll was closed into its `PollResult`. `poll_hash` commits to
+/// the poll's final account data.
+#[event]
+pub struct PollArchived {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub result: Pubkey,
+    pub winner: Option<u8>,
+    pub poll_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+/// `gc_expired_poll` closed `records_closed` vote records of a poll past its
+/// retention period, and archived the poll if `archived`.
+#[event]
+pub struct ExpiredPollCollected {
+    pub poll: Pubkey,
+    pub poll_id: u64,
+    pub records_closed: u32,
+    pub archived: bool,
+    pub timestamp: i64,
+}
+
+/// Compact finalization record from `finalize_minimal`, logged without
+/// self-CPI. Full tallies can be read from the poll account. `winner` is
+/// `None` while results are embargoed.
+#[event]
+pub struct PollSealed {
+    pub poll: Pubkey,
+    pub winner: Option<u8>,
 }
 
 // ─── Error Codes ─────────────────────────────────────────────────────────────
@@ -195,9 +13759,9 @@ impl VoteRecord {
 pub enum VotingError {
     #[msg("Poll must have at least 2 candidates")]
     TooFewCandidates,
-    #[msg("Poll cannot have more than 10 candidates")]
+    #[msg("Poll has more candidates than the program config allows")]
     TooManyCandidates,
-    #[msg("Title must be 100 characters or less")]
+    #[msg("Title is longer than the poll's limit in UTF-8 bytes")]
     TitleTooLong,
     #[msg("This poll is closed")]
     PollClosed,
@@ -205,4 +13769,582 @@ pub enum VotingError {
     InvalidCandidate,
     #[msg("Only the poll admin can perform this action")]
     Unauthorized,
+    #[msg("This poll uses parallel tallies; vote with vote_parallel")]
+    ParallelTalliesEnabled,
+    #[msg("This poll does not use parallel tallies")]
+    ParallelTalliesDisabled,
+    #[msg("Tally accounts are missing, out of order, or belong to another poll")]
+    InvalidTallyAccount,
+    #[msg("Poll must be closed first")]
+    PollNotClosed,
+    #[msg("Only active or closed polls can be cancelled")]
+    PollNotCancellable,
+    #[msg("Parallel tallies must be aggregated before finalizing")]
+    TalliesNotAggregated,
+    #[msg("The poll's end must be after its start and in the future")]
+    InvalidSchedule,
+    #[msg("Voting has not started yet")]
+    VotingNotStarted,
+    #[msg("The voting deadline has passed")]
+    VotingEnded,
+    #[msg("Delegation shares must be positive, unique, exclude yourself and total at most 100%")]
+    InvalidDelegation,
+    #[msg("Too many delegates in one delegation")]
+    TooManyDelegates,
+    #[msg("Signer is not a delegate of this delegator")]
+    NotADelegate,
+    #[msg("The delegator has already voted directly")]
+    DelegatorAlreadyVoted,
+    #[msg("The delegator's ballot has already been fully cast")]
+    DelegationExhausted,
+    #[msg("Poll has not been finalized")]
+    PollNotFinalized,
+    #[msg("The poll's winner is not the expected candidate")]
+    WinnerMismatch,
+    #[msg("Consumer program does not match the calling program")]
+    ConsumerMismatch,
+    #[msg("No admin transfer is pending for this poll")]
+    NoPendingAdminTransfer,
+    #[msg("Signer is not the pending admin of this poll")]
+    NotPendingAdmin,
+    #[msg("Permission bits are empty or unknown")]
+    InvalidPermissions,
+    #[msg("The program config does not permit this action for the signer")]
+    ActionNotPermitted,
+    #[msg("Approvers must be unique, at most 5, and the threshold between 1 and their count")]
+    InvalidMultisig,
+    #[msg("Signer is not an approver of this poll")]
+    NotAnApprover,
+    #[msg("Close approvals need an active poll, finalize approvals a closed one")]
+    ApprovalOutOfOrder,
+    #[msg("Not enough approvals for this action yet")]
+    ApprovalThresholdNotMet,
+    #[msg("This poll does not allow vote changes")]
+    VoteChangesDisabled,
+    #[msg("Only direct, undelegated ballots can be changed")]
+    BallotNotChangeable,
+    #[msg("This vote has been changed the maximum number of times")]
+    VoteChangeLimitReached,
+    #[msg("The ballot is already for this candidate")]
+    VoteUnchanged,
+    #[msg("This poll does not use a voter roll")]
+    VoterRollDisabled,
+    #[msg("Voter has not proven membership of this poll's voter roll")]
+    NotOnVoterRoll,
+    #[msg("Config limits must be non-zero, allow 2+ candidates and stay within the hard caps")]
+    InvalidConfig,
+    #[msg("A candidate name is longer than the poll's limit in UTF-8 bytes")]
+    CandidateNameTooLong,
+    #[msg("The program is paused")]
+    ProgramPaused,
+    #[msg("This poll only accepts content as a hash and URI")]
+    InlineContentDisabled,
+    #[msg("Content text or URI is too long")]
+    ContentTooLong,
+    #[msg("The poll already has votes")]
+    PollHasVotes,
+    #[msg("Cancellation reason is too long")]
+    CancelReasonTooLong,
+    #[msg("Only a draft poll can be activated")]
+    PollNotDraft,
+    #[msg("Only a poll closed with close_poll, before its deadline and aggregation, can be reopened")]
+    PollNotReopenable,
+    #[msg("The poll's reopen window has elapsed")]
+    ReopenWindowElapsed,
+    #[msg("Account is not a vote record of this poll")]
+    InvalidVoteRecord,
+    #[msg("Vote records must be processed once each, in ascending voter order")]
+    TallyOutOfOrder,
+    #[msg("New deadline must be later than the current one and within the poll's maximum extension")]
+    InvalidDeadlineExtension,
+    #[msg("The poll is not active or its deadline has not passed")]
+    PollNotExpired,
+    #[msg("This poll does not publish attendance")]
+    AttendanceNotPublished,
+    #[msg("The poll has no deadline or it has not passed yet")]
+    DeadlineNotReached,
+    #[msg("Reporting threshold cannot exceed 100%")]
+    InvalidReportingThreshold,
+    #[msg("These limits allow polls larger than an account can be allocated")]
+    PollAccountTooLarge,
+    #[msg("A turnout quorum must be at most 100% and needs a voter roll")]
+    InvalidQuorum,
+    #[msg("The poll has a single leader and needs no tie-break")]
+    NotATie,
+    #[msg("Account is not a Switchboard randomness account")]
+    InvalidRandomnessAccount,
+    #[msg("Randomness must be committed in the previous slot and revealed in this one")]
+    RandomnessNotFresh,
+    #[msg("Commit this randomness account with commit_tie_break first")]
+    TieBreakNotCommitted,
+    #[msg("A runoff must keep at least 2 candidates and fewer than the poll has")]
+    InvalidRunoff,
+    #[msg("This runoff uses its first round's voter roll")]
+    VoterRollInherited,
+    #[msg("The poll's results are embargoed until its embargo slot")]
+    ResultsEmbargoed,
+    #[msg("The poll's results have already been published")]
+    ResultsAlreadyPublished,
+    #[msg("This poll does not accept write-in candidates")]
+    WriteInsDisabled,
+    #[msg("Write-in names must be non-empty and within the config's name length")]
+    InvalidWriteIn,
+    #[msg("The write-in matches a listed candidate; vote for it directly")]
+    WriteInMatchesCandidate,
+    #[msg("The poll has reached its maximum number of write-in candidates")]
+    TooManyWriteIns,
+    #[msg("Write-in candidates need direct tallies, not parallel_tallies")]
+    WriteInsNeedDirectTallies,
+    #[msg("This candidate has been disqualified")]
+    CandidateDisqualified,
+    #[msg("Disqualification reason is too long")]
+    DisqualifyReasonTooLong,
+    #[msg("The poll has already been finalized or cancelled")]
+    PollAlreadyFinalized,
+    #[msg("This poll does not accept vote memos")]
+    MemosDisabled,
+    #[msg("Vote memo is too long")]
+    MemoTooLong,
+    #[msg("A batch needs one writable (poll, vote record) pair per candidate index, up to MAX_BATCH_VOTES")]
+    InvalidBatch,
+    #[msg("The preceding instruction must verify the voter's ed25519 signature over this ballot")]
+    InvalidVoteSignature,
+    #[msg("Nonce must be greater than the voter's last used nonce")]
+    StaleNonce,
+    #[msg("Session expiry must be in the future and at most MAX_SESSION_DURATION away")]
+    InvalidSessionExpiry,
+    #[msg("Session has expired")]
+    SessionExpired,
+    #[msg("The proxy has already voted in this poll")]
+    ProxyAlreadyVoted,
+    #[msg("The proxy hasn't voted in this poll")]
+    ProxyNotVoted,
+    #[msg("This poll requires a gateway token from its gatekeeper network")]
+    GatewayTokenRequired,
+    #[msg("Gateway token is not an active, unexpired token of the voter")]
+    InvalidGatewayToken,
+    #[msg("This poll requires holding its non-transferable badge token")]
+    BadgeRequired,
+    #[msg("Badge must be a non-transferable Token-2022 mint held by the voter")]
+    InvalidBadge,
+    #[msg("This poll requires a minimum balance of its gate token")]
+    InsufficientTokenBalance,
+    #[msg("Token account must be the voter's associated token account")]
+    InvalidTokenAccount,
+    #[msg("Vote deposits need direct tallies, not parallel_tallies")]
+    DepositsNeedDirectTallies,
+    #[msg("Vote records can only be closed once the poll is finalized or cancelled")]
+    PollNotOver,
+    #[msg("Poll creation charges a fee; pass the treasury account")]
+    TreasuryRequired,
+    #[msg("Withdrawal exceeds the treasury's balance above rent")]
+    InsufficientTreasury,
+    #[msg("The poll has no winner")]
+    NoWinner,
+    #[msg("Signer is not the winning candidate's payout address")]
+    NotPrizeRecipient,
+    #[msg("Funding amount must be greater than zero")]
+    InvalidFundingAmount,
+    #[msg("Escrow token accounts are missing or don't match the escrow")]
+    InvalidEscrowAccounts,
+    #[msg("Escrowed funds can only be reclaimed from cancelled polls or polls that missed quorum")]
+    EscrowNotReclaimable,
+    #[msg("This vote record's reward has already been claimed")]
+    RewardAlreadyClaimed,
+    #[msg("The reward vault is empty")]
+    RewardVaultEmpty,
+    #[msg("Stake-weighted polls need the voter's active stake accounts")]
+    StakeRequired,
+    #[msg("Stake account is invalid, not delegated or not the voter's")]
+    InvalidStakeAccount,
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+    #[msg("Poll already uses the current account layout")]
+    PollUpToDate,
+    #[msg("Poll records compressed votes; vote with vote_compressed")]
+    CompressedVotesOnly,
+    #[msg("Poll does not record compressed votes")]
+    CompressedVotesDisabled,
+    #[msg("Poll already records compressed votes")]
+    CompressedVotesEnabled,
+    #[msg("Compressed votes can't be combined with parallel tallies, gates, stake weighting or encrypted ballots")]
+    CompressionUnsupported,
+    #[msg("Vote filter account is invalid")]
+    InvalidVoteFilter,
+    #[msg("Voter has already cast a compressed ballot in this poll")]
+    DuplicateVote,
+    #[msg("Poll takes private ballots; vote with vote_private")]
+    PrivateBallotsOnly,
+    #[msg("Poll does not take private ballots")]
+    PrivateBallotsDisabled,
+    #[msg("Poll already takes private ballots")]
+    PrivateBallotsEnabled,
+    #[msg("Private ballots need a fixed list of at most 6 candidates and no gates, stake weighting, parallel tallies, compressed votes or ballot key")]
+    PrivateBallotsUnsupported,
+    #[msg("Ballot or tally proof is missing or doesn't match")]
+    InvalidBallotProof,
+    #[msg("Private tally has already been posted")]
+    TallyAlreadyPosted,
+    #[msg("Poll takes encrypted ballots; vote with vote_encrypted")]
+    EncryptedBallotsOnly,
+    #[msg("Poll has no ballot key")]
+    EncryptedBallotsDisabled,
+    #[msg("A ballot key needs a valid Ristretto point, an end_ts, a reveal window and no write-ins, gates, deposits, stake weighting, parallel tallies or direct_votes_only; a reveal window or bond needs a ballot key")]
+    InvalidEncryptedBallotSettings,
+    #[msg("Encrypted ballot is malformed or belongs to another poll or voter")]
+    InvalidEncryptedBallot,
+    #[msg("Secret doesn't match the poll's ballot key")]
+    InvalidBallotKey,
+    #[msg("Ballot key has already been revealed")]
+    BallotKeyRevealed,
+    #[msg("Ballot key hasn't been revealed yet")]
+    BallotKeyNotRevealed,
+    #[msg("Deadline for revealing the ballot key has passed")]
+    RevealDeadlinePassed,
+    #[msg("Deadline for revealing the ballot key hasn't passed yet")]
+    RevealDeadlineNotPassed,
+    #[msg("Poll only accepts votes signed directly, not made through another program")]
+    DirectVoteRequired,
+    #[msg("Poll is taking too many votes right now; retry in a few slots")]
+    RateLimited,
+    #[msg("A rate limit needs both a vote count and a slot window, and direct tallies")]
+    InvalidRateLimit,
+    #[msg("Poll has reached its maximum number of votes")]
+    PollFull,
+    #[msg("max_votes must be above 0 and needs direct tallies and no ballot key")]
+    InvalidMaxVotes,
+    #[msg("Unknown poll category")]
+    InvalidCategory,
+    #[msg("At most 5 distinct tags of up to 16 lowercase letters, digits and dashes")]
+    InvalidTags,
+    #[msg("Prediction polls and polls holding vote deposits, conviction locks or an open prize pool, reward vault, rent sponsor or funding round can't be archived")]
+    PollNotArchivable,
+    #[msg("This poll's result is already certified")]
+    ResultAlreadyCertified,
+    #[msg("A challenge window can't be negative, and a challenge deposit needs one")]
+    InvalidChallengeSettings,
+    #[msg("This poll doesn't take result challenges")]
+    ChallengesDisabled,
+    #[msg("The challenge window has closed")]
+    ChallengeWindowClosed,
+    #[msg("The result can still be challenged")]
+    ChallengeWindowOpen,
+    #[msg("The result has an open challenge")]
+    ResultChallenged,
+    #[msg("A challenge to this result was upheld")]
+    ResultDisputed,
+    #[msg("Challenge reason is too long")]
+    ChallengeReasonTooLong,
+    #[msg("This poll doesn't keep an audit log")]
+    AuditLogDisabled,
+    #[msg("This poll keeps an audit log; pass its current page, opened with open_audit_page")]
+    AuditLogRequired,
+    #[msg("This vote record's rent is already paid")]
+    VoteRecordSponsored,
+    #[msg("The poll's rent sponsor can't cover another vote record")]
+    RentSponsorDrained,
+    #[msg("Voting on this poll is frozen")]
+    PollFrozen,
+    #[msg("This poll isn't frozen")]
+    PollNotFrozen,
+    #[msg("The poll's voting window is shorter than the program's minimum duration")]
+    PollTooShort,
+    #[msg("Polls with votes can't be closed before the minimum duration without a multisig")]
+    MinDurationNotMet,
+    #[msg("Conviction polls need a lock period and can't be stake-weighted")]
+    InvalidConvictionSettings,
+    #[msg("This poll doesn't use conviction voting")]
+    ConvictionVotingDisabled,
+    #[msg("Conviction lock tier out of range")]
+    InvalidConvictionTier,
+    #[msg("Ballots on this poll need a conviction lock")]
+    ConvictionLockRequired,
+    #[msg("Conviction tokens stay locked until the poll and the lock period end")]
+    ConvictionLocked,
+    #[msg("Quadratic funding rounds can't take write-in candidates or a quorum")]
+    InvalidQuadraticFundingSettings,
+    #[msg("This poll isn't a quadratic funding round")]
+    QuadraticFundingDisabled,
+    #[msg("The matching pool can't change once contributions have started")]
+    FundingRoundStarted,
+    #[msg("This funding round's matches are already computed")]
+    FundingRoundMatched,
+    #[msg("This funding round's matches aren't computed yet")]
+    FundingRoundNotMatched,
+    #[msg("This project's funding was already claimed")]
+    FundingAlreadyClaimed,
+    #[msg("Prediction polls can't combine with other weighting or parallel tallies, and only they take a prediction mint or oracle")]
+    InvalidPredictionSettings,
+    #[msg("This poll isn't a prediction poll")]
+    PredictionMarketDisabled,
+    #[msg("Ballots on prediction polls are placed with place_stake")]
+    StakeOnOutcomeRequired,
+    #[msg("This prediction poll's outcome is already resolved")]
+    OutcomeAlreadyResolved,
+    #[msg("This prediction poll's outcome isn't resolved yet")]
+    OutcomeNotResolved,
+    #[msg("This stake backed a losing outcome")]
+    LosingStake,
+    #[msg("Petitions have a single option and no weighting, parallel tallies or encrypted ballots")]
+    InvalidPetition,
+    #[msg("Vote records on this poll are pseudonymous and can't be matched to voters")]
+    VoteRecordsPseudonymous,
+    #[msg("NFT-weighted polls can't use other ballot weights, parallel tallies, encrypted ballots or petitions")]
+    InvalidNftSettings,
+    #[msg("NFT-weighted polls need the voter's NFTs of the poll's collection")]
+    NftRequired,
+    #[msg("Account is not the voter's NFT of the poll's verified collection")]
+    InvalidNft,
+    #[msg("This poll needs a higher reputation score to vote")]
+    ReputationTooLow,
+    #[msg("This vote record's participation was already recorded")]
+    ParticipationRecorded,
+    #[msg("This poll has no vote receipt tree")]
+    VoteReceiptsDisabled,
+    #[msg("A receipt was already minted for this vote")]
+    VoteReceiptMinted,
+    #[msg("Organization name must be 1 to 32 bytes")]
+    InvalidOrganizationName,
+    #[msg("Roles on organization polls are managed through the organization")]
+    OrganizationPoll,
+    #[msg("This vote was invalidated")]
+    VoteInvalidated,
+    #[msg("Votes can only be invalidated before finalization or within the challenge window")]
+    InvalidationWindowClosed,
+    #[msg("Only direct ballots tallied on the poll can be invalidated")]
+    BallotNotInvalidatable,
+    #[msg("No retention period is set, so polls are kept forever")]
+    GarbageCollectionDisabled,
+    #[msg("This poll is still within its retention period")]
+    RetentionPeriodActive,
+    #[msg("Per-poll title and name limits can't exceed the program config's")]
+    InvalidStringLimits,
+    #[msg("Hidden tallies need ballots that can be recounted from plain vote records")]
+    InvalidHiddenTallySettings,
+    #[msg("Not available while the poll's tallies are hidden")]
+    TalliesHidden,
+    #[msg("The poll's tallies aren't hidden")]
+    TalliesNotHidden,
+    #[msg("The recount doesn't account for every ballot yet")]
+    RecountIncomplete,
+    #[msg("A snapshot oracle needs a token-gated poll")]
+    InvalidSnapshotSettings,
+    #[msg("This poll doesn't take snapshot balances")]
+    SnapshotDisabled,
+    #[msg("Weight decay needs an end time and plain ballots, and can't exceed 100%")]
+    InvalidDecaySettings,
+    #[msg("Voter tiers need a voter roll, nonzero weights and plain direct ballots")]
+    InvalidVoterTiers,
+    #[msg("No such voter tier on this poll")]
+    InvalidVoterTier,
+    #[msg("Only plain draft polls can have their candidates and settings reworked")]
+    DraftNotReconfigurable,
+    #[msg("Tally shards need parallel tallies, and at most 32 counters in all")]
+    InvalidTallyShards,
+    #[msg("No such tally shard on this poll")]
+    InvalidTallyShard,
+    #[msg("A sponsored vote record must be created by the next instruction")]
+    SponsoredBallotMissing,
+    #[msg("A clone of a pseudonymous poll needs a new non-zero record salt, others none")]
+    RecordSaltReused,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+
+    #[test]
+    fn checked_math_rejects_overflow() {
+        assert_eq!(u64::MAX.safe_sub(1).unwrap(), u64::MAX - 1);
+        assert_eq!((u64::MAX - 1).safe_add(1).unwrap(), u64::MAX);
+        assert!(u64::MAX.safe_add(1).is_err());
+        assert!(0u64.safe_sub(1).is_err());
+        assert!(u8::MAX.safe_add(1).is_err());
+        assert!(u16::MAX.safe_add(1).is_err());
+    }
+
+    #[test]
+    fn result_hash_is_borsh_of_the_published_result() {
+        let candidates = vec![
+            Candidate { name: "Yes".to_string(), votes: 2, weight: 20_000, disqualified: false },
+            Candidate { name: "Nö".to_string(), votes: 1, weight: 10_000, disqualified: true },
+        ];
+        let namespace = Pubkey::new_unique();
+        let published: Vec<(String, u64, u64, bool)> = candidates
+            .iter()
+            .map(|c| (c.name.clone(), c.votes, c.weight, c.disqualified))
+            .collect();
+        let encoded = (namespace, 7u64, PollStatus::Finalized, published).try_to_vec().unwrap();
+        assert_eq!(
+            compute_result_hash(&namespace, 7, PollStatus::Finalized, &candidates),
+            hashv(&[b"solana_voting:result:v1", &encoded]).to_bytes()
+        );
+        assert_ne!(
+            compute_result_hash(&namespace, 7, PollStatus::QuorumNotMet, &candidates),
+            compute_result_hash(&namespace, 7, PollStatus::Finalized, &candidates)
+        );
+    }
+
+    #[test]
+    fn v1_polls_decode_and_upgrade() {
+        // A v1 poll is the current encoding cut off before `vote_tree`, the
+        // first field v2 added, followed by the account's unused tail.
+        let mut data = Poll::DISCRIMINATOR.to_vec();
+        data.resize(8 + Poll::LAYOUT_SLACK, 0);
+        let mut poll = Poll::decode_any_version(&data).unwrap();
+        poll.title = "v1 poll".to_string();
+        poll.candidates = vec![
+            Candidate { name: "Yes".to_string(), votes: 2, weight: 20_000, disqualified: false },
+            Candidate { name: "No".to_string(), votes: 1, weight: 10_000, disqualified: false },
+        ];
+        poll.status = PollStatus::Finalized;
+        poll.end_ts = 1_700_000_000;
+        poll.version = 1;
+        poll.vote_tree = Pubkey::new_unique();
+        let mut current = Vec::new();
+        poll.try_serialize(&mut current).unwrap();
+        let cut = current
+            .windows(32)
+            .position(|window| window == poll.vote_tree.as_ref())
+            .unwrap();
+        let mut v1 = current[..cut].to_vec();
+        v1.resize(cut + 64, 0); // the v1 layout's padding
+
+        let mut migrated = Poll::decode_any_version(&v1).unwrap();
+        assert_eq!(migrated.version, 1);
+        assert_eq!(migrated.vote_tree, Pubkey::default());
+        assert_eq!(migrated.candidates[1].weight, 10_000);
+        migrated.upgrade();
+        assert_eq!(migrated.version, POLL_VERSION);
+        assert_eq!(
+            migrated.canonical_result_hash,
+            compute_result_hash(&migrated.namespace, 0, PollStatus::Finalized, &poll.candidates)
+        );
+
+        // v2 fields took the place of v1 records' padding, so v1 records
+        // (allocated at the same SPACE) decode as they are.
+        let mut record = VoteRecord::DISCRIMINATOR.to_vec();
+        record.resize(VoteRecord::SPACE, 0);
+        // No changes and no memo: voter through reward_claimed, then version
+        record[8 + 32 + 8 + 1 + 1 + 8 + 2 + 4 + 8 + 8 + 4 + 8 + 1] = 1;
+        let record = VoteRecord::try_deserialize(&mut &record[..]).unwrap();
+        assert_eq!(record.version, 1);
+        assert!(!record.invalidated);
+    }
+
+    #[test]
+    fn bps_share_handles_max_weights() {
+        assert_eq!(bps_share(u64::MAX, BPS_DENOMINATOR), u64::MAX);
+        assert_eq!(bps_share(u64::MAX, 5_000), u64::MAX / 2);
+        assert_eq!(bps_share(u64::MAX, 0), 0);
+    }
+
+    #[test]
+    fn poll_tags_and_categories_validate() {
+        assert_eq!(PollCategory::from_u16(1), Some(PollCategory::Governance));
+        assert_eq!(PollCategory::from_u16(6), None);
+        assert!(PollCategory::valid_tag("defi-2024"));
+        assert!(!PollCategory::valid_tag(""));
+        assert!(!PollCategory::valid_tag("DeFi"));
+        assert!(!PollCategory::valid_tag("a-very-long-tag-name"));
+    }
+
+    #[test]
+    fn quadratic_funding_favours_many_contributors() {
+        let project = |contributions: &[u64]| {
+            let mut project = ProjectFunding::default();
+            for &amount in contributions {
+                project.contributed += amount;
+                project.sqrt_sum += ProjectFunding::root(amount);
+            }
+            project
+        };
+        // (√100)² − 100: a single contributor earns no match.
+        assert_eq!(project(&[100]).ideal_match(), 0);
+        // (4 × √25)² − 100 = 300.
+        assert_eq!(project(&[25, 25, 25, 25]).ideal_match(), 300);
+        assert_eq!(ProjectFunding::root(2) >> 8, 362); // √2 ≈ 1.414 in 8.8
+    }
+
+    #[test]
+    fn nft_metadata_reads_the_collection() {
+        let (mint, collection) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = vec![NftMetadata::KEY_METADATA_V1];
+        data.extend_from_slice(&[7; 32]); // update authority
+        data.extend_from_slice(mint.as_ref());
+        for field in ["Ticket #1", "TIX", "https://example.com/1.json"] {
+            data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+            data.extend_from_slice(field.as_bytes());
+        }
+        data.extend_from_slice(&500u16.to_le_bytes());
+        data.extend_from_slice(&[1, 2, 0, 0, 0]); // two creators
+        data.extend_from_slice(&[0; 2 * NftMetadata::CREATOR_SIZE]);
+        data.extend_from_slice(&[1, 1, 1, 255, 1, 0]); // flags, edition nonce, token standard
+        data.extend_from_slice(&[1, 1]);
+        data.extend_from_slice(collection.as_ref());
+
+        let metadata = NftMetadata::parse(&data).unwrap();
+        assert_eq!(metadata.mint, mint);
+        assert_eq!(metadata.collection, Some((collection, true)));
+        assert!(NftMetadata::parse(&data[..data.len() - 1]).is_none());
+    }
+
+    #[test]
+    fn gateway_token_decodes_the_published_layout() {
+        let (owner, network, gatekeeper) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        // Accounts can be allocated for the largest token, so one without a
+        // parent or identity is followed by zeroed padding.
+        let mut data = vec![0]; // features
+        data.push(0); // parent_gateway_token: None
+        data.extend_from_slice(owner.as_ref());
+        data.push(0); // owner_identity: None
+        data.extend_from_slice(network.as_ref());
+        data.extend_from_slice(gatekeeper.as_ref());
+        data.push(0); // state: Active
+        data.push(1); // expire_time: Some
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        data.resize(1 + 33 + 32 + 33 + 32 + 32 + 1 + 9, 0);
+
+        let token = GatewayToken::deserialize(&mut &data[..]).unwrap();
+        assert_eq!(token.owner_wallet, owner);
+        assert_eq!(token.gatekeeper_network, network);
+        assert_eq!(token.issuing_gatekeeper, gatekeeper);
+        assert!(token.state == GatewayTokenState::Active);
+        assert_eq!(token.expire_time, Some(1_700_000_000));
+
+        data[1 + 1 + 32 + 1 + 32 + 32] = 2; // Revoked
+        let token = GatewayToken::deserialize(&mut &data[..]).unwrap();
+        assert!(token.state == GatewayTokenState::Revoked);
+    }
+
+    #[test]
+    fn encrypted_ballot_round_trips() {
+        let scalar = |n: u8| {
+            let mut bytes = [0; 32];
+            bytes[0] = n;
+            PodScalar(bytes)
+        };
+        let base = PodRistrettoPoint(RISTRETTO_BASEPOINT);
+        assert_eq!(multiply_ristretto(&scalar(1), &base), Some(base));
+
+        let secret = scalar(7);
+        let ballot_key = multiply_ristretto(&secret, &base).unwrap();
+        let r = scalar(11);
+        let shared = multiply_ristretto(&r, &ballot_key).unwrap();
+        let (poll, voter) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut ballot = EncryptedBallot {
+            poll,
+            voter,
+            ephemeral_key: multiply_ristretto(&r, &base).unwrap().0,
+            masked_choice: 2 ^ EncryptedBallot::mask(&shared.0, &poll, &voter)[0],
+            bump: 0,
+        };
+        assert_eq!(ballot.decrypt(&secret.0, 3), Some(2));
+        assert_eq!(ballot.decrypt(&secret.0, 2), None);
+
+        ballot.voter = Pubkey::new_unique();
+        assert_ne!(ballot.decrypt(&secret.0, 256), Some(2));
+    }
 }
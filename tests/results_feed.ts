@@ -0,0 +1,58 @@
+import { assert } from "chai";
+import { readFileSync } from "fs";
+import { join } from "path";
+import { rankResults } from "../app/server/services/results.service";
+
+describe("results feed", () => {
+  const poll = (votes: number[], reportingThresholdBps: number) => ({
+    candidates: votes.map((v, i) => ({ name: `Candidate ${i}`, votes: v })),
+    totalVotes: votes.reduce((sum, v) => sum + v, 0),
+    reportingThresholdBps,
+  });
+
+  it("Groups candidates below the reporting threshold as Other", () => {
+    const { results, rawResults } = rankResults(poll([3, 60, 2, 35], 500));
+
+    assert.deepEqual(
+      results.map((r) => [r.rank, r.name, r.votes, r.percentage]),
+      [
+        [1, "Candidate 1", 60, "60.0"],
+        [2, "Candidate 3", 35, "35.0"],
+        [3, "Other", 5, "5.0"],
+      ]
+    );
+    // Raw tallies keep every candidate
+    assert.deepEqual(
+      rawResults.map((r) => r.votes),
+      [60, 35, 3, 2]
+    );
+  });
+
+  it("Reports a candidate exactly at the threshold", () => {
+    const { results } = rankResults(poll([95, 5], 500));
+    assert.deepEqual(
+      results.map((r) => r.name),
+      ["Candidate 0", "Candidate 1"]
+    );
+  });
+
+  it("Publishes every candidate without a threshold", () => {
+    const { results } = rankResults(poll([1, 99], 0));
+    assert.notInclude(
+      results.map((r) => r.name),
+      "Other"
+    );
+    assert.equal(results.length, 2);
+  });
+
+  it("Ships app IDLs that decode the poll's reporting settings", () => {
+    for (const dir of ["app/server/idl", "app/src/idl"]) {
+      const idl = JSON.parse(
+        readFileSync(join(__dirname, "..", dir, "solana_voting.json"), "utf8")
+      );
+      const pollAccount = idl.accounts.find((a: any) => a.name === "Poll");
+      const fields = pollAccount.type.fields.map((f: any) => f.name);
+      assert.includeMembers(fields, ["status", "reportingThresholdBps", "embargoSlot"], dir);
+    }
+  });
+});
@@ -3,6 +3,25 @@ import { Program } from "@coral-xyz/anchor";
 import { SolanaVoting } from "../target/types/solana_voting";
 import { assert, expect } from "chai";
 import { PublicKey, SystemProgram } from "@solana/web3.js";
+import {
+  ExtensionType,
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  createAssociatedTokenAccountIdempotent,
+  createInitializeMintInstruction,
+  createInitializeNonTransferableMintInstruction,
+  createMint,
+  getMintLen,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
+import {
+  SPL_ACCOUNT_COMPRESSION_PROGRAM_ID,
+  SPL_NOOP_PROGRAM_ID,
+  createAllocTreeIx,
+} from "@solana/spl-account-compression";
+import { RistrettoPoint } from "@noble/curves/ed25519";
+import { createHash, randomBytes } from "crypto";
 
 describe("solana-voting", () => {
   const provider = anchor.AnchorProvider.env();
@@ -11,38 +30,270 @@ describe("solana-voting", () => {
   const program = anchor.workspace.SolanaVoting as Program<SolanaVoting>;
   const admin = provider.wallet;
 
-  const pollId = new anchor.BN(1);
-  const title = "Best Blockchain";
-  const candidates = ["Solana", "Ethereum", "Polygon"];
+  // Accounts required by every instruction that emits events via self-CPI
+  const [eventAuthority] = PublicKey.findProgramAddressSync(
+    [Buffer.from("__event_authority")],
+    program.programId
+  );
+  const eventAccounts = { eventAuthority, program: program.programId };
+
+  // Decodes the events a transaction emitted through self-CPI
+  const fetchCpiEvents = async (signature: string) => {
+    await provider.connection.confirmTransaction(signature, "confirmed");
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const events = [];
+    for (const inner of tx.meta.innerInstructions ?? []) {
+      for (const ix of inner.instructions) {
+        const data = anchor.utils.bytes.bs58.decode(ix.data);
+        const event = program.coder.events.decode(
+          anchor.utils.bytes.base64.encode(data.subarray(8))
+        );
+        if (event) events.push(event);
+      }
+    }
+    return events;
+  };
+
+  // Settings for an open-ended poll; individual tests override fields
+  const pollSettings = (overrides: object = {}) => ({
+    parallelTallies: false,
+    clockSource: { unixTimestamp: {} },
+    startTs: new anchor.BN(0),
+    endTs: new anchor.BN(0),
+    approvers: [],
+    approvalThreshold: 0,
+    allowVoteChanges: false,
+    voterRoll: false,
+    contentMode: { inline: {} },
+    startAsDraft: false,
+    eventVerbosity: { standard: {} },
+    reopenWindow: new anchor.BN(0),
+    maxExtension: new anchor.BN(0),
+    publishAttendance: false,
+    finalizeTip: new anchor.BN(0),
+    reportingThresholdBps: 0,
+    quorum: new anchor.BN(0),
+    quorumBps: 0,
+    embargoSlot: new anchor.BN(0),
+    allowWriteIns: false,
+    disqualificationPolicy: { freeze: {} },
+    allowMemos: false,
+    gatekeeperNetwork: PublicKey.default,
+    badgeMint: PublicKey.default,
+    tokenGateMint: PublicKey.default,
+    minTokenBalance: new anchor.BN(0),
+    voteDeposit: new anchor.BN(0),
+    stakeWeighted: false,
+    ballotKey: PublicKey.default,
+    revealWindow: new anchor.BN(0),
+    revealBond: new anchor.BN(0),
+    directVotesOnly: false,
+    rateLimitVotes: 0,
+    rateLimitSlots: new anchor.BN(0),
+    maxVotes: null,
+    category: 0,
+    tags: [],
+    certifier: PublicKey.default,
+    challengeWindow: new anchor.BN(0),
+    challengeDeposit: new anchor.BN(0),
+    auditLog: false,
+    convictionMint: PublicKey.default,
+    convictionPeriod: new anchor.BN(0),
+    quadraticFunding: false,
+    predictionMarket: false,
+    predictionMint: PublicKey.default,
+    oracle: PublicKey.default,
+    petitionThreshold: new anchor.BN(0),
+    recordSalt: Array(32).fill(0),
+    nftCollection: PublicKey.default,
+    minReputation: new anchor.BN(0),
+    maxTitleBytes: 0,
+    maxCandidateNameBytes: 0,
+    hideResultsUntilClose: false,
+    snapshotOracle: PublicKey.default,
+    decayBps: 0,
+    tierWeights: [],
+    tallyShards: 0,
+    ...overrides,
+  });
 
-  // Derive Poll PDA
-  const [pollPda] = PublicKey.findProgramAddressSync(
-    [Buffer.from("poll"), pollId.toArrayLike(Buffer, "le", 8)],
+  const [pollCounterPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("poll_counter")],
     program.programId
   );
+  const [programData] = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
 
-  it("Creates a poll", async () => {
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+
+  const findPollPda = (id: anchor.BN) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("poll"), id.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+
+  const findVoteRecordPda = (id: anchor.BN, voter: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("vote"), id.toArrayLike(Buffer, "le", 8), voter.toBuffer()],
+      program.programId
+    )[0];
+
+  const findAdminProfilePda = (owner: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("admin_profile"), owner.toBuffer()],
+      program.programId
+    )[0];
+
+  const findAdminPollEntryPda = (owner: PublicKey, index: anchor.BN) =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("admin_poll"),
+        owner.toBuffer(),
+        index.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    )[0];
+
+  // Profile and next index entry that poll creation by `owner` will write
+  const adminPollAccounts = async (owner: PublicKey) => {
+    const adminProfile = findAdminProfilePda(owner);
+    const profile = await program.account.adminProfile.fetchNullable(adminProfile);
+    const index = profile ? profile.pollsCreated : new anchor.BN(0);
+    return { adminProfile, adminPollEntry: findAdminPollEntryPda(owner, index) };
+  };
+
+  // Creates a poll under the counter's next ID and returns where it landed
+  const createPoll = async (
+    pollTitle: string,
+    pollCandidates: string[],
+    settings = pollSettings()
+  ) => {
+    const { nextPollId } = await program.account.pollCounter.fetch(
+      pollCounterPda
+    );
+    const newPollPda = findPollPda(nextPollId);
     const tx = await program.methods
-      .createPoll(pollId, title, candidates)
+      .createPoll(pollTitle, pollCandidates, settings)
       .accounts({
-        poll: pollPda,
+        ...eventAccounts,
+        pollCounter: pollCounterPda,
+        config: configPda,
+        poll: newPollPda,
+        ...(await adminPollAccounts(admin.publicKey)),
         admin: admin.publicKey,
         systemProgram: SystemProgram.programId,
       })
       .rpc();
+    return { pollId: nextPollId, pollPda: newPollPda, tx };
+  };
+
+  // Generates a keypair with enough SOL to pay for its own accounts
+  const fundedKeypair = async () => {
+    const keypair = anchor.web3.Keypair.generate();
+    const airdropSig = await provider.connection.requestAirdrop(
+      keypair.publicKey,
+      2 * anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(airdropSig);
+    return keypair;
+  };
+
+  // Polls until the cluster has moved past `slot`
+  const waitForSlot = async (slot: number) => {
+    while ((await provider.connection.getSlot()) <= slot) {
+      await new Promise((resolve) => setTimeout(resolve, 200));
+    }
+  };
+
+  const title = "Best Blockchain";
+  const candidates = ["Solana", "Ethereum", "Polygon"];
+
+  let pollId: anchor.BN;
+  let pollPda: PublicKey;
+
+  it("Initializes the poll counter as the upgrade authority", async () => {
+    const initializeCounter = (authority: PublicKey, signers: anchor.web3.Keypair[] = []) =>
+      program.methods
+        .initializeCounter(new anchor.BN(1))
+        .accounts({
+          pollCounter: pollCounterPda,
+          program: program.programId,
+          programData,
+          authority,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers(signers)
+        .rpc();
+
+    const outsider = await fundedKeypair();
+    try {
+      await initializeCounter(outsider.publicKey, [outsider]);
+      assert.fail("Only the upgrade authority may initialize the counter");
+    } catch (err) {
+      expect(err.toString()).to.include("Unauthorized");
+    }
+    await initializeCounter(admin.publicKey);
+
+    const counter = await program.account.pollCounter.fetch(pollCounterPda);
+    assert.equal(counter.nextPollId.toNumber(), 1);
+  });
+
+  it("Initializes the program config as the upgrade authority", async () => {
+    await program.methods
+      .initializeConfig()
+      .accounts({
+        config: configPda,
+        program: program.programId,
+        programData,
+        authority: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const config = await program.account.programConfig.fetch(configPda);
+    assert.equal(config.authority.toBase58(), admin.publicKey.toBase58());
+  });
 
-    console.log("Create poll tx:", tx);
+  it("Reports program capabilities through return data", async () => {
+    const capabilities = await program.methods
+      .getProgramCapabilities()
+      .accounts({ config: configPda })
+      .view();
+    assert.equal(capabilities.version, "0.1.0");
+    assert.isAbove(capabilities.features.toNumber(), 0);
+    assert.equal(capabilities.limits.maxCandidates, 10);
+    assert.isFalse(capabilities.paused);
+  });
+
+  it("Creates a poll", async () => {
+    const created = await createPoll(title, candidates);
+    pollId = created.pollId;
+    pollPda = created.pollPda;
+
+    console.log("Create poll tx:", created.tx);
 
     const pollAccount = await program.account.poll.fetch(pollPda);
     assert.equal(pollAccount.title, title);
     assert.equal(pollAccount.candidates.length, 3);
     assert.equal(pollAccount.totalVotes.toNumber(), 0);
-    assert.equal(pollAccount.isActive, true);
+    assert.deepEqual(pollAccount.status, { active: {} });
+    assert.equal(pollAccount.pollId.toNumber(), pollId.toNumber());
     assert.equal(
       pollAccount.admin.toBase58(),
       admin.publicKey.toBase58()
     );
 
+    const counter = await program.account.pollCounter.fetch(pollCounterPda);
+    assert.equal(counter.nextPollId.toNumber(), pollId.toNumber() + 1);
+
     console.log("Poll created successfully:");
     pollAccount.candidates.forEach((c, i) => {
       console.log(`  Candidate ${i}: ${c.name} - ${c.votes.toNumber()} votes`);
@@ -53,19 +304,14 @@ describe("solana-voting", () => {
     const candidateIndex = 0; // Vote for "Solana"
 
     // Derive Vote Record PDA
-    const [voteRecordPda] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from("vote"),
-        pollId.toArrayLike(Buffer, "le", 8),
-        admin.publicKey.toBuffer(),
-      ],
-      program.programId
-    );
+    const voteRecordPda = findVoteRecordPda(pollId, admin.publicKey);
 
     const tx = await program.methods
       .vote(pollId, candidateIndex)
       .accounts({
+        ...eventAccounts,
         poll: pollPda,
+        config: configPda,
         voteRecord: voteRecordPda,
         voter: admin.publicKey,
         systemProgram: SystemProgram.programId,
@@ -84,6 +330,13 @@ describe("solana-voting", () => {
       voteRecord.voter.toBase58(),
       admin.publicKey.toBase58()
     );
+    await provider.connection.confirmTransaction(tx, "confirmed");
+    const landed = await provider.connection.getTransaction(tx, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    assert.equal(voteRecord.slot.toNumber(), landed.slot);
+    assert.isAbove(voteRecord.votedAt.toNumber(), 0);
 
     console.log("Vote recorded successfully!");
   });
@@ -91,20 +344,15 @@ describe("solana-voting", () => {
   it("Prevents double voting", async () => {
     const candidateIndex = 1;
 
-    const [voteRecordPda] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from("vote"),
-        pollId.toArrayLike(Buffer, "le", 8),
-        admin.publicKey.toBuffer(),
-      ],
-      program.programId
-    );
+    const voteRecordPda = findVoteRecordPda(pollId, admin.publicKey);
 
     try {
       await program.methods
         .vote(pollId, candidateIndex)
         .accounts({
+          ...eventAccounts,
           poll: pollPda,
+          config: configPda,
           voteRecord: voteRecordPda,
           voter: admin.publicKey,
           systemProgram: SystemProgram.programId,
@@ -130,19 +378,14 @@ describe("solana-voting", () => {
 
     const candidateIndex = 1; // Vote for "Ethereum"
 
-    const [voteRecordPda] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from("vote"),
-        pollId.toArrayLike(Buffer, "le", 8),
-        voter2.publicKey.toBuffer(),
-      ],
-      program.programId
-    );
+    const voteRecordPda = findVoteRecordPda(pollId, voter2.publicKey);
 
     const tx = await program.methods
       .vote(pollId, candidateIndex)
       .accounts({
+        ...eventAccounts,
         poll: pollPda,
+        config: configPda,
         voteRecord: voteRecordPda,
         voter: voter2.publicKey,
         systemProgram: SystemProgram.programId,
@@ -152,6 +395,15 @@ describe("solana-voting", () => {
 
     console.log("Voter 2 tx:", tx);
 
+    const voteCast = (await fetchCpiEvents(tx)).find(
+      (event) => event.name === "VoteCast"
+    )?.data as any;
+    assert.ok(voteCast, "VoteCast event should be emitted");
+    assert.equal(voteCast.voter.toBase58(), voter2.publicKey.toBase58());
+    assert.equal(voteCast.candidateIndex, candidateIndex);
+    assert.equal(voteCast.candidateVotes.toNumber(), 1);
+    assert.equal(voteCast.totalVotes.toNumber(), 2);
+
     const pollAccount = await program.account.poll.fetch(pollPda);
     assert.equal(pollAccount.candidates[0].votes.toNumber(), 1); // Solana
     assert.equal(pollAccount.candidates[1].votes.toNumber(), 1); // Ethereum
@@ -163,11 +415,74 @@ describe("solana-voting", () => {
     });
   });
 
+  it("Stamps layout versions and skips migrating current polls", async () => {
+    const pollAccount = await program.account.poll.fetch(pollPda);
+    assert.equal(pollAccount.version, 2);
+    const record = await program.account.voteRecord.fetch(
+      findVoteRecordPda(pollId, admin.publicKey)
+    );
+    assert.equal(record.version, 2);
+
+    try {
+      await program.methods
+        .migratePoll(PublicKey.default, pollId)
+        .accounts({
+          poll: pollPda,
+          payer: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      assert.fail("Current polls should not migrate");
+    } catch (err) {
+      expect(err.toString()).to.include("PollUpToDate");
+    }
+  });
+
+  it("Enumerates an admin's polls by index", async () => {
+    const profile = await program.account.adminProfile.fetch(
+      findAdminProfilePda(admin.publicKey)
+    );
+    assert.isAtLeast(profile.pollsCreated.toNumber(), 1);
+
+    const first = await program.account.adminPollEntry.fetch(
+      findAdminPollEntryPda(admin.publicKey, new anchor.BN(0))
+    );
+    assert.equal(first.poll.toBase58(), pollPda.toBase58());
+    assert.equal(first.pollId.toNumber(), pollId.toNumber());
+  });
+
+  it("Runs a practice ballot without touching tallies", async () => {
+    const before = await program.account.poll.fetch(pollPda);
+
+    const tx = await program.methods
+      .practiceVote(pollId, 2)
+      .accounts({
+        ...eventAccounts,
+        poll: pollPda,
+        voteRecord: findVoteRecordPda(pollId, admin.publicKey),
+        voter: admin.publicKey,
+      })
+      .rpc();
+
+    const practice = (await fetchCpiEvents(tx)).find(
+      (event) => event.name === "PracticeBallot"
+    )?.data as any;
+    assert.ok(practice, "PracticeBallot event should be emitted");
+    assert.equal(practice.candidateIndex, 2);
+    assert.equal(practice.alreadyVoted, true);
+
+    const after = await program.account.poll.fetch(pollPda);
+    assert.equal(after.totalVotes.toNumber(), before.totalVotes.toNumber());
+    assert.equal(after.candidates[2].votes.toNumber(), 0);
+  });
+
   it("Closes a poll", async () => {
     const tx = await program.methods
       .closePoll(pollId)
       .accounts({
+        ...eventAccounts,
         poll: pollPda,
+        config: configPda,
         admin: admin.publicKey,
       })
       .rpc();
@@ -175,7 +490,7 @@ describe("solana-voting", () => {
     console.log("Close poll tx:", tx);
 
     const pollAccount = await program.account.poll.fetch(pollPda);
-    assert.equal(pollAccount.isActive, false);
+    assert.deepEqual(pollAccount.status, { closed: {} });
     console.log("Poll closed successfully!");
   });
 
@@ -187,20 +502,15 @@ describe("solana-voting", () => {
     );
     await provider.connection.confirmTransaction(airdropSig);
 
-    const [voteRecordPda] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from("vote"),
-        pollId.toArrayLike(Buffer, "le", 8),
-        voter3.publicKey.toBuffer(),
-      ],
-      program.programId
-    );
+    const voteRecordPda = findVoteRecordPda(pollId, voter3.publicKey);
 
     try {
       await program.methods
         .vote(pollId, 0)
         .accounts({
+          ...eventAccounts,
           poll: pollPda,
+          config: configPda,
           voteRecord: voteRecordPda,
           voter: voter3.publicKey,
           systemProgram: SystemProgram.programId,
@@ -213,4 +523,4909 @@ describe("solana-voting", () => {
       expect(err).to.exist;
     }
   });
+
+  it("Finalizes a closed poll", async () => {
+    await program.methods
+      .finalizePoll(pollId)
+      .accounts({ poll: pollPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+      .rpc();
+
+    const pollAccount = await program.account.poll.fetch(pollPda);
+    assert.deepEqual(pollAccount.status, { finalized: {} });
+    // Solana and Ethereum tied on one vote each
+    assert.isNull(pollAccount.winnerIndex);
+    assert.equal(pollAccount.winningWeight.toNumber(), 10000);
+    assert.equal(pollAccount.runnerUpWeight.toNumber(), 10000);
+    assert.isTrue(pollAccount.isTie);
+  });
+
+  it("Seals a closed poll through the minimal finalization path", async () => {
+    const { pollId: minPollId, pollPda: minPollPda } = await createPoll(
+      "Minimal Finalize",
+      ["A", "B"]
+    );
+    await program.methods
+      .closePoll(minPollId)
+      .accounts({ poll: minPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+      .rpc();
+
+    await program.methods
+      .finalizeMinimal()
+      .accounts({ poll: minPollPda, config: configPda, admin: admin.publicKey })
+      .rpc();
+
+    const pollAccount = await program.account.poll.fetch(minPollPda);
+    assert.deepEqual(pollAccount.status, { finalized: {} });
+  });
+
+  it("Refuses assert_winner on a tied result", async () => {
+    const [receiptPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("receipt"), pollPda.toBuffer(), program.programId.toBuffer()],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .assertWinner(0)
+        .accounts({
+          poll: pollPda,
+          receipt: receiptPda,
+          consumerProgram: program.programId,
+          payer: admin.publicKey,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      assert.fail("Solana and Ethereum are tied, so there is no winner");
+    } catch (err) {
+      expect(err.toString()).to.include("WinnerMismatch");
+    }
+  });
+
+  it("Cancels a poll and refuses to finalize it", async () => {
+    const { pollId: cancelPollId, pollPda: cancelPollPda } = await createPoll(
+      "Cancelled Poll",
+      ["A", "B"]
+    );
+
+    await program.methods
+      .cancelPoll(cancelPollId, "Duplicate of another poll")
+      .accounts({ poll: cancelPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+      .rpc();
+
+    const pollAccount = await program.account.poll.fetch(cancelPollPda);
+    assert.deepEqual(pollAccount.status, { cancelled: {} });
+    assert.equal(pollAccount.cancelReason, "Duplicate of another poll");
+
+    try {
+      await program.methods
+        .finalizePoll(cancelPollId)
+        .accounts({ poll: cancelPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+        .rpc();
+      assert.fail("Should not finalize a cancelled poll");
+    } catch (err) {
+      expect(err.toString()).to.include("PollNotClosed");
+    }
+  });
+
+  it("Keeps draft polls closed to votes until activated", async () => {
+    const { pollId: draftPollId, pollPda: draftPollPda } = await createPoll(
+      "Draft Poll",
+      ["A", "B"],
+      pollSettings({ startAsDraft: true })
+    );
+    let pollAccount = await program.account.poll.fetch(draftPollPda);
+    assert.deepEqual(pollAccount.status, { draft: {} });
+
+    const voteRecordPda = findVoteRecordPda(draftPollId, admin.publicKey);
+    const castVote = () =>
+      program.methods
+        .vote(draftPollId, 0)
+        .accounts({
+          poll: draftPollPda,
+          config: configPda,
+          voteRecord: voteRecordPda,
+          voter: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+          ...eventAccounts,
+        })
+        .rpc();
+
+    try {
+      await castVote();
+      assert.fail("Should not vote on a draft poll");
+    } catch (err) {
+      expect(err.toString()).to.include("PollClosed");
+    }
+
+    await program.methods
+      .activatePoll(draftPollId)
+      .accounts({ poll: draftPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+      .rpc();
+    await castVote();
+
+    pollAccount = await program.account.poll.fetch(draftPollPda);
+    assert.deepEqual(pollAccount.status, { active: {} });
+    assert.equal(pollAccount.totalVotes.toNumber(), 1);
+  });
+
+  it("Reworks a draft's candidates and settings until it is published", async () => {
+    const { pollId: draftPollId, pollPda: draftPollPda } = await createPoll(
+      "Draft Ballot",
+      ["A", "B"],
+      pollSettings({ startAsDraft: true })
+    );
+    const editDraft = (candidates: string[], settings: object) =>
+      program.methods
+        .editDraft(draftPollId, "Final Ballot", candidates, settings)
+        .accounts({
+          poll: draftPollPda,
+          config: configPda,
+          auditLog: null,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+          ...eventAccounts,
+        })
+        .rpc();
+
+    await editDraft(["A", "B", "C", "D"], pollSettings({ allowVoteChanges: true }));
+    let pollAccount = await program.account.poll.fetch(draftPollPda);
+    assert.deepEqual(pollAccount.status, { draft: {} });
+    assert.equal(pollAccount.title, "Final Ballot");
+    assert.deepEqual(
+      pollAccount.candidates.map((candidate) => candidate.name),
+      ["A", "B", "C", "D"]
+    );
+    assert.isTrue(pollAccount.allowVoteChanges);
+
+    await program.methods
+      .activatePoll(draftPollId)
+      .accounts({ poll: draftPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+      .rpc();
+    try {
+      await editDraft(["A"], pollSettings());
+      assert.fail("Should not rework a published poll");
+    } catch (err) {
+      expect(err.toString()).to.include("PollNotDraft");
+    }
+  });
+
+  it("Scales per-ballot events with the poll's verbosity", async () => {
+    const voteWithVerbosity = async (eventVerbosity: object) => {
+      const { pollId: id, pollPda: pda } = await createPoll(
+        "Verbosity Poll",
+        ["A", "B"],
+        pollSettings({ eventVerbosity })
+      );
+      const signature = await program.methods
+        .vote(id, 1)
+        .accounts({
+          poll: pda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(id, admin.publicKey),
+          voter: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+          ...eventAccounts,
+        })
+        .rpc();
+      return fetchCpiEvents(signature);
+    };
+
+    expect(await voteWithVerbosity({ essential: {} })).to.be.empty;
+    const standard = await voteWithVerbosity({ standard: {} });
+    expect(standard.map((event) => event.name)).to.deep.equal(["VoteCast"]);
+
+    const events = await voteWithVerbosity({ verbose: {} });
+    expect(events.map((event) => event.name)).to.deep.equal(["VoteCast", "BallotDetail"]);
+    assert.deepEqual(events[1].data.cohort, { open: {} });
+    assert.equal(events[1].data.totalWeight.toNumber(), events[1].data.weight.toNumber());
+  });
+
+  it("Reopens a manually closed poll within its window", async () => {
+    const closeAndReopen = async (reopenWindow: number) => {
+      const { pollId: id, pollPda: pda } = await createPoll(
+        "Reopen Poll",
+        ["A", "B"],
+        pollSettings({ reopenWindow: new anchor.BN(reopenWindow) })
+      );
+      const accounts = { poll: pda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+      await program.methods.closePoll(id).accounts(accounts).rpc();
+      await program.methods.reopenPoll(id).accounts(accounts).rpc();
+      return pda;
+    };
+
+    const reopenedPda = await closeAndReopen(3600);
+    const pollAccount = await program.account.poll.fetch(reopenedPda);
+    assert.deepEqual(pollAccount.status, { active: {} });
+    assert.equal(pollAccount.closedAt.toNumber(), 0);
+
+    try {
+      await closeAndReopen(0);
+      assert.fail("Polls without a reopen window cannot be reopened");
+    } catch (err) {
+      expect(err.toString()).to.include("ReopenWindowElapsed");
+    }
+  });
+
+  it("Freezes and unfreezes voting without closing the poll", async () => {
+    const { pollId: frozenPollId, pollPda: frozenPollPda } = await createPoll(
+      "Frozen Poll",
+      ["A", "B"]
+    );
+    const accounts = { poll: frozenPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    const voteAccounts = {
+      ...eventAccounts,
+      poll: frozenPollPda,
+      config: configPda,
+      voteRecord: findVoteRecordPda(frozenPollId, admin.publicKey),
+      voter: admin.publicKey,
+      systemProgram: SystemProgram.programId,
+    };
+    await program.methods.freezePoll(frozenPollId).accounts(accounts).rpc();
+    let pollAccount = await program.account.poll.fetch(frozenPollPda);
+    assert.isTrue(pollAccount.frozen);
+    assert.deepEqual(pollAccount.status, { active: {} });
+
+    try {
+      await program.methods.vote(frozenPollId, 0).accounts(voteAccounts).rpc();
+      assert.fail("Frozen polls should reject votes");
+    } catch (err) {
+      expect(err.toString()).to.include("PollFrozen");
+    }
+
+    await program.methods.unfreezePoll(frozenPollId).accounts(accounts).rpc();
+    await program.methods.vote(frozenPollId, 0).accounts(voteAccounts).rpc();
+    pollAccount = await program.account.poll.fetch(frozenPollPda);
+    assert.isFalse(pollAccount.frozen);
+    assert.equal(pollAccount.totalVotes.toNumber(), 1);
+  });
+
+  it("Recounts vote records page by page with a checkpoint hash", async () => {
+    const { pollId: tallyPollId, pollPda: tallyPollPda } = await createPoll(
+      "Recount Poll",
+      ["A", "B"]
+    );
+    const voters = [admin, await fundedKeypair(), await fundedKeypair()];
+    for (const [i, voter] of voters.entries()) {
+      await program.methods
+        .vote(tallyPollId, i % 2)
+        .accounts({
+          poll: tallyPollPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(tallyPollId, voter.publicKey),
+          voter: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+          ...eventAccounts,
+        })
+        .signers(voter === admin ? [] : [voter])
+        .rpc();
+    }
+    await program.methods
+      .closePoll(tallyPollId)
+      .accounts({ poll: tallyPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+      .rpc();
+
+    const [tallyStatePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("tally"), tallyPollPda.toBuffer(), admin.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .beginTally(tallyPollId)
+      .accounts({
+        poll: tallyPollPda,
+        tallyState: tallyStatePda,
+        cranker: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    // Records must be processed in ascending voter order
+    const sorted = voters
+      .map((voter) => voter.publicKey)
+      .sort((a, b) => Buffer.compare(a.toBuffer(), b.toBuffer()));
+    const tallyPage = (page: PublicKey[]) =>
+      program.methods
+        .tallyPage(tallyPollId)
+        .accounts({ poll: tallyPollPda, tallyState: tallyStatePda, cranker: admin.publicKey })
+        .remainingAccounts(
+          page.map((voter) => ({
+            pubkey: findVoteRecordPda(tallyPollId, voter),
+            isWritable: false,
+            isSigner: false,
+          }))
+        )
+        .rpc();
+    await tallyPage(sorted.slice(0, 1));
+    try {
+      await tallyPage(sorted.slice(0, 1));
+      assert.fail("A record cannot be tallied twice");
+    } catch (err) {
+      expect(err.toString()).to.include("TallyOutOfOrder");
+    }
+    await tallyPage(sorted.slice(1));
+
+    let expected = Buffer.alloc(32);
+    for (const voter of sorted) {
+      const record = await program.account.voteRecord.fetch(
+        findVoteRecordPda(tallyPollId, voter)
+      );
+      expected = createHash("sha256")
+        .update(expected)
+        .update(voter.toBuffer())
+        .update(Buffer.from([record.candidateIndex]))
+        .update(record.weight.toArrayLike(Buffer, "le", 8))
+        .digest();
+    }
+    const tallyState = await program.account.tallyState.fetch(tallyStatePda);
+    assert.equal(tallyState.processed.toNumber(), 3);
+    assert.deepEqual(tallyState.votes.map((votes) => votes.toNumber()), [2, 1]);
+    assert.deepEqual(Buffer.from(tallyState.checkpoint), expected);
+  });
+
+  it("Hides live tallies until a recount reveals them", async () => {
+    const { pollId: hiddenPollId, pollPda: hiddenPollPda } = await createPoll(
+      "Sequential Election",
+      ["A", "B"],
+      pollSettings({ hideResultsUntilClose: true })
+    );
+    const voters = [admin, await fundedKeypair(), await fundedKeypair()];
+    for (const [i, voter] of voters.entries()) {
+      await program.methods
+        .vote(hiddenPollId, i % 2)
+        .accounts({
+          poll: hiddenPollPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(hiddenPollId, voter.publicKey),
+          voter: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+          ...eventAccounts,
+        })
+        .signers(voter === admin ? [] : [voter])
+        .rpc();
+    }
+    let pollAccount = await program.account.poll.fetch(hiddenPollPda);
+    assert.equal(pollAccount.totalVotes.toNumber(), 3);
+    assert.deepEqual(pollAccount.candidates.map((c) => c.votes.toNumber()), [0, 0]);
+
+    await program.methods
+      .closePoll(hiddenPollId)
+      .accounts({ poll: hiddenPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+      .rpc();
+    const finalize = () =>
+      program.methods
+        .finalizePoll(hiddenPollId)
+        .accounts({ poll: hiddenPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+        .rpc();
+    try {
+      await finalize();
+      assert.fail("Hidden tallies must be revealed before finalization");
+    } catch (err) {
+      expect(err.toString()).to.include("TalliesNotAggregated");
+    }
+
+    const tallyStatePda = (cranker: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("tally"), hiddenPollPda.toBuffer(), cranker.toBuffer()],
+        program.programId
+      )[0];
+    const beginTally = (cranker: PublicKey, signers: anchor.web3.Keypair[] = []) =>
+      program.methods
+        .beginTally(hiddenPollId)
+        .accounts({
+          poll: hiddenPollPda,
+          tallyState: tallyStatePda(cranker),
+          cranker,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers(signers)
+        .rpc();
+    const reveal = (cranker: PublicKey) =>
+      program.methods
+        .revealTallies(hiddenPollId)
+        .accounts({ poll: hiddenPollPda, tallyState: tallyStatePda(cranker), ...eventAccounts })
+        .rpc();
+    const sorted = voters
+      .map((voter) => voter.publicKey)
+      .sort((a, b) => Buffer.compare(a.toBuffer(), b.toBuffer()));
+    const tallyPage = (
+      page: PublicKey[],
+      cranker: PublicKey = admin.publicKey,
+      signers: anchor.web3.Keypair[] = []
+    ) =>
+      program.methods
+        .tallyPage(hiddenPollId)
+        .accounts({ poll: hiddenPollPda, tallyState: tallyStatePda(cranker), cranker })
+        .remainingAccounts(
+          page.map((voter) => ({
+            pubkey: findVoteRecordPda(hiddenPollId, voter),
+            isWritable: false,
+            isSigner: false,
+          }))
+        )
+        .signers(signers)
+        .rpc();
+
+    // Skipping straight to the highest voter spoils only the griefer's own
+    // recount, and they can restart it
+    const griefer = await fundedKeypair();
+    await beginTally(griefer.publicKey, [griefer]);
+    await tallyPage(sorted.slice(2), griefer.publicKey, [griefer]);
+    try {
+      await tallyPage(sorted.slice(0, 2), griefer.publicKey, [griefer]);
+      assert.fail("Skipped records cannot be tallied afterwards");
+    } catch (err) {
+      expect(err.toString()).to.include("TallyOutOfOrder");
+    }
+    await beginTally(griefer.publicKey, [griefer]);
+    const restarted = await program.account.tallyState.fetch(tallyStatePda(griefer.publicKey));
+    assert.equal(restarted.processed.toNumber(), 0);
+
+    await beginTally(admin.publicKey);
+    try {
+      await program.methods
+        .tallyPage(hiddenPollId)
+        .accounts({
+          poll: hiddenPollPda,
+          tallyState: tallyStatePda(admin.publicKey),
+          cranker: griefer.publicKey,
+        })
+        .signers([griefer])
+        .rpc();
+      assert.fail("Only the cranker can add pages to a recount");
+    } catch (err) {
+      expect(err.toString()).to.include("ConstraintSeeds");
+    }
+    await tallyPage(sorted.slice(0, 2));
+    try {
+      await reveal(admin.publicKey);
+      assert.fail("A partial recount cannot reveal the tallies");
+    } catch (err) {
+      expect(err.toString()).to.include("RecountIncomplete");
+    }
+    await tallyPage(sorted.slice(2));
+    const [revealed] = await fetchCpiEvents(await reveal(admin.publicKey));
+    assert.equal(revealed.name, "TalliesRevealed");
+
+    pollAccount = await program.account.poll.fetch(hiddenPollPda);
+    assert.deepEqual(pollAccount.candidates.map((c) => c.votes.toNumber()), [2, 1]);
+    await finalize();
+    pollAccount = await program.account.poll.fetch(hiddenPollPda);
+    assert.equal(pollAccount.winnerIndex, 0);
+  });
+
+  it("Publishes attendance without choices after finalization", async () => {
+    const { pollId: attendancePollId, pollPda: attendancePollPda } = await createPoll(
+      "Attendance Poll",
+      ["A", "B"],
+      pollSettings({ publishAttendance: true })
+    );
+    const voters = [admin, await fundedKeypair()];
+    for (const voter of voters) {
+      await program.methods
+        .vote(attendancePollId, 0)
+        .accounts({
+          poll: attendancePollPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(attendancePollId, voter.publicKey),
+          voter: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+          ...eventAccounts,
+        })
+        .signers(voter === admin ? [] : [voter])
+        .rpc();
+    }
+    const adminAccounts = {
+      poll: attendancePollPda,
+      config: configPda,
+      admin: admin.publicKey,
+      ...eventAccounts,
+    };
+    await program.methods.closePoll(attendancePollId).accounts(adminAccounts).rpc();
+    await program.methods.finalizePoll(attendancePollId).accounts(adminAccounts).rpc();
+
+    const sorted = voters
+      .map((voter) => voter.publicKey)
+      .sort((a, b) => Buffer.compare(a.toBuffer(), b.toBuffer()));
+    const [attendancePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("attendance"), attendancePollPda.toBuffer()],
+      program.programId
+    );
+    const signature = await program.methods
+      .publishAttendance(attendancePollId)
+      .accounts({
+        poll: attendancePollPda,
+        attendance: attendancePda,
+        payer: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+        ...eventAccounts,
+      })
+      .remainingAccounts(
+        sorted.map((voter) => ({
+          pubkey: findVoteRecordPda(attendancePollId, voter),
+          isWritable: false,
+          isSigner: false,
+        }))
+      )
+      .rpc();
+
+    const [published] = await fetchCpiEvents(signature);
+    assert.equal(published.name, "AttendancePublished");
+    assert.deepEqual(
+      published.data.voters.map((voter) => voter.toBase58()),
+      sorted.map((voter) => voter.toBase58())
+    );
+    const roll = await program.account.attendanceRoll.fetch(attendancePda);
+    assert.equal(roll.published.toNumber(), 2);
+  });
+
+  it("Stores a salted hash of the voter in pseudonymous vote records", async () => {
+    const recordSalt = randomBytes(32);
+    const { pollId: privatePollId, pollPda: privatePollPda } = await createPoll(
+      "Pseudonymous Poll",
+      ["A", "B"],
+      pollSettings({ recordSalt: [...recordSalt] })
+    );
+    const voteRecordPda = findVoteRecordPda(privatePollId, admin.publicKey);
+    await program.methods
+      .vote(privatePollId, 1)
+      .accounts({
+        poll: privatePollPda,
+        config: configPda,
+        voteRecord: voteRecordPda,
+        voter: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+        ...eventAccounts,
+      })
+      .rpc();
+
+    const record = await program.account.voteRecord.fetch(voteRecordPda);
+    const pseudonym = createHash("sha256")
+      .update(Buffer.concat([admin.publicKey.toBuffer(), recordSalt]))
+      .digest();
+    assert.equal(record.voter.toBase58(), new PublicKey(pseudonym).toBase58());
+    assert.equal(record.candidateIndex, 1);
+
+    try {
+      await createPoll(
+        "Pseudonymous Attendance",
+        ["A", "B"],
+        pollSettings({ recordSalt: [...recordSalt], publishAttendance: true })
+      );
+      assert.fail("Pseudonymous records can't publish attendance");
+    } catch (err) {
+      expect(err.toString()).to.include("VoteRecordsPseudonymous");
+    }
+  });
+
+  it("Finalizes low-turnout polls as QuorumNotMet", async () => {
+    const { pollId: quorumPollId, pollPda: quorumPollPda } = await createPoll(
+      "Quorum Poll",
+      ["A", "B"],
+      pollSettings({ quorum: new anchor.BN(2) })
+    );
+    await program.methods
+      .vote(quorumPollId, 0)
+      .accounts({
+        poll: quorumPollPda,
+        config: configPda,
+        voteRecord: findVoteRecordPda(quorumPollId, admin.publicKey),
+        voter: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+        ...eventAccounts,
+      })
+      .rpc();
+    const adminAccounts = { poll: quorumPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    await program.methods.closePoll(quorumPollId).accounts(adminAccounts).rpc();
+    const signature = await program.methods.finalizePoll(quorumPollId).accounts(adminAccounts).rpc();
+
+    const [finalized] = await fetchCpiEvents(signature);
+    assert.isFalse(finalized.data.quorumMet);
+    assert.isNull(finalized.data.winner);
+    const pollAccount = await program.account.poll.fetch(quorumPollPda);
+    assert.deepEqual(pollAccount.status, { quorumNotMet: {} });
+  });
+
+  it("Counts write-ins for the same name on one candidate", async () => {
+    const { pollId: writeInPollId, pollPda: writeInPollPda } = await createPoll(
+      "Write-in Poll",
+      ["A", "B"],
+      pollSettings({ allowWriteIns: true })
+    );
+    const writeInPda = (name: string) =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("write_in"),
+          writeInPollPda.toBuffer(),
+          createHash("sha256").update(name.trim().toLowerCase()).digest(),
+        ],
+        program.programId
+      )[0];
+    const voter = await fundedKeypair();
+    for (const [signer, name] of [[admin, "  Dave "], [voter, "DAVE"]] as const) {
+      await program.methods
+        .voteWriteIn(writeInPollId, name)
+        .accounts({
+          poll: writeInPollPda,
+          config: configPda,
+          writeIn: writeInPda(name),
+          voteRecord: findVoteRecordPda(writeInPollId, signer.publicKey),
+          voter: signer.publicKey,
+          systemProgram: SystemProgram.programId,
+          ...eventAccounts,
+        })
+        .signers(signer === admin ? [] : [voter])
+        .rpc();
+    }
+
+    const pollAccount = await program.account.poll.fetch(writeInPollPda);
+    assert.equal(pollAccount.candidates.length, 3);
+    assert.equal(pollAccount.candidates[2].name, "Dave");
+    assert.equal(pollAccount.candidates[2].votes.toNumber(), 2);
+    assert.equal(pollAccount.writeInCount, 1);
+  });
+
+  it("Votes in several polls with one batch instruction", async () => {
+    const polls = [
+      await createPoll("Batch Poll A", ["Red", "Blue"], pollSettings()),
+      await createPoll("Batch Poll B", ["Cats", "Dogs", "Fish"], pollSettings()),
+    ];
+    const voter = await fundedKeypair();
+    await program.methods
+      .voteBatch(Buffer.from([1, 2]))
+      .accounts({
+        ...eventAccounts,
+        config: configPda,
+        voter: voter.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(
+        polls.flatMap(({ pollId, pollPda }) => [
+          { pubkey: pollPda, isWritable: true, isSigner: false },
+          {
+            pubkey: findVoteRecordPda(pollId, voter.publicKey),
+            isWritable: true,
+            isSigner: false,
+          },
+        ])
+      )
+      .signers([voter])
+      .rpc();
+
+    for (const [i, { pollId, pollPda }] of polls.entries()) {
+      const pollAccount = await program.account.poll.fetch(pollPda);
+      assert.equal(pollAccount.candidates[i + 1].votes.toNumber(), 1);
+      const record = await program.account.voteRecord.fetch(
+        findVoteRecordPda(pollId, voter.publicKey)
+      );
+      assert.equal(record.candidateIndex, i + 1);
+    }
+  });
+
+  it("Relays a ballot signed by a voter without SOL", async () => {
+    const voter = anchor.web3.Keypair.generate();
+    const [voteNoncePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("nonce"), voter.publicKey.toBuffer()],
+      program.programId
+    );
+    const relay = (
+      { pollId, pollPda }: { pollId: anchor.BN; pollPda: PublicKey },
+      candidateIndex: number,
+      nonce: anchor.BN
+    ) =>
+      program.methods
+        .voteWithSignature(pollId, voter.publicKey, candidateIndex, nonce)
+        .accounts({
+          ...eventAccounts,
+          poll: pollPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(pollId, voter.publicKey),
+          voteNonce: voteNoncePda,
+          relayer: admin.publicKey,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .preInstructions([
+          anchor.web3.Ed25519Program.createInstructionWithPrivateKey({
+            privateKey: voter.secretKey,
+            message: Buffer.concat([
+              pollPda.toBuffer(),
+              pollId.toArrayLike(Buffer, "le", 8),
+              Buffer.from([candidateIndex]),
+              nonce.toArrayLike(Buffer, "le", 8),
+            ]),
+          }),
+        ])
+        .rpc();
+
+    const first = await createPoll("Gasless Poll", ["Yes", "No"], pollSettings());
+    await relay(first, 1, new anchor.BN(1));
+    const record = await program.account.voteRecord.fetch(
+      findVoteRecordPda(first.pollId, voter.publicKey)
+    );
+    assert.ok(record.voter.equals(voter.publicKey));
+    assert.equal(record.candidateIndex, 1);
+    const nonceAccount = await program.account.voteNonce.fetch(voteNoncePda);
+    assert.equal(nonceAccount.lastNonce.toNumber(), 1);
+
+    const second = await createPoll("Gasless Poll 2", ["Yes", "No"], pollSettings());
+    try {
+      await relay(second, 0, new anchor.BN(1));
+      assert.fail("A reused nonce should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("StaleNonce");
+    }
+    await relay(second, 0, new anchor.BN(2));
+  });
+
+  it("Votes through a session key on the wallet's behalf", async () => {
+    const sessionKey = await fundedKeypair();
+    const [sessionTokenPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("session"), admin.publicKey.toBuffer(), sessionKey.publicKey.toBuffer()],
+      program.programId
+    );
+    const now = Math.floor(Date.now() / 1000);
+    await program.methods
+      .createSession(sessionKey.publicKey, new anchor.BN(now + 600))
+      .accounts({
+        config: configPda,
+        sessionToken: sessionTokenPda,
+        authority: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const questions = [
+      await createPoll("Session Question 1", ["Yes", "No"], pollSettings()),
+      await createPoll("Session Question 2", ["Yes", "No"], pollSettings()),
+    ];
+    for (const { pollId, pollPda } of questions) {
+      await program.methods
+        .vote(pollId, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: pollPda,
+          config: configPda,
+          sessionToken: sessionTokenPda,
+          voteRecord: findVoteRecordPda(pollId, admin.publicKey),
+          voter: sessionKey.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([sessionKey])
+        .rpc();
+      const record = await program.account.voteRecord.fetch(
+        findVoteRecordPda(pollId, admin.publicKey)
+      );
+      assert.ok(record.voter.equals(admin.publicKey));
+    }
+
+    try {
+      await program.methods
+        .createSession(sessionKey.publicKey, new anchor.BN(now + 2 * 24 * 60 * 60))
+        .accounts({
+          config: configPda,
+          sessionToken: sessionTokenPda,
+          authority: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      assert.fail("Sessions longer than a day should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidSessionExpiry");
+    }
+  });
+
+  it("Requires a gateway token on polls with a gatekeeper network", async () => {
+    const { pollId: gatedPollId, pollPda: gatedPollPda } = await createPoll(
+      "Gated Poll",
+      ["Yes", "No"],
+      pollSettings({ gatekeeperNetwork: anchor.web3.Keypair.generate().publicKey })
+    );
+    const voteAccounts = {
+      ...eventAccounts,
+      poll: gatedPollPda,
+      config: configPda,
+      voteRecord: findVoteRecordPda(gatedPollId, admin.publicKey),
+      voter: admin.publicKey,
+      systemProgram: SystemProgram.programId,
+    };
+    try {
+      await program.methods.vote(gatedPollId, 0).accounts(voteAccounts).rpc();
+      assert.fail("A vote without a gateway token should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("GatewayTokenRequired");
+    }
+
+    // Any account the gateway program doesn't own is not a gateway token
+    try {
+      await program.methods
+        .vote(gatedPollId, 0)
+        .accounts({ ...voteAccounts, gatewayToken: configPda })
+        .rpc();
+      assert.fail("A fake gateway token should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidGatewayToken");
+    }
+  });
+
+  it("Only counts directly signed ballots on direct-votes-only polls", async () => {
+    const { pollId: directPollId, pollPda: directPollPda } = await createPoll(
+      "Direct Poll",
+      ["Yes", "No"],
+      pollSettings({ directVotesOnly: true })
+    );
+    const voteAccounts = {
+      ...eventAccounts,
+      poll: directPollPda,
+      config: configPda,
+      voteRecord: findVoteRecordPda(directPollId, admin.publicKey),
+      voter: admin.publicKey,
+      systemProgram: SystemProgram.programId,
+    };
+    try {
+      await program.methods.vote(directPollId, 0).accounts(voteAccounts).rpc();
+      assert.fail("A vote without the instructions sysvar should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("DirectVoteRequired");
+    }
+
+    await program.methods
+      .vote(directPollId, 0)
+      .accounts({ ...voteAccounts, instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY })
+      .rpc();
+    const pollAccount = await program.account.poll.fetch(directPollPda);
+    assert.isTrue(pollAccount.directVotesOnly);
+    assert.equal(pollAccount.candidates[0].votes.toNumber(), 1);
+  });
+
+  it("Rate-limits ballots per slot window", async () => {
+    const { pollId: limitedPollId, pollPda: limitedPollPda } = await createPoll(
+      "Rate-limited Poll",
+      ["Yes", "No"],
+      pollSettings({ rateLimitVotes: 1, rateLimitSlots: new anchor.BN(1_000) })
+    );
+    const voteAs = (voter: anchor.web3.Keypair) =>
+      program.methods
+        .vote(limitedPollId, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: limitedPollPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(limitedPollId, voter.publicKey),
+          voter: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+
+    await voteAs(await fundedKeypair());
+    try {
+      await voteAs(await fundedKeypair());
+      assert.fail("A second ballot in the window should be rate-limited");
+    } catch (err) {
+      expect(err.toString()).to.include("RateLimited");
+    }
+    const pollAccount = await program.account.poll.fetch(limitedPollPda);
+    assert.equal(pollAccount.totalVotes.toNumber(), 1);
+    assert.equal(pollAccount.rateWindowVotes, 1);
+  });
+
+  it("Stores a category and tags on the poll", async () => {
+    const { pollPda: taggedPollPda, tx } = await createPoll(
+      "Treasury Proposal",
+      ["Yes", "No"],
+      pollSettings({ category: 1, tags: ["treasury", "q3-2024"] })
+    );
+    const pollAccount = await program.account.poll.fetch(taggedPollPda);
+    assert.equal(pollAccount.category, 1);
+    assert.deepEqual(pollAccount.tags, ["treasury", "q3-2024"]);
+    const [created] = await fetchCpiEvents(tx);
+    assert.equal(created.name, "PollCreated");
+    assert.deepEqual(created.data.tags, ["treasury", "q3-2024"]);
+
+    try {
+      await createPoll("Bad Tags", ["Yes", "No"], pollSettings({ tags: ["Not Lowercase"] }));
+      assert.fail("Malformed tags should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidTags");
+    }
+    try {
+      await createPoll("Bad Category", ["Yes", "No"], pollSettings({ category: 99 }));
+      assert.fail("Unknown categories should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidCategory");
+    }
+  });
+
+  it("Validates names in UTF-8 bytes against per-poll limits", async () => {
+    const limits = { maxTitleBytes: 24, maxCandidateNameBytes: 8 };
+    // "候选人" is 3 characters but 9 bytes
+    try {
+      await createPoll("Small Poll", ["候选人", "No"], pollSettings(limits));
+      assert.fail("Names over the byte budget should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("CandidateNameTooLong");
+    }
+    try {
+      await createPoll("Over Limit", ["Yes", "No"], pollSettings({ maxTitleBytes: 1000 }));
+      assert.fail("Per-poll limits above the config's should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidStringLimits");
+    }
+
+    const { pollPda: smallPollPda } = await createPoll(
+      "Small Poll 🗳",
+      ["候选", "No"],
+      pollSettings(limits)
+    );
+    const pollAccount = await program.account.poll.fetch(smallPollPda);
+    assert.equal(pollAccount.maxTitleBytes, 24);
+    assert.equal(pollAccount.maxCandidateNameBytes, 8);
+    const { pollPda: defaultPollPda } = await createPoll("Default Poll", ["Yes", "No"]);
+    const [small, full] = await Promise.all(
+      [smallPollPda, defaultPollPda].map((pda) => provider.connection.getAccountInfo(pda))
+    );
+    assert.isBelow(small.data.length, full.data.length);
+  });
+
+  it("Links created polls into an on-chain list", async () => {
+    const { pollsListed } = await program.account.programConfig.fetch(configPda);
+    const first = await createPoll("Listed Poll 1", ["Yes", "No"]);
+    const second = await createPoll("Listed Poll 2", ["Yes", "No"]);
+
+    const config = await program.account.programConfig.fetch(configPda);
+    assert.equal(config.latestPoll.toBase58(), second.pollPda.toBase58());
+    assert.equal(config.pollsListed.toNumber(), pollsListed.toNumber() + 2);
+    const latest = await program.account.poll.fetch(second.pollPda);
+    assert.equal(latest.prevPoll.toBase58(), first.pollPda.toBase58());
+  });
+
+  it("Closes a poll once it reaches its vote cap", async () => {
+    const { pollId: cappedPollId, pollPda: cappedPollPda } = await createPoll(
+      "First Responders",
+      ["Yes", "No"],
+      pollSettings({ maxVotes: new anchor.BN(1) })
+    );
+    const voteAs = (voter: anchor.web3.Keypair) =>
+      program.methods
+        .vote(cappedPollId, 1)
+        .accounts({
+          ...eventAccounts,
+          poll: cappedPollPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(cappedPollId, voter.publicKey),
+          voter: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+
+    const signature = await voteAs(await fundedKeypair());
+    const events = await fetchCpiEvents(signature);
+    assert.include(
+      events.map((event) => event.name),
+      "PollClosed"
+    );
+    const pollAccount = await program.account.poll.fetch(cappedPollPda);
+    assert.deepEqual(pollAccount.status, { closed: {} });
+    assert.equal(pollAccount.totalVotes.toNumber(), 1);
+
+    try {
+      await voteAs(await fundedKeypair());
+      assert.fail("Ballots past the cap should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("PollFull");
+    }
+  });
+
+  it("Certifies a finalized result once, by the designated certifier", async () => {
+    const certifier = await fundedKeypair();
+    const { pollId: certPollId, pollPda: certPollPda } = await createPoll(
+      "Certified Poll",
+      ["Yes", "No"],
+      pollSettings({ certifier: certifier.publicKey })
+    );
+    const adminAccounts = { poll: certPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    const certify = (signer: anchor.web3.Keypair) =>
+      program.methods
+        .certifyResult(certPollId)
+        .accounts({ ...eventAccounts, poll: certPollPda, certifier: signer.publicKey })
+        .signers([signer])
+        .rpc();
+
+    await program.methods.closePoll(certPollId).accounts(adminAccounts).rpc();
+    try {
+      await certify(certifier);
+      assert.fail("Unsealed results should not be certifiable");
+    } catch (err) {
+      expect(err.toString()).to.include("PollNotFinalized");
+    }
+    await program.methods.finalizePoll(certPollId).accounts(adminAccounts).rpc();
+    try {
+      await certify(await fundedKeypair());
+      assert.fail("Only the admin or certifier may certify");
+    } catch (err) {
+      expect(err.toString()).to.include("Unauthorized");
+    }
+
+    const signature = await certify(certifier);
+    const [certified] = await fetchCpiEvents(signature);
+    assert.equal(certified.name, "ResultCertified");
+    const pollAccount = await program.account.poll.fetch(certPollPda);
+    assert.equal(pollAccount.certifiedBy.toBase58(), certifier.publicKey.toBase58());
+    assert.isAbove(pollAccount.certifiedAt.toNumber(), 0);
+    assert.deepEqual(pollAccount.resultHash, pollAccount.canonicalResultHash);
+    assert.notDeepEqual(pollAccount.resultHash, new Array(32).fill(0));
+
+    try {
+      await certify(certifier);
+      assert.fail("A result is certified once");
+    } catch (err) {
+      expect(err.toString()).to.include("ResultAlreadyCertified");
+    }
+  });
+
+  it("Stores a canonical result hash when finalizing", async () => {
+    const { pollId: hashPollId, pollPda: hashPollPda } = await createPoll("Hashed Poll", ["Yes", "No"]);
+    const adminAccounts = { poll: hashPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    await program.methods
+      .vote(hashPollId, 0)
+      .accounts({
+        poll: hashPollPda,
+        config: configPda,
+        voteRecord: findVoteRecordPda(hashPollId, admin.publicKey),
+        voter: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+        ...eventAccounts,
+      })
+      .rpc();
+    await program.methods.closePoll(hashPollId).accounts(adminAccounts).rpc();
+    const signature = await program.methods.finalizePoll(hashPollId).accounts(adminAccounts).rpc();
+
+    const pollAccount = await program.account.poll.fetch(hashPollPda);
+    const u32 = (value: number) => {
+      const buffer = Buffer.alloc(4);
+      buffer.writeUInt32LE(value);
+      return buffer;
+    };
+    const hash = createHash("sha256")
+      .update("solana_voting:result:v1")
+      .update(pollAccount.namespace.toBuffer())
+      .update(hashPollId.toArrayLike(Buffer, "le", 8))
+      .update(Buffer.from([3])) // PollStatus::Finalized
+      .update(u32(pollAccount.candidates.length));
+    for (const candidate of pollAccount.candidates) {
+      const name = Buffer.from(candidate.name, "utf8");
+      hash
+        .update(u32(name.length))
+        .update(name)
+        .update(candidate.votes.toArrayLike(Buffer, "le", 8))
+        .update(candidate.weight.toArrayLike(Buffer, "le", 8))
+        .update(Buffer.from([candidate.disqualified ? 1 : 0]));
+    }
+    const expected = hash.digest();
+    assert.deepEqual(Buffer.from(pollAccount.canonicalResultHash), expected);
+
+    const [finalized] = await fetchCpiEvents(signature);
+    assert.deepEqual(Buffer.from(finalized.data.canonicalResultHash), expected);
+  });
+
+  it("Holds certification while a result challenge is open", async () => {
+    const deposit = 50_000_000;
+    const { pollId: disputedPollId, pollPda: disputedPollPda } = await createPoll(
+      "Disputed Poll",
+      ["Yes", "No"],
+      pollSettings({ challengeWindow: new anchor.BN(3600), challengeDeposit: new anchor.BN(deposit) })
+    );
+    const voter = await fundedKeypair();
+    const voteRecord = findVoteRecordPda(disputedPollId, voter.publicKey);
+    await program.methods
+      .vote(disputedPollId, 1)
+      .accounts({
+        ...eventAccounts,
+        poll: disputedPollPda,
+        config: configPda,
+        voteRecord,
+        voter: voter.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([voter])
+      .rpc();
+    const adminAccounts = { poll: disputedPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    await program.methods.closePoll(disputedPollId).accounts(adminAccounts).rpc();
+    await program.methods.finalizePoll(disputedPollId).accounts(adminAccounts).rpc();
+
+    const [challenge] = PublicKey.findProgramAddressSync(
+      [Buffer.from("challenge"), disputedPollPda.toBuffer(), voter.publicKey.toBuffer()],
+      program.programId
+    );
+    const [treasuryPda] = PublicKey.findProgramAddressSync([Buffer.from("treasury")], program.programId);
+    const challengeAs = () =>
+      program.methods
+        .challengeResult(disputedPollId, "Ballots were miscounted")
+        .accounts({
+          ...eventAccounts,
+          poll: disputedPollPda,
+          voteRecord,
+          challenge,
+          challenger: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+    const resolve = (upheld: boolean) =>
+      program.methods
+        .resolveChallenge(disputedPollId, upheld)
+        .accounts({
+          ...eventAccounts,
+          poll: disputedPollPda,
+          challenge,
+          challenger: voter.publicKey,
+          treasury: treasuryPda,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    const certify = () =>
+      program.methods
+        .certifyResult(disputedPollId)
+        .accounts({ ...eventAccounts, poll: disputedPollPda, certifier: admin.publicKey })
+        .rpc();
+
+    await challengeAs();
+    assert.isAtLeast(await provider.connection.getBalance(challenge), deposit);
+    try {
+      await certify();
+      assert.fail("Certification should wait for the challenge");
+    } catch (err) {
+      expect(err.toString()).to.include("ResultChallenged");
+    }
+
+    const treasuryBefore = (await provider.connection.getAccountInfo(treasuryPda))?.lamports ?? 0;
+    await resolve(false);
+    const treasuryAfter = await provider.connection.getBalance(treasuryPda);
+    assert.isAtLeast(treasuryAfter - treasuryBefore, deposit);
+    assert.isNull(await provider.connection.getAccountInfo(challenge));
+
+    await challengeAs();
+    await resolve(true);
+    const pollAccount = await program.account.poll.fetch(disputedPollPda);
+    assert.isTrue(pollAccount.resultDisputed);
+    assert.equal(pollAccount.openChallenges, 0);
+    try {
+      await certify();
+      assert.fail("Results stay uncertified during the challenge window");
+    } catch (err) {
+      expect(err.toString()).to.include("ChallengeWindowOpen");
+    }
+  });
+
+  it("Appends admin actions to the poll's audit log", async () => {
+    const { pollId: auditPollId, pollPda: auditPollPda } = await createPoll(
+      "Audited Poll",
+      ["Yes", "No"],
+      pollSettings({ auditLog: true })
+    );
+    const pageBuffer = Buffer.alloc(4);
+    const [auditLog] = PublicKey.findProgramAddressSync(
+      [Buffer.from("audit_log"), auditPollPda.toBuffer(), pageBuffer],
+      program.programId
+    );
+    const adminAccounts = { poll: auditPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    const reason = "Duplicate of an earlier poll";
+
+    try {
+      await program.methods.cancelPoll(auditPollId, reason).accounts(adminAccounts).rpc();
+      assert.fail("Audited polls need their audit log");
+    } catch (err) {
+      expect(err.toString()).to.include("AuditLogRequired");
+    }
+
+    await program.methods
+      .openAuditPage(auditPollId)
+      .accounts({
+        poll: auditPollPda,
+        auditLog,
+        payer: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    await program.methods.cancelPoll(auditPollId, reason).accounts({ ...adminAccounts, auditLog }).rpc();
+
+    const log = await program.account.auditLog.fetch(auditLog);
+    assert.equal(log.page, 0);
+    assert.lengthOf(log.entries, 1);
+    const [entry] = log.entries;
+    assert.equal(entry.actor.toBase58(), admin.publicKey.toBase58());
+    assert.deepEqual(entry.action, { cancel: {} });
+    assert.deepEqual(
+      Buffer.from(entry.payloadHash),
+      createHash("sha256").update(reason).digest()
+    );
+    const pollAccount = await program.account.poll.fetch(auditPollPda);
+    assert.equal(pollAccount.auditEntries.toNumber(), 1);
+  });
+
+  it("Archives a finalized poll into a compact result", async () => {
+    const { pollId: archivePollId, pollPda: archivePollPda } = await createPoll(
+      "Archived Poll",
+      ["Yes", "No"]
+    );
+    const voter = await fundedKeypair();
+    await program.methods
+      .vote(archivePollId, 0)
+      .accounts({
+        ...eventAccounts,
+        poll: archivePollPda,
+        config: configPda,
+        voteRecord: findVoteRecordPda(archivePollId, voter.publicKey),
+        voter: voter.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([voter])
+      .rpc();
+    const adminAccounts = { poll: archivePollPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    await program.methods.closePoll(archivePollId).accounts(adminAccounts).rpc();
+    await program.methods.finalizePoll(archivePollId).accounts(adminAccounts).rpc();
+
+    const pollData = (await provider.connection.getAccountInfo(archivePollPda)).data;
+    const [resultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("result"), archivePollPda.toBuffer()],
+      program.programId
+    );
+    const [prizePool] = PublicKey.findProgramAddressSync(
+      [Buffer.from("prize"), archivePollPda.toBuffer()],
+      program.programId
+    );
+    const [rewardVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("reward"), archivePollPda.toBuffer()],
+      program.programId
+    );
+    const [rentSponsor] = PublicKey.findProgramAddressSync(
+      [Buffer.from("rent_sponsor"), archivePollPda.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .archivePoll(archivePollId)
+      .accounts({
+        ...eventAccounts,
+        poll: archivePollPda,
+        result: resultPda,
+        prizePool,
+        rewardVault,
+        rentSponsor,
+        fundingRound: PublicKey.findProgramAddressSync(
+          [Buffer.from("funding"), archivePollPda.toBuffer()],
+          program.programId
+        )[0],
+        admin: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    assert.isNull(await provider.connection.getAccountInfo(archivePollPda));
+    const result = await program.account.pollResult.fetch(resultPda);
+    assert.equal(result.winner, 0);
+    assert.equal(result.candidateCount, 2);
+    assert.equal(result.tallies[0].toNumber(), 1);
+    assert.equal(result.tallies[1].toNumber(), 0);
+    assert.equal(result.totalVotes.toNumber(), 1);
+    assert.deepEqual(
+      Buffer.from(result.pollHash),
+      createHash("sha256").update(pollData).digest()
+    );
+
+    const voteRecord = findVoteRecordPda(archivePollId, voter.publicKey);
+    await program.methods
+      .closeArchivedVoteRecord()
+      .accounts({ result: resultPda, voteRecord, voter: voter.publicKey })
+      .signers([voter])
+      .rpc();
+    assert.isNull(await provider.connection.getAccountInfo(voteRecord));
+  });
+
+  it("Garbage-collects a poll past its retention period", async () => {
+    const { pollId: gcPollId, pollPda: gcPollPda } = await createPoll("Expired Poll", ["Yes", "No"]);
+    const voter = await fundedKeypair();
+    const voteRecord = findVoteRecordPda(gcPollId, voter.publicKey);
+    await program.methods
+      .vote(gcPollId, 1)
+      .accounts({
+        ...eventAccounts,
+        poll: gcPollPda,
+        config: configPda,
+        voteRecord,
+        voter: voter.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([voter])
+      .rpc();
+    const adminAccounts = { poll: gcPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    await program.methods.closePoll(gcPollId).accounts(adminAccounts).rpc();
+    await program.methods.finalizePoll(gcPollId).accounts(adminAccounts).rpc();
+
+    const pda = (seed: string) =>
+      PublicKey.findProgramAddressSync([Buffer.from(seed), gcPollPda.toBuffer()], program.programId)[0];
+    const cranker = await fundedKeypair();
+    const collect = () =>
+      program.methods
+        .gcExpiredPoll(gcPollId)
+        .accounts({
+          ...eventAccounts,
+          config: configPda,
+          poll: gcPollPda,
+          admin: admin.publicKey,
+          result: pda("result"),
+          prizePool: pda("prize"),
+          rewardVault: pda("reward"),
+          rentSponsor: pda("rent_sponsor"),
+          fundingRound: pda("funding"),
+          cranker: cranker.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([
+          { pubkey: voteRecord, isSigner: false, isWritable: true },
+          { pubkey: voter.publicKey, isSigner: false, isWritable: true },
+        ])
+        .signers([cranker])
+        .rpc();
+    const setRetention = (seconds: number) =>
+      program.methods
+        .setRetentionPeriod(new anchor.BN(seconds))
+        .accounts({ ...eventAccounts, config: configPda, authority: admin.publicKey })
+        .rpc();
+
+    try {
+      await collect();
+      assert.fail("Polls are kept forever without a retention period");
+    } catch (err) {
+      expect(err.toString()).to.include("GarbageCollectionDisabled");
+    }
+    await setRetention(1);
+    try {
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+      const voterBalance = await provider.connection.getBalance(voter.publicKey);
+      const recordRent = await provider.connection.getBalance(voteRecord);
+      await collect();
+
+      assert.isNull(await provider.connection.getAccountInfo(voteRecord));
+      assert.equal(await provider.connection.getBalance(voter.publicKey), voterBalance + recordRent);
+      assert.isNull(await provider.connection.getAccountInfo(gcPollPda));
+      const result = await program.account.pollResult.fetch(pda("result"));
+      assert.equal(result.winner, 1);
+    } finally {
+      await setRetention(0);
+    }
+  });
+
+  it("Pays vote record rent from the poll's rent sponsor", async () => {
+    const { pollId: sponsoredPollId, pollPda: sponsoredPollPda } = await createPoll(
+      "Sponsored Poll",
+      ["Yes", "No"]
+    );
+    const [rentSponsor] = PublicKey.findProgramAddressSync(
+      [Buffer.from("rent_sponsor"), sponsoredPollPda.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .fundRentSponsor(sponsoredPollId, new anchor.BN(anchor.web3.LAMPORTS_PER_SOL / 100))
+      .accounts({
+        poll: sponsoredPollPda,
+        rentSponsor,
+        funder: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    // The voter holds no SOL; the admin's wallet pays the transaction fee.
+    const voter = anchor.web3.Keypair.generate();
+    const voteRecord = findVoteRecordPda(sponsoredPollId, voter.publicKey);
+    const sponsor = (voter: anchor.web3.Keypair) =>
+      program.methods.sponsorVoteRecord(sponsoredPollId).accounts({
+        poll: sponsoredPollPda,
+        rentSponsor,
+        voteRecord: findVoteRecordPda(sponsoredPollId, voter.publicKey),
+        voter: voter.publicKey,
+        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      });
+    const tx = new anchor.web3.Transaction().add(
+      await sponsor(voter).instruction(),
+      await program.methods
+        .vote(sponsoredPollId, 1)
+        .accounts({
+          ...eventAccounts,
+          poll: sponsoredPollPda,
+          config: configPda,
+          voteRecord,
+          voter: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .instruction()
+    );
+    await provider.sendAndConfirm(tx, [voter]);
+
+    assert.equal(await provider.connection.getBalance(voter.publicKey), 0);
+    assert.equal((await program.account.voteRecord.fetch(voteRecord)).candidateIndex, 1);
+    assert.equal((await program.account.rentSponsor.fetch(rentSponsor)).sponsored.toNumber(), 1);
+
+    try {
+      await sponsor(voter).signers([voter]).rpc();
+      assert.fail("Should have thrown");
+    } catch (e) {
+      assert.include(e.toString(), "VoteRecordSponsored");
+    }
+    // Without the ballot right after it, nothing vouches for the voter.
+    try {
+      const loner = anchor.web3.Keypair.generate();
+      await sponsor(loner).signers([loner]).rpc();
+      assert.fail("Should have thrown");
+    } catch (e) {
+      assert.include(e.toString(), "SponsoredBallotMissing");
+    }
+
+    // No receipt tree was enabled for this poll.
+    try {
+      await program.methods
+        .mintVoteReceipt(sponsoredPollId)
+        .accounts({
+          ...eventAccounts,
+          poll: sponsoredPollPda,
+          voteRecord,
+          rentSponsor,
+          treeAuthority: PublicKey.default,
+          receiptTree: PublicKey.default,
+          voter: voter.publicKey,
+          bubblegumProgram: new PublicKey("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY"),
+          compressionProgram: SPL_ACCOUNT_COMPRESSION_PROGRAM_ID,
+          noopProgram: SPL_NOOP_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+      assert.fail("Should have thrown");
+    } catch (e) {
+      assert.include(e.toString(), "VoteReceiptsDisabled");
+    }
+  });
+
+  it("Only counts ballots from holders of a soulbound badge", async () => {
+    const badgeMint = anchor.web3.Keypair.generate();
+    const mintLen = getMintLen([ExtensionType.NonTransferable]);
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: admin.publicKey,
+          newAccountPubkey: badgeMint.publicKey,
+          space: mintLen,
+          lamports: await provider.connection.getMinimumBalanceForRentExemption(mintLen),
+          programId: TOKEN_2022_PROGRAM_ID,
+        }),
+        createInitializeNonTransferableMintInstruction(
+          badgeMint.publicKey,
+          TOKEN_2022_PROGRAM_ID
+        ),
+        createInitializeMintInstruction(
+          badgeMint.publicKey,
+          0,
+          admin.publicKey,
+          null,
+          TOKEN_2022_PROGRAM_ID
+        )
+      ),
+      [badgeMint]
+    );
+
+    const { pollId: badgePollId, pollPda: badgePollPda } = await createPoll(
+      "Members Poll",
+      ["Yes", "No"],
+      pollSettings({ badgeMint: badgeMint.publicKey })
+    );
+    const payer = (provider.wallet as anchor.Wallet).payer;
+    const badgeVote = async (voter: anchor.web3.Keypair, mint: number) => {
+      const badgeToken = await createAssociatedTokenAccountIdempotent(
+        provider.connection,
+        payer,
+        badgeMint.publicKey,
+        voter.publicKey,
+        {},
+        TOKEN_2022_PROGRAM_ID
+      );
+      if (mint > 0) {
+        await mintTo(
+          provider.connection,
+          payer,
+          badgeMint.publicKey,
+          badgeToken,
+          admin.publicKey,
+          mint,
+          [],
+          {},
+          TOKEN_2022_PROGRAM_ID
+        );
+      }
+      return program.methods
+        .vote(badgePollId, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: badgePollPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(badgePollId, voter.publicKey),
+          badgeMint: badgeMint.publicKey,
+          badgeToken,
+          voter: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+    };
+
+    try {
+      await badgeVote(await fundedKeypair(), 0);
+      assert.fail("A voter without the badge should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidBadge");
+    }
+    await badgeVote(await fundedKeypair(), 1);
+    const pollAccount = await program.account.poll.fetch(badgePollPda);
+    assert.equal(pollAccount.candidates[0].votes.toNumber(), 1);
+  });
+
+  it("Requires a minimum token balance without weighting ballots", async () => {
+    const payer = (provider.wallet as anchor.Wallet).payer;
+    const mint = await createMint(provider.connection, payer, admin.publicKey, null, 0);
+    const { pollId: tokenPollId, pollPda: tokenPollPda } = await createPoll(
+      "Holders Poll",
+      ["Yes", "No"],
+      pollSettings({ tokenGateMint: mint, minTokenBalance: new anchor.BN(100) })
+    );
+    const holderVote = async (balance: number) => {
+      const voter = await fundedKeypair();
+      const gateToken = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        mint,
+        voter.publicKey
+      );
+      await mintTo(provider.connection, payer, mint, gateToken.address, admin.publicKey, balance);
+      return program.methods
+        .vote(tokenPollId, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: tokenPollPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(tokenPollId, voter.publicKey),
+          gateToken: gateToken.address,
+          voter: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+    };
+
+    try {
+      await holderVote(99);
+      assert.fail("A balance below the minimum should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("InsufficientTokenBalance");
+    }
+    await holderVote(5000);
+    const pollAccount = await program.account.poll.fetch(tokenPollPda);
+    assert.equal(pollAccount.candidates[0].votes.toNumber(), 1);
+    assert.equal(pollAccount.candidates[0].weight.toNumber(), 10000);
+  });
+
+  it("Judges token-gated ballots by attested snapshot balances", async () => {
+    const payer = (provider.wallet as anchor.Wallet).payer;
+    const mint = await createMint(provider.connection, payer, admin.publicKey, null, 0);
+    const oracle = await fundedKeypair();
+    const { pollId: snapshotPollId, pollPda: snapshotPollPda, tx } = await createPoll(
+      "Snapshot Poll",
+      ["Yes", "No"],
+      pollSettings({
+        tokenGateMint: mint,
+        minTokenBalance: new anchor.BN(100),
+        snapshotOracle: oracle.publicKey,
+      })
+    );
+    const { slot } = await provider.connection.getTransaction(tx, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    let pollAccount = await program.account.poll.fetch(snapshotPollPda);
+    assert.equal(pollAccount.snapshotSlot.toNumber(), slot);
+
+    const findSnapshotPda = (voter: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("snapshot"), snapshotPollPda.toBuffer(), voter.toBuffer()],
+        program.programId
+      )[0];
+    const attest = (voter: PublicKey, balance: number, signer = oracle) =>
+      program.methods
+        .attestSnapshotBalance(snapshotPollId, voter, new anchor.BN(balance))
+        .accounts({
+          ...eventAccounts,
+          poll: snapshotPollPda,
+          snapshotBalance: findSnapshotPda(voter),
+          oracle: signer.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([signer])
+        .rpc();
+    const snapshotVote = async (voter: anchor.web3.Keypair, liveBalance: number) => {
+      const gateToken = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        mint,
+        voter.publicKey
+      );
+      await mintTo(provider.connection, payer, mint, gateToken.address, admin.publicKey, liveBalance);
+      return program.methods
+        .vote(snapshotPollId, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: snapshotPollPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(snapshotPollId, voter.publicKey),
+          gateToken: gateToken.address,
+          snapshotBalance: findSnapshotPda(voter.publicKey),
+          voter: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+    };
+
+    // Bought after the snapshot: a large live balance doesn't help
+    const latecomer = await fundedKeypair();
+    await attest(latecomer.publicKey, 0);
+    try {
+      await snapshotVote(latecomer, 5000);
+      assert.fail("Tokens bought after the snapshot should confer nothing");
+    } catch (err) {
+      expect(err.toString()).to.include("InsufficientTokenBalance");
+    }
+    const holder = await fundedKeypair();
+    try {
+      await attest(holder.publicKey, 500, await fundedKeypair());
+      assert.fail("Only the poll's snapshot oracle may attest");
+    } catch (err) {
+      expect(err.toString()).to.include("Unauthorized");
+    }
+    await attest(holder.publicKey, 500);
+    await snapshotVote(holder, 0);
+    pollAccount = await program.account.poll.fetch(snapshotPollPda);
+    assert.equal(pollAccount.candidates[0].votes.toNumber(), 1);
+  });
+
+  it("Decays the weight of late ballots", async () => {
+    try {
+      await createPoll("Open-ended Decay", ["Yes", "No"], pollSettings({ decayBps: 5000 }));
+      assert.fail("Decay needs an end time");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidDecaySettings");
+    }
+
+    const { pollId: decayPollId, pollPda: decayPollPda } = await createPoll(
+      "Urgent Signal",
+      ["Yes", "No"],
+      pollSettings({
+        endTs: new anchor.BN(Math.floor(Date.now() / 1000) + 8),
+        decayBps: 10000,
+      })
+    );
+    const decayVote = async () => {
+      const voter = await fundedKeypair();
+      await program.methods
+        .vote(decayPollId, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: decayPollPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(decayPollId, voter.publicKey),
+          voter: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+      const record = await program.account.voteRecord.fetch(
+        findVoteRecordPda(decayPollId, voter.publicKey)
+      );
+      return record.weight.toNumber();
+    };
+    const early = await decayVote();
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+    const late = await decayVote();
+    assert.isBelow(late, early);
+    assert.isAbove(late, 0);
+
+    const pollAccount = await program.account.poll.fetch(decayPollPda);
+    assert.equal(pollAccount.candidates[0].votes.toNumber(), 2);
+    assert.equal(pollAccount.candidates[0].weight.toNumber(), early + late);
+  });
+
+  it("Weighs conviction ballots by lock tier", async () => {
+    const payer = (provider.wallet as anchor.Wallet).payer;
+    const mint = await createMint(provider.connection, payer, admin.publicKey, null, 0);
+    const { pollId: convictionPollId, pollPda: convictionPollPda } = await createPoll(
+      "Conviction Poll",
+      ["Yes", "No"],
+      pollSettings({ convictionMint: mint, convictionPeriod: new anchor.BN(3600) })
+    );
+    const lockAndVote = async (amount: number, tier: number) => {
+      const voter = await fundedKeypair();
+      const voterToken = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        mint,
+        voter.publicKey
+      );
+      await mintTo(provider.connection, payer, mint, voterToken.address, admin.publicKey, amount);
+      const [convictionLock] = PublicKey.findProgramAddressSync(
+        [Buffer.from("conviction"), convictionPollPda.toBuffer(), voter.publicKey.toBuffer()],
+        program.programId
+      );
+      const { address: lockVault } = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        mint,
+        convictionLock,
+        true
+      );
+      await program.methods
+        .lockConviction(convictionPollId, new anchor.BN(amount), tier)
+        .accounts({
+          ...eventAccounts,
+          poll: convictionPollPda,
+          convictionLock,
+          mint,
+          lockVault,
+          voterToken: voterToken.address,
+          voter: voter.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+      await program.methods
+        .vote(convictionPollId, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: convictionPollPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(convictionPollId, voter.publicKey),
+          convictionLock,
+          voter: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+      return { voter, voterToken: voterToken.address, convictionLock, lockVault };
+    };
+
+    await lockAndVote(100, 0);
+    const locked = await lockAndVote(100, 2);
+    const pollAccount = await program.account.poll.fetch(convictionPollPda);
+    assert.equal(pollAccount.candidates[0].votes.toNumber(), 2);
+    assert.equal(pollAccount.candidates[0].weight.toNumber(), 500);
+    assert.equal(pollAccount.convictionLocks, 2);
+
+    try {
+      await program.methods
+        .unlockConviction(convictionPollId)
+        .accounts({
+          poll: convictionPollPda,
+          convictionLock: locked.convictionLock,
+          mint,
+          lockVault: locked.lockVault,
+          voterToken: locked.voterToken,
+          voter: locked.voter.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([locked.voter])
+        .rpc();
+      assert.fail("Tokens should stay locked while the poll runs");
+    } catch (err) {
+      expect(err.toString()).to.include("ConvictionLocked");
+    }
+  });
+
+  it("Accepts Token-2022 balances for the minimum token balance", async () => {
+    const payer = (provider.wallet as anchor.Wallet).payer;
+    const mint = await createMint(
+      provider.connection,
+      payer,
+      admin.publicKey,
+      null,
+      0,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const { pollId: tokenPollId, pollPda: tokenPollPda } = await createPoll(
+      "Token-2022 Holders Poll",
+      ["Yes", "No"],
+      pollSettings({ tokenGateMint: mint, minTokenBalance: new anchor.BN(100) })
+    );
+    const voter = await fundedKeypair();
+    const gateToken = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      payer,
+      mint,
+      voter.publicKey,
+      false,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(
+      provider.connection,
+      payer,
+      mint,
+      gateToken.address,
+      admin.publicKey,
+      500,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const holderVote = (gateMint: PublicKey | null) =>
+      program.methods
+        .vote(tokenPollId, 1)
+        .accounts({
+          ...eventAccounts,
+          poll: tokenPollPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(tokenPollId, voter.publicKey),
+          gateToken: gateToken.address,
+          gateMint,
+          voter: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+
+    try {
+      await holderVote(null);
+      assert.fail("Token-2022 balances need the gate mint");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidTokenAccount");
+    }
+    await holderVote(mint);
+    const pollAccount = await program.account.poll.fetch(tokenPollPda);
+    assert.equal(pollAccount.candidates[1].votes.toNumber(), 1);
+  });
+
+  it("Builds reputation from ballots and finalized polls", async () => {
+    const voter = await fundedKeypair();
+    const [reputationPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("reputation"), voter.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initReputation()
+      .accounts({ reputation: reputationPda, voter: voter.publicKey, systemProgram: SystemProgram.programId })
+      .signers([voter])
+      .rpc();
+
+    const reputationVote = (id: anchor.BN, pda: PublicKey) =>
+      program.methods
+        .vote(id, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: pda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(id, voter.publicKey),
+          reputation: reputationPda,
+          voter: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+
+    const { pollId: gatedId, pollPda: gatedPda } = await createPoll(
+      "Members Poll",
+      ["A", "B"],
+      pollSettings({ minReputation: new anchor.BN(2) })
+    );
+    try {
+      await reputationVote(gatedId, gatedPda);
+      assert.fail("A fresh wallet should not meet the minimum reputation");
+    } catch (err) {
+      expect(err.toString()).to.include("ReputationTooLow");
+    }
+
+    const { pollId: openId, pollPda: openPda } = await createPoll("Open Poll", ["A", "B"]);
+    await reputationVote(openId, openPda);
+    const adminAccounts = { poll: openPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    await program.methods.closePoll(openId).accounts(adminAccounts).rpc();
+    await program.methods.finalizePoll(openId).accounts(adminAccounts).rpc();
+    const recordParticipation = () =>
+      program.methods
+        .recordParticipation(openId)
+        .accounts({
+          poll: openPda,
+          reputation: reputationPda,
+          voteRecord: findVoteRecordPda(openId, voter.publicKey),
+        })
+        .rpc();
+    await recordParticipation();
+    try {
+      await recordParticipation();
+      assert.fail("Participation is recorded once per vote record");
+    } catch (err) {
+      expect(err.toString()).to.include("ParticipationRecorded");
+    }
+
+    const reputation = await program.account.reputation.fetch(reputationPda);
+    assert.equal(reputation.votesCast.toNumber(), 1);
+    assert.equal(reputation.pollsCompleted.toNumber(), 1);
+    await reputationVote(gatedId, gatedPda);
+  });
+
+  it("Requires collection NFTs on NFT-weighted polls", async () => {
+    const nftCollection = anchor.web3.Keypair.generate().publicKey;
+    try {
+      await createPoll("Holder Poll", ["Yes", "No"], pollSettings({ nftCollection, stakeWeighted: true }));
+      assert.fail("NFT weighting can't be combined with stake weighting");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidNftSettings");
+    }
+
+    const { pollId: nftPollId, pollPda: nftPollPda } = await createPoll(
+      "Holder Poll",
+      ["Yes", "No"],
+      pollSettings({ nftCollection })
+    );
+    const [nftBallotPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("nft_ballot"), nftPollPda.toBuffer(), configPda.toBuffer()],
+      program.programId
+    );
+    for (const remainingAccounts of [
+      [],
+      [configPda, configPda, nftBallotPda].map((pubkey) => ({ pubkey, isWritable: true, isSigner: false })),
+    ]) {
+      try {
+        await program.methods
+          .vote(nftPollId, 0)
+          .accounts({
+            ...eventAccounts,
+            poll: nftPollPda,
+            config: configPda,
+            voteRecord: findVoteRecordPda(nftPollId, admin.publicKey),
+            voter: admin.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(remainingAccounts)
+          .rpc();
+        assert.fail("Ballots need the voter's NFTs of the collection");
+      } catch (err) {
+        expect(err.toString()).to.match(/NftRequired|InvalidNft/);
+      }
+    }
+  });
+
+  it("Weighs stake-weighted ballots only by delegated stake", async () => {
+    const { pollId: stakePollId, pollPda: stakePollPda } = await createPoll(
+      "Validator Poll",
+      ["Yes", "No"],
+      pollSettings({ stakeWeighted: true })
+    );
+    const voter = await fundedKeypair();
+    const stakeAccount = anchor.web3.Keypair.generate();
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        anchor.web3.StakeProgram.createAccount({
+          fromPubkey: admin.publicKey,
+          stakePubkey: stakeAccount.publicKey,
+          authorized: new anchor.web3.Authorized(voter.publicKey, voter.publicKey),
+          lamports: anchor.web3.LAMPORTS_PER_SOL,
+        })
+      ),
+      [stakeAccount]
+    );
+    const [stakeBallotPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("stake_ballot"), stakePollPda.toBuffer(), stakeAccount.publicKey.toBuffer()],
+      program.programId
+    );
+    const stakeVote = (remainingAccounts: anchor.web3.AccountMeta[]) =>
+      program.methods
+        .vote(stakePollId, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: stakePollPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(stakePollId, voter.publicKey),
+          voter: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(remainingAccounts)
+        .signers([voter])
+        .rpc();
+
+    try {
+      await stakeVote([]);
+      assert.fail("Ballots without stake accounts should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidStakeAccount");
+    }
+    try {
+      await stakeVote([
+        { pubkey: stakeAccount.publicKey, isSigner: false, isWritable: false },
+        { pubkey: stakeBallotPda, isSigner: false, isWritable: true },
+      ]);
+      assert.fail("Undelegated stake should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidStakeAccount");
+    }
+  });
+
+  it("Holds a vote deposit in the poll until the record is closed", async () => {
+    const deposit = anchor.web3.LAMPORTS_PER_SOL / 100;
+    const { pollId: depositPollId, pollPda: depositPollPda } = await createPoll(
+      "Deposit Poll",
+      ["Yes", "No"],
+      pollSettings({ voteDeposit: new anchor.BN(deposit) })
+    );
+    const voter = await fundedKeypair();
+    const voteRecordPda = findVoteRecordPda(depositPollId, voter.publicKey);
+    const pollLamports = await provider.connection.getBalance(depositPollPda);
+    await program.methods
+      .vote(depositPollId, 1)
+      .accounts({
+        ...eventAccounts,
+        poll: depositPollPda,
+        config: configPda,
+        voteRecord: voteRecordPda,
+        voter: voter.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([voter])
+      .rpc();
+    assert.equal(
+      await provider.connection.getBalance(depositPollPda),
+      pollLamports + deposit
+    );
+
+    const closeRecord = () =>
+      program.methods
+        .closeVoteRecord(depositPollId)
+        .accounts({ poll: depositPollPda, voteRecord: voteRecordPda, voter: voter.publicKey })
+        .signers([voter])
+        .rpc();
+    try {
+      await closeRecord();
+      assert.fail("Records can't be closed while the poll is open");
+    } catch (err) {
+      expect(err.toString()).to.include("PollNotOver");
+    }
+
+    const adminAccounts = { poll: depositPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    await program.methods.closePoll(depositPollId).accounts(adminAccounts).rpc();
+    await program.methods.finalizePoll(depositPollId).accounts(adminAccounts).rpc();
+    await closeRecord();
+
+    assert.equal(await provider.connection.getBalance(depositPollPda), pollLamports);
+    assert.isNull(await program.account.voteRecord.fetchNullable(voteRecordPda));
+  });
+
+  it("Pays a lamport prize pool to the winner's payout address", async () => {
+    const { pollId: prizePollId, pollPda: prizePollPda } = await createPoll("Prize Poll", ["Alice", "Bob"]);
+    const [prizePoolPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("prize"), prizePollPda.toBuffer()],
+      program.programId
+    );
+    const winner = await fundedKeypair();
+    const payoutPda = (index: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("payout"), prizePollPda.toBuffer(), Buffer.from([index])],
+        program.programId
+      )[0];
+    for (const [index, payout] of [
+      [0, winner.publicKey],
+      [1, admin.publicKey],
+    ] as [number, PublicKey][]) {
+      await program.methods
+        .setCandidatePayout(index, payout)
+        .accounts({
+          poll: prizePollPda,
+          config: configPda,
+          candidatePayout: payoutPda(index),
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    const prize = anchor.web3.LAMPORTS_PER_SOL / 10;
+    await program.methods
+      .fundPrizePool(prizePollId, new anchor.BN(prize))
+      .accounts({
+        poll: prizePollPda,
+        prizePool: prizePoolPda,
+        admin: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    await program.methods
+      .vote(prizePollId, 0)
+      .accounts({
+        ...eventAccounts,
+        poll: prizePollPda,
+        config: configPda,
+        voteRecord: findVoteRecordPda(prizePollId, admin.publicKey),
+        voter: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    const adminAccounts = { poll: prizePollPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    await program.methods.closePoll(prizePollId).accounts(adminAccounts).rpc();
+    await program.methods.finalizePoll(prizePollId).accounts(adminAccounts).rpc();
+
+    const claim = (index: number, recipient: anchor.web3.Keypair) =>
+      program.methods
+        .claimPrize(prizePollId)
+        .accounts({
+          ...eventAccounts,
+          poll: prizePollPda,
+          prizePool: prizePoolPda,
+          candidatePayout: payoutPda(index),
+          recipient: recipient.publicKey,
+        })
+        .signers([recipient])
+        .rpc();
+    try {
+      await claim(1, (provider.wallet as anchor.Wallet).payer);
+      assert.fail("Only the winner's payout address can claim");
+    } catch (err) {
+      expect(err.toString()).to.include("NotPrizeRecipient");
+    }
+
+    const before = await provider.connection.getBalance(winner.publicKey);
+    await claim(0, winner);
+    assert.isAbove(await provider.connection.getBalance(winner.publicKey), before + prize - 10_000);
+    assert.isNull(await program.account.prizePool.fetchNullable(prizePoolPda));
+  });
+
+  it("Returns a prize nobody can claim to the admin", async () => {
+    const { pollId: prizePollId, pollPda: prizePollPda } = await createPoll("Unclaimable Prize", ["Alice", "Bob"]);
+    const [prizePoolPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("prize"), prizePollPda.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .fundPrizePool(prizePollId, new anchor.BN(anchor.web3.LAMPORTS_PER_SOL / 10))
+      .accounts({
+        poll: prizePollPda,
+        prizePool: prizePoolPda,
+        admin: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    await program.methods
+      .vote(prizePollId, 1)
+      .accounts({
+        ...eventAccounts,
+        poll: prizePollPda,
+        config: configPda,
+        voteRecord: findVoteRecordPda(prizePollId, admin.publicKey),
+        voter: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    const adminAccounts = { poll: prizePollPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    await program.methods.closePoll(prizePollId).accounts(adminAccounts).rpc();
+    await program.methods.finalizePoll(prizePollId).accounts(adminAccounts).rpc();
+
+    const reclaim = (winnerPayout: PublicKey | null) =>
+      program.methods
+        .reclaimPrize(prizePollId)
+        .accounts({ ...eventAccounts, poll: prizePollPda, prizePool: prizePoolPda, winnerPayout, admin: admin.publicKey })
+        .rpc();
+    try {
+      await reclaim(null);
+      assert.fail("Without the payout address the winner may still claim until the window ends");
+    } catch (err) {
+      expect(err.toString()).to.include("EscrowNotReclaimable");
+    }
+    // Bob never registered a payout address, so the prize can't be claimed
+    const [bobPayout] = PublicKey.findProgramAddressSync(
+      [Buffer.from("payout"), prizePollPda.toBuffer(), Buffer.from([1])],
+      program.programId
+    );
+    await reclaim(bobPayout);
+    assert.isNull(await program.account.prizePool.fetchNullable(prizePoolPda));
+  });
+
+  it("Matches a quadratic funding round's contributions", async () => {
+    try {
+      await createPoll("Quorum Round", ["A", "B"], pollSettings({ quadraticFunding: true, quorum: new anchor.BN(2) }));
+      assert.fail("Funding rounds can't take a quorum");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidQuadraticFundingSettings");
+    }
+    const { pollId: roundId, pollPda: roundPda } = await createPoll(
+      "Funding Round",
+      ["Many Backers", "One Backer"],
+      pollSettings({ quadraticFunding: true })
+    );
+    const [fundingRound] = PublicKey.findProgramAddressSync(
+      [Buffer.from("funding"), roundPda.toBuffer()],
+      program.programId
+    );
+    const projects = [await fundedKeypair(), await fundedKeypair()];
+    const payoutPda = (index: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("payout"), roundPda.toBuffer(), Buffer.from([index])],
+        program.programId
+      )[0];
+    for (const [index, project] of projects.entries()) {
+      await program.methods
+        .setCandidatePayout(index, project.publicKey)
+        .accounts({
+          poll: roundPda,
+          config: configPda,
+          candidatePayout: payoutPda(index),
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+    await program.methods
+      .fundMatchingPool(roundId, new anchor.BN(1_000_000))
+      .accounts({
+        poll: roundPda,
+        fundingRound,
+        admin: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const contribute = async (candidateIndex: number, amount: number) => {
+      const contributor = await fundedKeypair();
+      await program.methods
+        .contribute(roundId, candidateIndex, new anchor.BN(amount))
+        .accounts({
+          ...eventAccounts,
+          poll: roundPda,
+          fundingRound,
+          contribution: PublicKey.findProgramAddressSync(
+            [
+              Buffer.from("contribution"),
+              roundPda.toBuffer(),
+              contributor.publicKey.toBuffer(),
+              Buffer.from([candidateIndex]),
+            ],
+            program.programId
+          )[0],
+          contributor: contributor.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([contributor])
+        .rpc();
+    };
+    for (let i = 0; i < 4; i++) {
+      await contribute(0, 25_000);
+    }
+    await contribute(1, 100_000);
+
+    const adminAccounts = { poll: roundPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    await program.methods.closePoll(roundId).accounts(adminAccounts).rpc();
+    await program.methods.finalizePoll(roundId).accounts(adminAccounts).rpc();
+    await program.methods
+      .finalizeFundingRound(roundId)
+      .accounts({ ...eventAccounts, poll: roundPda, fundingRound })
+      .rpc();
+
+    const round = await program.account.fundingRound.fetch(fundingRound);
+    assert.equal(round.projects[0].contributors, 4);
+    assert.equal(round.projects[0].matched.toNumber(), 300_000);
+    assert.equal(round.projects[1].matched.toNumber(), 0);
+
+    const reclaim = () =>
+      program.methods
+        .reclaimMatchingPool(roundId)
+        .accounts({ poll: roundPda, fundingRound, admin: admin.publicKey })
+        .rpc();
+    const claim = (index: number) =>
+      program.methods
+        .claimFunding(roundId, index)
+        .accounts({
+          ...eventAccounts,
+          poll: roundPda,
+          fundingRound,
+          candidatePayout: payoutPda(index),
+          recipient: projects[index].publicKey,
+        })
+        .signers([projects[index]])
+        .rpc();
+    const before = await provider.connection.getBalance(projects[0].publicKey);
+    await claim(0);
+    assert.isAbove(await provider.connection.getBalance(projects[0].publicKey), before + 400_000 - 10_000);
+    try {
+      await reclaim();
+      assert.fail("Leftovers stay until every project has claimed");
+    } catch (err) {
+      expect(err.toString()).to.include("EscrowNotReclaimable");
+    }
+
+    await claim(1);
+    const adminBefore = await provider.connection.getBalance(admin.publicKey);
+    await reclaim();
+    // The unmatched 700_000 plus the round's rent
+    assert.isAbove(await provider.connection.getBalance(admin.publicKey), adminBefore + 700_000);
+    assert.isNull(await program.account.fundingRound.fetchNullable(fundingRound));
+  });
+
+  it("Seals a petition once it reaches its signature threshold", async () => {
+    const { pollId: petitionId, pollPda: petitionPda } = await createPoll(
+      "Fix the potholes",
+      ["Support"],
+      pollSettings({ petitionThreshold: new anchor.BN(2) })
+    );
+    const sign = async (signer: anchor.web3.Keypair) =>
+      program.methods
+        .vote(petitionId, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: petitionPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(petitionId, signer.publicKey),
+          voter: signer.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([signer])
+        .rpc();
+
+    await sign(await fundedKeypair());
+    let petition = await program.account.poll.fetch(petitionPda);
+    assert.deepEqual(petition.status, { active: {} });
+
+    const signature = await sign(await fundedKeypair());
+    const events = await fetchCpiEvents(signature);
+    assert.isTrue(events.some((event) => event.name === "PetitionThresholdReached"));
+    petition = await program.account.poll.fetch(petitionPda);
+    assert.deepEqual(petition.status, { thresholdReached: {} });
+    assert.equal(petition.winnerIndex, 0);
+
+    try {
+      await sign(await fundedKeypair());
+      assert.fail("Petitions take no signatures after the threshold");
+    } catch (err) {
+      expect(err.toString()).to.include("PollClosed");
+    }
+  });
+
+  it("Pays prediction stakers on the resolved outcome", async () => {
+    const oracle = await fundedKeypair();
+    const { pollId: marketId, pollPda: marketPda } = await createPoll(
+      "Will it rain?",
+      ["Rain", "Sun"],
+      pollSettings({ predictionMarket: true, oracle: oracle.publicKey, voteDeposit: new anchor.BN(50_000) })
+    );
+    const [predictionPool] = PublicKey.findProgramAddressSync(
+      [Buffer.from("prediction"), marketPda.toBuffer()],
+      program.programId
+    );
+    const stake = async (candidateIndex: number, amount: number) => {
+      const voter = await fundedKeypair();
+      await program.methods
+        .placeStake(marketId, candidateIndex, new anchor.BN(amount))
+        .accounts({
+          ...eventAccounts,
+          poll: marketPda,
+          config: configPda,
+          predictionPool,
+          voteRecord: findVoteRecordPda(marketId, voter.publicKey),
+          voter: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+      return voter;
+    };
+    const rainA = await stake(0, 100_000);
+    await stake(0, 300_000);
+    const sun = await stake(1, 200_000);
+
+    try {
+      await program.methods
+        .vote(marketId, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: marketPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(marketId, admin.publicKey),
+          voter: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      assert.fail("Prediction polls only take stakes");
+    } catch (err) {
+      expect(err.toString()).to.include("StakeOnOutcomeRequired");
+    }
+
+    await program.methods
+      .closePoll(marketId)
+      .accounts({ poll: marketPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+      .rpc();
+    await program.methods
+      .resolveOutcome(marketId, 0)
+      .accounts({ ...eventAccounts, poll: marketPda, predictionPool, resolver: oracle.publicKey })
+      .signers([oracle])
+      .rpc();
+
+    const claim = (voter: anchor.web3.Keypair) =>
+      program.methods
+        .claimWinnings(marketId)
+        .accounts({
+          ...eventAccounts,
+          poll: marketPda,
+          predictionPool,
+          voteRecord: findVoteRecordPda(marketId, voter.publicKey),
+          voter: voter.publicKey,
+        })
+        .signers([voter])
+        .rpc();
+    try {
+      await claim(sun);
+      assert.fail("Losing stakes can't claim");
+    } catch (err) {
+      expect(err.toString()).to.include("LosingStake");
+    }
+    // 100k stake plus a quarter of the 200k staked on Sun.
+    const before = await provider.connection.getBalance(rainA.publicKey);
+    const pollBefore = await provider.connection.getBalance(marketPda);
+    await claim(rainA);
+    assert.isAbove(await provider.connection.getBalance(rainA.publicKey), before + 150_000 - 10_000);
+    // The claim also refunds the ballot's vote deposit from the poll
+    assert.equal(await provider.connection.getBalance(marketPda), pollBefore - 50_000);
+  });
+
+  it("Splits a voter reward vault equally among voters", async () => {
+    const { pollId: rewardPollId, pollPda: rewardPollPda } = await createPoll("Turnout Poll", ["Yes", "No"]);
+    const [rewardVaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("reward"), rewardPollPda.toBuffer()],
+      program.programId
+    );
+    const reward = anchor.web3.LAMPORTS_PER_SOL / 10;
+    await program.methods
+      .fundRewardVault(rewardPollId, new anchor.BN(reward), new anchor.BN(0))
+      .accounts({
+        poll: rewardPollPda,
+        rewardVault: rewardVaultPda,
+        admin: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const voters = [await fundedKeypair(), await fundedKeypair()];
+    for (const voter of voters) {
+      await program.methods
+        .vote(rewardPollId, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: rewardPollPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(rewardPollId, voter.publicKey),
+          voter: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+    }
+    const adminAccounts = { poll: rewardPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    await program.methods.closePoll(rewardPollId).accounts(adminAccounts).rpc();
+    await program.methods.finalizePoll(rewardPollId).accounts(adminAccounts).rpc();
+
+    const claim = (voter: anchor.web3.Keypair) =>
+      program.methods
+        .claimReward(rewardPollId)
+        .accounts({
+          ...eventAccounts,
+          poll: rewardPollPda,
+          rewardVault: rewardVaultPda,
+          voteRecord: findVoteRecordPda(rewardPollId, voter.publicKey),
+          voter: voter.publicKey,
+        })
+        .signers([voter])
+        .rpc();
+    const before = await provider.connection.getBalance(voters[0].publicKey);
+    await claim(voters[0]);
+    assert.isAbove(await provider.connection.getBalance(voters[0].publicKey), before + reward / 2 - 10_000);
+    try {
+      await claim(voters[0]);
+      assert.fail("A reward can only be claimed once");
+    } catch (err) {
+      expect(err.toString()).to.include("RewardAlreadyClaimed");
+    }
+    const reclaim = () =>
+      program.methods
+        .reclaimReward(rewardPollId)
+        .accounts({ poll: rewardPollPda, rewardVault: rewardVaultPda, admin: admin.publicKey })
+        .rpc();
+    try {
+      await reclaim();
+      assert.fail("Unclaimed rewards stay until the claim window ends");
+    } catch (err) {
+      expect(err.toString()).to.include("EscrowNotReclaimable");
+    }
+    await claim(voters[1]);
+
+    const vault = await program.account.rewardVault.fetch(rewardVaultPda);
+    assert.equal(vault.share.toNumber(), reward / 2);
+    assert.equal(vault.claims.toNumber(), 2);
+    await reclaim();
+    assert.isNull(await program.account.rewardVault.fetchNullable(rewardVaultPda));
+  });
+
+  it("Attaches a memo to a ballot in the same transaction", async () => {
+    const { pollId: memoPollId, pollPda: memoPollPda } = await createPoll(
+      "Feedback Poll",
+      ["Yes", "No"],
+      pollSettings({ allowMemos: true })
+    );
+    const voteRecordPda = findVoteRecordPda(memoPollId, admin.publicKey);
+    const memoAccounts = { poll: memoPollPda, voteRecord: voteRecordPda, voter: admin.publicKey };
+    await program.methods
+      .vote(memoPollId, 0)
+      .accounts({
+        ...eventAccounts,
+        poll: memoPollPda,
+        config: configPda,
+        voteRecord: voteRecordPda,
+        voter: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .postInstructions([
+        await program.methods
+          .setVoteMemo(memoPollId, "More vegetarian options please")
+          .accounts(memoAccounts)
+          .instruction(),
+      ])
+      .rpc();
+
+    const record = await program.account.voteRecord.fetch(voteRecordPda);
+    assert.equal(record.memo, "More vegetarian options please");
+
+    try {
+      await program.methods
+        .setVoteMemo(memoPollId, "x".repeat(141))
+        .accounts(memoAccounts)
+        .rpc();
+      assert.fail("Memos over 140 bytes should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("MemoTooLong");
+    }
+  });
+
+  it("Lets a candidate withdraw through its payout address", async () => {
+    const { pollId: raceId, pollPda: racePda } = await createPoll("Race", ["Alice", "Bob"]);
+    const alice = await fundedKeypair();
+    const [candidatePayout] = PublicKey.findProgramAddressSync(
+      [Buffer.from("payout"), racePda.toBuffer(), Buffer.from([0])],
+      program.programId
+    );
+    await program.methods
+      .setCandidatePayout(0, alice.publicKey)
+      .accounts({
+        poll: racePda,
+        config: configPda,
+        candidatePayout,
+        admin: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const withdraw = (signer: anchor.web3.Keypair) =>
+      program.methods
+        .withdrawCandidacy(raceId, 0)
+        .accounts({ ...eventAccounts, poll: racePda, candidatePayout, candidate: signer.publicKey })
+        .signers([signer])
+        .rpc();
+    try {
+      await withdraw(await fundedKeypair());
+      assert.fail("Only the candidate can withdraw");
+    } catch (err) {
+      expect(err.toString()).to.include("Unauthorized");
+    }
+    await withdraw(alice);
+
+    const race = await program.account.poll.fetch(racePda);
+    assert.isTrue(race.candidates[0].disqualified);
+    assert.equal(race.withdrawnCandidates.toNumber(), 1);
+    try {
+      await program.methods
+        .vote(raceId, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: racePda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(raceId, admin.publicKey),
+          voter: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      assert.fail("Withdrawn candidates take no ballots");
+    } catch (err) {
+      expect(err.toString()).to.include("CandidateDisqualified");
+    }
+  });
+
+  it("Disqualifies a candidate and excludes its ballots at finalization", async () => {
+    const { pollId: dqPollId, pollPda: dqPollPda } = await createPoll(
+      "Disqualification Poll",
+      ["A", "B"],
+      pollSettings({ disqualificationPolicy: { exclude: {} } })
+    );
+    // Votes as the provider wallet unless another signer is given
+    const voteFor = (candidateIndex: number, signer?: anchor.web3.Keypair) =>
+      program.methods
+        .vote(dqPollId, candidateIndex)
+        .accounts({
+          poll: dqPollPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(dqPollId, (signer ?? admin).publicKey),
+          voter: (signer ?? admin).publicKey,
+          systemProgram: SystemProgram.programId,
+          ...eventAccounts,
+        })
+        .signers(signer ? [signer] : []);
+    await voteFor(0).rpc();
+
+    const adminAccounts = { poll: dqPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    await program.methods
+      .disqualifyCandidate(0, "Ineligible")
+      .accounts(adminAccounts)
+      .rpc();
+
+    const voter = await fundedKeypair();
+    try {
+      await voteFor(0, voter).rpc();
+      assert.fail("Votes for a disqualified candidate should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("CandidateDisqualified");
+    }
+    await voteFor(1, voter).rpc();
+
+    await program.methods.closePoll(dqPollId).accounts(adminAccounts).rpc();
+    const signature = await program.methods.finalizePoll(dqPollId).accounts(adminAccounts).rpc();
+    const [finalized] = await fetchCpiEvents(signature);
+    assert.equal(finalized.data.winner, 1);
+    assert.equal(finalized.data.totalVotes.toNumber(), 1);
+    assert.deepEqual(
+      finalized.data.tallies.map((t) => t.toNumber()),
+      [1, 1]
+    );
+
+    const results = await program.methods
+      .getResults(dqPollId)
+      .accounts({ poll: adminAccounts.poll })
+      .view();
+    assert.deepEqual(results.status, { finalized: {} });
+    assert.deepEqual(results.tallies.map((t) => t.toNumber()), [1, 1]);
+    assert.deepEqual(results.disqualified, [true, false]);
+    assert.equal(results.totalVotes.toNumber(), 1);
+    assert.equal(results.winner, 1);
+
+    // The disqualified candidate doesn't count as the runner-up
+    const sealed = await program.account.poll.fetch(dqPollPda);
+    assert.equal(sealed.winnerIndex, 1);
+    assert.equal(sealed.winningWeight.toNumber(), 10000);
+    assert.equal(sealed.runnerUpWeight.toNumber(), 0);
+    assert.isFalse(sealed.isTie);
+  });
+
+  it("Invalidates a fraudulent vote and keeps its record", async () => {
+    const { pollId: fraudPollId, pollPda: fraudPollPda } = await createPoll("Fraud Poll", ["A", "B"]);
+    const bot = await fundedKeypair();
+    const botRecord = findVoteRecordPda(fraudPollId, bot.publicKey);
+    await program.methods
+      .vote(fraudPollId, 0)
+      .accounts({
+        ...eventAccounts,
+        poll: fraudPollPda,
+        config: configPda,
+        voteRecord: botRecord,
+        voter: bot.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bot])
+      .rpc();
+
+    // Signs as the provider wallet unless another signer is given
+    const invalidate = (signer?: anchor.web3.Keypair) =>
+      program.methods
+        .invalidateVote(bot.publicKey, 2)
+        .accounts({
+          ...eventAccounts,
+          poll: fraudPollPda,
+          config: configPda,
+          voteRecord: botRecord,
+          admin: (signer ?? admin).publicKey,
+        })
+        .signers(signer ? [signer] : []);
+    try {
+      await invalidate(bot).rpc();
+      assert.fail("Only the admin should invalidate votes");
+    } catch (err) {
+      expect(err.toString()).to.include("Unauthorized");
+    }
+    const signature = await invalidate().rpc();
+    const [event] = await fetchCpiEvents(signature);
+    assert.equal(event.name, "VoteInvalidated");
+    assert.equal(event.data.reason, 2);
+
+    const pollAccount = await program.account.poll.fetch(fraudPollPda);
+    assert.equal(pollAccount.candidates[0].votes.toNumber(), 0);
+    assert.equal(pollAccount.totalVotes.toNumber(), 0);
+    const record = await program.account.voteRecord.fetch(botRecord);
+    assert.isTrue(record.invalidated);
+    assert.equal(record.invalidationReason, 2);
+
+    try {
+      await invalidate().rpc();
+      assert.fail("A vote should only be invalidated once");
+    } catch (err) {
+      expect(err.toString()).to.include("VoteInvalidated");
+    }
+  });
+
+  it("Withholds finalized results until the embargo slot", async () => {
+    const embargoSlot = (await provider.connection.getSlot()) + 10;
+    const { pollId: embargoPollId, pollPda: embargoPollPda } = await createPoll(
+      "Embargo Poll",
+      ["A", "B"],
+      pollSettings({ embargoSlot: new anchor.BN(embargoSlot) })
+    );
+    const adminAccounts = { poll: embargoPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    await program.methods.closePoll(embargoPollId).accounts(adminAccounts).rpc();
+    const sealSignature = await program.methods.finalizePoll(embargoPollId).accounts(adminAccounts).rpc();
+    assert.isEmpty(await fetchCpiEvents(sealSignature));
+
+    const publishAccounts = { poll: embargoPollPda, ...eventAccounts };
+    try {
+      await program.methods.publishResults(embargoPollId).accounts(publishAccounts).rpc();
+      assert.fail("Results should stay embargoed");
+    } catch (err) {
+      expect(err.toString()).to.include("ResultsEmbargoed");
+    }
+
+    await waitForSlot(embargoSlot);
+    const signature = await program.methods.publishResults(embargoPollId).accounts(publishAccounts).rpc();
+    const [finalized] = await fetchCpiEvents(signature);
+    assert.equal(finalized.name, "PollFinalized");
+    const pollAccount = await program.account.poll.fetch(embargoPollPda);
+    assert.isTrue(pollAccount.resultsPublished);
+  });
+
+  it("Only commits tie-breaks to Switchboard randomness accounts", async () => {
+    const { pollId: tiedPollId, pollPda: tiedPollPda } = await createPoll("Tied Poll", ["A", "B"]);
+    await program.methods
+      .closePoll(tiedPollId)
+      .accounts({ poll: tiedPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+      .rpc();
+
+    try {
+      await program.methods
+        .commitTieBreak(tiedPollId)
+        .accounts({
+          poll: tiedPollPda,
+          config: configPda,
+          randomness: configPda,
+          admin: admin.publicKey,
+        })
+        .rpc();
+      assert.fail("Accounts not owned by Switchboard should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidRandomnessAccount");
+    }
+  });
+
+  it("Starts a runoff between the leading candidates", async () => {
+    const { pollId: firstId, pollPda: firstPda } = await createPoll(
+      "First Round",
+      ["A", "B", "C"]
+    );
+    const voter = await fundedKeypair();
+    for (const [signer, candidateIndex] of [[admin, 0], [voter, 1]] as const) {
+      await program.methods
+        .vote(firstId, candidateIndex)
+        .accounts({
+          poll: firstPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(firstId, signer.publicKey),
+          voter: signer.publicKey,
+          systemProgram: SystemProgram.programId,
+          ...eventAccounts,
+        })
+        .signers(signer === admin ? [] : [voter])
+        .rpc();
+    }
+    const adminAccounts = { poll: firstPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    await program.methods.closePoll(firstId).accounts(adminAccounts).rpc();
+    await program.methods.finalizePoll(firstId).accounts(adminAccounts).rpc();
+
+    const runoffPda = PublicKey.findProgramAddressSync(
+      [Buffer.from("poll"), firstPda.toBuffer(), firstId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+    await program.methods
+      .startRunoff(firstId, 2, pollSettings())
+      .accounts({
+        ...eventAccounts,
+        config: configPda,
+        parentPoll: firstPda,
+        poll: runoffPda,
+        ...(await adminPollAccounts(admin.publicKey)),
+        admin: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const runoff = await program.account.poll.fetch(runoffPda);
+    assert.deepEqual(runoff.candidates.map((c) => c.name), ["A", "B"]);
+    assert.equal(runoff.parentPoll.toBase58(), firstPda.toBase58());
+    assert.equal(runoff.round, 2);
+    assert.deepEqual(runoff.status, { active: {} });
+  });
+
+  it("Clones a poll's candidates and settings onto a new schedule", async () => {
+    const { pollId: sourceId, pollPda: sourcePda } = await createPoll(
+      "Monthly Board Vote",
+      ["Alice", "Bob", "Carol"],
+      pollSettings({ quorum: new anchor.BN(5), tags: ["board"] })
+    );
+    await program.methods
+      .vote(sourceId, 1)
+      .accounts({
+        ...eventAccounts,
+        poll: sourcePda,
+        config: configPda,
+        voteRecord: findVoteRecordPda(sourceId, admin.publicKey),
+        voter: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const newId = new anchor.BN(Date.now());
+    const clonePda = PublicKey.findProgramAddressSync(
+      [Buffer.from("poll"), admin.publicKey.toBuffer(), newId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+    const endTs = Math.floor(Date.now() / 1000) + 30 * 24 * 3600;
+    const signature = await program.methods
+      .clonePoll(newId, new anchor.BN(0), new anchor.BN(endTs), Array(32).fill(0))
+      .accounts({
+        ...eventAccounts,
+        config: configPda,
+        sourcePoll: sourcePda,
+        poll: clonePda,
+        ...(await adminPollAccounts(admin.publicKey)),
+        admin: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const clone = await program.account.poll.fetch(clonePda);
+    assert.equal(clone.title, "Monthly Board Vote");
+    assert.deepEqual(clone.candidates.map((c) => c.name), ["Alice", "Bob", "Carol"]);
+    assert.isTrue(clone.candidates.every((c) => c.votes.toNumber() === 0));
+    assert.equal(clone.quorum.toNumber(), 5);
+    assert.deepEqual(clone.tags, ["board"]);
+    assert.equal(clone.endTs.toNumber(), endTs);
+    assert.equal(clone.namespace.toBase58(), admin.publicKey.toBase58());
+    const events = await fetchCpiEvents(signature);
+    const cloned = events.find((event) => event.name === "PollCloned");
+    assert.equal(cloned.data.sourcePoll.toBase58(), sourcePda.toBase58());
+  });
+
+  it("Gives a clone of a pseudonymous poll its own record salt", async () => {
+    const sourceSalt = Array(32).fill(7);
+    const { pollPda: sourcePda } = await createPoll(
+      "Weekly Pulse",
+      ["Yes", "No"],
+      pollSettings({ recordSalt: sourceSalt })
+    );
+    const newId = new anchor.BN(Date.now());
+    const clonePda = PublicKey.findProgramAddressSync(
+      [Buffer.from("poll"), admin.publicKey.toBuffer(), newId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+    const clone = async (salt: number[]) =>
+      program.methods
+        .clonePoll(newId, new anchor.BN(0), new anchor.BN(0), salt)
+        .accounts({
+          ...eventAccounts,
+          config: configPda,
+          sourcePoll: sourcePda,
+          poll: clonePda,
+          ...(await adminPollAccounts(admin.publicKey)),
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    for (const salt of [sourceSalt, Array(32).fill(0)]) {
+      try {
+        await clone(salt);
+        assert.fail("Clones must not share or drop the record salt");
+      } catch (err) {
+        expect(err.toString()).to.include("RecordSaltReused");
+      }
+    }
+    await clone(Array(32).fill(9));
+
+    const source = await program.account.poll.fetch(sourcePda);
+    const cloned = await program.account.poll.fetch(clonePda);
+    assert.deepEqual(source.recordSalt, sourceSalt);
+    assert.deepEqual(cloned.recordSalt, Array(32).fill(9));
+    assert.notDeepEqual(cloned.recordSalt, source.recordSalt);
+  });
+
+  it("Stores a reporting threshold of at most 100%", async () => {
+    const { pollPda: reportingPollPda } = await createPoll(
+      "Reporting Poll",
+      ["A", "B", "C"],
+      pollSettings({ reportingThresholdBps: 500 })
+    );
+    const pollAccount = await program.account.poll.fetch(reportingPollPda);
+    assert.equal(pollAccount.reportingThresholdBps, 500);
+
+    try {
+      await createPoll("Reporting Poll", ["A", "B"], pollSettings({ reportingThresholdBps: 10_001 }));
+      assert.fail("Threshold above 100% should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidReportingThreshold");
+    }
+  });
+
+  it("Extends a deadline up to the maximum set at creation", async () => {
+    const endTs = Math.floor(Date.now() / 1000) + 3600;
+    const { pollPda: extendPollPda } = await createPoll(
+      "Extendable Poll",
+      ["A", "B"],
+      pollSettings({ endTs: new anchor.BN(endTs), maxExtension: new anchor.BN(600) })
+    );
+    const extend = (newEndTs: number) =>
+      program.methods
+        .extendDeadline(new anchor.BN(newEndTs))
+        .accounts({ poll: extendPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+        .rpc();
+
+    const signature = await extend(endTs + 300);
+    const [extended] = await fetchCpiEvents(signature);
+    assert.equal(extended.name, "DeadlineExtended");
+    assert.equal(extended.data.previousEndTs.toNumber(), endTs);
+    await extend(endTs + 600);
+
+    for (const newEndTs of [endTs + 600, endTs + 601]) {
+      try {
+        await extend(newEndTs);
+        assert.fail("Deadline must move later and stay within the maximum extension");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidDeadlineExtension");
+      }
+    }
+    const pollAccount = await program.account.poll.fetch(extendPollPda);
+    assert.equal(pollAccount.endTs.toNumber(), endTs + 600);
+  });
+
+  it("Rejects votes before a slot-based start", async () => {
+    const currentSlot = await provider.connection.getSlot();
+    const { pollId: slotPollId, pollPda: slotPollPda } = await createPoll(
+      "Slot Poll",
+      ["A", "B"],
+      pollSettings({
+        clockSource: { slot: {} },
+        startTs: new anchor.BN(currentSlot + 1_000_000),
+        endTs: new anchor.BN(currentSlot + 2_000_000),
+      })
+    );
+
+    const voteRecordPda = findVoteRecordPda(slotPollId, admin.publicKey);
+
+    try {
+      await program.methods
+        .vote(slotPollId, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: slotPollPda,
+          config: configPda,
+          voteRecord: voteRecordPda,
+          voter: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      assert.fail("Should not accept votes before the start slot");
+    } catch (err) {
+      expect(err.toString()).to.include("VotingNotStarted");
+    }
+  });
+
+  it("Closes polls lazily once their deadline passes", async () => {
+    const createExpiringPoll = async () => {
+      const currentSlot = await provider.connection.getSlot();
+      return createPoll(
+        "Expiring Poll",
+        ["A", "B"],
+        pollSettings({ clockSource: { slot: {} }, endTs: new anchor.BN(currentSlot + 20) })
+      );
+    };
+    const { pollId: votedPollId, pollPda: votedPollPda } = await createExpiringPoll();
+    const { pollId: crankedPollId, pollPda: crankedPollPda } = await createExpiringPoll();
+    try {
+      await program.methods
+        .crankCloseExpired(crankedPollId)
+        .accounts({ poll: crankedPollPda, ...eventAccounts })
+        .rpc();
+      assert.fail("Polls before their deadline cannot be cranked closed");
+    } catch (err) {
+      expect(err.toString()).to.include("PollNotExpired");
+    }
+    const { endTs } = await program.account.poll.fetch(crankedPollPda);
+    await waitForSlot(endTs.toNumber());
+
+    // A late vote closes the poll but is not counted
+    const voteRecordPda = findVoteRecordPda(votedPollId, admin.publicKey);
+    await program.methods
+      .vote(votedPollId, 0)
+      .accounts({
+        poll: votedPollPda,
+        config: configPda,
+        voteRecord: voteRecordPda,
+        voter: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+        ...eventAccounts,
+      })
+      .rpc();
+    const votedPoll = await program.account.poll.fetch(votedPollPda);
+    assert.deepEqual(votedPoll.status, { closed: {} });
+    assert.equal(votedPoll.totalVotes.toNumber(), 0);
+    assert.isNull(await provider.connection.getAccountInfo(voteRecordPda));
+
+    await program.methods
+      .crankCloseExpired(crankedPollId)
+      .accounts({ poll: crankedPollPda, ...eventAccounts })
+      .rpc();
+    const crankedPoll = await program.account.poll.fetch(crankedPollPda);
+    assert.deepEqual(crankedPoll.status, { closed: {} });
+  });
+
+  it("Pays a tip to whoever auto-finalizes after the deadline", async () => {
+    const tip = 10_000;
+    const currentSlot = await provider.connection.getSlot();
+    const { pollId: autoPollId, pollPda: autoPollPda } = await createPoll(
+      "Auto Poll",
+      ["A", "B"],
+      pollSettings({
+        clockSource: { slot: {} },
+        endTs: new anchor.BN(currentSlot + 20),
+        finalizeTip: new anchor.BN(tip),
+      })
+    );
+    const [feeVaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("fee_vault"), autoPollPda.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .fundFeeVault(autoPollId, new anchor.BN(anchor.web3.LAMPORTS_PER_SOL / 100))
+      .accounts({
+        poll: autoPollPda,
+        feeVault: feeVaultPda,
+        funder: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const cranker = await fundedKeypair();
+    const autoFinalize = () =>
+      program.methods
+        .autoFinalize(autoPollId)
+        .accounts({ poll: autoPollPda, feeVault: feeVaultPda, cranker: cranker.publicKey, ...eventAccounts })
+        .signers([cranker])
+        .rpc();
+    try {
+      await autoFinalize();
+      assert.fail("Should not auto-finalize before the deadline");
+    } catch (err) {
+      expect(err.toString()).to.include("DeadlineNotReached");
+    }
+
+    await waitForSlot(currentSlot + 20);
+    const vaultBefore = await provider.connection.getBalance(feeVaultPda);
+    await autoFinalize();
+
+    const pollAccount = await program.account.poll.fetch(autoPollPda);
+    assert.deepEqual(pollAccount.status, { finalized: {} });
+    assert.equal(vaultBefore - (await provider.connection.getBalance(feeVaultPda)), tip);
+  });
+
+  it("Hands a poll over to a new admin in two steps", async () => {
+    const { pollId: handoverPollId, pollPda: handoverPollPda } = await createPoll(
+      "Handover Poll",
+      ["A", "B"]
+    );
+    const newAdmin = await fundedKeypair();
+
+    await program.methods
+      .proposeAdminTransfer(newAdmin.publicKey)
+      .accounts({ poll: handoverPollPda, admin: admin.publicKey, ...eventAccounts })
+      .rpc();
+
+    try {
+      await program.methods
+        .acceptAdminTransfer()
+        .accounts({ poll: handoverPollPda, newAdmin: admin.publicKey, ...eventAccounts })
+        .rpc();
+      assert.fail("Only the pending admin can accept");
+    } catch (err) {
+      expect(err.toString()).to.include("NotPendingAdmin");
+    }
+
+    await program.methods
+      .acceptAdminTransfer()
+      .accounts({ poll: handoverPollPda, newAdmin: newAdmin.publicKey, ...eventAccounts })
+      .signers([newAdmin])
+      .rpc();
+
+    const pollAccount = await program.account.poll.fetch(handoverPollPda);
+    assert.equal(pollAccount.admin.toBase58(), newAdmin.publicKey.toBase58());
+    assert.equal(pollAccount.pendingAdmin.toBase58(), PublicKey.default.toBase58());
+
+    try {
+      await program.methods
+        .closePoll(handoverPollId)
+        .accounts({ poll: handoverPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+        .rpc();
+      assert.fail("The previous admin should no longer close the poll");
+    } catch (err) {
+      expect(err.toString()).to.include("Unauthorized");
+    }
+  });
+
+  it("Lets a co-admin close but not finalize", async () => {
+    const { pollId: rolePollId, pollPda: rolePollPda } = await createPoll(
+      "Co-admin Poll",
+      ["A", "B"]
+    );
+    const coAdmin = await fundedKeypair();
+    const [rolePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("role"), rolePollPda.toBuffer(), coAdmin.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .grantRole(coAdmin.publicKey, 1) // PERMISSION_CLOSE
+      .accounts({
+        ...eventAccounts,
+        poll: rolePollPda,
+        config: configPda,
+        role: rolePda,
+        admin: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    await program.methods
+      .closePoll(rolePollId)
+      .accounts({ poll: rolePollPda, config: configPda, role: rolePda, admin: coAdmin.publicKey, ...eventAccounts })
+      .signers([coAdmin])
+      .rpc();
+
+    try {
+      await program.methods
+        .finalizePoll(rolePollId)
+        .accounts({ poll: rolePollPda, config: configPda, role: rolePda, admin: coAdmin.publicKey, ...eventAccounts })
+        .signers([coAdmin])
+        .rpc();
+      assert.fail("A close-only co-admin should not finalize");
+    } catch (err) {
+      expect(err.toString()).to.include("Unauthorized");
+    }
+
+    await program.methods
+      .revokeRole()
+      .accounts({ poll: rolePollPda, config: configPda, role: rolePda, admin: admin.publicKey, ...eventAccounts })
+      .rpc();
+    assert.isNull(await program.account.pollRole.fetchNullable(rolePda));
+  });
+
+  it("Lets the operations key close and finalize but nothing else", async () => {
+    const { pollId: opsPollId, pollPda: opsPollPda } = await createPoll("Ops Poll", ["A", "B"]);
+    const opsBot = await fundedKeypair();
+    await program.methods
+      .setOperationsKey(opsBot.publicKey)
+      .accounts({ poll: opsPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+      .rpc();
+    const opsAccounts = { poll: opsPollPda, config: configPda, admin: opsBot.publicKey, ...eventAccounts };
+
+    try {
+      await program.methods
+        .cancelPoll(opsPollId, "Not the bot's call")
+        .accounts(opsAccounts)
+        .signers([opsBot])
+        .rpc();
+      assert.fail("The operations key should not cancel");
+    } catch (err) {
+      expect(err.toString()).to.include("Unauthorized");
+    }
+
+    await program.methods.closePoll(opsPollId).accounts(opsAccounts).signers([opsBot]).rpc();
+    await program.methods.finalizePoll(opsPollId).accounts(opsAccounts).signers([opsBot]).rpc();
+    const pollAccount = await program.account.poll.fetch(opsPollPda);
+    assert.deepEqual(pollAccount.status, { finalized: {} });
+    assert.equal(pollAccount.operationsKey.toBase58(), opsBot.publicKey.toBase58());
+  });
+
+  it("Restricts poll creation through the permission matrix", async () => {
+    const defaults = (await program.account.programConfig.fetch(configPda)).permissions;
+    const outsider = await fundedKeypair();
+    const { nextPollId } = await program.account.pollCounter.fetch(pollCounterPda);
+
+    // Anyone: nothing; other rows unchanged
+    await program.methods
+      .setPermissionMatrix([0, defaults[1], defaults[2], defaults[3]])
+      .accounts({ ...eventAccounts, config: configPda, authority: admin.publicKey })
+      .rpc();
+
+    try {
+      await program.methods
+        .createPoll("Outsider Poll", ["A", "B"], pollSettings())
+        .accounts({
+          ...eventAccounts,
+          pollCounter: pollCounterPda,
+          config: configPda,
+          poll: findPollPda(nextPollId),
+          ...(await adminPollAccounts(outsider.publicKey)),
+          admin: outsider.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([outsider])
+        .rpc();
+      assert.fail("Creation should be restricted to the config authority");
+    } catch (err) {
+      expect(err.toString()).to.include("ActionNotPermitted");
+    }
+
+    // The config authority keeps ACTION_CREATE
+    await createPoll("Authority Poll", ["A", "B"]);
+
+    await program.methods
+      .setPermissionMatrix(defaults)
+      .accounts({ ...eventAccounts, config: configPda, authority: admin.publicKey })
+      .rpc();
+  });
+
+  it("Enforces tunable limits from the program config", async () => {
+    const { limits } = await program.account.programConfig.fetch(configPda);
+    assert.equal(limits.maxCandidates, 10);
+
+    await program.methods
+      .updateConfig({ ...limits, maxCandidates: 3 })
+      .accounts({ ...eventAccounts, config: configPda, authority: admin.publicKey })
+      .rpc();
+
+    try {
+      await createPoll("Too Many", ["A", "B", "C", "D"]);
+      assert.fail("Should respect the configured candidate limit");
+    } catch (err) {
+      expect(err.toString()).to.include("TooManyCandidates");
+    }
+
+    try {
+      await program.methods
+        .updateConfig({ ...limits, maxCandidateNameLen: 101 })
+        .accounts({ ...eventAccounts, config: configPda, authority: admin.publicKey })
+        .rpc();
+      assert.fail("Limits above the hard caps should be rejected");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidConfig");
+      expect(err.logs.join("\n")).to.include("max_candidate_name_len");
+    }
+
+    await program.methods
+      .updateConfig(limits)
+      .accounts({ ...eventAccounts, config: configPda, authority: admin.publicKey })
+      .rpc();
+  });
+
+  it("Charges the poll creation fee into the treasury", async () => {
+    const [treasuryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury")],
+      program.programId
+    );
+    const fee = anchor.web3.LAMPORTS_PER_SOL / 10;
+    const setFee = (lamports: number) =>
+      program.methods
+        .setPollCreationFee(new anchor.BN(lamports))
+        .accounts({
+          config: configPda,
+          treasury: treasuryPda,
+          authority: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    await setFee(fee);
+    try {
+      try {
+        await createPoll("Unpaid Poll", ["A", "B"]);
+        assert.fail("Creation should need the treasury while a fee is set");
+      } catch (err) {
+        expect(err.toString()).to.include("TreasuryRequired");
+      }
+
+      const before = await provider.connection.getBalance(treasuryPda);
+      const { nextPollId } = await program.account.pollCounter.fetch(pollCounterPda);
+      await program.methods
+        .createPoll("Paid Poll", ["A", "B"], pollSettings())
+        .accounts({
+          ...eventAccounts,
+          pollCounter: pollCounterPda,
+          config: configPda,
+          poll: findPollPda(nextPollId),
+          ...(await adminPollAccounts(admin.publicKey)),
+          treasury: treasuryPda,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      assert.equal(await provider.connection.getBalance(treasuryPda), before + fee);
+    } finally {
+      await setFee(0);
+    }
+
+    const destination = anchor.web3.Keypair.generate().publicKey;
+    const withdraw = (lamports: number, authority = admin.publicKey) =>
+      program.methods
+        .withdrawTreasury(new anchor.BN(lamports))
+        .accounts({ ...eventAccounts, config: configPda, treasury: treasuryPda, destination, authority });
+    const outsider = await fundedKeypair();
+    try {
+      await withdraw(fee, outsider.publicKey).signers([outsider]).rpc();
+      assert.fail("Only the config authority can withdraw");
+    } catch (err) {
+      expect(err.toString()).to.include("Unauthorized");
+    }
+    await withdraw(fee).rpc();
+    assert.equal(await provider.connection.getBalance(destination), fee);
+    const treasury = await program.account.treasury.fetch(treasuryPda);
+    assert.equal(treasury.totalWithdrawn.toNumber(), fee);
+  });
+
+  it("Pauses voting and poll creation", async () => {
+    const setPause = (paused: boolean) =>
+      program.methods
+        .setPause(paused)
+        .accounts({ ...eventAccounts, config: configPda, authority: admin.publicKey })
+        .rpc();
+
+    await setPause(true);
+    try {
+      await createPoll("Paused Poll", ["A", "B"]);
+      assert.fail("Should not create polls while paused");
+    } catch (err) {
+      expect(err.toString()).to.include("ProgramPaused");
+    }
+    await setPause(false);
+
+    const config = await program.account.programConfig.fetch(configPda);
+    assert.isFalse(config.paused);
+  });
+
+  it("Enforces the minimum poll duration", async () => {
+    const setMinDuration = (seconds: number) =>
+      program.methods
+        .setMinPollDuration(new anchor.BN(seconds))
+        .accounts({ ...eventAccounts, config: configPda, authority: admin.publicKey })
+        .rpc();
+    const endingIn = (seconds: number) =>
+      pollSettings({ endTs: new anchor.BN(Math.floor(Date.now() / 1000) + seconds) });
+
+    await setMinDuration(3600);
+    try {
+      try {
+        await createPoll("Snap Poll", ["A", "B"], endingIn(600));
+        assert.fail("Should not create polls shorter than the minimum");
+      } catch (err) {
+        expect(err.toString()).to.include("PollTooShort");
+      }
+
+      const { pollId: id, pollPda: pda } = await createPoll("Long Poll", ["A", "B"], endingIn(7200));
+      await program.methods
+        .vote(id, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: pda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(id, admin.publicKey),
+          voter: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      try {
+        await program.methods
+          .closePoll(id)
+          .accounts({ poll: pda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+          .rpc();
+        assert.fail("Should not close a poll with votes before the minimum duration");
+      } catch (err) {
+        expect(err.toString()).to.include("MinDurationNotMet");
+      }
+    } finally {
+      await setMinDuration(0);
+    }
+  });
+
+  it("Records vote changes in the vote record's history", async () => {
+    const { pollId: changePollId, pollPda: changePollPda } = await createPoll(
+      "Changeable Poll",
+      ["A", "B", "C"],
+      pollSettings({ allowVoteChanges: true })
+    );
+    const voteRecordPda = findVoteRecordPda(changePollId, admin.publicKey);
+    const voteAccounts = {
+      ...eventAccounts,
+      poll: changePollPda,
+      config: configPda,
+      voteRecord: voteRecordPda,
+      voter: admin.publicKey,
+      systemProgram: SystemProgram.programId,
+    };
+
+    await program.methods.vote(changePollId, 0).accounts(voteAccounts).rpc();
+    await program.methods.changeVote(changePollId, 2).accounts(voteAccounts).rpc();
+
+    const pollAccount = await program.account.poll.fetch(changePollPda);
+    assert.equal(pollAccount.candidates[0].votes.toNumber(), 0);
+    assert.equal(pollAccount.candidates[2].votes.toNumber(), 1);
+    assert.equal(pollAccount.totalVotes.toNumber(), 1);
+
+    const record = await program.account.voteRecord.fetch(voteRecordPda);
+    assert.equal(record.candidateIndex, 2);
+    assert.equal(record.changes.length, 1);
+    assert.equal(record.changes[0].previousIndex, 0);
+    assert.equal(record.changes[0].newIndex, 2);
+  });
+
+  it("Only accepts ballots from proven voter roll members", async () => {
+    const { pollId: rollPollId, pollPda: rollPollPda } = await createPoll(
+      "Private Roll Poll",
+      ["A", "B"],
+      pollSettings({ voterRoll: true })
+    );
+    const member = await fundedKeypair();
+    const outsider = await fundedKeypair();
+    const salt = randomBytes(32);
+    const identityHash = createHash("sha256")
+      .update(Buffer.concat([rollPollPda.toBuffer(), member.publicKey.toBuffer(), salt]))
+      .digest();
+    const [rollEntry] = PublicKey.findProgramAddressSync(
+      [Buffer.from("roll"), rollPollPda.toBuffer(), identityHash],
+      program.programId
+    );
+    const findMembershipPda = (voter: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("member"), rollPollPda.toBuffer(), voter.toBuffer()],
+        program.programId
+      )[0];
+
+    await program.methods
+      .addRollEntry([...identityHash], 0)
+      .accounts({
+        poll: rollPollPda,
+        config: configPda,
+        rollEntry,
+        admin: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    await program.methods
+      .proveMembership([...salt])
+      .accounts({
+        poll: rollPollPda,
+        rollEntry,
+        membership: findMembershipPda(member.publicKey),
+        voter: member.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([member])
+      .rpc();
+
+    await program.methods
+      .vote(rollPollId, 1)
+      .accounts({
+        ...eventAccounts,
+        poll: rollPollPda,
+        config: configPda,
+        voteRecord: findVoteRecordPda(rollPollId, member.publicKey),
+        membership: findMembershipPda(member.publicKey),
+        voter: member.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([member])
+      .rpc();
+
+    try {
+      await program.methods
+        .vote(rollPollId, 1)
+        .accounts({
+          ...eventAccounts,
+          poll: rollPollPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(rollPollId, outsider.publicKey),
+          voter: outsider.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([outsider])
+        .rpc();
+      assert.fail("Non-members should not vote on a voter roll poll");
+    } catch (err) {
+      expect(err.toString()).to.include("NotOnVoterRoll");
+    }
+  });
+
+  it("Measures turnout quorum against the voter roll", async () => {
+    try {
+      await createPoll("Turnout Poll", ["A", "B"], pollSettings({ quorumBps: 6000 }));
+      assert.fail("A turnout quorum needs a voter roll");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidQuorum");
+    }
+
+    const { pollId: turnoutPollId, pollPda: turnoutPollPda } = await createPoll(
+      "Turnout Poll",
+      ["A", "B"],
+      pollSettings({ voterRoll: true, quorumBps: 6000 })
+    );
+    const member = await fundedKeypair();
+    const salt = randomBytes(32);
+    const memberHash = createHash("sha256")
+      .update(Buffer.concat([turnoutPollPda.toBuffer(), member.publicKey.toBuffer(), salt]))
+      .digest();
+    const findRollEntryPda = (identityHash: Buffer) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("roll"), turnoutPollPda.toBuffer(), identityHash],
+        program.programId
+      )[0];
+    const [membership] = PublicKey.findProgramAddressSync(
+      [Buffer.from("member"), turnoutPollPda.toBuffer(), member.publicKey.toBuffer()],
+      program.programId
+    );
+
+    // Two eligible voters, only one of whom votes: 50% turnout
+    for (const identityHash of [memberHash, randomBytes(32)]) {
+      await program.methods
+        .addRollEntry([...identityHash], 0)
+        .accounts({
+          poll: turnoutPollPda,
+          config: configPda,
+          rollEntry: findRollEntryPda(identityHash),
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+    await program.methods
+      .proveMembership([...salt])
+      .accounts({
+        poll: turnoutPollPda,
+        rollEntry: findRollEntryPda(memberHash),
+        membership,
+        voter: member.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([member])
+      .rpc();
+    await program.methods
+      .vote(turnoutPollId, 0)
+      .accounts({
+        ...eventAccounts,
+        poll: turnoutPollPda,
+        config: configPda,
+        voteRecord: findVoteRecordPda(turnoutPollId, member.publicKey),
+        membership,
+        voter: member.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([member])
+      .rpc();
+
+    const adminAccounts = { poll: turnoutPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts };
+    await program.methods.closePoll(turnoutPollId).accounts(adminAccounts).rpc();
+    await program.methods.finalizePoll(turnoutPollId).accounts(adminAccounts).rpc();
+
+    const pollAccount = await program.account.poll.fetch(turnoutPollPda);
+    assert.equal(pollAccount.eligibleVoterCount.toNumber(), 2);
+    assert.deepEqual(pollAccount.status, { quorumNotMet: {} });
+  });
+
+  it("Weighs and tallies ballots per voter tier", async () => {
+    try {
+      await createPoll("Tiers Without Roll", ["A", "B"], pollSettings({ tierWeights: [3, 1] }));
+      assert.fail("Voter tiers need a voter roll");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidVoterTiers");
+    }
+
+    // A council where each senior member counts three times a junior one
+    const { pollId: councilPollId, pollPda: councilPollPda } = await createPoll(
+      "Council Election",
+      ["A", "B"],
+      pollSettings({ voterRoll: true, tierWeights: [3, 1] })
+    );
+    const findRollEntryPda = (identityHash: Buffer) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("roll"), councilPollPda.toBuffer(), identityHash],
+        program.programId
+      )[0];
+    const addMember = async (tier: number) => {
+      const member = await fundedKeypair();
+      const salt = randomBytes(32);
+      const identityHash = createHash("sha256")
+        .update(Buffer.concat([councilPollPda.toBuffer(), member.publicKey.toBuffer(), salt]))
+        .digest();
+      await program.methods
+        .addRollEntry([...identityHash], tier)
+        .accounts({
+          poll: councilPollPda,
+          config: configPda,
+          rollEntry: findRollEntryPda(identityHash),
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      const [membership] = PublicKey.findProgramAddressSync(
+        [Buffer.from("member"), councilPollPda.toBuffer(), member.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .proveMembership([...salt])
+        .accounts({
+          poll: councilPollPda,
+          rollEntry: findRollEntryPda(identityHash),
+          membership,
+          voter: member.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([member])
+        .rpc();
+      return { member, membership };
+    };
+    const tierVote = async (tier: number, candidate: number) => {
+      const { member, membership } = await addMember(tier);
+      await program.methods
+        .vote(councilPollId, candidate)
+        .accounts({
+          ...eventAccounts,
+          poll: councilPollPda,
+          config: configPda,
+          voteRecord: findVoteRecordPda(councilPollId, member.publicKey),
+          membership,
+          voter: member.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([member])
+        .rpc();
+    };
+
+    try {
+      await addMember(2);
+      assert.fail("Roll entries must name one of the poll's tiers");
+    } catch (err) {
+      expect(err.toString()).to.include("InvalidVoterTier");
+    }
+    await tierVote(0, 0);
+    await tierVote(1, 1);
+    await tierVote(1, 1);
+
+    const pollAccount = await program.account.poll.fetch(councilPollPda);
+    // Two junior ballots for B are outweighed by one senior ballot for A
+    assert.deepEqual(pollAccount.candidates.map((c) => c.votes.toNumber()), [1, 2]);
+    assert.isAbove(
+      pollAccount.candidates[0].weight.toNumber(),
+      pollAccount.candidates[1].weight.toNumber()
+    );
+    const results = await program.methods
+      .getResults(councilPollId)
+      .accounts({ poll: councilPollPda })
+      .view();
+    assert.deepEqual(
+      results.tierTallies.map((tallies) => tallies.map((votes) => votes.toNumber())),
+      [
+        [1, 0],
+        [0, 2],
+      ]
+    );
+  });
+
+  it("Edits poll metadata only before the first vote", async () => {
+    const { pollId: metaPollId, pollPda: metaPollPda } = await createPoll(
+      "Metadata Poll",
+      ["A", "B"]
+    );
+    const updateMetadata = (description: string) =>
+      program.methods
+        .updatePollMetadata(description, "https://example.org/poll.json")
+        .accounts({ poll: metaPollPda, config: configPda, admin: admin.publicKey })
+        .rpc();
+
+    await updateMetadata("Pick the next community project.");
+    const metadataHash = [...createHash("sha256").update('{"rules":"one wallet, one vote"}').digest()];
+    const setMetadataHash = () =>
+      program.methods
+        .setMetadataHash(metadataHash)
+        .accounts({ poll: metaPollPda, config: configPda, admin: admin.publicKey })
+        .rpc();
+    await setMetadataHash();
+    const pollAccount = await program.account.poll.fetch(metaPollPda);
+    assert.equal(pollAccount.description, "Pick the next community project.");
+    assert.equal(pollAccount.metadataUri, "https://example.org/poll.json");
+    assert.deepEqual(pollAccount.metadataHash, metadataHash);
+
+    await program.methods
+      .vote(metaPollId, 0)
+      .accounts({
+        ...eventAccounts,
+        poll: metaPollPda,
+        config: configPda,
+        voteRecord: findVoteRecordPda(metaPollId, admin.publicKey),
+        voter: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    try {
+      await updateMetadata("Changed after voting");
+      assert.fail("Metadata should be frozen once votes exist");
+    } catch (err) {
+      expect(err.toString()).to.include("PollHasVotes");
+    }
+    try {
+      await setMetadataHash();
+      assert.fail("The metadata hash should be frozen once votes exist");
+    } catch (err) {
+      expect(err.toString()).to.include("PollHasVotes");
+    }
+  });
+
+  it("Fixes a title typo before voting starts", async () => {
+    const { pollPda: typoPollPda } = await createPoll("Best Blokchain", ["A", "B"]);
+
+    await program.methods
+      .updatePollTitle("Best Blockchain")
+      .accounts({
+        ...eventAccounts,
+        poll: typoPollPda,
+        config: configPda,
+        admin: admin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const pollAccount = await program.account.poll.fetch(typoPollPda);
+    assert.equal(pollAccount.title, "Best Blockchain");
+  });
+
+  it("Stores only hashes and URIs for hash-only content", async () => {
+    const { pollPda: contentPollPda } = await createPoll(
+      "Hash-only Poll",
+      ["A", "B"],
+      pollSettings({ contentMode: { hashOnly: {} } })
+    );
+    const [descriptionPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("description"), contentPollPda.toBuffer(), Buffer.from([0])],
+      program.programId
+    );
+    const setDescription = (content: object) =>
+      program.methods
+        .setCandidateDescription(0, content)
+        .accounts({
+          poll: contentPollPda,
+          config: configPda,
+          description: descriptionPda,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    try {
+      await setDescription({ text: { 0: "A long manifesto" } });
+      assert.fail("Inline text should be rejected in hash-only mode");
+    } catch (err) {
+      expect(err.toString()).to.include("InlineContentDisabled");
+    }
+
+    const text = "A long manifesto";
+    const hash = createHash("sha256").update(text).digest();
+    await setDescription({ hashed: { hash: [...hash], uri: "ipfs://manifesto" } });
+
+    const description = await program.account.candidateDescription.fetch(descriptionPda);
+    assert.equal(description.content.hashed.uri, "ipfs://manifesto");
+  });
+
+  it("Requires 2-of-3 approvals to close a multisig poll", async () => {
+    const approvers = [await fundedKeypair(), await fundedKeypair(), await fundedKeypair()];
+    const { pollId: msPollId, pollPda: msPollPda } = await createPoll(
+      "Multisig Poll",
+      ["A", "B"],
+      pollSettings({
+        approvers: approvers.map((a) => a.publicKey),
+        approvalThreshold: 2,
+      })
+    );
+    const approve = (approver: anchor.web3.Keypair) =>
+      program.methods
+        .approveAction({ close: {} })
+        .accounts({
+          ...eventAccounts,
+          poll: msPollPda,
+          approval: PublicKey.findProgramAddressSync(
+            [
+              Buffer.from("approval"),
+              msPollPda.toBuffer(),
+              Buffer.from([0]),
+              approver.publicKey.toBuffer(),
+            ],
+            program.programId
+          )[0],
+          approver: approver.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([approver])
+        .rpc();
+    const close = () =>
+      program.methods
+        .closePoll(msPollId)
+        .accounts({ poll: msPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+        .rpc();
+
+    await approve(approvers[0]);
+    try {
+      await close();
+      assert.fail("One approval should not be enough");
+    } catch (err) {
+      expect(err.toString()).to.include("ApprovalThresholdNotMet");
+    }
+
+    await approve(approvers[2]);
+    await close();
+
+    const pollAccount = await program.account.poll.fetch(msPollPda);
+    assert.deepEqual(pollAccount.status, { closed: {} });
+    assert.equal(pollAccount.closeApprovals, 2);
+  });
+
+  describe("parallel tallies", () => {
+    let parallelPollId: anchor.BN;
+    let parallelPollPda: PublicKey;
+    const tallyPda = (index: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("tally"), parallelPollPda.toBuffer(), Buffer.from([index])],
+        program.programId
+      )[0];
+
+    it("Creates a parallel-tally poll and its counters", async () => {
+      ({ pollId: parallelPollId, pollPda: parallelPollPda } = await createPoll(
+        "Parallel Poll",
+        ["Yes", "No"],
+        pollSettings({ parallelTallies: true })
+      ));
+
+      for (const index of [0, 1]) {
+        await program.methods
+          .initCandidateTally(parallelPollId, index, 0)
+          .accounts({
+            poll: parallelPollPda,
+            tally: tallyPda(index),
+            admin: admin.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+
+      const pollAccount = await program.account.poll.fetch(parallelPollPda);
+      assert.equal(pollAccount.parallelTallies, true);
+    });
+
+    it("Counts parallel votes on the tally accounts", async () => {
+      const voteRecordPda = findVoteRecordPda(parallelPollId, admin.publicKey);
+
+      await program.methods
+        .voteParallel(parallelPollId, 1)
+        .accounts({
+          ...eventAccounts,
+          poll: parallelPollPda,
+          config: configPda,
+          tally: tallyPda(1),
+          voteRecord: voteRecordPda,
+          voter: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const tally = await program.account.candidateTally.fetch(tallyPda(1));
+      assert.equal(tally.votes.toNumber(), 1);
+
+      // The poll itself is untouched until aggregation
+      const pollAccount = await program.account.poll.fetch(parallelPollPda);
+      assert.equal(pollAccount.totalVotes.toNumber(), 0);
+    });
+
+    it("Aggregates tallies after the poll closes", async () => {
+      await program.methods
+        .closePoll(parallelPollId)
+        .accounts({ poll: parallelPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+        .rpc();
+
+      await program.methods
+        .aggregateTallies(parallelPollId)
+        .accounts({ poll: parallelPollPda })
+        .remainingAccounts(
+          [0, 1].map((index) => ({
+            pubkey: tallyPda(index),
+            isSigner: false,
+            isWritable: false,
+          }))
+        )
+        .rpc();
+
+      const pollAccount = await program.account.poll.fetch(parallelPollPda);
+      assert.equal(pollAccount.candidates[0].votes.toNumber(), 0);
+      assert.equal(pollAccount.candidates[1].votes.toNumber(), 1);
+      assert.equal(pollAccount.totalVotes.toNumber(), 1);
+      assert.equal(pollAccount.talliesAggregated, true);
+    });
+
+    it("Spreads a candidate's ballots over tally shards", async () => {
+      const shards = 4;
+      const { pollId: shardedPollId, pollPda: shardedPollPda } = await createPoll(
+        "Sharded Poll",
+        ["Yes", "No"],
+        pollSettings({ parallelTallies: true, tallyShards: shards })
+      );
+      const shardPda = (index: number, shard: number) =>
+        PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("tally"),
+            shardedPollPda.toBuffer(),
+            Buffer.from([index]),
+            Buffer.from(shard === 0 ? [] : [shard]),
+          ],
+          program.programId
+        )[0];
+      const counters = [0, 1].flatMap((index) =>
+        [...Array(shards).keys()].map((shard) => ({ index, shard }))
+      );
+      for (const { index, shard } of counters) {
+        await program.methods
+          .initCandidateTally(shardedPollId, index, shard)
+          .accounts({
+            poll: shardedPollPda,
+            tally: shardPda(index, shard),
+            admin: admin.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+
+      const voterShard =
+        createHash("sha256")
+          .update(Buffer.concat([Buffer.from("tally_shard"), admin.publicKey.toBuffer()]))
+          .digest()[0] % shards;
+      await program.methods
+        .voteParallel(shardedPollId, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: shardedPollPda,
+          config: configPda,
+          tally: shardPda(0, voterShard),
+          voteRecord: findVoteRecordPda(shardedPollId, admin.publicKey),
+          voter: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      const tally = await program.account.candidateTally.fetch(shardPda(0, voterShard));
+      assert.equal(tally.votes.toNumber(), 1);
+      assert.equal(tally.shard, voterShard);
+
+      await program.methods
+        .closePoll(shardedPollId)
+        .accounts({ poll: shardedPollPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+        .rpc();
+      await program.methods
+        .aggregateTallies(shardedPollId)
+        .accounts({ poll: shardedPollPda })
+        .remainingAccounts(
+          counters.map(({ index, shard }) => ({
+            pubkey: shardPda(index, shard),
+            isSigner: false,
+            isWritable: false,
+          }))
+        )
+        .rpc();
+
+      const pollAccount = await program.account.poll.fetch(shardedPollPda);
+      assert.equal(pollAccount.candidates[0].votes.toNumber(), 1);
+      assert.equal(pollAccount.totalVotes.toNumber(), 1);
+    });
+  });
+
+  describe("compressed votes", () => {
+    let compressedPollId: anchor.BN;
+    let compressedPollPda: PublicKey;
+    const voteTree = anchor.web3.Keypair.generate();
+    const voteFilter = anchor.web3.Keypair.generate();
+    const compressionAccounts = {
+      compressionProgram: SPL_ACCOUNT_COMPRESSION_PROGRAM_ID,
+      noopProgram: SPL_NOOP_PROGRAM_ID,
+    };
+
+    const voteCompressed = (voter: anchor.web3.Keypair) =>
+      program.methods
+        .voteCompressed(compressedPollId, 0)
+        .accounts({
+          ...eventAccounts,
+          ...compressionAccounts,
+          poll: compressedPollPda,
+          config: configPda,
+          voteTree: voteTree.publicKey,
+          voteFilter: voteFilter.publicKey,
+          voter: voter.publicKey,
+        })
+        .signers([voter]);
+
+    it("Switches a poll to compressed vote records", async () => {
+      ({ pollId: compressedPollId, pollPda: compressedPollPda } = await createPoll(
+        "Compressed Poll",
+        ["Yes", "No"]
+      ));
+
+      const filterSpace = 8 + 32 + 1 + 8 + 1024;
+      const tx = new anchor.web3.Transaction().add(
+        await createAllocTreeIx(
+          provider.connection,
+          voteTree.publicKey,
+          admin.publicKey,
+          { maxDepth: 14, maxBufferSize: 64 },
+          0
+        ),
+        SystemProgram.createAccount({
+          fromPubkey: admin.publicKey,
+          newAccountPubkey: voteFilter.publicKey,
+          lamports: await provider.connection.getMinimumBalanceForRentExemption(filterSpace),
+          space: filterSpace,
+          programId: program.programId,
+        })
+      );
+      await provider.sendAndConfirm(tx, [voteTree, voteFilter]);
+
+      await program.methods
+        .enableCompressedVotes(compressedPollId, 14, 64, 7)
+        .accounts({
+          ...compressionAccounts,
+          poll: compressedPollPda,
+          voteTree: voteTree.publicKey,
+          voteFilter: voteFilter.publicKey,
+          admin: admin.publicKey,
+        })
+        .rpc();
+
+      const pollAccount = await program.account.poll.fetch(compressedPollPda);
+      assert.equal(pollAccount.voteTree.toBase58(), voteTree.publicKey.toBase58());
+      assert.equal(pollAccount.voteFilter.toBase58(), voteFilter.publicKey.toBase58());
+    });
+
+    it("Appends compressed ballots and rejects repeats", async () => {
+      const voter = await fundedKeypair();
+      const signature = await voteCompressed(voter).rpc();
+
+      const [cast] = await fetchCpiEvents(signature);
+      assert.equal(cast.name, "CompressedVoteCast");
+      assert.equal(cast.data.leafIndex.toNumber(), 0);
+      const pollAccount = await program.account.poll.fetch(compressedPollPda);
+      assert.equal(pollAccount.candidates[0].votes.toNumber(), 1);
+      assert.equal(pollAccount.totalVotes.toNumber(), 1);
+
+      try {
+        await voteCompressed(voter).rpc();
+        assert.fail("A second compressed ballot should be rejected");
+      } catch (err) {
+        expect(err.toString()).to.include("DuplicateVote");
+      }
+    });
+
+    it("Closes the vote record path on compressed polls", async () => {
+      try {
+        await program.methods
+          .vote(compressedPollId, 1)
+          .accounts({
+            ...eventAccounts,
+            poll: compressedPollPda,
+            config: configPda,
+            voteRecord: findVoteRecordPda(compressedPollId, admin.publicKey),
+            voter: admin.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        assert.fail("Vote records should be rejected on compressed polls");
+      } catch (err) {
+        expect(err.toString()).to.include("CompressedVotesOnly");
+      }
+    });
+  });
+
+  describe("private ballots", () => {
+    let privatePollId: anchor.BN;
+    let privatePollPda: PublicKey;
+    let encryptedTallyPda: PublicKey;
+    const tallyAuthority = anchor.web3.Keypair.generate();
+
+    it("Switches a poll to private ballots", async () => {
+      ({ pollId: privatePollId, pollPda: privatePollPda } = await createPoll(
+        "Private Poll",
+        ["Yes", "No", "Abstain"]
+      ));
+      [encryptedTallyPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("encrypted_tally"), privatePollPda.toBuffer()],
+        program.programId
+      );
+      const elgamalPubkey = Array.from(anchor.web3.Keypair.generate().publicKey.toBytes());
+
+      await program.methods
+        .enablePrivateBallots(privatePollId, elgamalPubkey, tallyAuthority.publicKey)
+        .accounts({
+          poll: privatePollPda,
+          encryptedTally: encryptedTallyPda,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const pollAccount = await program.account.poll.fetch(privatePollPda);
+      assert.equal(pollAccount.encryptedTally.toBase58(), encryptedTallyPda.toBase58());
+      const tally = await program.account.encryptedTally.fetch(encryptedTallyPda);
+      assert.equal(tally.tallies.length, 3);
+      assert.deepEqual(tally.elgamalPubkey, elgamalPubkey);
+      assert.equal(tally.tallyAuthority.toBase58(), tallyAuthority.publicKey.toBase58());
+    });
+
+    it("Closes the plain vote path on private polls", async () => {
+      try {
+        await program.methods
+          .vote(privatePollId, 0)
+          .accounts({
+            ...eventAccounts,
+            poll: privatePollPda,
+            config: configPda,
+            voteRecord: findVoteRecordPda(privatePollId, admin.publicKey),
+            voter: admin.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        assert.fail("Vote records should be rejected on private polls");
+      } catch (err) {
+        expect(err.toString()).to.include("PrivateBallotsOnly");
+      }
+    });
+
+    it("Rejects private ballots without verified proofs", async () => {
+      const voter = await fundedKeypair();
+      const [nullifier] = PublicKey.findProgramAddressSync(
+        [Buffer.from("nullifier"), privatePollPda.toBuffer(), voter.publicKey.toBuffer()],
+        program.programId
+      );
+      try {
+        await program.methods
+          .votePrivate(privatePollId, [0, 1, 2].map(() => new Array(64).fill(0)))
+          .accounts({
+            ...eventAccounts,
+            poll: privatePollPda,
+            config: configPda,
+            encryptedTally: encryptedTallyPda,
+            nullifier,
+            membership: null,
+            rangeProof: configPda,
+            voter: voter.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([voter])
+          .rpc();
+        assert.fail("A ballot without proofs should be rejected");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidBallotProof");
+      }
+    });
+
+    it("Rejects private ballots on polls that can't take them", async () => {
+      const { pollId, pollPda } = await createPoll(
+        "Write-in Poll",
+        ["Yes", "No"],
+        pollSettings({ allowWriteIns: true })
+      );
+      try {
+        await program.methods
+          .enablePrivateBallots(pollId, new Array(32).fill(1), tallyAuthority.publicKey)
+          .accounts({
+            poll: pollPda,
+            encryptedTally: PublicKey.findProgramAddressSync(
+              [Buffer.from("encrypted_tally"), pollPda.toBuffer()],
+              program.programId
+            )[0],
+            admin: admin.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        assert.fail("Write-in polls should not take private ballots");
+      } catch (err) {
+        expect(err.toString()).to.include("PrivateBallotsUnsupported");
+      }
+    });
+  });
+
+  describe("encrypted ballots", () => {
+    const revealBond = anchor.web3.LAMPORTS_PER_SOL / 100;
+
+    // Random scalar below the group order, and its little-endian encoding
+    const randomScalar = () => BigInt("0x" + randomBytes(31).toString("hex"));
+    const scalarBytes = (scalar: bigint) =>
+      Buffer.from(scalar.toString(16).padStart(64, "0"), "hex").reverse();
+
+    const createEncryptedPoll = async (secret: bigint, revealWindow: number) => {
+      const currentSlot = await provider.connection.getSlot();
+      return createPoll(
+        "Encrypted Poll",
+        ["A", "B", "C"],
+        pollSettings({
+          clockSource: { slot: {} },
+          endTs: new anchor.BN(currentSlot + 40),
+          ballotKey: new PublicKey(RistrettoPoint.BASE.multiply(secret).toRawBytes()),
+          revealWindow: new anchor.BN(revealWindow),
+          revealBond: new anchor.BN(revealBond),
+        })
+      );
+    };
+
+    const findBallotPda = (pollPda: PublicKey, voter: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("encrypted_ballot"), pollPda.toBuffer(), voter.toBuffer()],
+        program.programId
+      )[0];
+
+    // Encrypts `choice` to the poll's ballot key, as EncryptedBallot::mask
+    const voteEncrypted = async (
+      pollId: anchor.BN,
+      pollPda: PublicKey,
+      voter: anchor.web3.Keypair,
+      choice: number
+    ) => {
+      const { ballotKey } = await program.account.poll.fetch(pollPda);
+      const r = randomScalar();
+      const shared = RistrettoPoint.fromHex(ballotKey.toBytes()).multiply(r).toRawBytes();
+      const mask = createHash("sha256")
+        .update("encrypted_ballot")
+        .update(shared)
+        .update(pollPda.toBuffer())
+        .update(voter.publicKey.toBuffer())
+        .digest();
+      return program.methods
+        .voteEncrypted(
+          pollId,
+          Array.from(RistrettoPoint.BASE.multiply(r).toRawBytes()),
+          choice ^ mask[0]
+        )
+        .accounts({
+          ...eventAccounts,
+          poll: pollPda,
+          config: configPda,
+          ballot: findBallotPda(pollPda, voter.publicKey),
+          membership: null,
+          voter: voter.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+    };
+
+    it("Counts encrypted ballots once the ballot key is revealed", async () => {
+      const voters = [await fundedKeypair(), await fundedKeypair(), await fundedKeypair()];
+      const secret = randomScalar();
+      const { pollId, pollPda } = await createEncryptedPoll(secret, 1_000);
+      await voteEncrypted(pollId, pollPda, voters[0], 1);
+      await voteEncrypted(pollId, pollPda, voters[1], 1);
+      await voteEncrypted(pollId, pollPda, voters[2], 2);
+
+      try {
+        await program.methods
+          .vote(pollId, 0)
+          .accounts({
+            ...eventAccounts,
+            poll: pollPda,
+            config: configPda,
+            voteRecord: findVoteRecordPda(pollId, admin.publicKey),
+            voter: admin.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        assert.fail("Plain ballots should be rejected on polls with a ballot key");
+      } catch (err) {
+        expect(err.toString()).to.include("EncryptedBallotsOnly");
+      }
+      let pollAccount = await program.account.poll.fetch(pollPda);
+      assert.equal(pollAccount.sealedBallots.toNumber(), 3);
+      assert.equal(pollAccount.totalVotes.toNumber(), 0);
+
+      await waitForSlot(pollAccount.endTs.toNumber());
+      await program.methods
+        .crankCloseExpired(pollId)
+        .accounts({ poll: pollPda, ...eventAccounts })
+        .rpc();
+
+      const reveal = (scalar: bigint) =>
+        program.methods
+          .revealBallotKey(pollId, Array.from(scalarBytes(scalar)))
+          .accounts({ ...eventAccounts, poll: pollPda, admin: admin.publicKey })
+          .rpc();
+      try {
+        await reveal(secret + BigInt(1));
+        assert.fail("A wrong secret should be rejected");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidBallotKey");
+      }
+      const before = await provider.connection.getBalance(pollPda);
+      await reveal(secret);
+      assert.equal(before - (await provider.connection.getBalance(pollPda)), revealBond);
+
+      await program.methods
+        .tallyEncryptedVotes(pollId)
+        .accounts({ ...eventAccounts, poll: pollPda })
+        .remainingAccounts(
+          voters.flatMap((voter) => [
+            { pubkey: findBallotPda(pollPda, voter.publicKey), isWritable: true, isSigner: false },
+            { pubkey: voter.publicKey, isWritable: true, isSigner: false },
+          ])
+        )
+        .rpc();
+      assert.isNull(
+        await provider.connection.getAccountInfo(findBallotPda(pollPda, voters[0].publicKey))
+      );
+
+      await program.methods
+        .finalizePoll(pollId)
+        .accounts({ poll: pollPda, config: configPda, admin: admin.publicKey, ...eventAccounts })
+        .rpc();
+      pollAccount = await program.account.poll.fetch(pollPda);
+      assert.deepEqual(
+        pollAccount.candidates.map((c) => c.votes.toNumber()),
+        [0, 2, 1]
+      );
+      assert.equal(pollAccount.winnerIndex, 1);
+    });
+
+    it("Slashes the reveal bond when the key is never revealed", async () => {
+      const { pollId, pollPda } = await createEncryptedPoll(randomScalar(), 5);
+      const [treasuryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("treasury")],
+        program.programId
+      );
+      const slash = () =>
+        program.methods
+          .slashRevealBond(pollId)
+          .accounts({
+            ...eventAccounts,
+            poll: pollPda,
+            treasury: treasuryPda,
+            caller: admin.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+
+      try {
+        await slash();
+        assert.fail("The bond can't be slashed before the reveal deadline");
+      } catch (err) {
+        expect(err.toString()).to.include("PollNotClosed");
+      }
+
+      const { endTs, revealWindow } = await program.account.poll.fetch(pollPda);
+      await waitForSlot(endTs.toNumber() + revealWindow.toNumber() + 1);
+      const before = await provider.connection.getBalance(treasuryPda);
+      await slash();
+      assert.equal((await provider.connection.getBalance(treasuryPda)) - before, revealBond);
+
+      const pollAccount = await program.account.poll.fetch(pollPda);
+      assert.deepEqual(pollAccount.status, { cancelled: {} });
+      assert.equal(pollAccount.revealBond.toNumber(), 0);
+    });
+  });
+
+  describe("weighted delegation", () => {
+    let delegationPollId: anchor.BN;
+    let delegationPollPda: PublicKey;
+    let delegator: anchor.web3.Keypair;
+    let delegateA: anchor.web3.Keypair;
+    let delegateB: anchor.web3.Keypair;
+
+    const findDelegationPda = (owner: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("delegation"), owner.toBuffer()],
+        program.programId
+      )[0];
+
+    const voteDelegated = (delegate: anchor.web3.Keypair, candidateIndex: number) =>
+      program.methods
+        .voteDelegated(delegationPollId, candidateIndex)
+        .accounts({
+          ...eventAccounts,
+          poll: delegationPollPda,
+          config: configPda,
+          delegation: findDelegationPda(delegator.publicKey),
+          delegatorVoteRecord: findVoteRecordPda(
+            delegationPollId,
+            delegator.publicKey
+          ),
+          delegatedVote: PublicKey.findProgramAddressSync(
+            [
+              Buffer.from("delegated_vote"),
+              delegationPollPda.toBuffer(),
+              delegator.publicKey.toBuffer(),
+              delegate.publicKey.toBuffer(),
+            ],
+            program.programId
+          )[0],
+          delegate: delegate.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([delegate])
+        .rpc();
+
+    before(async () => {
+      delegator = await fundedKeypair();
+      delegateA = await fundedKeypair();
+      delegateB = await fundedKeypair();
+      ({ pollId: delegationPollId, pollPda: delegationPollPda } =
+        await createPoll("Delegated Poll", ["Red", "Blue"]));
+    });
+
+    it("Splits a delegator's weight 60/40 across two delegates", async () => {
+      await program.methods
+        .setDelegation([
+          { delegate: delegateA.publicKey, bps: 6000 },
+          { delegate: delegateB.publicKey, bps: 4000 },
+        ])
+        .accounts({
+          delegation: findDelegationPda(delegator.publicKey),
+          delegator: delegator.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([delegator])
+        .rpc();
+
+      await voteDelegated(delegateA, 0);
+      await voteDelegated(delegateB, 1);
+
+      const pollAccount = await program.account.poll.fetch(delegationPollPda);
+      assert.equal(pollAccount.candidates[0].weight.toNumber(), 6000);
+      assert.equal(pollAccount.candidates[1].weight.toNumber(), 4000);
+      assert.equal(pollAccount.totalWeight.toNumber(), 10000);
+
+      const ballot = await program.account.voteRecord.fetch(
+        findVoteRecordPda(delegationPollId, delegator.publicKey)
+      );
+      assert.equal(ballot.delegatedBps, 10000);
+    });
+
+    it("Prevents the delegator from also voting directly", async () => {
+      try {
+        await program.methods
+          .vote(delegationPollId, 0)
+          .accounts({
+            ...eventAccounts,
+            poll: delegationPollPda,
+            config: configPda,
+            voteRecord: findVoteRecordPda(delegationPollId, delegator.publicKey),
+            voter: delegator.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([delegator])
+          .rpc();
+        assert.fail("Delegator's ballot was already cast by delegates");
+      } catch (err) {
+        expect(err).to.exist;
+      }
+    });
+
+    it("Scopes a proxy to one poll and lets the delegator override it", async () => {
+      const { pollId, pollPda } = await createPoll("Proxy Poll", ["Red", "Blue"]);
+      const proxyPda = PublicKey.findProgramAddressSync(
+        [Buffer.from("proxy"), pollPda.toBuffer(), delegator.publicKey.toBuffer()],
+        program.programId
+      )[0];
+      const setProxy = (proxy: PublicKey) =>
+        program.methods
+          .setPollProxy(pollId, proxy)
+          .accounts({
+            poll: pollPda,
+            pollProxy: proxyPda,
+            delegator: delegator.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([delegator])
+          .rpc();
+      const revoke = () =>
+        program.methods
+          .revokeDelegation(pollId)
+          .accounts({ poll: pollPda, pollProxy: proxyPda, delegator: delegator.publicKey })
+          .signers([delegator])
+          .rpc();
+
+      // Revoked before voting, so delegate B is named instead
+      await setProxy(delegateA.publicKey);
+      await revoke();
+      await setProxy(delegateB.publicKey);
+      await program.methods
+        .voteAsProxy(pollId, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: pollPda,
+          config: configPda,
+          pollProxy: proxyPda,
+          voteRecord: findVoteRecordPda(pollId, delegator.publicKey),
+          proxy: delegateB.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([delegateB])
+        .rpc();
+
+      try {
+        await revoke();
+        assert.fail("A proxy that voted can't be revoked");
+      } catch (err) {
+        expect(err.toString()).to.include("ProxyAlreadyVoted");
+      }
+
+      await program.methods
+        .overrideProxyVote(pollId, 1)
+        .accounts({
+          ...eventAccounts,
+          poll: pollPda,
+          config: configPda,
+          pollProxy: proxyPda,
+          voteRecord: findVoteRecordPda(pollId, delegator.publicKey),
+          delegator: delegator.publicKey,
+        })
+        .signers([delegator])
+        .rpc();
+
+      const pollAccount = await program.account.poll.fetch(pollPda);
+      assert.equal(pollAccount.candidates[0].votes.toNumber(), 0);
+      assert.equal(pollAccount.candidates[1].votes.toNumber(), 1);
+      assert.isNull(await program.account.pollProxy.fetchNullable(proxyPda));
+    });
+
+    it("Refuses to override an invalidated proxy ballot", async () => {
+      const { pollId, pollPda } = await createPoll("Struck Proxy Poll", ["Red", "Blue"]);
+      const proxyPda = PublicKey.findProgramAddressSync(
+        [Buffer.from("proxy"), pollPda.toBuffer(), delegator.publicKey.toBuffer()],
+        program.programId
+      )[0];
+      const voteRecord = findVoteRecordPda(pollId, delegator.publicKey);
+      await program.methods
+        .setPollProxy(pollId, delegateA.publicKey)
+        .accounts({
+          poll: pollPda,
+          pollProxy: proxyPda,
+          delegator: delegator.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([delegator])
+        .rpc();
+      await program.methods
+        .voteAsProxy(pollId, 0)
+        .accounts({
+          ...eventAccounts,
+          poll: pollPda,
+          config: configPda,
+          pollProxy: proxyPda,
+          voteRecord,
+          proxy: delegateA.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([delegateA])
+        .rpc();
+      await program.methods
+        .invalidateVote(delegator.publicKey, 1)
+        .accounts({ ...eventAccounts, poll: pollPda, config: configPda, voteRecord, admin: admin.publicKey })
+        .rpc();
+
+      try {
+        await program.methods
+          .overrideProxyVote(pollId, 1)
+          .accounts({
+            ...eventAccounts,
+            poll: pollPda,
+            config: configPda,
+            pollProxy: proxyPda,
+            voteRecord,
+            delegator: delegator.publicKey,
+          })
+          .signers([delegator])
+          .rpc();
+        assert.fail("A struck ballot can't be moved");
+      } catch (err) {
+        expect(err.toString()).to.include("VoteInvalidated");
+      }
+
+      const pollAccount = await program.account.poll.fetch(pollPda);
+      assert.equal(pollAccount.candidates[0].votes.toNumber(), 0);
+      assert.equal(pollAccount.candidates[1].votes.toNumber(), 0);
+      assert.equal(pollAccount.totalVotes.toNumber(), 0);
+    });
+  });
+
+  describe("v2 settings-hash polls", () => {
+    const borshString = (s: string) => {
+      const bytes = Buffer.from(s, "utf8");
+      const len = Buffer.alloc(4);
+      len.writeUInt32LE(bytes.length);
+      return Buffer.concat([len, bytes]);
+    };
+
+    const findV2PollPda = (v2Title: string, v2Candidates: string[], settings: object) => {
+      const count = Buffer.alloc(4);
+      count.writeUInt32LE(v2Candidates.length);
+      const hash = createHash("sha256")
+        .update(
+          Buffer.concat([
+            Buffer.from("poll_v2"),
+            admin.publicKey.toBuffer(),
+            borshString(v2Title),
+            count,
+            ...v2Candidates.map(borshString),
+            program.coder.types.encode("PollSettings", settings),
+          ])
+        )
+        .digest();
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("poll"), hash, new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+    };
+
+    it("Makes retried creation idempotent", async () => {
+      const settings = pollSettings();
+      const v2PollPda = findV2PollPda("Board Election", ["Ann", "Bob"], settings);
+      const create = async () =>
+        program.methods
+          .createPollV2("Board Election", ["Ann", "Bob"], settings)
+          .accounts({
+            ...eventAccounts,
+            config: configPda,
+            poll: v2PollPda,
+            ...(await adminPollAccounts(admin.publicKey)),
+            admin: admin.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+
+      await create();
+      try {
+        await create();
+        assert.fail("A retried creation should not create a second poll");
+      } catch (err) {
+        expect((err.logs ?? []).join("\n")).to.include("already in use");
+      }
+
+      const pollAccount = await program.account.poll.fetch(v2PollPda);
+      assert.equal(pollAccount.title, "Board Election");
+      assert.equal(pollAccount.pollId.toNumber(), 0);
+    });
+  });
+
+  describe("namespaced polls", () => {
+    const localId = new anchor.BN(1);
+
+    const findNamespacedPollPda = (owner: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("poll"), owner.toBuffer(), localId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+
+    it("Lets two admins use the same poll ID", async () => {
+      const otherAdmin = await fundedKeypair();
+
+      await program.methods
+        .createNamespacedPoll(localId, "Org A Poll", ["A", "B"], pollSettings())
+        .accounts({
+          ...eventAccounts,
+          poll: findNamespacedPollPda(admin.publicKey),
+          config: configPda,
+          ...(await adminPollAccounts(admin.publicKey)),
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .createNamespacedPoll(localId, "Org B Poll", ["A", "B"], pollSettings())
+        .accounts({
+          ...eventAccounts,
+          poll: findNamespacedPollPda(otherAdmin.publicKey),
+          config: configPda,
+          ...(await adminPollAccounts(otherAdmin.publicKey)),
+          admin: otherAdmin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([otherAdmin])
+        .rpc();
+
+      const pollA = await program.account.poll.fetch(
+        findNamespacedPollPda(admin.publicKey)
+      );
+      const pollB = await program.account.poll.fetch(
+        findNamespacedPollPda(otherAdmin.publicKey)
+      );
+      assert.equal(pollA.title, "Org A Poll");
+      assert.equal(pollB.title, "Org B Poll");
+      assert.equal(pollB.namespace.toBase58(), otherAdmin.publicKey.toBase58());
+    });
+
+    it("Votes on a namespaced poll", async () => {
+      const namespacedPollPda = findNamespacedPollPda(admin.publicKey);
+      const [voteRecordPda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("vote"),
+          admin.publicKey.toBuffer(),
+          localId.toArrayLike(Buffer, "le", 8),
+          admin.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+
+      await program.methods
+        .vote(localId, 1)
+        .accounts({
+          ...eventAccounts,
+          poll: namespacedPollPda,
+          config: configPda,
+          voteRecord: voteRecordPda,
+          voter: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const pollAccount = await program.account.poll.fetch(namespacedPollPda);
+      assert.equal(pollAccount.candidates[1].votes.toNumber(), 1);
+    });
+
+    it("Creates and closes a poll for an admin that doesn't pay", async () => {
+      // Stands in for a governance PDA: it signs but holds no lamports
+      const governance = anchor.web3.Keypair.generate();
+      const governedPollPda = findNamespacedPollPda(governance.publicKey);
+      await program.methods
+        .createGovernedPoll(localId, "DAO Poll", ["For", "Against"], pollSettings())
+        .accounts({
+          ...eventAccounts,
+          config: configPda,
+          poll: governedPollPda,
+          ...(await adminPollAccounts(governance.publicKey)),
+          admin: governance.publicKey,
+          payer: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([governance])
+        .rpc();
+
+      await program.methods
+        .closePoll(localId)
+        .accounts({
+          ...eventAccounts,
+          poll: governedPollPda,
+          config: configPda,
+          admin: governance.publicKey,
+        })
+        .signers([governance])
+        .rpc();
+
+      const pollAccount = await program.account.poll.fetch(governedPollPda);
+      assert.equal(pollAccount.admin.toBase58(), governance.publicKey.toBase58());
+      assert.deepEqual(pollAccount.status, { closed: {} });
+      assert.equal(await provider.connection.getBalance(governance.publicKey), 0);
+    });
+
+    it("Lets organization members create and manage the organization's polls", async () => {
+      const [orgPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("org"), admin.publicKey.toBuffer(), Buffer.from("core-team")],
+        program.programId
+      );
+      const member = await fundedKeypair();
+      const outsider = await fundedKeypair();
+      const findOrgRolePda = (grantee: PublicKey) =>
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("role"), orgPda.toBuffer(), grantee.toBuffer()],
+          program.programId
+        )[0];
+      const memberRole = findOrgRolePda(member.publicKey);
+
+      await program.methods
+        .createOrganization("core-team")
+        .accounts({ ...eventAccounts, organization: orgPda, owner: admin.publicKey, systemProgram: SystemProgram.programId })
+        .rpc();
+      await program.methods
+        .setOrgMember(member.publicKey, 0b10001) // PERMISSION_CLOSE | PERMISSION_CREATE_POLLS
+        .accounts({
+          ...eventAccounts,
+          organization: orgPda,
+          role: memberRole,
+          owner: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const orgPollPda = findNamespacedPollPda(orgPda);
+      const createAs = async (creator: anchor.web3.Keypair, role: PublicKey | null) =>
+        program.methods
+          .createOrgPoll(localId, "Team Poll", ["A", "B"], pollSettings())
+          .accounts({
+            ...eventAccounts,
+            config: configPda,
+            organization: orgPda,
+            role,
+            poll: orgPollPda,
+            ...(await adminPollAccounts(orgPda)),
+            creator: creator.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([creator])
+          .rpc();
+
+      try {
+        await createAs(outsider, null);
+        assert.fail("A non-member should not create organization polls");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+      await createAs(member, memberRole);
+
+      const pollAccount = await program.account.poll.fetch(orgPollPda);
+      assert.equal(pollAccount.admin.toBase58(), admin.publicKey.toBase58());
+      assert.equal(pollAccount.organization.toBase58(), orgPda.toBase58());
+      const entry = await program.account.adminPollEntry.fetch(findAdminPollEntryPda(orgPda, new anchor.BN(0)));
+      assert.equal(entry.poll.toBase58(), orgPollPda.toBase58());
+
+      // Organization roles stand in for per-poll co-admin roles
+      try {
+        await program.methods
+          .grantRole(outsider.publicKey, 1)
+          .accounts({
+            ...eventAccounts,
+            poll: orgPollPda,
+            config: configPda,
+            role: PublicKey.findProgramAddressSync(
+              [Buffer.from("role"), orgPollPda.toBuffer(), outsider.publicKey.toBuffer()],
+              program.programId
+            )[0],
+            admin: admin.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        assert.fail("Organization polls should not take per-poll roles");
+      } catch (err) {
+        expect(err.toString()).to.include("OrganizationPoll");
+      }
+      await program.methods
+        .closePoll(localId)
+        .accounts({ ...eventAccounts, poll: orgPollPda, config: configPda, role: memberRole, admin: member.publicKey })
+        .signers([member])
+        .rpc();
+      assert.deepEqual((await program.account.poll.fetch(orgPollPda)).status, { closed: {} });
+
+      await program.methods
+        .removeOrgMember()
+        .accounts({ ...eventAccounts, organization: orgPda, role: memberRole, owner: admin.publicKey })
+        .rpc();
+      assert.isNull(await program.account.pollRole.fetchNullable(memberRole));
+    });
+  });
 });